@@ -0,0 +1,188 @@
+use clap::ValueEnum;
+use solana_client::rpc_config::RpcBlockConfig;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_transaction_status::{
+    EncodedTransactionWithStatusMeta, TransactionDetails, UiConfirmedBlock, UiTransactionEncoding,
+};
+
+use crate::SolanaBalanceChecker;
+
+// `getBlock` only serves blocks at confirmed or finalized commitment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum BlockCommitment {
+    Confirmed,
+    Finalized,
+}
+
+impl BlockCommitment {
+    fn to_config(self) -> CommitmentConfig {
+        match self {
+            Self::Confirmed => CommitmentConfig::confirmed(),
+            Self::Finalized => CommitmentConfig::finalized(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactionSummary {
+    pub signature: String,
+    pub fee: Option<u64>,
+    // The transaction's error, if it failed on chain
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockDetail {
+    pub slot: u64,
+    pub blockhash: String,
+    pub previous_blockhash: String,
+    pub parent_slot: u64,
+    // Unix timestamp; not known for very old blocks
+    pub block_time: Option<i64>,
+    pub block_height: Option<u64>,
+    pub transaction_count: usize,
+    // Only filled in when transactions were requested
+    pub transactions: Option<Vec<TransactionSummary>>,
+}
+
+impl BlockDetail {
+    fn from_block(slot: u64, block: UiConfirmedBlock) -> Self {
+        // Without full transactions the block still lists every signature
+        let transaction_count = match (&block.transactions, &block.signatures) {
+            (Some(transactions), _) => transactions.len(),
+            (None, Some(signatures)) => signatures.len(),
+            (None, None) => 0,
+        };
+        Self {
+            slot,
+            blockhash: block.blockhash,
+            previous_blockhash: block.previous_blockhash,
+            parent_slot: block.parent_slot,
+            block_time: block.block_time,
+            block_height: block.block_height,
+            transaction_count,
+            transactions: block
+                .transactions
+                .map(|transactions| transactions.iter().map(summarize).collect()),
+        }
+    }
+}
+
+fn summarize(transaction: &EncodedTransactionWithStatusMeta) -> TransactionSummary {
+    let signature = transaction
+        .transaction
+        .decode()
+        .and_then(|decoded| decoded.signatures.first().map(|s| s.to_string()))
+        .unwrap_or_default();
+    TransactionSummary {
+        signature,
+        fee: transaction.meta.as_ref().map(|meta| meta.fee),
+        error: transaction
+            .meta
+            .as_ref()
+            .and_then(|meta| meta.err.as_ref())
+            .map(|err| err.to_string()),
+    }
+}
+
+impl SolanaBalanceChecker {
+    // Block at `slot`; with `include_transactions` each transaction is summarized too
+    pub async fn get_block(
+        &self,
+        slot: u64,
+        include_transactions: bool,
+        commitment: BlockCommitment,
+    ) -> Result<BlockDetail, String> {
+        let config = RpcBlockConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            transaction_details: Some(if include_transactions {
+                TransactionDetails::Full
+            } else {
+                TransactionDetails::Signatures
+            }),
+            rewards: Some(false),
+            commitment: Some(commitment.to_config()),
+            max_supported_transaction_version: Some(0),
+        };
+        let block = self
+            .client
+            .get_block_with_config(slot, config)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(BlockDetail::from_block(slot, block))
+    }
+
+    // Newest slot at `commitment`, for `block-info --latest`
+    pub async fn get_latest_slot(&self, commitment: BlockCommitment) -> Result<u64, String> {
+        self.client
+            .get_slot_with_commitment(commitment.to_config())
+            .await
+            .map_err(|e| e.to_string())
+    }
+}
+
+pub fn print_block(block: &BlockDetail) {
+    println!("=== Block {} ===\n", block.slot);
+    println!("Blockhash: {}", block.blockhash);
+    println!("Previous blockhash: {}", block.previous_blockhash);
+    println!("Parent slot: {}", block.parent_slot);
+    println!(
+        "Block time: {}",
+        block
+            .block_time
+            .map_or("unknown".to_string(), |time| time.to_string())
+    );
+    println!(
+        "Block height: {}",
+        block
+            .block_height
+            .map_or("unknown".to_string(), |height| height.to_string())
+    );
+    println!("Transactions: {}", block.transaction_count);
+
+    if let Some(transactions) = &block.transactions {
+        println!();
+        for transaction in transactions {
+            let fee = transaction
+                .fee
+                .map_or("-".to_string(), |fee| fee.to_string());
+            match &transaction.error {
+                Some(error) => println!(
+                    "❌ {} (fee {} lamports): {}",
+                    transaction.signature, fee, error
+                ),
+                None => println!("✅ {} (fee {} lamports)", transaction.signature, fee),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(json: serde_json::Value) -> UiConfirmedBlock {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_signatures_only_block_is_counted() {
+        let detail = BlockDetail::from_block(
+            101,
+            block(serde_json::json!({
+                "blockhash": "9QjQfsQbFmGtE5mWUPAHpEKXGVumsX4aLTeq6jZBuDbS",
+                "previousBlockhash": "4sGjMW1sUnHzSxGspuhpqLDx6wiyjNtZAMdL4VZHirAn",
+                "parentSlot": 100,
+                "blockTime": 1700000000,
+                "blockHeight": 90,
+                "signatures": ["sig1", "sig2", "sig3"]
+            })),
+        );
+
+        assert_eq!(detail.parent_slot, 100);
+        assert_eq!(detail.block_time, Some(1_700_000_000));
+        assert_eq!(detail.block_height, Some(90));
+        assert_eq!(detail.transaction_count, 3);
+        assert!(detail.transactions.is_none());
+    }
+}
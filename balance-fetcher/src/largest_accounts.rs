@@ -0,0 +1,165 @@
+use clap::ValueEnum;
+use solana_client::rpc_config::{RpcLargestAccountsConfig, RpcLargestAccountsFilter};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use crate::SolanaBalanceChecker;
+
+// `getLargestAccounts` is expensive for the node, so results are reused for this long
+pub const LARGEST_ACCOUNTS_CACHE_TTL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ValueEnum)]
+pub enum LargestAccountsFilter {
+    All,
+    Circulating,
+    NonCirculating,
+}
+
+impl LargestAccountsFilter {
+    fn to_rpc(self) -> Option<RpcLargestAccountsFilter> {
+        match self {
+            Self::All => None,
+            Self::Circulating => Some(RpcLargestAccountsFilter::Circulating),
+            Self::NonCirculating => Some(RpcLargestAccountsFilter::NonCirculating),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccountWithBalance {
+    pub address: Pubkey,
+    pub lamports: u64,
+    pub sol: f64,
+}
+
+// Last `getLargestAccounts` answer per filter
+pub(crate) type LargestAccountsCache =
+    std::sync::Mutex<Vec<(LargestAccountsFilter, Instant, Vec<AccountWithBalance>)>>;
+
+impl SolanaBalanceChecker {
+    // Top SOL holders (the RPC returns at most 20), largest first
+    pub async fn get_largest_accounts(
+        &self,
+        filter: LargestAccountsFilter,
+        limit: usize,
+    ) -> Result<Vec<AccountWithBalance>, String> {
+        let config = RpcLargestAccountsConfig {
+            filter: filter.to_rpc(),
+            ..Default::default()
+        };
+        let response = self
+            .client
+            .get_largest_accounts_with_config(config)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        response
+            .value
+            .into_iter()
+            .take(limit)
+            .map(|account| {
+                let address = Pubkey::from_str(&account.address)
+                    .map_err(|e| format!("Invalid pubkey {}: {}", account.address, e))?;
+                Ok(AccountWithBalance {
+                    address,
+                    lamports: account.lamports,
+                    sol: Self::lamports_to_sol(account.lamports),
+                })
+            })
+            .collect()
+    }
+
+    // Same as `get_largest_accounts`, reusing a result younger than 60 seconds
+    pub async fn get_largest_accounts_cached(
+        &self,
+        filter: LargestAccountsFilter,
+        limit: usize,
+    ) -> Result<Vec<AccountWithBalance>, String> {
+        if let Some(accounts) = self.cached_largest_accounts(filter, limit) {
+            return Ok(accounts);
+        }
+
+        // Fetch the full list so any later limit can be served from the cache
+        let accounts = self.get_largest_accounts(filter, usize::MAX).await?;
+        let mut cache = self.largest_accounts_cache.lock().unwrap();
+        cache.retain(|(cached, _, _)| *cached != filter);
+        cache.push((filter, Instant::now(), accounts.clone()));
+
+        Ok(accounts.into_iter().take(limit).collect())
+    }
+
+    fn cached_largest_accounts(
+        &self,
+        filter: LargestAccountsFilter,
+        limit: usize,
+    ) -> Option<Vec<AccountWithBalance>> {
+        let cache = self.largest_accounts_cache.lock().unwrap();
+        cache
+            .iter()
+            .find(|(cached, fetched_at, _)| {
+                *cached == filter && fetched_at.elapsed() < LARGEST_ACCOUNTS_CACHE_TTL
+            })
+            .map(|(_, _, accounts)| accounts.iter().take(limit).cloned().collect())
+    }
+}
+
+pub fn print_largest_accounts(filter: LargestAccountsFilter, accounts: &[AccountWithBalance]) {
+    println!(
+        "=== Largest Accounts ({}) ===\n",
+        filter
+            .to_possible_value()
+            .map_or("all".to_string(), |v| v.get_name().to_string())
+    );
+
+    for (rank, account) in accounts.iter().enumerate() {
+        println!("{:>2}. {}", rank + 1, account.address);
+        println!(
+            "    Balance: {} lamports ({:.9} SOL)",
+            account.lamports, account.sol
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_serves_fresh_entries_only() {
+        let checker = SolanaBalanceChecker::new("http://127.0.0.1:8899".to_string());
+        let accounts: Vec<AccountWithBalance> = (1..=3)
+            .map(|sol| AccountWithBalance {
+                address: Pubkey::new_unique(),
+                lamports: sol * 1_000_000_000,
+                sol: sol as f64,
+            })
+            .collect();
+
+        checker.largest_accounts_cache.lock().unwrap().push((
+            LargestAccountsFilter::Circulating,
+            Instant::now(),
+            accounts.clone(),
+        ));
+
+        assert_eq!(
+            checker.cached_largest_accounts(LargestAccountsFilter::Circulating, 2),
+            Some(accounts[..2].to_vec())
+        );
+        assert!(
+            checker
+                .cached_largest_accounts(LargestAccountsFilter::All, 2)
+                .is_none()
+        );
+
+        let stale = Instant::now()
+            .checked_sub(LARGEST_ACCOUNTS_CACHE_TTL + Duration::from_secs(1))
+            .unwrap();
+        checker.largest_accounts_cache.lock().unwrap()[0].1 = stale;
+        assert!(
+            checker
+                .cached_largest_accounts(LargestAccountsFilter::Circulating, 2)
+                .is_none()
+        );
+    }
+}
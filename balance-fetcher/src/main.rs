@@ -1,3 +1,4 @@
+use clap::{CommandFactory, Parser, Subcommand};
 use futures::future::join_all;
 use serde::Deserialize;
 use solana_client::nonblocking::rpc_client::RpcClient;
@@ -6,39 +7,199 @@ use std::collections::HashMap;
 use std::fs;
 use std::str::FromStr;
 
+mod blocks;
+mod config_migration;
+mod largest_accounts;
+mod performance;
+mod price;
+mod ranking;
+mod reconcile;
+
+use blocks::BlockCommitment;
+use largest_accounts::{LargestAccountsCache, LargestAccountsFilter};
+use price::{CoinGeckoOracle, PriceOracle};
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Fetch Solana wallet balances from config.yaml")]
+struct Cli {
+    /// Config file to read
+    #[arg(long, value_name = "PATH", default_value = "config.yaml")]
+    config: String,
+
+    /// Also write the balances to this CSV file
+    #[arg(long, value_name = "PATH")]
+    output: Option<String>,
+
+    /// Print a shell completion script to stdout and exit
+    #[arg(long, value_name = "SHELL")]
+    completions: Option<clap_complete::Shell>,
+
+    /// Write a documented example config to PATH and exit
+    #[arg(long, value_name = "PATH")]
+    generate_config: Option<String>,
+
+    /// Also show balances in USD, priced via CoinGecko
+    #[arg(long)]
+    usd: bool,
+
+    /// Print the last 10 network performance samples (TPS, slot time) first
+    #[arg(long)]
+    perf: bool,
+
+    /// Also show the slot each balance was read at
+    #[arg(long)]
+    show_slot: bool,
+
+    /// Rank the configured wallets and print the N largest balances instead
+    #[arg(long, value_name = "N", conflicts_with = "bottom")]
+    top: Option<usize>,
+
+    /// Rank the configured wallets and print the N smallest balances instead, e.g.
+    /// to find dust worth consolidating
+    #[arg(long, value_name = "N")]
+    bottom: Option<usize>,
+
+    /// Load and check the config, then list what would be queried without
+    /// contacting the RPC node
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Log each RPC request and failure to stderr (same as `--log-level debug`)
+    #[arg(long, short, conflicts_with = "log_level")]
+    verbose: bool,
+
+    /// Log diagnostic events at this level and above to stderr: error, warn, info,
+    /// debug or trace [default: warn]
+    #[arg(long, value_name = "LEVEL")]
+    log_level: Option<tracing::Level>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+// Without a subcommand the configured wallets' balances are printed
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Show a block's hashes, parent, time, height and transaction count
+    BlockInfo {
+        /// Slot of the block
+        #[arg(required_unless_present = "latest", conflicts_with = "latest")]
+        slot: Option<u64>,
+        /// Use the newest block at the chosen commitment
+        #[arg(long)]
+        latest: bool,
+        /// Also list each transaction's signature, fee and error
+        #[arg(long)]
+        transactions: bool,
+        /// Commitment the block must have reached
+        #[arg(long, value_enum, default_value_t = BlockCommitment::Confirmed)]
+        commitment: BlockCommitment,
+    },
+    /// List the accounts holding the most SOL
+    LargestAccounts {
+        /// Restrict to circulating or non-circulating supply
+        #[arg(long, value_enum, default_value_t = LargestAccountsFilter::All)]
+        filter: LargestAccountsFilter,
+        /// Number of accounts to show (the RPC returns at most 20)
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Compare wallet balances with the expected ones from a CSV file
+    Reconcile {
+        /// CSV of `address,lamports` rows
+        file: String,
+        /// Difference in lamports, either way, still counted as a match
+        #[arg(long, default_value_t = 0)]
+        tolerance: u64,
+    },
+}
+
 #[derive(Debug, Deserialize)]
 struct Config {
-    solana_rpc_url: String,
+    // Layout version the file was written for; older layouts are migrated on load
+    #[serde(default = "default_config_version")]
+    config_version: u32,
+    rpc: RpcConfig,
     wallets: Vec<String>,
 }
 
+// Files from before `config_version` existed use the first layout
+fn default_config_version() -> u32 {
+    1
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcConfig {
+    url: String,
+}
+
+// Example config with every field documented; kept parseable by a test
+const CONFIG_TEMPLATE: &str = include_str!("config_template.yaml");
+
+impl Config {
+    // Write the documented example config, refusing to overwrite an existing file
+    fn generate_template(path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if std::path::Path::new(path).exists() {
+            return Err(format!("{} already exists", path).into());
+        }
+        fs::write(path, CONFIG_TEMPLATE)?;
+        Ok(())
+    }
+}
+
 pub struct SolanaBalanceChecker {
     client: RpcClient,
+    largest_accounts_cache: LargestAccountsCache,
 }
 
 impl SolanaBalanceChecker {
     pub fn new(rpc_url: String) -> Self {
         Self {
             client: RpcClient::new(rpc_url),
+            largest_accounts_cache: Default::default(),
         }
     }
 
+    // Balance in lamports and the slot it was read at. A later call can pass that
+    // slot as `minContextSlot` so a lagging node can't answer with an older state.
+    pub async fn get_balance_with_context(&self, pubkey: &Pubkey) -> Result<(u64, u64), String> {
+        tracing::debug!(%pubkey, "getBalance");
+        let response = self
+            .client
+            .get_balance_with_commitment(pubkey, self.client.commitment())
+            .await
+            .map_err(|e| {
+                tracing::warn!(%pubkey, error = %e, "getBalance failed");
+                e.to_string()
+            })?;
+        Ok((response.value, response.context.slot))
+    }
+
     pub async fn get_balances(
         &self,
         wallet_addresses: Vec<String>,
     ) -> HashMap<String, Result<u64, String>> {
+        self.get_balances_with_context(wallet_addresses)
+            .await
+            .into_iter()
+            .map(|(address, balance)| (address, balance.map(|(lamports, _)| lamports)))
+            .collect()
+    }
+
+    // Same as `get_balances`, with the slot each balance was read at
+    pub async fn get_balances_with_context(
+        &self,
+        wallet_addresses: Vec<String>,
+    ) -> HashMap<String, Result<(u64, u64), String>> {
         let tasks: Vec<_> = wallet_addresses
             .into_iter()
-            .map(|address| {
-                let client = &self.client;
-                async move {
-                    match Pubkey::from_str(&address) {
-                        Ok(pubkey) => match client.get_balance(&pubkey).await {
-                            Ok(balance) => (address, Ok(balance)),
-                            Err(e) => (address, Err(e.to_string())),
-                        },
-                        Err(e) => (address, Err(format!("Invalid pubkey: {}", e))),
+            .map(|address| async move {
+                match Pubkey::from_str(&address) {
+                    Ok(pubkey) => {
+                        let balance = self.get_balance_with_context(&pubkey).await;
+                        (address, balance)
                     }
+                    Err(e) => (address, Err(format!("Invalid pubkey: {}", e))),
                 }
             })
             .collect();
@@ -52,26 +213,263 @@ impl SolanaBalanceChecker {
     }
 }
 
+// One row per wallet: address, lamports, SOL, USD (with --usd) and any error
+fn balances_csv(
+    balances: &HashMap<String, Result<u64, String>>,
+    sol_price_usd: Option<f64>,
+) -> String {
+    let mut wallets: Vec<&String> = balances.keys().collect();
+    wallets.sort();
+
+    let mut csv = String::from("address,lamports,sol,usd,error\n");
+    for wallet in wallets {
+        let row = match &balances[wallet] {
+            Ok(lamports) => {
+                let sol = SolanaBalanceChecker::lamports_to_sol(*lamports);
+                let usd = sol_price_usd
+                    .map(|price| format!("{:.2}", price::sol_to_usd(sol, price)))
+                    .unwrap_or_default();
+                format!("{},{},{:.9},{},", wallet, lamports, sol, usd)
+            }
+            Err(error) => format!("{},,,,\"{}\"", wallet, error.replace('"', "\"\"")),
+        };
+        csv.push_str(&row);
+        csv.push('\n');
+    }
+    csv
+}
+
+// The name a value enum is given on the command line
+fn value_name(value: &impl clap::ValueEnum) -> String {
+    value
+        .to_possible_value()
+        .map_or_else(String::new, |value| value.get_name().to_string())
+}
+
+// What a real run with these flags would query, after checking every wallet
+// address parses. Nothing is sent to the RPC node.
+fn print_dry_run(cli: &Cli, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    println!("🔍 Dry run: nothing will be queried\n");
+    println!("Config: {} (version {})", cli.config, config.config_version);
+    println!("RPC: {}", config.rpc.url);
+
+    let query = match &cli.command {
+        Some(Command::BlockInfo {
+            slot: Some(slot), ..
+        }) => format!("block at slot {}", slot),
+        Some(Command::BlockInfo { commitment, .. }) => {
+            format!("latest {} block", value_name(commitment))
+        }
+        Some(Command::LargestAccounts { filter, limit }) => {
+            format!("{} largest accounts ({})", limit, value_name(filter))
+        }
+        Some(Command::Reconcile { file, .. }) => {
+            let expected = reconcile::load_expected_balances(file)?;
+            format!("{} balance(s) listed in {}", expected.len(), file)
+        }
+        None => match (cli.top, cli.bottom) {
+            (Some(n), _) => format!(
+                "balances of {} wallet(s), top {} shown",
+                config.wallets.len(),
+                n
+            ),
+            (_, Some(n)) => format!(
+                "balances of {} wallet(s), bottom {} shown",
+                config.wallets.len(),
+                n
+            ),
+            _ => format!("balances of {} wallet(s)", config.wallets.len()),
+        },
+    };
+    println!("Query: {}", query);
+    if cli.perf {
+        println!(
+            "Also: last {} performance samples",
+            performance::PERF_SAMPLE_LIMIT
+        );
+    }
+    if cli.usd {
+        println!("Also: SOL price from CoinGecko");
+    }
+
+    let invalid: Vec<&String> = config
+        .wallets
+        .iter()
+        .filter(|wallet| Pubkey::from_str(wallet).is_err())
+        .collect();
+    for wallet in &invalid {
+        println!("❌ Invalid wallet address: {}", wallet);
+    }
+    if !invalid.is_empty() {
+        return Err(format!(
+            "{} invalid wallet address(es) in {}",
+            invalid.len(),
+            cli.config
+        )
+        .into());
+    }
+    Ok(())
+}
+
 fn load_config(path: &str) -> Result<Config, Box<dyn std::error::Error>> {
     let contents = fs::read_to_string(path)?;
-    let config: Config = serde_yaml::from_str(&contents)?;
+    let config: Config = config_migration::parse_config(&contents)?;
+    if config.config_version < config_migration::CURRENT_CONFIG_VERSION {
+        println!(
+            "Warning: {} uses config version {}; it was migrated to version {} on load. \
+             See --generate-config for the current layout.",
+            path,
+            config.config_version,
+            config_migration::CURRENT_CONFIG_VERSION
+        );
+    }
     Ok(config)
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let config = load_config("config.yaml")?;
-    let balance_checker = SolanaBalanceChecker::new(config.solana_rpc_url);
-    let balances = balance_checker.get_balances(config.wallets).await;
+    let cli = Cli::parse();
+    if let Some(shell) = cli.completions {
+        clap_complete::generate(
+            shell,
+            &mut Cli::command(),
+            env!("CARGO_PKG_NAME"),
+            &mut std::io::stdout(),
+        );
+        return Ok(());
+    }
+    if let Some(path) = &cli.generate_config {
+        Config::generate_template(path)?;
+        println!("Example config written to {}", path);
+        return Ok(());
+    }
+
+    let log_level = match (cli.log_level, cli.verbose) {
+        (Some(level), _) => level,
+        (None, true) => tracing::Level::DEBUG,
+        (None, false) => tracing::Level::WARN,
+    };
+    tracing_subscriber::fmt()
+        .with_max_level(log_level)
+        .with_writer(std::io::stderr)
+        .init();
+
+    let config = load_config(&cli.config)?;
+    if cli.dry_run {
+        return print_dry_run(&cli, &config);
+    }
+    let balance_checker = SolanaBalanceChecker::new(config.rpc.url);
+
+    match cli.command {
+        Some(Command::BlockInfo {
+            slot,
+            latest: _,
+            transactions,
+            commitment,
+        }) => {
+            let slot = match slot {
+                Some(slot) => slot,
+                None => balance_checker.get_latest_slot(commitment).await?,
+            };
+            let block = balance_checker
+                .get_block(slot, transactions, commitment)
+                .await?;
+            blocks::print_block(&block);
+            return Ok(());
+        }
+        Some(Command::LargestAccounts { filter, limit }) => {
+            let accounts = balance_checker
+                .get_largest_accounts_cached(filter, limit)
+                .await?;
+            largest_accounts::print_largest_accounts(filter, &accounts);
+            return Ok(());
+        }
+        Some(Command::Reconcile { file, tolerance }) => {
+            let expected = reconcile::load_expected_balances(&file)?;
+            let report = balance_checker.compare_balances(expected, tolerance).await;
+            reconcile::print_report(&report);
+            if report.errors() > 0 {
+                return Err(format!("{} balance(s) could not be fetched", report.errors()).into());
+            }
+            return Ok(());
+        }
+        None => {}
+    }
+
+    // Network health is informational; a failure doesn't stop the balance check
+    if cli.perf {
+        match balance_checker
+            .get_recent_performance_samples(performance::PERF_SAMPLE_LIMIT)
+            .await
+        {
+            Ok(samples) => performance::print_performance_samples(&samples),
+            Err(e) => println!("⚠️  Could not fetch performance samples: {}\n", e),
+        }
+    }
+
+    if let Some(n) = cli.top {
+        let ranked = balance_checker.top_n_wallets(config.wallets, n).await?;
+        ranking::print_ranking(&format!("Top {} Wallets by Balance", n), &ranked);
+        return Ok(());
+    }
+    if let Some(n) = cli.bottom {
+        let ranked = balance_checker.bottom_n_wallets(config.wallets, n).await?;
+        ranking::print_ranking(&format!("Bottom {} Wallets by Balance", n), &ranked);
+        return Ok(());
+    }
+
+    let balances_with_context = balance_checker
+        .get_balances_with_context(config.wallets)
+        .await;
+    let slots: HashMap<String, u64> = balances_with_context
+        .iter()
+        .filter_map(|(wallet, balance)| Some((wallet.clone(), balance.as_ref().ok()?.1)))
+        .collect();
+    let balances: HashMap<String, Result<u64, String>> = balances_with_context
+        .into_iter()
+        .map(|(wallet, balance)| (wallet, balance.map(|(lamports, _)| lamports)))
+        .collect();
+
+    // A price lookup failure only drops the USD column
+    let sol_price_usd = if cli.usd {
+        match CoinGeckoOracle::new().get_sol_price_usd().await {
+            Ok(price) => Some(price),
+            Err(e) => {
+                println!("⚠️  Could not fetch the SOL price, showing SOL only: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
 
     println!("=== Solana Wallet Balances ===\n");
+    if let Some(price) = sol_price_usd {
+        println!("SOL price: ${:.2}\n", price);
+    }
+
+    if let Some(path) = &cli.output {
+        fs::write(path, balances_csv(&balances, sol_price_usd))?;
+        println!("Balances written to {}\n", path);
+    }
 
     for (wallet, balance_result) in balances {
         match balance_result {
             Ok(lamports) => {
                 let sol_balance = SolanaBalanceChecker::lamports_to_sol(lamports);
                 println!("Wallet: {}", wallet);
-                println!("Balance: {} lamports ({:.9} SOL)", lamports, sol_balance);
+                match sol_price_usd {
+                    Some(price) => println!(
+                        "Balance: {} lamports ({:.9} SOL, ${:.2})",
+                        lamports,
+                        sol_balance,
+                        price::sol_to_usd(sol_balance, price)
+                    ),
+                    None => println!("Balance: {} lamports ({:.9} SOL)", lamports, sol_balance),
+                }
+                if cli.show_slot {
+                    println!("Slot: {}", slots[&wallet]);
+                }
                 println!("---");
             }
             Err(error) => {
@@ -102,6 +500,54 @@ mod tests {
         assert!(!checker.client.url().is_empty());
     }
 
+    #[tokio::test]
+    async fn test_balances_carry_the_slot_they_were_read_at() {
+        let checker = SolanaBalanceChecker {
+            // The mock answers getBalance with 50 lamports at slot 1
+            client: RpcClient::new_mock("succeeds".to_string()),
+            largest_accounts_cache: Default::default(),
+        };
+        let wallet = Pubkey::new_unique().to_string();
+
+        let balances = checker
+            .get_balances_with_context(vec![wallet.clone(), "invalid".to_string()])
+            .await;
+        assert_eq!(balances[&wallet], Ok((50, 1)));
+        assert!(balances["invalid"].is_err());
+
+        assert_eq!(
+            checker.get_balances(vec![wallet.clone()]).await[&wallet],
+            Ok(50)
+        );
+    }
+
+    #[test]
+    fn test_config_template_parses() {
+        let config: Config = serde_yaml::from_str(CONFIG_TEMPLATE).unwrap();
+        assert_eq!(
+            config.config_version,
+            config_migration::CURRENT_CONFIG_VERSION
+        );
+        assert_eq!(config.wallets.len(), 2);
+    }
+
+    #[test]
+    fn test_balances_csv() {
+        let balances = HashMap::from([
+            (
+                "wallet-b".to_string(),
+                Err("Invalid pubkey: \"x\"".to_string()),
+            ),
+            ("wallet-a".to_string(), Ok(1_500_000_000)),
+        ]);
+        assert_eq!(
+            balances_csv(&balances, Some(100.0)),
+            "address,lamports,sol,usd,error\n\
+             wallet-a,1500000000,1.500000000,150.00,\n\
+             wallet-b,,,,\"Invalid pubkey: \"\"x\"\"\"\n"
+        );
+    }
+
     #[test]
     fn test_pubkey_validation() {
         assert!(Pubkey::from_str("9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM").is_ok());
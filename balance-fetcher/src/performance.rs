@@ -0,0 +1,118 @@
+use solana_client::rpc_response::RpcPerfSample;
+
+use crate::SolanaBalanceChecker;
+
+// Samples shown by `--perf`
+pub const PERF_SAMPLE_LIMIT: u32 = 10;
+
+// One `getRecentPerformanceSamples` entry: activity over `sample_period_secs`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PerfSample {
+    pub slot: u64,
+    pub num_transactions: u64,
+    // Not reported by older nodes
+    pub num_non_vote_transactions: Option<u64>,
+    pub num_slots: u64,
+    pub sample_period_secs: u16,
+}
+
+impl From<RpcPerfSample> for PerfSample {
+    fn from(sample: RpcPerfSample) -> Self {
+        Self {
+            slot: sample.slot,
+            num_transactions: sample.num_transactions,
+            num_non_vote_transactions: sample.num_non_vote_transactions,
+            num_slots: sample.num_slots,
+            sample_period_secs: sample.sample_period_secs,
+        }
+    }
+}
+
+impl PerfSample {
+    fn period_secs(&self) -> Option<f64> {
+        (self.sample_period_secs > 0).then_some(self.sample_period_secs as f64)
+    }
+
+    pub fn tps(&self) -> f64 {
+        self.period_secs()
+            .map_or(0.0, |secs| self.num_transactions as f64 / secs)
+    }
+
+    pub fn non_vote_tps(&self) -> Option<f64> {
+        self.num_non_vote_transactions
+            .map(|count| self.period_secs().map_or(0.0, |secs| count as f64 / secs))
+    }
+
+    pub fn avg_slot_time_ms(&self) -> f64 {
+        match self.num_slots {
+            0 => 0.0,
+            slots => self.sample_period_secs as f64 * 1000.0 / slots as f64,
+        }
+    }
+}
+
+impl SolanaBalanceChecker {
+    // Most recent performance samples (one per minute), newest first
+    pub async fn get_recent_performance_samples(
+        &self,
+        limit: u32,
+    ) -> Result<Vec<PerfSample>, String> {
+        let samples = self
+            .client
+            .get_recent_performance_samples(Some(limit as usize))
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(samples.into_iter().map(PerfSample::from).collect())
+    }
+}
+
+pub fn print_performance_samples(samples: &[PerfSample]) {
+    println!("=== Network Performance ===\n");
+    for sample in samples {
+        let non_vote_tps = sample
+            .non_vote_tps()
+            .map_or("-".to_string(), |tps| format!("{:.0}", tps));
+        println!(
+            "Slot {}: {:.0} TPS ({} non-vote), {:.0} ms/slot over {}s",
+            sample.slot,
+            sample.tps(),
+            non_vote_tps,
+            sample.avg_slot_time_ms(),
+            sample.sample_period_secs
+        );
+    }
+    println!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_rates() {
+        let sample = PerfSample {
+            slot: 300_000_000,
+            num_transactions: 240_000,
+            num_non_vote_transactions: Some(60_000),
+            num_slots: 150,
+            sample_period_secs: 60,
+        };
+        assert_eq!(sample.tps(), 4_000.0);
+        assert_eq!(sample.non_vote_tps(), Some(1_000.0));
+        assert_eq!(sample.avg_slot_time_ms(), 400.0);
+    }
+
+    #[test]
+    fn test_empty_sample_does_not_divide_by_zero() {
+        let sample = PerfSample {
+            slot: 1,
+            num_transactions: 0,
+            num_non_vote_transactions: None,
+            num_slots: 0,
+            sample_period_secs: 0,
+        };
+        assert_eq!(sample.tps(), 0.0);
+        assert_eq!(sample.non_vote_tps(), None);
+        assert_eq!(sample.avg_slot_time_ms(), 0.0);
+    }
+}
@@ -0,0 +1,112 @@
+use serde::Deserialize;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+pub const COINGECKO_SOL_PRICE_URL: &str =
+    "https://api.coingecko.com/api/v3/simple/price?ids=solana&vs_currencies=usd";
+
+// CoinGecko's free tier is rate limited, so one price is reused for this long
+pub const PRICE_CACHE_TTL: Duration = Duration::from_secs(60);
+
+// A source for the SOL/USD price, so CoinGecko can be swapped out (e.g. for an
+// on-chain Pyth feed)
+pub trait PriceOracle {
+    fn get_sol_price_usd(&self) -> impl Future<Output = Result<f64, String>> + Send;
+}
+
+#[derive(Debug, Deserialize)]
+struct SimplePriceResponse {
+    solana: UsdPrice,
+}
+
+#[derive(Debug, Deserialize)]
+struct UsdPrice {
+    usd: f64,
+}
+
+pub struct CoinGeckoOracle {
+    client: reqwest::Client,
+    url: String,
+    // Last price and when it was fetched
+    cache: Mutex<Option<(Instant, f64)>>,
+}
+
+impl CoinGeckoOracle {
+    pub fn new() -> Self {
+        Self::with_url(COINGECKO_SOL_PRICE_URL.to_string())
+    }
+
+    pub fn with_url(url: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url,
+            cache: Mutex::new(None),
+        }
+    }
+
+    fn cached_price(&self) -> Option<f64> {
+        self.cache
+            .lock()
+            .unwrap()
+            .filter(|(fetched_at, _)| fetched_at.elapsed() < PRICE_CACHE_TTL)
+            .map(|(_, price)| price)
+    }
+}
+
+impl Default for CoinGeckoOracle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PriceOracle for CoinGeckoOracle {
+    async fn get_sol_price_usd(&self) -> Result<f64, String> {
+        if let Some(price) = self.cached_price() {
+            return Ok(price);
+        }
+
+        let response: SimplePriceResponse = self
+            .client
+            .get(&self.url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| format!("CoinGecko request failed: {}", e))?
+            .json()
+            .await
+            .map_err(|e| format!("Unexpected CoinGecko response: {}", e))?;
+
+        let price = response.solana.usd;
+        *self.cache.lock().unwrap() = Some((Instant::now(), price));
+        Ok(price)
+    }
+}
+
+pub fn sol_to_usd(sol: f64, price_usd: f64) -> f64 {
+    sol * price_usd
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cached_price_skips_the_request() {
+        // Nothing listens here, so only a cache hit can succeed
+        let oracle = CoinGeckoOracle::with_url("http://127.0.0.1:9/price".to_string());
+        *oracle.cache.lock().unwrap() = Some((Instant::now(), 150.0));
+        assert_eq!(oracle.get_sol_price_usd().await, Ok(150.0));
+
+        let stale = Instant::now()
+            .checked_sub(PRICE_CACHE_TTL + Duration::from_secs(1))
+            .unwrap();
+        *oracle.cache.lock().unwrap() = Some((stale, 150.0));
+        assert!(oracle.get_sol_price_usd().await.is_err());
+    }
+
+    #[test]
+    fn test_sol_to_usd() {
+        assert_eq!(sol_to_usd(2.5, 100.0), 250.0);
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+
+use crate::SolanaBalanceChecker;
+
+impl SolanaBalanceChecker {
+    // The `n` wallets holding the most lamports, largest first. Wallets whose
+    // balance couldn't be fetched are left out.
+    pub async fn top_n_wallets(
+        &self,
+        wallet_addresses: Vec<String>,
+        n: usize,
+    ) -> Result<Vec<(String, u64)>, String> {
+        let balances = self.get_balances(wallet_addresses).await;
+        rank(balances, n, true)
+    }
+
+    // The `n` wallets holding the fewest lamports, smallest first, e.g. dust worth
+    // consolidating. Wallets whose balance couldn't be fetched are left out.
+    pub async fn bottom_n_wallets(
+        &self,
+        wallet_addresses: Vec<String>,
+        n: usize,
+    ) -> Result<Vec<(String, u64)>, String> {
+        let balances = self.get_balances(wallet_addresses).await;
+        rank(balances, n, false)
+    }
+}
+
+// Fetched balances sorted by lamports (ties by address), cut to `n`. Fails only when
+// there were wallets but none of their balances could be fetched.
+fn rank(
+    balances: HashMap<String, Result<u64, String>>,
+    n: usize,
+    largest_first: bool,
+) -> Result<Vec<(String, u64)>, String> {
+    let wallets = balances.len();
+    let mut ranked: Vec<(String, u64)> = balances
+        .into_iter()
+        .filter_map(|(address, balance)| balance.ok().map(|lamports| (address, lamports)))
+        .collect();
+    if ranked.is_empty() && wallets > 0 {
+        return Err(format!(
+            "none of the {} wallet balances could be fetched",
+            wallets
+        ));
+    }
+
+    ranked.sort_by(|a, b| {
+        let by_balance = if largest_first {
+            b.1.cmp(&a.1)
+        } else {
+            a.1.cmp(&b.1)
+        };
+        by_balance.then_with(|| a.0.cmp(&b.0))
+    });
+    ranked.truncate(n);
+    Ok(ranked)
+}
+
+pub fn print_ranking(title: &str, ranked: &[(String, u64)]) {
+    println!("=== {} ===\n", title);
+
+    for (rank, (address, lamports)) in ranked.iter().enumerate() {
+        println!("{:>2}. {}", rank + 1, address);
+        println!(
+            "    Balance: {} lamports ({:.9} SOL)",
+            lamports,
+            SolanaBalanceChecker::lamports_to_sol(*lamports)
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn balances() -> HashMap<String, Result<u64, String>> {
+        HashMap::from([
+            ("wallet-a".to_string(), Ok(5_000)),
+            ("wallet-b".to_string(), Ok(90_000)),
+            ("wallet-c".to_string(), Err("Invalid pubkey".to_string())),
+            ("wallet-d".to_string(), Ok(5_000)),
+            ("wallet-e".to_string(), Ok(1)),
+        ])
+    }
+
+    fn addresses(ranked: &[(String, u64)]) -> Vec<&str> {
+        ranked.iter().map(|(address, _)| address.as_str()).collect()
+    }
+
+    #[test]
+    fn test_top_is_largest_first() {
+        let ranked = rank(balances(), 3, true).unwrap();
+        assert_eq!(addresses(&ranked), ["wallet-b", "wallet-a", "wallet-d"]);
+        assert_eq!(ranked[0].1, 90_000);
+    }
+
+    #[test]
+    fn test_bottom_is_smallest_first() {
+        let ranked = rank(balances(), 10, false).unwrap();
+        assert_eq!(
+            addresses(&ranked),
+            ["wallet-e", "wallet-a", "wallet-d", "wallet-b"]
+        );
+    }
+
+    #[test]
+    fn test_only_failures_is_an_error() {
+        let failed = HashMap::from([("wallet-c".to_string(), Err("timeout".to_string()))]);
+        assert!(rank(failed, 5, true).is_err());
+        assert_eq!(rank(HashMap::new(), 5, true).unwrap(), []);
+    }
+}
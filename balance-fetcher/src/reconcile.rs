@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+use std::fs;
+
+use crate::SolanaBalanceChecker;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReconciliationStatus {
+    // Within the tolerance of the expected balance
+    Match,
+    Shortfall { deficit: u64 },
+    Excess { surplus: u64 },
+    // The balance couldn't be fetched
+    Error(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WalletReconciliation {
+    pub address: String,
+    pub expected: u64,
+    pub actual: Option<u64>,
+    pub status: ReconciliationStatus,
+}
+
+// One entry per wallet, sorted by address, plus the totals over all of them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReconciliationReport {
+    pub wallets: Vec<WalletReconciliation>,
+    pub total_shortfall: u64,
+    pub total_excess: u64,
+}
+
+impl ReconciliationReport {
+    // Compare fetched balances with the expected ones; differences up to
+    // `tolerance_lamports` either way count as a match
+    pub fn build(
+        expected: &HashMap<String, u64>,
+        balances: &HashMap<String, Result<u64, String>>,
+        tolerance_lamports: u64,
+    ) -> Self {
+        let mut wallets: Vec<WalletReconciliation> = expected
+            .iter()
+            .map(|(address, expected)| {
+                let (actual, status) = match balances.get(address) {
+                    Some(Ok(actual)) => (
+                        Some(*actual),
+                        compare(*expected, *actual, tolerance_lamports),
+                    ),
+                    Some(Err(error)) => (None, ReconciliationStatus::Error(error.clone())),
+                    None => (
+                        None,
+                        ReconciliationStatus::Error("no balance returned".to_string()),
+                    ),
+                };
+                WalletReconciliation {
+                    address: address.clone(),
+                    expected: *expected,
+                    actual,
+                    status,
+                }
+            })
+            .collect();
+        wallets.sort_by(|a, b| a.address.cmp(&b.address));
+
+        let total = |amount: fn(&ReconciliationStatus) -> u64| -> u64 {
+            wallets.iter().map(|wallet| amount(&wallet.status)).sum()
+        };
+        let total_shortfall = total(|status| match status {
+            ReconciliationStatus::Shortfall { deficit } => *deficit,
+            _ => 0,
+        });
+        let total_excess = total(|status| match status {
+            ReconciliationStatus::Excess { surplus } => *surplus,
+            _ => 0,
+        });
+
+        Self {
+            wallets,
+            total_shortfall,
+            total_excess,
+        }
+    }
+
+    pub fn errors(&self) -> usize {
+        self.wallets
+            .iter()
+            .filter(|wallet| matches!(wallet.status, ReconciliationStatus::Error(_)))
+            .count()
+    }
+}
+
+fn compare(expected: u64, actual: u64, tolerance_lamports: u64) -> ReconciliationStatus {
+    if actual.abs_diff(expected) <= tolerance_lamports {
+        ReconciliationStatus::Match
+    } else if actual < expected {
+        ReconciliationStatus::Shortfall {
+            deficit: expected - actual,
+        }
+    } else {
+        ReconciliationStatus::Excess {
+            surplus: actual - expected,
+        }
+    }
+}
+
+impl SolanaBalanceChecker {
+    // Fetch the balance of every wallet in `expected` and compare it to the expected value
+    pub async fn compare_balances(
+        &self,
+        expected: HashMap<String, u64>,
+        tolerance_lamports: u64,
+    ) -> ReconciliationReport {
+        let balances = self.get_balances(expected.keys().cloned().collect()).await;
+        ReconciliationReport::build(&expected, &balances, tolerance_lamports)
+    }
+}
+
+// Expected balances from `address,lamports` rows. A header row, blank lines and
+// `#` comments are skipped; every bad row is reported.
+pub fn parse_expected_balances(contents: &str) -> Result<HashMap<String, u64>, Vec<String>> {
+    let mut expected = HashMap::new();
+    let mut errors = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || (index == 0 && line.starts_with("address")) {
+            continue;
+        }
+        let row = index + 1;
+        let Some((address, lamports)) = line.split_once(',') else {
+            errors.push(format!("row {}: expected `address,lamports`", row));
+            continue;
+        };
+        match lamports.trim().parse::<u64>() {
+            Ok(lamports) => {
+                if expected
+                    .insert(address.trim().to_string(), lamports)
+                    .is_some()
+                {
+                    errors.push(format!("row {}: {} listed twice", row, address.trim()));
+                }
+            }
+            Err(e) => errors.push(format!(
+                "row {}: invalid lamports '{}': {}",
+                row, lamports, e
+            )),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(expected)
+    } else {
+        Err(errors)
+    }
+}
+
+pub fn load_expected_balances(
+    path: &str,
+) -> Result<HashMap<String, u64>, Box<dyn std::error::Error>> {
+    let contents = fs::read_to_string(path)?;
+    parse_expected_balances(&contents).map_err(|errors| {
+        format!(
+            "{} invalid row(s) in {}:\n  {}",
+            errors.len(),
+            path,
+            errors.join("\n  ")
+        )
+        .into()
+    })
+}
+
+pub fn print_report(report: &ReconciliationReport) {
+    println!("=== Balance Reconciliation ===\n");
+    for wallet in &report.wallets {
+        let status = match &wallet.status {
+            ReconciliationStatus::Match => "✅ match".to_string(),
+            ReconciliationStatus::Shortfall { deficit } => {
+                format!("❌ short by {} lamports", deficit)
+            }
+            ReconciliationStatus::Excess { surplus } => {
+                format!("⚠️  over by {} lamports", surplus)
+            }
+            ReconciliationStatus::Error(error) => format!("❌ error: {}", error),
+        };
+        println!("Wallet: {}", wallet.address);
+        println!(
+            "Expected: {} lamports, actual: {}",
+            wallet.expected,
+            wallet
+                .actual
+                .map_or("-".to_string(), |actual| format!("{} lamports", actual))
+        );
+        println!("Status: {}", status);
+        println!("---");
+    }
+    println!(
+        "Total shortfall: {} lamports ({:.9} SOL)",
+        report.total_shortfall,
+        SolanaBalanceChecker::lamports_to_sol(report.total_shortfall)
+    );
+    println!(
+        "Total excess: {} lamports ({:.9} SOL)",
+        report.total_excess,
+        SolanaBalanceChecker::lamports_to_sol(report.total_excess)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_categorises_wallets() {
+        let expected = HashMap::from([
+            ("a".to_string(), 1_000),
+            ("b".to_string(), 1_000),
+            ("c".to_string(), 1_000),
+            ("d".to_string(), 1_000),
+        ]);
+        let balances = HashMap::from([
+            ("a".to_string(), Ok(1_005)),
+            ("b".to_string(), Ok(400)),
+            ("c".to_string(), Ok(3_000)),
+            ("d".to_string(), Err("timeout".to_string())),
+        ]);
+
+        let report = ReconciliationReport::build(&expected, &balances, 10);
+        let statuses: Vec<&ReconciliationStatus> =
+            report.wallets.iter().map(|wallet| &wallet.status).collect();
+        assert_eq!(
+            statuses,
+            [
+                &ReconciliationStatus::Match,
+                &ReconciliationStatus::Shortfall { deficit: 600 },
+                &ReconciliationStatus::Excess { surplus: 2_000 },
+                &ReconciliationStatus::Error("timeout".to_string()),
+            ]
+        );
+        assert_eq!(report.total_shortfall, 600);
+        assert_eq!(report.total_excess, 2_000);
+        assert_eq!(report.errors(), 1);
+    }
+
+    #[test]
+    fn test_parse_expected_balances() {
+        let expected =
+            parse_expected_balances("address,lamports\n# treasury\nwallet-a, 1500\n\nwallet-b,0\n")
+                .unwrap();
+        assert_eq!(expected["wallet-a"], 1_500);
+        assert_eq!(expected.len(), 2);
+
+        let errors = parse_expected_balances("wallet-a\nwallet-b,1.5\nwallet-c,1\nwallet-c,2\n")
+            .unwrap_err();
+        assert_eq!(errors.len(), 3);
+        assert!(errors[0].starts_with("row 1"));
+    }
+}
@@ -0,0 +1,163 @@
+//! Versioned YAML configs for the workspace's binaries. Each binary lists the
+//! migrations between its own layouts; this crate works out a file's version,
+//! applies the migrations it's missing and parses the result.
+
+use serde::de::DeserializeOwned;
+use serde_yaml::{Mapping, Value};
+use std::fmt;
+
+/// Upgrades a raw config by one layout version
+pub type Migration = fn(Value) -> Value;
+
+/// A binary's migrations in order: `steps[i]` upgrades a version `i + 1` config
+/// to version `i + 2`. Files from before `config_version` existed are version 1.
+pub struct Migrations {
+    steps: &'static [Migration],
+}
+
+/// Why a config file couldn't be read
+#[derive(Debug)]
+pub enum Error {
+    Yaml(serde_yaml::Error),
+    InvalidVersion(String),
+    TooNew { version: u32, supported: u32 },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Yaml(e) => e.fmt(f),
+            Self::InvalidVersion(version) => write!(f, "invalid config_version: {}", version),
+            Self::TooNew { version, supported } => write!(
+                f,
+                "config_version {} is newer than this build supports ({})",
+                version, supported
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<serde_yaml::Error> for Error {
+    fn from(e: serde_yaml::Error) -> Self {
+        Self::Yaml(e)
+    }
+}
+
+impl Migrations {
+    pub const fn new(steps: &'static [Migration]) -> Self {
+        Self { steps }
+    }
+
+    /// Layout version written by `--generate-config`; older files are migrated on load
+    pub const fn current_version(&self) -> u32 {
+        self.steps.len() as u32 + 1
+    }
+
+    /// Bring a raw config from `from_version` up to the current layout, one version
+    /// at a time. `config_version` itself is left alone so the loader can tell the
+    /// file is old.
+    pub fn migrate(&self, raw: Value, from_version: u32) -> Value {
+        self.steps
+            .iter()
+            .skip(from_version.saturating_sub(1) as usize)
+            .fold(raw, |raw, migrate| migrate(raw))
+    }
+
+    /// Parse a config file of any supported version into the current layout
+    pub fn parse<T: DeserializeOwned>(&self, contents: &str) -> Result<T, Error> {
+        let raw: Value = serde_yaml::from_str(contents)?;
+        let version = config_version(&raw)?;
+        if version > self.current_version() {
+            return Err(Error::TooNew {
+                version,
+                supported: self.current_version(),
+            });
+        }
+        Ok(serde_yaml::from_value(self.migrate(raw, version))?)
+    }
+}
+
+// `config_version` of a raw config; files from before versioning are version 1
+fn config_version(raw: &Value) -> Result<u32, Error> {
+    match raw.get("config_version") {
+        None => Ok(1),
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| Error::InvalidVersion(format!("{:?}", version))),
+    }
+}
+
+/// v1 -> v2 of balance-fetcher and sol-transfer: `solana_rpc_url` became `rpc.url`
+pub fn nest_rpc_url(mut raw: Value) -> Value {
+    let Value::Mapping(config) = &mut raw else {
+        return raw;
+    };
+    if let Some(url) = config.remove("solana_rpc_url") {
+        let rpc = config
+            .entry(Value::from("rpc"))
+            .or_insert_with(|| Value::Mapping(Mapping::new()));
+        if let Value::Mapping(rpc) = rpc {
+            rpc.entry(Value::from("url")).or_insert(url);
+        }
+    }
+    raw
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    const MIGRATIONS: Migrations = Migrations::new(&[nest_rpc_url]);
+
+    #[derive(Debug, Deserialize)]
+    struct Config {
+        #[serde(default)]
+        config_version: Option<u32>,
+        rpc: Rpc,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Rpc {
+        url: String,
+    }
+
+    #[test]
+    fn test_v1_rpc_url_moves_under_rpc() {
+        let raw: Value =
+            serde_yaml::from_str("solana_rpc_url: \"http://127.0.0.1:8899\"\n").unwrap();
+        let migrated = MIGRATIONS.migrate(raw, 1);
+
+        assert!(migrated.get("solana_rpc_url").is_none());
+        assert_eq!(migrated["rpc"]["url"], Value::from("http://127.0.0.1:8899"));
+    }
+
+    #[test]
+    fn test_unversioned_file_is_migrated_from_version_one() {
+        let config: Config = MIGRATIONS
+            .parse("solana_rpc_url: \"http://127.0.0.1:8899\"\n")
+            .unwrap();
+        assert_eq!(config.config_version, None);
+        assert_eq!(config.rpc.url, "http://127.0.0.1:8899");
+    }
+
+    #[test]
+    fn test_current_version_is_untouched() {
+        let raw: Value =
+            serde_yaml::from_str("config_version: 2\nrpc:\n  url: \"http://127.0.0.1:8899\"\n")
+                .unwrap();
+        assert_eq!(MIGRATIONS.current_version(), 2);
+        assert_eq!(MIGRATIONS.migrate(raw.clone(), 2), raw);
+    }
+
+    #[test]
+    fn test_bad_versions_are_rejected() {
+        let newer = MIGRATIONS.parse::<Config>("config_version: 3\n");
+        assert!(newer.unwrap_err().to_string().contains("newer"));
+        let invalid = MIGRATIONS.parse::<Config>("config_version: two\n");
+        assert!(matches!(invalid, Err(Error::InvalidVersion(_))));
+    }
+}
@@ -0,0 +1,232 @@
+use {
+    serde::{Deserialize, Serialize},
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey},
+    std::{
+        collections::HashMap,
+        str::FromStr,
+        time::{Duration, Instant},
+    },
+    tracing::{info, warn},
+};
+
+// Most addresses `getMultipleAccounts` accepts in one call
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// An address whose SOL balance is tracked between blocks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchedAccount {
+    pub address: String,
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Alert when a single flush sees the balance move by at least this much
+    #[serde(default)]
+    pub alert_threshold_lamports: Option<u64>,
+}
+
+impl WatchedAccount {
+    fn name(&self) -> &str {
+        self.label.as_deref().unwrap_or(&self.address)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountWatchConfig {
+    /// Solana RPC endpoint used to read balances
+    pub rpc_url: String,
+    pub accounts: Vec<WatchedAccount>,
+    /// Check balances after this many blocks...
+    #[serde(default = "default_flush_every_blocks")]
+    pub flush_every_blocks: u64,
+    /// ...or once this many seconds have passed, whichever comes first
+    #[serde(default = "default_flush_interval_secs")]
+    pub flush_interval_secs: u64,
+}
+
+fn default_flush_every_blocks() -> u64 {
+    10
+}
+
+fn default_flush_interval_secs() -> u64 {
+    30
+}
+
+/// A balance difference seen between two flushes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BalanceChange {
+    pub address: String,
+    pub previous: u64,
+    pub current: u64,
+}
+
+impl BalanceChange {
+    pub fn delta(&self) -> i128 {
+        self.current as i128 - self.previous as i128
+    }
+}
+
+// Balances that differ from the last known ones; first sightings only set the baseline
+fn diff_balances(
+    last_known: &mut HashMap<String, u64>,
+    current: impl IntoIterator<Item = (String, u64)>,
+) -> Vec<BalanceChange> {
+    let mut changes = Vec::new();
+
+    for (address, balance) in current {
+        match last_known.insert(address.clone(), balance) {
+            Some(previous) if previous != balance => changes.push(BalanceChange {
+                address,
+                previous,
+                current: balance,
+            }),
+            _ => {}
+        }
+    }
+
+    changes
+}
+
+/// Watches a set of accounts for SOL balance changes, reading them in one batch
+/// every few blocks rather than on every block update
+pub struct AccountChangeDetector {
+    rpc: RpcClient,
+    accounts: Vec<(Pubkey, WatchedAccount)>,
+    last_known: HashMap<String, u64>,
+    flush_every_blocks: u64,
+    flush_interval: Duration,
+    blocks_since_flush: u64,
+    last_flush: Instant,
+}
+
+impl AccountChangeDetector {
+    pub fn new(config: &AccountWatchConfig) -> anyhow::Result<Self> {
+        let accounts = config
+            .accounts
+            .iter()
+            .map(|account| {
+                let pubkey = Pubkey::from_str(&account.address).map_err(|e| {
+                    anyhow::anyhow!("invalid watched address {}: {}", account.address, e)
+                })?;
+                Ok((pubkey, account.clone()))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(Self {
+            rpc: RpcClient::new_with_commitment(
+                config.rpc_url.clone(),
+                CommitmentConfig::confirmed(),
+            ),
+            accounts,
+            last_known: HashMap::new(),
+            flush_every_blocks: config.flush_every_blocks.max(1),
+            flush_interval: Duration::from_secs(config.flush_interval_secs),
+            blocks_since_flush: 0,
+            last_flush: Instant::now(),
+        })
+    }
+
+    /// Record a new block and check balances once enough blocks or time have passed
+    pub async fn on_block(&mut self, slot: u64) {
+        self.blocks_since_flush += 1;
+        if self.blocks_since_flush < self.flush_every_blocks
+            && self.last_flush.elapsed() < self.flush_interval
+        {
+            return;
+        }
+
+        self.blocks_since_flush = 0;
+        self.last_flush = Instant::now();
+
+        match self.flush().await {
+            Ok(changes) => self.report(slot, &changes),
+            Err(e) => warn!(error = %e, "failed to fetch watched balances"),
+        }
+    }
+
+    async fn fetch_balances(&self) -> anyhow::Result<Vec<(String, u64)>> {
+        let mut balances = Vec::with_capacity(self.accounts.len());
+
+        for chunk in self.accounts.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let pubkeys: Vec<Pubkey> = chunk.iter().map(|(pubkey, _)| *pubkey).collect();
+            let accounts = self.rpc.get_multiple_accounts(&pubkeys).await?;
+
+            balances.extend(chunk.iter().zip(accounts).map(|((_, watched), account)| {
+                (
+                    watched.address.clone(),
+                    account.map_or(0, |account| account.lamports),
+                )
+            }));
+        }
+
+        Ok(balances)
+    }
+
+    async fn flush(&mut self) -> anyhow::Result<Vec<BalanceChange>> {
+        let balances = self.fetch_balances().await?;
+        Ok(diff_balances(&mut self.last_known, balances))
+    }
+
+    fn report(&self, slot: u64, changes: &[BalanceChange]) {
+        for change in changes {
+            let Some((_, watched)) = self
+                .accounts
+                .iter()
+                .find(|(_, watched)| watched.address == change.address)
+            else {
+                continue;
+            };
+
+            info!(
+                slot,
+                account = %watched.name(),
+                pubkey = %change.address,
+                previous = change.previous,
+                current = change.current,
+                delta = %format!("{:+}", change.delta()),
+                "balance change"
+            );
+
+            if let Some(threshold) = watched.alert_threshold_lamports
+                && change.delta().unsigned_abs() >= threshold as u128
+            {
+                warn!(
+                    account = %watched.name(),
+                    pubkey = %change.address,
+                    delta = %format!("{:+}", change.delta()),
+                    threshold,
+                    "balance alert: change over threshold"
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_balances_sets_baseline_then_reports_changes() {
+        let mut last_known = HashMap::new();
+
+        let first = diff_balances(
+            &mut last_known,
+            [("a".to_string(), 100), ("b".to_string(), 5)],
+        );
+        assert!(first.is_empty());
+
+        let second = diff_balances(
+            &mut last_known,
+            [("a".to_string(), 40), ("b".to_string(), 5)],
+        );
+        assert_eq!(
+            second,
+            vec![BalanceChange {
+                address: "a".to_string(),
+                previous: 100,
+                current: 40,
+            }]
+        );
+        assert_eq!(second[0].delta(), -60);
+    }
+}
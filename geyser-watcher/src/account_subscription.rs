@@ -0,0 +1,242 @@
+use {
+    serde::{Deserialize, Serialize},
+    std::collections::HashMap,
+    tracing::info,
+    yellowstone_grpc_proto::geyser::{
+        SubscribeRequestAccountsDataSlice, SubscribeRequestFilterAccounts,
+        SubscribeRequestFilterAccountsFilter, SubscribeRequestFilterAccountsFilterMemcmp,
+        SubscribeUpdateAccount, subscribe_request_filter_accounts_filter::Filter,
+        subscribe_request_filter_accounts_filter_memcmp::Data,
+    },
+};
+
+// Leading bytes shown for account data over the display limit
+const DATA_PREVIEW_BYTES: usize = 16;
+
+/// Match accounts whose data has `bytes` (base58) at `offset`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemcmpFilter {
+    pub offset: u64,
+    pub bytes: String,
+}
+
+/// `length` bytes of account data starting at `offset`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataSlice {
+    pub offset: u64,
+    pub length: u64,
+}
+
+/// Accounts to stream updates for, mapped onto `SubscribeRequestFilterAccounts`.
+/// An account matches if it's listed in `pubkeys` or owned by one of `owners`, and
+/// passes every data filter.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountSubscriptionConfig {
+    #[serde(default)]
+    pub pubkeys: Vec<String>,
+    /// Program ids; every account they own is streamed
+    #[serde(default)]
+    pub owners: Vec<String>,
+    /// Only accounts with exactly this much data
+    #[serde(default)]
+    pub data_size: Option<u64>,
+    #[serde(default)]
+    pub memcmp: Vec<MemcmpFilter>,
+    /// Data longer than this is summarized as its length and first bytes
+    #[serde(default = "default_max_data_display_bytes")]
+    pub max_data_display_bytes: usize,
+    /// Send only these parts of each account's data instead of all of it. Applies
+    /// to every account update on the subscription.
+    #[serde(default)]
+    pub data_slice: Vec<DataSlice>,
+}
+
+fn default_max_data_display_bytes() -> usize {
+    64
+}
+
+impl Default for AccountSubscriptionConfig {
+    fn default() -> Self {
+        Self {
+            pubkeys: Vec::new(),
+            owners: Vec::new(),
+            data_size: None,
+            memcmp: Vec::new(),
+            max_data_display_bytes: default_max_data_display_bytes(),
+            data_slice: Vec::new(),
+        }
+    }
+}
+
+impl AccountSubscriptionConfig {
+    pub fn data_slices(&self) -> Vec<SubscribeRequestAccountsDataSlice> {
+        self.data_slice
+            .iter()
+            .map(|slice| SubscribeRequestAccountsDataSlice {
+                offset: slice.offset,
+                length: slice.length,
+            })
+            .collect()
+    }
+
+    pub fn filter(&self) -> SubscribeRequestFilterAccounts {
+        let mut filters: Vec<SubscribeRequestFilterAccountsFilter> = self
+            .memcmp
+            .iter()
+            .map(|memcmp| SubscribeRequestFilterAccountsFilter {
+                filter: Some(Filter::Memcmp(SubscribeRequestFilterAccountsFilterMemcmp {
+                    offset: memcmp.offset,
+                    data: Some(Data::Base58(memcmp.bytes.clone())),
+                })),
+            })
+            .collect();
+        if let Some(size) = self.data_size {
+            filters.push(SubscribeRequestFilterAccountsFilter {
+                filter: Some(Filter::Datasize(size)),
+            });
+        }
+
+        SubscribeRequestFilterAccounts {
+            account: self.pubkeys.clone(),
+            owner: self.owners.clone(),
+            filters,
+            nonempty_txn_signature: None,
+        }
+    }
+}
+
+/// What gets logged for one account update
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountUpdateSummary {
+    pub pubkey: String,
+    pub owner: String,
+    pub slot: u64,
+    pub lamports: u64,
+    /// Change since the previous update for this pubkey; `None` for the first one
+    pub lamports_delta: Option<i128>,
+    pub write_version: u64,
+    pub data: String,
+}
+
+impl AccountUpdateSummary {
+    pub fn log(&self) {
+        info!(
+            pubkey = %self.pubkey,
+            slot = self.slot,
+            lamports = self.lamports,
+            lamports_delta = ?self.lamports_delta,
+            owner = %self.owner,
+            write_version = self.write_version,
+            data = %self.data,
+            "account update"
+        );
+    }
+}
+
+/// Remembers each account's last balance to report deltas
+pub struct AccountUpdateTracker {
+    max_data_display_bytes: usize,
+    previous_lamports: HashMap<String, u64>,
+}
+
+impl AccountUpdateTracker {
+    pub fn new(config: &AccountSubscriptionConfig) -> Self {
+        Self {
+            max_data_display_bytes: config.max_data_display_bytes,
+            previous_lamports: HashMap::new(),
+        }
+    }
+
+    pub fn on_update(&mut self, update: &SubscribeUpdateAccount) -> Option<AccountUpdateSummary> {
+        let account = update.account.as_ref()?;
+        let pubkey = bs58::encode(&account.pubkey).into_string();
+        let lamports_delta = self
+            .previous_lamports
+            .insert(pubkey.clone(), account.lamports)
+            .map(|previous| account.lamports as i128 - previous as i128);
+
+        Some(AccountUpdateSummary {
+            pubkey,
+            owner: bs58::encode(&account.owner).into_string(),
+            slot: update.slot,
+            lamports: account.lamports,
+            lamports_delta,
+            write_version: account.write_version,
+            data: describe_data(&account.data, self.max_data_display_bytes),
+        })
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn describe_data(data: &[u8], max_display_bytes: usize) -> String {
+    if data.len() <= max_display_bytes {
+        format!("0x{}", hex(data))
+    } else {
+        format!(
+            "{} bytes, starting 0x{}…",
+            data.len(),
+            hex(&data[..DATA_PREVIEW_BYTES.min(data.len())])
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, yellowstone_grpc_proto::geyser::SubscribeUpdateAccountInfo};
+
+    fn update(lamports: u64, data: Vec<u8>) -> SubscribeUpdateAccount {
+        SubscribeUpdateAccount {
+            account: Some(SubscribeUpdateAccountInfo {
+                pubkey: vec![7; 32],
+                lamports,
+                owner: vec![0; 32],
+                data,
+                write_version: 3,
+                ..Default::default()
+            }),
+            slot: 10,
+            is_startup: false,
+        }
+    }
+
+    #[test]
+    fn test_filter_maps_config() {
+        let config = AccountSubscriptionConfig {
+            owners: vec!["TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA".to_string()],
+            data_size: Some(165),
+            memcmp: vec![MemcmpFilter {
+                offset: 32,
+                bytes: "11111111111111111111111111111111".to_string(),
+            }],
+            ..Default::default()
+        };
+
+        let filter = config.filter();
+        assert_eq!(filter.owner, config.owners);
+        assert_eq!(filter.filters.len(), 2);
+        assert_eq!(filter.filters[1].filter, Some(Filter::Datasize(165)));
+    }
+
+    #[test]
+    fn test_lamports_delta_per_pubkey() {
+        let mut tracker = AccountUpdateTracker::new(&AccountSubscriptionConfig::default());
+
+        let first = tracker.on_update(&update(1_000, vec![])).unwrap();
+        assert_eq!(first.lamports_delta, None);
+        let second = tracker.on_update(&update(400, vec![])).unwrap();
+        assert_eq!(second.lamports_delta, Some(-600));
+        assert_eq!(second.write_version, 3);
+    }
+
+    #[test]
+    fn test_large_data_is_summarized() {
+        assert_eq!(describe_data(&[0xab, 0x01], 4), "0xab01");
+        assert_eq!(
+            describe_data(&[0xff; 20], 4),
+            "20 bytes, starting 0xffffffffffffffffffffffffffffffff…"
+        );
+    }
+}
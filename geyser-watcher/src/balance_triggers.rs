@@ -0,0 +1,301 @@
+use {
+    crate::transfer_trigger::TransferSender,
+    serde::{Deserialize, Serialize},
+    solana_sdk::native_token::LAMPORTS_PER_SOL,
+    std::collections::HashMap,
+    tracing::{info, warn},
+};
+
+/// Balance movement a trigger reacts to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BalanceCondition {
+    #[serde(rename = "lamports_increase")]
+    Increase,
+    #[serde(rename = "lamports_decrease")]
+    Decrease,
+    #[serde(rename = "lamports_change")]
+    Change,
+}
+
+/// What a matching balance change does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BalanceAction {
+    /// Print the change
+    Log,
+    /// POST the change as JSON to `webhook_url`
+    Webhook,
+    /// Forward the change's amount from the sender to the recipient
+    Transfer,
+}
+
+/// Fire `action` when `account`'s balance moves as described by `on` by at least
+/// `min_delta_sol`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BalanceTriggerConfig {
+    pub account: String,
+    pub on: BalanceCondition,
+    #[serde(default)]
+    pub min_delta_sol: f64,
+    pub action: BalanceAction,
+    /// Required by the webhook action
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+}
+
+impl BalanceTriggerConfig {
+    fn matches(&self, old_lamports: u64, new_lamports: u64) -> bool {
+        let min_delta = (self.min_delta_sol * LAMPORTS_PER_SOL as f64) as u64;
+        let moved = match self.on {
+            BalanceCondition::Increase => new_lamports.checked_sub(old_lamports),
+            BalanceCondition::Decrease => old_lamports.checked_sub(new_lamports),
+            BalanceCondition::Change => Some(new_lamports.abs_diff(old_lamports)),
+        };
+        moved.is_some_and(|moved| moved > 0 && moved >= min_delta)
+    }
+}
+
+/// A balance change that matched a trigger; also the webhook payload
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BalanceChange {
+    pub pubkey: String,
+    pub old_lamports: u64,
+    pub new_lamports: u64,
+    pub slot: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct LastSeen {
+    lamports: u64,
+    slot: u64,
+    write_version: u64,
+}
+
+/// Compares account updates against each watched account's last balance and runs
+/// the actions of the triggers they match
+pub struct BalanceTriggers {
+    triggers: Vec<BalanceTriggerConfig>,
+    last_seen: HashMap<String, LastSeen>,
+    http: reqwest::Client,
+    transfer_sender: Option<TransferSender>,
+}
+
+impl BalanceTriggers {
+    /// `transfer_sender` is required when any trigger uses the transfer action
+    pub fn new(
+        triggers: Vec<BalanceTriggerConfig>,
+        transfer_sender: Option<TransferSender>,
+    ) -> anyhow::Result<Self> {
+        for trigger in &triggers {
+            bs58::decode(&trigger.account).into_vec().map_err(|e| {
+                anyhow::anyhow!("invalid trigger account {}: {}", trigger.account, e)
+            })?;
+            if trigger.action == BalanceAction::Webhook && trigger.webhook_url.is_none() {
+                anyhow::bail!("trigger for {} needs a webhook_url", trigger.account);
+            }
+            if trigger.action == BalanceAction::Transfer && transfer_sender.is_none() {
+                anyhow::bail!("trigger for {} needs a transfer sender", trigger.account);
+            }
+        }
+
+        Ok(Self {
+            triggers,
+            last_seen: HashMap::new(),
+            http: reqwest::Client::new(),
+            transfer_sender,
+        })
+    }
+
+    /// Watched accounts, to subscribe to
+    pub fn accounts(&self) -> Vec<String> {
+        let mut accounts: Vec<String> = self
+            .triggers
+            .iter()
+            .map(|trigger| trigger.account.clone())
+            .collect();
+        accounts.sort();
+        accounts.dedup();
+        accounts
+    }
+
+    /// Record an update and return the matching triggers with the change. The first
+    /// update for an account only sets its baseline; updates at or before the last
+    /// seen slot and write version are duplicates and ignored.
+    fn evaluate(
+        &mut self,
+        pubkey: &str,
+        lamports: u64,
+        slot: u64,
+        write_version: u64,
+    ) -> Vec<(&BalanceTriggerConfig, BalanceChange)> {
+        if !self
+            .triggers
+            .iter()
+            .any(|trigger| trigger.account == pubkey)
+        {
+            return Vec::new();
+        }
+
+        let current = LastSeen {
+            lamports,
+            slot,
+            write_version,
+        };
+        let previous = match self.last_seen.get(pubkey) {
+            Some(previous) if (slot, write_version) <= (previous.slot, previous.write_version) => {
+                return Vec::new();
+            }
+            previous => previous.copied(),
+        };
+        self.last_seen.insert(pubkey.to_string(), current);
+        let Some(previous) = previous else {
+            return Vec::new();
+        };
+
+        self.triggers
+            .iter()
+            .filter(|trigger| {
+                trigger.account == pubkey && trigger.matches(previous.lamports, lamports)
+            })
+            .map(|trigger| {
+                (
+                    trigger,
+                    BalanceChange {
+                        pubkey: pubkey.to_string(),
+                        old_lamports: previous.lamports,
+                        new_lamports: lamports,
+                        slot,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// Evaluate an account update and fire the actions it triggers. Webhooks and
+    /// transfers run on spawned tasks; their failures are only logged.
+    pub fn on_update(&mut self, pubkey: &str, lamports: u64, slot: u64, write_version: u64) {
+        let http = self.http.clone();
+        let mut transfers = Vec::new();
+        for (trigger, change) in self.evaluate(pubkey, lamports, slot, write_version) {
+            info!(
+                pubkey = %change.pubkey,
+                old_lamports = change.old_lamports,
+                new_lamports = change.new_lamports,
+                slot = change.slot,
+                on = ?trigger.on,
+                "balance trigger fired"
+            );
+            match trigger.action {
+                BalanceAction::Log => {}
+                BalanceAction::Webhook => {
+                    let url = trigger.webhook_url.clone().unwrap_or_default();
+                    let http = http.clone();
+                    tokio::spawn(async move {
+                        let result = http
+                            .post(&url)
+                            .json(&change)
+                            .send()
+                            .await
+                            .and_then(|response| response.error_for_status());
+                        if let Err(e) = result {
+                            warn!(url = %url, error = %e, "balance trigger webhook failed");
+                        }
+                    });
+                }
+                BalanceAction::Transfer => transfers.push(change),
+            }
+        }
+
+        if let Some(sender) = &self.transfer_sender {
+            for change in transfers {
+                sender.spawn(
+                    change.new_lamports.abs_diff(change.old_lamports),
+                    format!(
+                        "balance change of {} at slot {}",
+                        change.pubkey, change.slot
+                    ),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ACCOUNT: &str = "11111111111111111111111111111111";
+
+    fn triggers(on: BalanceCondition, min_delta_sol: f64) -> BalanceTriggers {
+        BalanceTriggers::new(
+            vec![BalanceTriggerConfig {
+                account: ACCOUNT.to_string(),
+                on,
+                min_delta_sol,
+                action: BalanceAction::Log,
+                webhook_url: None,
+            }],
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_first_update_sets_baseline() {
+        let mut triggers = triggers(BalanceCondition::Change, 0.0);
+        assert!(triggers.evaluate(ACCOUNT, 5_000, 10, 1).is_empty());
+
+        let fired = triggers.evaluate(ACCOUNT, 7_000, 11, 2);
+        assert_eq!(fired.len(), 1);
+        assert_eq!(
+            fired[0].1,
+            BalanceChange {
+                pubkey: ACCOUNT.to_string(),
+                old_lamports: 5_000,
+                new_lamports: 7_000,
+                slot: 11,
+            }
+        );
+    }
+
+    #[test]
+    fn test_duplicate_update_does_not_fire_twice() {
+        let mut triggers = triggers(BalanceCondition::Increase, 0.0);
+        triggers.evaluate(ACCOUNT, 5_000, 10, 1);
+        assert_eq!(triggers.evaluate(ACCOUNT, 9_000, 11, 2).len(), 1);
+        assert!(triggers.evaluate(ACCOUNT, 9_000, 11, 2).is_empty());
+        // Stale updates are ignored too
+        assert!(triggers.evaluate(ACCOUNT, 1_000, 10, 5).is_empty());
+    }
+
+    #[test]
+    fn test_condition_and_min_delta() {
+        let mut triggers = triggers(BalanceCondition::Increase, 0.1);
+        triggers.evaluate(ACCOUNT, LAMPORTS_PER_SOL, 10, 1);
+        // Too small, then a decrease, then big enough
+        assert!(
+            triggers
+                .evaluate(ACCOUNT, LAMPORTS_PER_SOL + 1_000, 11, 1)
+                .is_empty()
+        );
+        assert!(triggers.evaluate(ACCOUNT, 0, 12, 1).is_empty());
+        assert_eq!(
+            triggers
+                .evaluate(ACCOUNT, LAMPORTS_PER_SOL / 10, 13, 1)
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_webhook_needs_url() {
+        let config = BalanceTriggerConfig {
+            account: ACCOUNT.to_string(),
+            on: BalanceCondition::Change,
+            min_delta_sol: 0.0,
+            action: BalanceAction::Webhook,
+            webhook_url: None,
+        };
+        assert!(BalanceTriggers::new(vec![config], None).is_err());
+    }
+}
@@ -0,0 +1,184 @@
+use {
+    serde::{Deserialize, Serialize},
+    tracing::Level,
+    yellowstone_grpc_proto::geyser::{
+        SubscribeRequestFilterBlocks, SubscribeUpdateBlock, SubscribeUpdateBlockMeta,
+    },
+};
+
+/// Which update type new blocks are read from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockSubscriptionMode {
+    /// Full block updates
+    #[default]
+    Blocks,
+    /// Block metadata only: same header fields, far less data
+    BlocksMeta,
+}
+
+/// What full block updates carry, mapped onto `SubscribeRequestFilterBlocks`. By
+/// default blocks come without transactions, accounts or entries.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BlockFilterConfig {
+    /// Only blocks touching any of these accounts; empty streams every block
+    #[serde(default)]
+    pub account_include: Vec<String>,
+    #[serde(default)]
+    pub include_transactions: bool,
+    #[serde(default)]
+    pub include_accounts: bool,
+    #[serde(default)]
+    pub include_entries: bool,
+}
+
+impl BlockFilterConfig {
+    /// `with_transactions` adds transactions even when the config leaves them out,
+    /// for features that decode them
+    pub fn filter(&self, with_transactions: bool) -> SubscribeRequestFilterBlocks {
+        SubscribeRequestFilterBlocks {
+            account_include: self.account_include.clone(),
+            include_transactions: Some(self.include_transactions || with_transactions),
+            include_accounts: Some(self.include_accounts),
+            include_entries: Some(self.include_entries),
+        }
+    }
+
+    /// Every block's full contents, which is a lot of data
+    pub fn is_broad(&self, with_transactions: bool) -> bool {
+        self.account_include.is_empty()
+            && (self.include_transactions || self.include_accounts || with_transactions)
+    }
+}
+
+/// The block fields everything downstream uses, from either update type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockInfo {
+    pub slot: u64,
+    pub blockhash: String,
+    pub parent_slot: u64,
+    pub block_height: Option<u64>,
+    pub block_time: Option<i64>,
+    pub transaction_count: u64,
+}
+
+// The per-block line, at the given level
+macro_rules! new_block {
+    ($level:expr, $block:expr) => {
+        tracing::event!(
+            $level,
+            slot = $block.slot,
+            blockhash = %$block.blockhash,
+            parent_slot = $block.parent_slot,
+            block_height = ?$block.block_height,
+            block_time = ?$block.block_time,
+            transactions = $block.transaction_count,
+            "new block"
+        )
+    };
+}
+
+impl BlockInfo {
+    pub fn log(&self) {
+        new_block!(Level::INFO, self);
+    }
+
+    /// Same line at debug level, for when interval summaries stand in for it
+    pub fn log_debug(&self) {
+        new_block!(Level::DEBUG, self);
+    }
+
+    /// `received_at_ms` minus the block time, when the update carried a usable one
+    pub fn latency_ms(&self, received_at_ms: u64) -> Option<f64> {
+        self.block_time
+            .filter(|time| *time > 0)
+            .map(|time| received_at_ms as f64 - time as f64 * 1000.0)
+    }
+}
+
+impl From<&SubscribeUpdateBlock> for BlockInfo {
+    fn from(block: &SubscribeUpdateBlock) -> Self {
+        Self {
+            slot: block.slot,
+            blockhash: block.blockhash.clone(),
+            parent_slot: block.parent_slot,
+            block_height: block.block_height.map(|height| height.block_height),
+            block_time: block.block_time.map(|time| time.timestamp),
+            transaction_count: block.executed_transaction_count,
+        }
+    }
+}
+
+impl From<&SubscribeUpdateBlockMeta> for BlockInfo {
+    fn from(meta: &SubscribeUpdateBlockMeta) -> Self {
+        Self {
+            slot: meta.slot,
+            blockhash: meta.blockhash.clone(),
+            parent_slot: meta.parent_slot,
+            block_height: meta.block_height.map(|height| height.block_height),
+            block_time: meta.block_time.map(|time| time.timestamp),
+            transaction_count: meta.executed_transaction_count,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        yellowstone_grpc_proto::prelude::{BlockHeight, UnixTimestamp},
+    };
+
+    #[test]
+    fn test_block_and_meta_give_the_same_info() {
+        let block = SubscribeUpdateBlock {
+            slot: 7,
+            blockhash: "hash".to_string(),
+            parent_slot: 6,
+            block_height: Some(BlockHeight { block_height: 5 }),
+            block_time: Some(UnixTimestamp {
+                timestamp: 1_700_000_000,
+            }),
+            executed_transaction_count: 1_234,
+            ..Default::default()
+        };
+        let meta = SubscribeUpdateBlockMeta {
+            slot: 7,
+            blockhash: "hash".to_string(),
+            parent_slot: 6,
+            block_height: Some(BlockHeight { block_height: 5 }),
+            block_time: Some(UnixTimestamp {
+                timestamp: 1_700_000_000,
+            }),
+            executed_transaction_count: 1_234,
+            ..Default::default()
+        };
+
+        assert_eq!(BlockInfo::from(&block), BlockInfo::from(&meta));
+        assert_eq!(BlockInfo::from(&meta).block_height, Some(5));
+    }
+
+    #[test]
+    fn test_default_block_filter_excludes_everything() {
+        let filter = BlockFilterConfig::default().filter(false);
+        assert!(filter.account_include.is_empty());
+        assert_eq!(filter.include_transactions, Some(false));
+        assert_eq!(filter.include_accounts, Some(false));
+        assert_eq!(filter.include_entries, Some(false));
+        assert!(!BlockFilterConfig::default().is_broad(false));
+
+        assert_eq!(
+            BlockFilterConfig::default()
+                .filter(true)
+                .include_transactions,
+            Some(true)
+        );
+        assert!(BlockFilterConfig::default().is_broad(true));
+    }
+
+    #[test]
+    fn test_mode_parses_from_config() {
+        let mode: BlockSubscriptionMode = serde_yaml::from_str("blocks_meta").unwrap();
+        assert_eq!(mode, BlockSubscriptionMode::BlocksMeta);
+    }
+}
@@ -0,0 +1,280 @@
+use {
+    solana_sdk::epoch_schedule::EpochSchedule,
+    std::{collections::VecDeque, time::Instant},
+    tracing::info,
+};
+
+/// Blocks kept in the rolling window
+pub const BLOCK_STATS_WINDOW: usize = 100;
+/// Print a report after this many blocks
+pub const REPORT_EVERY_BLOCKS: u64 = 100;
+
+#[derive(Debug, Clone, Copy)]
+struct BlockSample {
+    tx_count: u32,
+    arrival_time: Instant,
+    /// Arrival wall clock minus the block's `block_time`, when it has one
+    latency_ms: Option<f64>,
+}
+
+/// Rolling block rate, transactions per block, block interval and propagation
+/// latency over the last `window` blocks
+pub struct BlockStats {
+    window: usize,
+    samples: VecDeque<BlockSample>,
+    current_slot: Option<u64>,
+    blocks_since_report: u64,
+    epoch_schedule: Option<EpochSchedule>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockStatsReport {
+    pub blocks_per_second: f64,
+    pub avg_tx_per_block: f64,
+    pub p99_block_interval_ms: f64,
+    pub current_slot: Option<u64>,
+    /// Epoch of `current_slot`, when the epoch schedule is known
+    pub epoch: Option<u64>,
+    pub slots_left_in_epoch: Option<u64>,
+    /// Over the blocks in the window that had a `block_time`
+    pub latency: Option<LatencySummary>,
+    /// Slots the RPC endpoint is ahead of the stream; filled in by the caller
+    pub slot_lag: Option<i64>,
+    /// Updates the sink has dropped so far, for sinks that can drop; filled in by
+    /// the caller
+    pub sink_dropped: Option<u64>,
+}
+
+/// Block propagation latency: arrival wall clock minus `block_time`. `block_time`
+/// has whole-second resolution, so single values are only accurate to a second.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LatencySummary {
+    pub min_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+}
+
+// Nearest-rank percentile of sorted values
+fn percentile(sorted: &[f64], quantile: f64) -> f64 {
+    match sorted.len() {
+        0 => 0.0,
+        len => sorted[((len as f64 * quantile).ceil() as usize).clamp(1, len) - 1],
+    }
+}
+
+impl BlockStats {
+    pub fn new(window: usize) -> Self {
+        Self {
+            window: window.max(2),
+            samples: VecDeque::with_capacity(window),
+            current_slot: None,
+            blocks_since_report: 0,
+            epoch_schedule: None,
+        }
+    }
+
+    /// Annotate reports with the current epoch and the slots left in it
+    pub fn with_epoch_schedule(mut self, schedule: EpochSchedule) -> Self {
+        self.epoch_schedule = Some(schedule);
+        self
+    }
+
+    pub fn record_block(
+        &mut self,
+        slot: u64,
+        tx_count: u32,
+        arrival_time: Instant,
+        latency_ms: Option<f64>,
+    ) {
+        if self.samples.len() == self.window {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(BlockSample {
+            tx_count,
+            arrival_time,
+            latency_ms,
+        });
+        self.current_slot = Some(self.current_slot.map_or(slot, |current| current.max(slot)));
+        self.blocks_since_report += 1;
+    }
+
+    /// The report, once every `REPORT_EVERY_BLOCKS` blocks
+    pub fn report_if_due(&mut self) -> Option<BlockStatsReport> {
+        if self.blocks_since_report < REPORT_EVERY_BLOCKS {
+            return None;
+        }
+        self.blocks_since_report = 0;
+        Some(self.report())
+    }
+
+    pub fn report(&self) -> BlockStatsReport {
+        let mut intervals_ms: Vec<f64> = self
+            .samples
+            .iter()
+            .zip(self.samples.iter().skip(1))
+            .map(|(previous, next)| {
+                next.arrival_time
+                    .saturating_duration_since(previous.arrival_time)
+                    .as_secs_f64()
+                    * 1000.0
+            })
+            .collect();
+        intervals_ms.sort_by(f64::total_cmp);
+
+        let span_secs = intervals_ms.iter().sum::<f64>() / 1000.0;
+        let blocks_per_second = if span_secs > 0.0 {
+            intervals_ms.len() as f64 / span_secs
+        } else {
+            0.0
+        };
+
+        let avg_tx_per_block = if self.samples.is_empty() {
+            0.0
+        } else {
+            self.samples
+                .iter()
+                .map(|sample| sample.tx_count as f64)
+                .sum::<f64>()
+                / self.samples.len() as f64
+        };
+
+        let p99_block_interval_ms = percentile(&intervals_ms, 0.99);
+
+        let mut latencies_ms: Vec<f64> = self
+            .samples
+            .iter()
+            .filter_map(|sample| sample.latency_ms)
+            .collect();
+        latencies_ms.sort_by(f64::total_cmp);
+        let latency = (!latencies_ms.is_empty()).then(|| LatencySummary {
+            min_ms: latencies_ms[0],
+            p50_ms: percentile(&latencies_ms, 0.5),
+            p95_ms: percentile(&latencies_ms, 0.95),
+        });
+
+        let epoch = self
+            .current_slot
+            .zip(self.epoch_schedule.as_ref())
+            .map(|(slot, schedule)| {
+                let (epoch, slot_index) = schedule.get_epoch_and_slot_index(slot);
+                (epoch, schedule.get_slots_in_epoch(epoch) - slot_index)
+            });
+
+        BlockStatsReport {
+            blocks_per_second,
+            avg_tx_per_block,
+            p99_block_interval_ms,
+            current_slot: self.current_slot,
+            epoch: epoch.map(|(epoch, _)| epoch),
+            slots_left_in_epoch: epoch.map(|(_, slots_left)| slots_left),
+            latency,
+            slot_lag: None,
+            sink_dropped: None,
+        }
+    }
+}
+
+impl BlockStatsReport {
+    pub fn log(&self) {
+        let latency = self.latency;
+        info!(
+            blocks_per_second = format_args!("{:.2}", self.blocks_per_second),
+            tx_per_block = format_args!("{:.0}", self.avg_tx_per_block),
+            tps = format_args!("{:.0}", self.blocks_per_second * self.avg_tx_per_block),
+            p99_interval_ms = format_args!("{:.0}", self.p99_block_interval_ms),
+            latency_min_ms = latency.map(|latency| latency.min_ms),
+            latency_p50_ms = latency.map(|latency| latency.p50_ms),
+            latency_p95_ms = latency.map(|latency| latency.p95_ms),
+            slot = self.current_slot,
+            epoch = self.epoch,
+            slots_left_in_epoch = self.slots_left_in_epoch,
+            slot_lag = self.slot_lag,
+            sink_dropped = self.sink_dropped,
+            "block stats"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::time::Duration};
+
+    #[test]
+    fn test_report_over_window() {
+        let start = Instant::now();
+        let mut stats = BlockStats::new(4);
+        // The first block falls out of the 4-block window
+        for (slot, arrival_ms, tx_count) in [
+            (100, 0, 9_999),
+            (101, 800, 1_000),
+            (102, 1_200, 2_000),
+            (103, 1_600, 3_000),
+            (104, 2_400, 4_000),
+        ] {
+            stats.record_block(
+                slot,
+                tx_count,
+                start + Duration::from_millis(arrival_ms),
+                None,
+            );
+        }
+
+        let report = stats.report();
+        assert_eq!(report.current_slot, Some(104));
+        assert_eq!(report.avg_tx_per_block, 2_500.0);
+        assert_eq!(report.p99_block_interval_ms, 800.0);
+        assert!((report.blocks_per_second - 3.0 / 1.6).abs() < 1e-9);
+        assert_eq!(report.epoch, None);
+        assert_eq!(report.latency, None);
+    }
+
+    #[test]
+    fn test_latency_percentiles_skip_blocks_without_time() {
+        let mut stats = BlockStats::new(BLOCK_STATS_WINDOW);
+        let now = Instant::now();
+        for (slot, latency_ms) in
+            (0..).zip([Some(900.0), None, Some(400.0), Some(1_500.0), Some(600.0)])
+        {
+            stats.record_block(slot, 0, now, latency_ms);
+        }
+
+        assert_eq!(
+            stats.report().latency,
+            Some(LatencySummary {
+                min_ms: 400.0,
+                p50_ms: 600.0,
+                p95_ms: 1_500.0,
+            })
+        );
+    }
+
+    #[test]
+    fn test_report_epoch_at_mainnet_boundary() {
+        // Mainnet: 432,000-slot epochs, no warmup; epoch 700 starts at slot 302,400,000
+        let mut stats = BlockStats::new(BLOCK_STATS_WINDOW)
+            .with_epoch_schedule(EpochSchedule::custom(432_000, 432_000, false));
+
+        stats.record_block(302_399_999, 0, Instant::now(), None);
+        let report = stats.report();
+        assert_eq!(report.epoch, Some(699));
+        assert_eq!(report.slots_left_in_epoch, Some(1));
+
+        stats.record_block(302_400_000, 0, Instant::now(), None);
+        let report = stats.report();
+        assert_eq!(report.epoch, Some(700));
+        assert_eq!(report.slots_left_in_epoch, Some(432_000));
+    }
+
+    #[test]
+    fn test_report_due_every_hundred_blocks() {
+        let mut stats = BlockStats::new(BLOCK_STATS_WINDOW);
+        let now = Instant::now();
+        for slot in 0..REPORT_EVERY_BLOCKS - 1 {
+            stats.record_block(slot, 0, now, None);
+            assert!(stats.report_if_due().is_none());
+        }
+        stats.record_block(REPORT_EVERY_BLOCKS, 0, now, None);
+        assert!(stats.report_if_due().is_some());
+        assert!(stats.report_if_due().is_none());
+    }
+}
@@ -0,0 +1,232 @@
+use {
+    crate::slot_tracker::SlotStatus,
+    serde::{Deserialize, Serialize},
+    std::{
+        collections::BTreeMap,
+        fmt,
+        time::{Duration, Instant},
+    },
+    tracing::{info, warn},
+};
+
+/// How many processed blocks are followed, and for how long, while waiting for
+/// their slot to be confirmed, finalized or to die
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockStatusConfig {
+    /// Most blocks followed at once; beyond this the oldest slot is let go
+    #[serde(default = "default_capacity")]
+    pub capacity: usize,
+    /// Blocks with no final status after this long are let go
+    #[serde(default = "default_retention_secs")]
+    pub retention_secs: u64,
+}
+
+impl Default for BlockStatusConfig {
+    fn default() -> Self {
+        Self {
+            capacity: default_capacity(),
+            retention_secs: default_retention_secs(),
+        }
+    }
+}
+
+fn default_capacity() -> usize {
+    1000
+}
+
+fn default_retention_secs() -> u64 {
+    120
+}
+
+/// A logged block whose slot has since been confirmed, finalized or has died
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockStatusChange {
+    pub slot: u64,
+    pub blockhash: String,
+    pub status: SlotStatus,
+    /// Time from the block arriving to this status
+    pub elapsed: Duration,
+}
+
+impl BlockStatusChange {
+    pub fn log(&self) {
+        match self.status {
+            SlotStatus::Dead => warn!(
+                slot = self.slot,
+                blockhash = %self.blockhash,
+                "{}",
+                self
+            ),
+            status => info!(
+                slot = self.slot,
+                blockhash = %self.blockhash,
+                status = %status,
+                elapsed_ms = self.elapsed.as_millis() as u64,
+                "{}",
+                self
+            ),
+        }
+    }
+}
+
+impl fmt::Display for BlockStatusChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.status {
+            SlotStatus::Dead => write!(f, "block {} DEAD — fork abandoned", self.slot),
+            status => write!(
+                f,
+                "block {} {} after {}s",
+                self.slot,
+                status,
+                self.elapsed.as_secs()
+            ),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct PendingBlock {
+    blockhash: String,
+    seen_at: Instant,
+    confirmed: bool,
+}
+
+/// Blocks logged at processed commitment, waiting for their slot to be
+/// confirmed and finalized, or to die
+#[derive(Debug)]
+pub struct PendingBlocks {
+    blocks: BTreeMap<u64, PendingBlock>,
+    capacity: usize,
+    retention: Duration,
+}
+
+impl PendingBlocks {
+    pub fn new(config: &BlockStatusConfig) -> Self {
+        Self {
+            blocks: BTreeMap::new(),
+            capacity: config.capacity.max(1),
+            retention: Duration::from_secs(config.retention_secs),
+        }
+    }
+
+    pub fn on_block(&mut self, slot: u64, blockhash: &str, seen_at: Instant) {
+        self.blocks.insert(
+            slot,
+            PendingBlock {
+                blockhash: blockhash.to_string(),
+                seen_at,
+                confirmed: false,
+            },
+        );
+        self.blocks
+            .retain(|_, block| seen_at.saturating_duration_since(block.seen_at) <= self.retention);
+        while self.blocks.len() > self.capacity {
+            self.blocks.pop_first();
+        }
+    }
+
+    /// Apply a slot status; returns the change to report for a pending block
+    pub fn on_status(
+        &mut self,
+        slot: u64,
+        status: SlotStatus,
+        now: Instant,
+    ) -> Option<BlockStatusChange> {
+        let change = |slot: u64, block: &PendingBlock| BlockStatusChange {
+            slot,
+            blockhash: block.blockhash.clone(),
+            status,
+            elapsed: now.saturating_duration_since(block.seen_at),
+        };
+        match status {
+            SlotStatus::Confirmed => {
+                let block = self
+                    .blocks
+                    .get_mut(&slot)
+                    .filter(|block| !block.confirmed)?;
+                block.confirmed = true;
+                Some(change(slot, block))
+            }
+            SlotStatus::Dead => self.blocks.remove(&slot).map(|block| change(slot, &block)),
+            // Anything still pending below a finalized slot missed its own
+            // updates; it can no longer change
+            SlotStatus::Finalized => {
+                let finalized = self.blocks.remove(&slot).map(|block| change(slot, &block));
+                self.blocks = self.blocks.split_off(&(slot + 1));
+                finalized
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pending() -> PendingBlocks {
+        PendingBlocks::new(&BlockStatusConfig::default())
+    }
+
+    #[test]
+    fn test_pending_block_reports_confirmation_then_finalization() {
+        let start = Instant::now();
+        let mut pending = pending();
+        pending.on_block(10, "hash10", start);
+
+        assert_eq!(pending.on_status(10, SlotStatus::Processed, start), None);
+        assert_eq!(
+            pending.on_status(10, SlotStatus::Confirmed, start + Duration::from_secs(1)),
+            Some(BlockStatusChange {
+                slot: 10,
+                blockhash: "hash10".to_string(),
+                status: SlotStatus::Confirmed,
+                elapsed: Duration::from_secs(1),
+            })
+        );
+        assert_eq!(pending.on_status(10, SlotStatus::Confirmed, start), None);
+
+        let finalized = pending
+            .on_status(10, SlotStatus::Finalized, start + Duration::from_secs(31))
+            .unwrap();
+        assert_eq!(finalized.to_string(), "block 10 finalized after 31s");
+        assert!(pending.blocks.is_empty());
+    }
+
+    #[test]
+    fn test_dead_slot_and_finalized_cleanup() {
+        let start = Instant::now();
+        let mut pending = pending();
+        pending.on_block(10, "hash10", start);
+        pending.on_block(11, "hash11", start);
+        pending.on_block(12, "hash12", start);
+
+        let dead = pending.on_status(12, SlotStatus::Dead, start).unwrap();
+        assert_eq!(dead.to_string(), "block 12 DEAD — fork abandoned");
+        assert_eq!(
+            pending
+                .on_status(11, SlotStatus::Finalized, start)
+                .map(|change| change.slot),
+            Some(11)
+        );
+        assert!(pending.blocks.is_empty());
+    }
+
+    #[test]
+    fn test_capacity_and_retention_let_blocks_go() {
+        let start = Instant::now();
+        let mut pending = PendingBlocks::new(&BlockStatusConfig {
+            capacity: 2,
+            retention_secs: 60,
+        });
+        pending.on_block(1, "hash1", start);
+        pending.on_block(2, "hash2", start);
+        pending.on_block(3, "hash3", start + Duration::from_secs(30));
+        assert_eq!(pending.blocks.keys().collect::<Vec<_>>(), [&2, &3]);
+
+        pending.on_block(4, "hash4", start + Duration::from_secs(61));
+        assert_eq!(pending.blocks.keys().collect::<Vec<_>>(), [&3, &4]);
+        pending.on_block(5, "hash5", start + Duration::from_secs(91));
+        assert_eq!(pending.blocks.keys().collect::<Vec<_>>(), [&4, &5]);
+    }
+}
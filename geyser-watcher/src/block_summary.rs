@@ -0,0 +1,182 @@
+use {
+    crate::block_source::BlockInfo,
+    std::time::{Duration, Instant},
+    tracing::info,
+};
+
+/// Block counts, slots, transactions and bytes since the last summary, for
+/// `stats_interval_secs` mode: one line per interval instead of one per block
+pub struct BlockSummary {
+    started: Instant,
+    blocks: u64,
+    first_arrival: Option<Instant>,
+    last_arrival: Option<Instant>,
+    min_slot: Option<u64>,
+    max_slot: Option<u64>,
+    transactions: u64,
+    bytes: u64,
+}
+
+/// One interval's summary
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockSummarySnapshot {
+    pub blocks: u64,
+    /// Between the first and last block of the interval; needs two blocks
+    pub avg_block_interval_ms: Option<f64>,
+    pub min_slot: Option<u64>,
+    pub max_slot: Option<u64>,
+    /// Executed transactions over the whole interval
+    pub tps: f64,
+    /// Every update received, blocks or not
+    pub bytes: u64,
+    pub elapsed: Duration,
+    /// Slots the RPC endpoint is ahead of the stream; filled in by the caller
+    pub slot_lag: Option<i64>,
+}
+
+impl BlockSummary {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            started: now,
+            blocks: 0,
+            first_arrival: None,
+            last_arrival: None,
+            min_slot: None,
+            max_slot: None,
+            transactions: 0,
+            bytes: 0,
+        }
+    }
+
+    pub fn record(&mut self, block: &BlockInfo, received_at: Instant) {
+        self.blocks += 1;
+        self.first_arrival.get_or_insert(received_at);
+        self.last_arrival = Some(received_at);
+        self.min_slot = Some(self.min_slot.map_or(block.slot, |min| min.min(block.slot)));
+        self.max_slot = Some(self.max_slot.map_or(block.slot, |max| max.max(block.slot)));
+        self.transactions += block.transaction_count;
+    }
+
+    /// Count an update's encoded size
+    pub fn record_bytes(&mut self, bytes: usize) {
+        self.bytes += bytes as u64;
+    }
+
+    /// Summarize the interval ending `now` and start the next one
+    pub fn snapshot(&mut self, now: Instant) -> BlockSummarySnapshot {
+        let elapsed = now.saturating_duration_since(self.started);
+        let avg_block_interval_ms = self
+            .first_arrival
+            .zip(self.last_arrival)
+            .filter(|_| self.blocks > 1)
+            .map(|(first, last)| {
+                last.saturating_duration_since(first).as_secs_f64() * 1000.0
+                    / (self.blocks - 1) as f64
+            });
+        let tps = if elapsed.is_zero() {
+            0.0
+        } else {
+            self.transactions as f64 / elapsed.as_secs_f64()
+        };
+
+        let snapshot = BlockSummarySnapshot {
+            blocks: self.blocks,
+            avg_block_interval_ms,
+            min_slot: self.min_slot,
+            max_slot: self.max_slot,
+            tps,
+            bytes: self.bytes,
+            elapsed,
+            slot_lag: None,
+        };
+        *self = Self::new(now);
+        snapshot
+    }
+}
+
+impl BlockSummarySnapshot {
+    pub fn log(&self) {
+        info!(
+            blocks = self.blocks,
+            avg_block_interval_ms = self.avg_block_interval_ms.map(f64::round),
+            min_slot = self.min_slot,
+            max_slot = self.max_slot,
+            tps = format_args!("{:.0}", self.tps),
+            bytes = self.bytes,
+            interval_secs = self.elapsed.as_secs(),
+            slot_lag = self.slot_lag,
+            "block summary"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(slot: u64, transaction_count: u64) -> BlockInfo {
+        BlockInfo {
+            slot,
+            blockhash: String::new(),
+            parent_slot: slot.saturating_sub(1),
+            block_height: None,
+            block_time: None,
+            transaction_count,
+        }
+    }
+
+    #[test]
+    fn test_snapshot_summarizes_the_interval() {
+        let start = Instant::now();
+        let mut summary = BlockSummary::new(start);
+        // Arrive out of order, 400ms apart
+        for (slot, arrival_ms, transactions) in
+            [(101, 400, 1_000), (100, 800, 2_000), (102, 1_200, 3_000)]
+        {
+            summary.record(
+                &block(slot, transactions),
+                start + Duration::from_millis(arrival_ms),
+            );
+        }
+        summary.record_bytes(1_500);
+        summary.record_bytes(500);
+
+        let snapshot = summary.snapshot(start + Duration::from_secs(2));
+        assert_eq!(
+            snapshot,
+            BlockSummarySnapshot {
+                blocks: 3,
+                avg_block_interval_ms: Some(400.0),
+                min_slot: Some(100),
+                max_slot: Some(102),
+                tps: 3_000.0,
+                bytes: 2_000,
+                elapsed: Duration::from_secs(2),
+                slot_lag: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_snapshot_starts_a_new_interval() {
+        let start = Instant::now();
+        let mut summary = BlockSummary::new(start);
+        summary.record(&block(100, 10), start);
+        summary.snapshot(start + Duration::from_secs(1));
+
+        let quiet = summary.snapshot(start + Duration::from_secs(2));
+        assert_eq!(quiet.blocks, 0);
+        assert_eq!(quiet.avg_block_interval_ms, None);
+        assert_eq!(quiet.max_slot, None);
+        assert_eq!(quiet.tps, 0.0);
+        assert_eq!(quiet.elapsed, Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_single_block_has_no_interval() {
+        let start = Instant::now();
+        let mut summary = BlockSummary::new(start);
+        summary.record(&block(100, 10), start);
+        assert_eq!(summary.snapshot(start).avg_block_interval_ms, None);
+    }
+}
@@ -0,0 +1,205 @@
+#[cfg(any(feature = "nats", feature = "redis-stream"))]
+use tracing::warn;
+use {
+    crate::sink::SinkUpdate,
+    std::sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    tokio::{sync::mpsc, task::JoinHandle},
+};
+
+/// Message bus a `BusSink` publishes to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BusTarget {
+    /// Subjects `<subject_prefix>.<kind>`
+    Nats { url: String, subject_prefix: String },
+    /// Streams `<key_prefix>:<kind>`, one `data` field per entry
+    RedisStream { url: String, key_prefix: String },
+}
+
+impl BusTarget {
+    /// Subject or stream key for an update
+    pub fn topic(&self, update: &SinkUpdate) -> String {
+        match self {
+            Self::Nats { subject_prefix, .. } => format!("{}.{}", subject_prefix, update.kind()),
+            Self::RedisStream { key_prefix, .. } => format!("{}:{}", key_prefix, update.kind()),
+        }
+    }
+}
+
+// Topic and serialized envelope
+type Message = (String, Vec<u8>);
+
+/// Publishes updates from its own task. Messages wait in a bounded buffer; when the
+/// bus can't keep up the rest are dropped and counted rather than slowing the stream.
+pub struct BusSink {
+    target: BusTarget,
+    messages: mpsc::Sender<Message>,
+    publisher: JoinHandle<()>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl BusSink {
+    pub fn start(target: BusTarget, buffer_size: usize) -> anyhow::Result<Self> {
+        let (messages, receiver) = mpsc::channel(buffer_size.max(1));
+        let dropped = Arc::new(AtomicU64::new(0));
+        let publisher = match &target {
+            BusTarget::Nats { url, .. } => spawn_nats(url.clone(), receiver, dropped.clone())?,
+            BusTarget::RedisStream { url, .. } => {
+                spawn_redis(url.clone(), receiver, dropped.clone())?
+            }
+        };
+        Ok(Self {
+            target,
+            messages,
+            publisher,
+            dropped,
+        })
+    }
+
+    pub fn write(&mut self, update: &SinkUpdate, envelope: Vec<u8>) {
+        if self
+            .messages
+            .try_send((self.target.topic(update), envelope))
+            .is_err()
+        {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Messages dropped so far, whether the buffer was full or publishing failed
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Stop accepting messages and wait until the buffered ones are published
+    pub async fn close(self) {
+        drop(self.messages);
+        let _ = self.publisher.await;
+    }
+}
+
+#[cfg(feature = "nats")]
+fn spawn_nats(
+    url: String,
+    mut messages: mpsc::Receiver<Message>,
+    dropped: Arc<AtomicU64>,
+) -> anyhow::Result<JoinHandle<()>> {
+    Ok(tokio::spawn(async move {
+        // The client reconnects by itself once the first connection is made
+        let client = loop {
+            match async_nats::connect(&url).await {
+                Ok(client) => break client,
+                Err(e) => {
+                    warn!(url = %url, error = %e, "NATS sink can't connect");
+                    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+                }
+            }
+        };
+        while let Some((subject, payload)) = messages.recv().await {
+            if client.publish(subject, payload.into()).await.is_err() {
+                dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        let _ = client.flush().await;
+    }))
+}
+
+#[cfg(not(feature = "nats"))]
+fn spawn_nats(
+    _url: String,
+    _messages: mpsc::Receiver<Message>,
+    _dropped: Arc<AtomicU64>,
+) -> anyhow::Result<JoinHandle<()>> {
+    anyhow::bail!("NATS sink support not compiled in (rebuild with --features nats)")
+}
+
+#[cfg(feature = "redis-stream")]
+fn spawn_redis(
+    url: String,
+    mut messages: mpsc::Receiver<Message>,
+    dropped: Arc<AtomicU64>,
+) -> anyhow::Result<JoinHandle<()>> {
+    let client = redis::Client::open(url.as_str())?;
+    Ok(tokio::spawn(async move {
+        let mut connection = None;
+        while let Some((key, payload)) = messages.recv().await {
+            if connection.is_none() {
+                match client.get_multiplexed_async_connection().await {
+                    Ok(connected) => connection = Some(connected),
+                    Err(e) => warn!(url = %url, error = %e, "Redis sink can't connect"),
+                }
+            }
+            let Some(conn) = &mut connection else {
+                dropped.fetch_add(1, Ordering::Relaxed);
+                continue;
+            };
+            let published: redis::RedisResult<String> = redis::cmd("XADD")
+                .arg(&key)
+                .arg("*")
+                .arg("data")
+                .arg(payload)
+                .query_async(conn)
+                .await;
+            if let Err(e) = published {
+                warn!(key = %key, error = %e, "Redis sink failed to publish");
+                dropped.fetch_add(1, Ordering::Relaxed);
+                // Reconnect on the next message
+                connection = None;
+            }
+        }
+    }))
+}
+
+#[cfg(not(feature = "redis-stream"))]
+fn spawn_redis(
+    _url: String,
+    _messages: mpsc::Receiver<Message>,
+    _dropped: Arc<AtomicU64>,
+) -> anyhow::Result<JoinHandle<()>> {
+    anyhow::bail!(
+        "Redis stream sink support not compiled in (rebuild with --features redis-stream)"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::sink::BlockRecord};
+
+    fn block() -> SinkUpdate {
+        SinkUpdate::BlockMeta(BlockRecord {
+            slot: 10,
+            blockhash: "hash".to_string(),
+            parent_slot: 9,
+            block_height: None,
+            block_time: None,
+            transaction_count: 0,
+        })
+    }
+
+    #[test]
+    fn test_topics_carry_the_update_kind() {
+        let nats = BusTarget::Nats {
+            url: "nats://localhost:4222".to_string(),
+            subject_prefix: "geyser".to_string(),
+        };
+        let redis = BusTarget::RedisStream {
+            url: "redis://localhost".to_string(),
+            key_prefix: "geyser".to_string(),
+        };
+        assert_eq!(nats.topic(&block()), "geyser.block_meta");
+        assert_eq!(redis.topic(&block()), "geyser:block_meta");
+    }
+
+    #[cfg(not(feature = "nats"))]
+    #[test]
+    fn test_missing_feature_is_reported() {
+        let target = BusTarget::Nats {
+            url: "nats://localhost:4222".to_string(),
+            subject_prefix: "geyser".to_string(),
+        };
+        let error = BusSink::start(target, 10).err().unwrap();
+        assert!(error.to_string().contains("--features nats"));
+    }
+}
@@ -0,0 +1,150 @@
+use {
+    config_migration::Migrations,
+    serde::de::DeserializeOwned,
+    serde_yaml::{Mapping, Value},
+};
+
+/// Each layout version's upgrade to the next, oldest first
+const MIGRATIONS: Migrations =
+    Migrations::new(&[endpoint_list, connection_section, checkpoint_path]);
+
+/// Layout version written by `--generate-config`; older files are migrated on load
+pub const CURRENT_CONFIG_VERSION: u32 = MIGRATIONS.current_version();
+
+// v1 -> v2: `geyser_endpoint` became a list of endpoints, and a non-blank
+// `geyser_x_token` became the first endpoint's `x_token`. GEYSER_X_TOKEN still
+// takes precedence over it
+fn endpoint_list(mut raw: Value) -> Value {
+    let Value::Mapping(config) = &mut raw else {
+        return raw;
+    };
+    let x_token = match config.remove("geyser_x_token") {
+        Some(Value::String(token)) if !token.trim().is_empty() => Some(token),
+        _ => None,
+    };
+    if let Some(Value::String(url)) = config.get("geyser_endpoint").cloned() {
+        let mut endpoint = Mapping::new();
+        endpoint.insert(Value::from("url"), Value::from(url));
+        if let Some(token) = x_token {
+            endpoint.insert(Value::from("x_token"), Value::from(token));
+        }
+        config.insert(
+            Value::from("geyser_endpoint"),
+            Value::Sequence(vec![Value::Mapping(endpoint)]),
+        );
+    }
+    raw
+}
+
+// v2 -> v3: the top-level HTTP/2 keepalive settings moved into `connection`, which
+// also holds the TCP keepalive; `keepalive_interval_secs` there now means TCP
+fn connection_section(mut raw: Value) -> Value {
+    let Value::Mapping(config) = &mut raw else {
+        return raw;
+    };
+    let mut connection = Mapping::new();
+    for (old, new) in [
+        ("keepalive_interval_secs", "http2_keep_alive_interval_secs"),
+        ("keepalive_timeout_secs", "keepalive_timeout_secs"),
+    ] {
+        if let Some(value) = config.remove(old) {
+            connection.insert(Value::from(new), value);
+        }
+    }
+    if !connection.is_empty() {
+        config.insert(Value::from("connection"), Value::Mapping(connection));
+    }
+    raw
+}
+
+// v3 -> v4: `slot_state_path` was renamed `checkpoint_path` when the file gained a
+// timestamp and periodic writes
+fn checkpoint_path(mut raw: Value) -> Value {
+    let Value::Mapping(config) = &mut raw else {
+        return raw;
+    };
+    if let Some(path) = config.remove("slot_state_path") {
+        config.insert(Value::from("checkpoint_path"), path);
+    }
+    raw
+}
+
+/// Parse a config file of any supported version into the current layout
+pub fn parse_config<T: DeserializeOwned>(contents: &str) -> anyhow::Result<T> {
+    Ok(MIGRATIONS.parse(contents)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::Config};
+
+    const V1_CONFIG: &str = "geyser_endpoint: \"https://grpc.example.com\"\ngeyser_x_token: \"\"\n";
+
+    #[test]
+    fn test_unversioned_file_is_version_one() {
+        let config: Config = parse_config(V1_CONFIG).unwrap();
+        assert_eq!(config.config_version, 1);
+    }
+
+    #[test]
+    fn test_v1_endpoint_becomes_a_list() {
+        let raw: Value = serde_yaml::from_str(V1_CONFIG).unwrap();
+        let migrated = MIGRATIONS.migrate(raw, 1);
+
+        assert_eq!(
+            migrated["geyser_endpoint"][0]["url"],
+            Value::from("https://grpc.example.com")
+        );
+        // A blank token is the same as none
+        assert!(migrated["geyser_endpoint"][0].get("x_token").is_none());
+
+        let config: Config = parse_config(V1_CONFIG).unwrap();
+        assert_eq!(config.geyser_endpoint.len(), 1);
+        assert_eq!(config.geyser_endpoint[0].x_token_env, "GEYSER_X_TOKEN");
+    }
+
+    #[test]
+    fn test_v1_x_token_moves_to_the_endpoint() {
+        let config: Config = parse_config(
+            "geyser_endpoint: \"https://grpc.example.com\"\ngeyser_x_token: \"secret\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.geyser_endpoint[0].x_token.as_deref(), Some("secret"));
+    }
+
+    #[test]
+    fn test_v2_keepalive_moves_into_connection() {
+        let config: Config = parse_config(
+            "config_version: 2\ngeyser_endpoint:\n  - url: \"https://grpc.example.com\"\nkeepalive_interval_secs: 30\nkeepalive_timeout_secs: 5\n",
+        )
+        .unwrap();
+        assert_eq!(config.connection.http2_keep_alive_interval_secs, Some(30));
+        assert_eq!(config.connection.keepalive_timeout_secs, 5);
+        assert_eq!(config.connection.keepalive_interval_secs, None);
+    }
+
+    #[test]
+    fn test_v3_slot_state_path_becomes_checkpoint_path() {
+        let config: Config = parse_config(
+            "config_version: 3\ngeyser_endpoint:\n  - url: \"https://grpc.example.com\"\nslot_state_path: \"watcher.slot\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.checkpoint_path.as_deref(), Some("watcher.slot"));
+        assert_eq!(config.checkpoint_every_slots, 1);
+    }
+
+    #[test]
+    fn test_current_version_is_untouched() {
+        let raw: Value = serde_yaml::from_str(
+            "config_version: 4\ngeyser_endpoint:\n  - url: \"https://grpc.example.com\"\n",
+        )
+        .unwrap();
+        assert_eq!(MIGRATIONS.migrate(raw.clone(), CURRENT_CONFIG_VERSION), raw);
+    }
+
+    #[test]
+    fn test_newer_version_is_rejected() {
+        let result: anyhow::Result<Config> = parse_config("config_version: 99\n");
+        assert!(result.unwrap_err().to_string().contains("newer"));
+    }
+}
@@ -0,0 +1,533 @@
+use {
+    futures::{
+        channel::mpsc,
+        future::{BoxFuture, FutureExt},
+        sink::Sink,
+        stream::{BoxStream, StreamExt},
+    },
+    serde::{Deserialize, Serialize},
+    std::{
+        pin::Pin,
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+        time::Duration,
+    },
+    tokio::sync::Mutex,
+    tonic::transport::{Certificate, channel::ClientTlsConfig},
+    tracing::{info, warn},
+    yellowstone_grpc_client::{GeyserGrpcClient, GeyserGrpcClientError},
+    yellowstone_grpc_proto::geyser::{SubscribeRequest, SubscribeUpdate},
+};
+
+pub type SubscribeSink = Pin<Box<dyn Sink<SubscribeRequest, Error = mpsc::SendError> + Send>>;
+pub type SubscribeStream = BoxStream<'static, Result<SubscribeUpdate, tonic::Status>>;
+
+// `connect()` hands back a client with an unnameable interceptor type, so each
+// pooled client is kept behind the one operation the pool needs
+type SubscribeFn = Box<
+    dyn Fn(SubscribeRequest) -> BoxFuture<'static, anyhow::Result<(SubscribeSink, SubscribeStream)>>
+        + Send
+        + Sync,
+>;
+
+/// Timeouts, size limits, flow control and keepalive of every Geyser connection.
+/// Keepalive stops idle sessions from being silently dropped by the network in
+/// between; the window sizes matter most for large block streams.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeyserConnectionConfig {
+    /// Give up on opening a connection after this long
+    #[serde(default = "default_timeout_secs")]
+    pub connect_timeout_secs: u64,
+    /// Give up on a request that gets no response within this long
+    #[serde(default = "default_timeout_secs")]
+    pub request_timeout_secs: u64,
+    /// Largest message accepted from the server; a bigger block fails the stream
+    #[serde(default = "default_max_decoding_message_size_mib")]
+    pub max_decoding_message_size_mib: usize,
+    /// Send small frames right away instead of batching them
+    #[serde(default = "default_tcp_nodelay")]
+    pub tcp_nodelay: bool,
+    /// Let HTTP/2 size its flow control windows from the measured bandwidth
+    #[serde(default)]
+    pub http2_adaptive_window: bool,
+    /// HTTP/2 flow control window per stream, in bytes; unset keeps the default
+    #[serde(default)]
+    pub initial_stream_window_size: Option<u32>,
+    /// HTTP/2 flow control window for the whole connection, in bytes; unset keeps
+    /// the default
+    #[serde(default)]
+    pub initial_connection_window_size: Option<u32>,
+    /// TCP keepalive probe interval on the socket; unset leaves it off
+    #[serde(default)]
+    pub keepalive_interval_secs: Option<u64>,
+    /// Drop the connection when an HTTP/2 keepalive ping isn't acknowledged within
+    /// this long
+    #[serde(default = "default_keepalive_timeout_secs")]
+    pub keepalive_timeout_secs: u64,
+    /// Keep sending HTTP/2 keepalive pings while no stream is open
+    #[serde(default = "default_keepalive_while_idle")]
+    pub keepalive_while_idle: bool,
+    /// HTTP/2 keepalive ping interval; unset leaves HTTP/2 keepalive off
+    #[serde(default)]
+    pub http2_keep_alive_interval_secs: Option<u64>,
+}
+
+impl Default for GeyserConnectionConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout_secs: default_timeout_secs(),
+            request_timeout_secs: default_timeout_secs(),
+            max_decoding_message_size_mib: default_max_decoding_message_size_mib(),
+            tcp_nodelay: default_tcp_nodelay(),
+            http2_adaptive_window: false,
+            initial_stream_window_size: None,
+            initial_connection_window_size: None,
+            keepalive_interval_secs: None,
+            keepalive_timeout_secs: default_keepalive_timeout_secs(),
+            keepalive_while_idle: default_keepalive_while_idle(),
+            http2_keep_alive_interval_secs: None,
+        }
+    }
+}
+
+fn default_timeout_secs() -> u64 {
+    10
+}
+
+fn default_max_decoding_message_size_mib() -> usize {
+    256
+}
+
+fn default_tcp_nodelay() -> bool {
+    true
+}
+
+fn default_keepalive_timeout_secs() -> u64 {
+    20
+}
+
+fn default_keepalive_while_idle() -> bool {
+    true
+}
+
+// HTTP/2 flow control windows: the protocol's initial size and its maximum
+const MIN_WINDOW_SIZE: u32 = 65_535;
+const MAX_WINDOW_SIZE: u32 = (1 << 31) - 1;
+
+impl GeyserConnectionConfig {
+    /// Reject values that would only show up later as confusing gRPC errors
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.connect_timeout_secs == 0 || self.request_timeout_secs == 0 {
+            anyhow::bail!(
+                "connection.connect_timeout_secs and connection.request_timeout_secs must be \
+                 at least 1; 0 would fail every connection or request immediately"
+            );
+        }
+        if self.max_decoding_message_size_mib == 0 {
+            anyhow::bail!(
+                "connection.max_decoding_message_size_mib must be at least 1; full blocks \
+                 are several MiB and would be refused with `message length too large`"
+            );
+        }
+        if self.keepalive_timeout_secs == 0 {
+            anyhow::bail!(
+                "connection.keepalive_timeout_secs must be at least 1; 0 would drop the \
+                 connection at the first keepalive ping"
+            );
+        }
+        for (name, size) in [
+            (
+                "initial_stream_window_size",
+                self.initial_stream_window_size,
+            ),
+            (
+                "initial_connection_window_size",
+                self.initial_connection_window_size,
+            ),
+        ] {
+            if let Some(size) = size
+                && !(MIN_WINDOW_SIZE..=MAX_WINDOW_SIZE).contains(&size)
+            {
+                anyhow::bail!(
+                    "connection.{} must be between {} and {} bytes (HTTP/2 limits), got {}",
+                    name,
+                    MIN_WINDOW_SIZE,
+                    MAX_WINDOW_SIZE,
+                    size
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One TLS connection; each subscription on it is its own HTTP/2 stream
+struct PooledConnection {
+    id: u64,
+    subscribe: SubscribeFn,
+    active_streams: Arc<AtomicUsize>,
+}
+
+/// Counts a subscription against its connection until dropped
+pub struct StreamLease {
+    connection_id: u64,
+    active_streams: Arc<AtomicUsize>,
+}
+
+impl Drop for StreamLease {
+    fn drop(&mut self) {
+        self.active_streams.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Up to `pool_size` Geyser connections shared by every subscription, each carrying
+/// at most `max_streams_per_connection` streams
+pub struct GeyserConnectionPool {
+    endpoint: String,
+    x_token: Option<String>,
+    pool_size: usize,
+    max_streams_per_connection: usize,
+    ca_certificate: Option<Certificate>,
+    connection: GeyserConnectionConfig,
+    connections: Vec<PooledConnection>,
+    next_id: u64,
+}
+
+impl GeyserConnectionPool {
+    pub fn new(
+        endpoint: String,
+        x_token: Option<String>,
+        pool_size: usize,
+        max_streams_per_connection: usize,
+    ) -> Self {
+        Self {
+            endpoint,
+            x_token,
+            pool_size: pool_size.max(1),
+            max_streams_per_connection: max_streams_per_connection.max(1),
+            ca_certificate: None,
+            connection: GeyserConnectionConfig::default(),
+            connections: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Also trust this CA (PEM), for private deployments with self-signed certificates
+    pub fn with_ca_certificate(mut self, pem: Vec<u8>) -> Self {
+        self.ca_certificate = Some(Certificate::from_pem(pem));
+        self
+    }
+
+    /// Timeouts, limits and keepalive settings for every connection the pool opens
+    pub fn with_connection_config(mut self, connection: GeyserConnectionConfig) -> Self {
+        self.connection = connection;
+        self
+    }
+
+    // `http://` endpoints, e.g. the plugin on a local test validator, speak plaintext
+    fn uses_tls(&self) -> bool {
+        !self
+            .endpoint
+            .get(.."http://".len())
+            .is_some_and(|scheme| scheme.eq_ignore_ascii_case("http://"))
+    }
+
+    fn tls_config(&self) -> ClientTlsConfig {
+        let tls_config = ClientTlsConfig::new().with_native_roots();
+        match &self.ca_certificate {
+            Some(certificate) => tls_config.ca_certificate(certificate.clone()),
+            None => tls_config,
+        }
+    }
+
+    #[tracing::instrument(name = "connect", skip_all, fields(endpoint = %self.endpoint))]
+    async fn connect(&mut self) -> anyhow::Result<PooledConnection> {
+        let mut builder = GeyserGrpcClient::build_from_shared(self.endpoint.clone())?
+            .x_token(self.x_token.clone())?
+            .connect_timeout(Duration::from_secs(self.connection.connect_timeout_secs))
+            .timeout(Duration::from_secs(self.connection.request_timeout_secs))
+            .max_decoding_message_size(self.connection.max_decoding_message_size_mib * 1024 * 1024)
+            .tcp_nodelay(self.connection.tcp_nodelay)
+            .http2_adaptive_window(self.connection.http2_adaptive_window)
+            .initial_stream_window_size(self.connection.initial_stream_window_size)
+            .initial_connection_window_size(self.connection.initial_connection_window_size)
+            .tcp_keepalive(
+                self.connection
+                    .keepalive_interval_secs
+                    .map(Duration::from_secs),
+            );
+        if self.uses_tls() {
+            builder = builder.tls_config(self.tls_config())?;
+        } else {
+            if self.x_token.is_some() {
+                warn!(
+                    "plaintext endpoint; the x-token is sent unencrypted, use https:// \
+                     for anything but a local validator"
+                );
+            }
+            if self.ca_certificate.is_some() {
+                warn!("plaintext endpoint; the configured TLS certificate is not used");
+            }
+        }
+        if let Some(interval) = self.connection.http2_keep_alive_interval_secs {
+            builder = builder
+                .http2_keep_alive_interval(Duration::from_secs(interval))
+                .keep_alive_timeout(Duration::from_secs(self.connection.keepalive_timeout_secs))
+                .keep_alive_while_idle(self.connection.keepalive_while_idle);
+        }
+        let client = builder.connect().await?;
+
+        let client = Arc::new(Mutex::new(client));
+        let subscribe: SubscribeFn = Box::new(move |request| {
+            let client = Arc::clone(&client);
+            async move {
+                let (sink, stream) = client
+                    .lock()
+                    .await
+                    .subscribe_with_request(Some(request))
+                    .await?;
+                Ok::<_, anyhow::Error>((Box::pin(sink) as SubscribeSink, stream.boxed()))
+            }
+            .boxed()
+        });
+
+        self.next_id += 1;
+        info!(
+            connection = self.next_id,
+            open = self.connections.len() + 1,
+            pool_size = self.pool_size,
+            "opened Geyser connection"
+        );
+        Ok(PooledConnection {
+            id: self.next_id,
+            subscribe,
+            active_streams: Arc::new(AtomicUsize::new(0)),
+        })
+    }
+
+    /// Index of the connection a new stream should use: the least busy one with room,
+    /// or `None` when a new connection should be opened
+    fn pick(&self) -> anyhow::Result<Option<usize>> {
+        let least_busy = self
+            .connections
+            .iter()
+            .enumerate()
+            .map(|(index, connection)| (index, connection.active_streams.load(Ordering::SeqCst)))
+            .filter(|(_, active)| *active < self.max_streams_per_connection)
+            .min_by_key(|(_, active)| *active);
+
+        match least_busy {
+            // An idle connection is always reused; a busy one only once the pool is full
+            Some((index, 0)) => Ok(Some(index)),
+            Some(_) if self.connections.len() < self.pool_size => Ok(None),
+            Some((index, _)) => Ok(Some(index)),
+            None if self.connections.len() < self.pool_size => Ok(None),
+            None => anyhow::bail!(
+                "all {} Geyser connections are at their limit of {} streams",
+                self.pool_size,
+                self.max_streams_per_connection
+            ),
+        }
+    }
+
+    /// Open a subscription on a pooled connection, connecting only when none has room
+    pub async fn subscribe(
+        &mut self,
+        request: SubscribeRequest,
+    ) -> anyhow::Result<(SubscribeSink, SubscribeStream, StreamLease)> {
+        let index = match self.pick()? {
+            Some(index) => index,
+            None => {
+                let connection = self.connect().await?;
+                self.connections.push(connection);
+                self.connections.len() - 1
+            }
+        };
+
+        let connection = &self.connections[index];
+        let (sink, stream) = (connection.subscribe)(request)
+            .await
+            .map_err(|e| self.explain_auth_failure(e))?;
+        connection.active_streams.fetch_add(1, Ordering::SeqCst);
+        let lease = StreamLease {
+            connection_id: connection.id,
+            active_streams: Arc::clone(&connection.active_streams),
+        };
+        Ok((sink, stream, lease))
+    }
+
+    // Point at the x-token when the endpoint refuses the subscription
+    fn explain_auth_failure(&self, error: anyhow::Error) -> anyhow::Error {
+        let refused = matches!(
+            error.downcast_ref::<GeyserGrpcClientError>(),
+            Some(GeyserGrpcClientError::TonicStatus(status))
+                if matches!(
+                    status.code(),
+                    tonic::Code::Unauthenticated | tonic::Code::PermissionDenied
+                )
+        );
+        match (refused, &self.x_token) {
+            (false, _) => error,
+            (true, None) => error.context(format!(
+                "{} wants an x-token; set x_token_file, x_token_env or x_token",
+                self.endpoint
+            )),
+            (true, Some(_)) => error.context(format!("{} rejected the x-token", self.endpoint)),
+        }
+    }
+
+    /// Drop the connection behind a failed stream so the next subscribe reconnects.
+    /// Other streams on it keep their own handle until they end.
+    pub fn discard(&mut self, lease: &StreamLease) {
+        self.connections
+            .retain(|connection| connection.id != lease.connection_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unconnected(
+        _: SubscribeRequest,
+    ) -> BoxFuture<'static, anyhow::Result<(SubscribeSink, SubscribeStream)>> {
+        async { Err(anyhow::anyhow!("not connected")) }.boxed()
+    }
+
+    fn pool_with(active: &[usize], pool_size: usize) -> GeyserConnectionPool {
+        let mut pool = GeyserConnectionPool::new(String::new(), None, pool_size, 2);
+        for &streams in active {
+            pool.next_id += 1;
+            pool.connections.push(PooledConnection {
+                id: pool.next_id,
+                subscribe: Box::new(unconnected),
+                active_streams: Arc::new(AtomicUsize::new(streams)),
+            });
+        }
+        pool
+    }
+
+    #[test]
+    fn test_idle_connection_is_reused() {
+        assert_eq!(pool_with(&[1, 0], 3).pick().unwrap(), Some(1));
+    }
+
+    #[test]
+    fn test_busy_pool_grows_until_full() {
+        assert_eq!(pool_with(&[1], 2).pick().unwrap(), None);
+        assert_eq!(pool_with(&[1, 1], 2).pick().unwrap(), Some(0));
+    }
+
+    #[test]
+    fn test_stream_limit_is_enforced() {
+        assert_eq!(pool_with(&[2, 1], 2).pick().unwrap(), Some(1));
+        assert!(pool_with(&[2, 2], 2).pick().is_err());
+    }
+
+    #[test]
+    fn test_lease_releases_its_stream() {
+        let mut pool = pool_with(&[1], 1);
+        let active_streams = Arc::clone(&pool.connections[0].active_streams);
+        let lease = StreamLease {
+            connection_id: pool.connections[0].id,
+            active_streams: Arc::clone(&active_streams),
+        };
+
+        pool.discard(&lease);
+        assert!(pool.connections.is_empty());
+        drop(lease);
+        assert_eq!(active_streams.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_refused_subscription_points_at_the_x_token() {
+        let mut pool = pool_with(&[0], 1);
+        pool.endpoint = "https://grpc.example.com".to_string();
+        pool.connections[0].subscribe = Box::new(|_| {
+            async {
+                Err(
+                    GeyserGrpcClientError::TonicStatus(tonic::Status::unauthenticated(
+                        "missing x-token",
+                    ))
+                    .into(),
+                )
+            }
+            .boxed()
+        });
+
+        let error = pool
+            .subscribe(SubscribeRequest::default())
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "https://grpc.example.com wants an x-token; set x_token_file, x_token_env or x_token"
+        );
+        assert!(error.downcast_ref::<GeyserGrpcClientError>().is_some());
+    }
+
+    #[test]
+    fn test_http_endpoints_skip_tls() {
+        let pool = |endpoint: &str| GeyserConnectionPool::new(endpoint.to_string(), None, 1, 1);
+        assert!(!pool("http://127.0.0.1:10000").uses_tls());
+        assert!(!pool("HTTP://localhost:10000").uses_tls());
+        assert!(pool("https://grpc.example.com").uses_tls());
+    }
+
+    #[tokio::test]
+    async fn test_subscribes_to_plaintext_server() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let incoming =
+            tonic::transport::server::TcpIncoming::from_listener(listener, true, None).unwrap();
+        // Answers every call, over h2c, with an HTTP 404 that the client reads as
+        // Unimplemented
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_routes(tonic::service::Routes::default())
+                .serve_with_incoming(incoming),
+        );
+
+        let mut pool = GeyserConnectionPool::new(format!("http://{}", address), None, 1, 1);
+        let error = pool
+            .subscribe(SubscribeRequest::default())
+            .await
+            .err()
+            .unwrap();
+        // The request reached the server instead of failing to connect
+        match error.downcast_ref::<GeyserGrpcClientError>() {
+            Some(GeyserGrpcClientError::TonicStatus(status)) => {
+                assert_eq!(status.code(), tonic::Code::Unimplemented)
+            }
+            _ => panic!("expected a gRPC status, got {:#}", error),
+        }
+        assert_eq!(pool.connections.len(), 1);
+    }
+
+    #[test]
+    fn test_connection_config_rejects_unusable_values() {
+        assert!(GeyserConnectionConfig::default().validate().is_ok());
+
+        let zero_timeout = GeyserConnectionConfig {
+            request_timeout_secs: 0,
+            ..Default::default()
+        };
+        assert!(zero_timeout.validate().is_err());
+
+        let no_messages = GeyserConnectionConfig {
+            max_decoding_message_size_mib: 0,
+            ..Default::default()
+        };
+        assert!(no_messages.validate().is_err());
+
+        let tiny_window = GeyserConnectionConfig {
+            initial_stream_window_size: Some(1024),
+            ..Default::default()
+        };
+        let error = tiny_window.validate().unwrap_err().to_string();
+        assert!(error.contains("initial_stream_window_size"), "{}", error);
+    }
+}
@@ -0,0 +1,217 @@
+use {
+    serde::{Deserialize, Serialize},
+    std::collections::{HashSet, VecDeque},
+    tracing::info,
+    yellowstone_grpc_proto::geyser::{
+        SubscribeRequestFilterTransactions, SubscribeUpdateTransaction,
+    },
+};
+
+// Recent block times kept to date deposits, which arrive without one
+const BLOCK_TIMES: usize = 64;
+
+/// Wallets whose incoming SOL is reported as deposits
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DepositWatchConfig {
+    pub wallets: Vec<String>,
+}
+
+impl DepositWatchConfig {
+    /// Successful transactions touching any of the wallets
+    pub fn filter(&self) -> SubscribeRequestFilterTransactions {
+        SubscribeRequestFilterTransactions {
+            vote: Some(false),
+            failed: Some(false),
+            signature: None,
+            account_include: self.wallets.clone(),
+            account_exclude: vec![],
+            account_required: vec![],
+        }
+    }
+}
+
+/// SOL received by a watched wallet in one transaction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DepositEvent {
+    pub wallet: String,
+    pub amount_lamports: u64,
+    /// The account that lost the most lamports in the transaction, if any did
+    pub from: Option<String>,
+    pub signature: String,
+    pub slot: u64,
+    /// Known when the slot's block arrived before the transaction
+    pub block_time: Option<i64>,
+}
+
+impl DepositEvent {
+    pub fn log(&self) {
+        info!(
+            wallet = %self.wallet,
+            lamports = self.amount_lamports,
+            from = self.from.as_deref(),
+            sig = %self.signature,
+            slot = self.slot,
+            "deposit"
+        );
+    }
+}
+
+/// Turns transaction updates into deposits. Replayed transactions are dropped by
+/// `ReplayDedup` before they get here
+pub struct DepositDetector {
+    wallets: HashSet<String>,
+    block_times: VecDeque<(u64, i64)>,
+}
+
+impl DepositDetector {
+    pub fn new(config: &DepositWatchConfig) -> Self {
+        Self {
+            wallets: config.wallets.iter().cloned().collect(),
+            block_times: VecDeque::new(),
+        }
+    }
+
+    pub fn on_block(&mut self, slot: u64, block_time: Option<i64>) {
+        if let Some(block_time) = block_time {
+            if self.block_times.len() == BLOCK_TIMES {
+                self.block_times.pop_front();
+            }
+            self.block_times.push_back((slot, block_time));
+        }
+    }
+
+    /// Every watched wallet whose balance went up in `update`. Failed transactions
+    /// give none.
+    pub fn on_transaction(&mut self, update: &SubscribeUpdateTransaction) -> Vec<DepositEvent> {
+        let Some(info) = update.transaction.as_ref() else {
+            return Vec::new();
+        };
+        let Some(meta) = info.meta.as_ref().filter(|meta| meta.err.is_none()) else {
+            return Vec::new();
+        };
+        let signature = bs58::encode(&info.signature).into_string();
+
+        // Balances line up with the static keys followed by the loaded ones
+        let keys: Vec<String> = info
+            .transaction
+            .as_ref()
+            .and_then(|transaction| transaction.message.as_ref())
+            .map(|message| message.account_keys.as_slice())
+            .unwrap_or_default()
+            .iter()
+            .chain(&meta.loaded_writable_addresses)
+            .chain(&meta.loaded_readonly_addresses)
+            .map(|key| bs58::encode(key).into_string())
+            .collect();
+        let changes: Vec<(&String, i128)> = keys
+            .iter()
+            .zip(meta.pre_balances.iter().zip(&meta.post_balances))
+            .map(|(key, (pre, post))| (key, i128::from(*post) - i128::from(*pre)))
+            .collect();
+
+        let block_time = self
+            .block_times
+            .iter()
+            .find(|(slot, _)| *slot == update.slot)
+            .map(|(_, time)| *time);
+        let mut reported = HashSet::new();
+        changes
+            .iter()
+            .filter(|(key, change)| *change > 0 && self.wallets.contains(*key))
+            .filter(|(key, _)| reported.insert(*key))
+            .map(|(wallet, change)| DepositEvent {
+                wallet: (*wallet).clone(),
+                amount_lamports: *change as u64,
+                from: changes
+                    .iter()
+                    .filter(|(key, change)| key != wallet && *change < 0)
+                    .min_by_key(|(_, change)| *change)
+                    .map(|(key, _)| (*key).clone()),
+                signature: signature.clone(),
+                slot: update.slot,
+                block_time,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        yellowstone_grpc_proto::{
+            geyser::SubscribeUpdateTransactionInfo,
+            prelude::{Message, Transaction, TransactionError, TransactionStatusMeta},
+        },
+    };
+
+    const PAYER: &str = "Vote111111111111111111111111111111111111111";
+    const WALLET: &str = "SysvarC1ock11111111111111111111111111111111";
+
+    fn update(signature: u8, balances: &[(u64, u64)], failed: bool) -> SubscribeUpdateTransaction {
+        let decode = |key: &str| bs58::decode(key).into_vec().unwrap();
+        SubscribeUpdateTransaction {
+            slot: 50,
+            transaction: Some(SubscribeUpdateTransactionInfo {
+                signature: vec![signature; 64],
+                transaction: Some(Transaction {
+                    message: Some(Message {
+                        account_keys: vec![decode(PAYER)],
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
+                meta: Some(TransactionStatusMeta {
+                    err: failed.then(|| TransactionError { err: vec![1] }),
+                    // The watched wallet comes in through a lookup table
+                    loaded_writable_addresses: vec![decode(WALLET)],
+                    pre_balances: balances.iter().map(|(pre, _)| *pre).collect(),
+                    post_balances: balances.iter().map(|(_, post)| *post).collect(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+        }
+    }
+
+    fn detector() -> DepositDetector {
+        DepositDetector::new(&DepositWatchConfig {
+            wallets: vec![WALLET.to_string()],
+        })
+    }
+
+    #[test]
+    fn test_reports_the_received_amount() {
+        let mut detector = detector();
+        detector.on_block(50, Some(1_700_000_000));
+        let transfer = update(1, &[(10_000, 4_000), (100, 5_100)], false);
+
+        let deposits = detector.on_transaction(&transfer);
+        assert_eq!(
+            deposits,
+            [DepositEvent {
+                wallet: WALLET.to_string(),
+                amount_lamports: 5_000,
+                from: Some(PAYER.to_string()),
+                signature: bs58::encode([1; 64]).into_string(),
+                slot: 50,
+                block_time: Some(1_700_000_000),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_failed_and_outgoing_transactions_are_not_deposits() {
+        let mut detector = detector();
+        assert!(
+            detector
+                .on_transaction(&update(1, &[(10_000, 4_000), (100, 5_100)], true))
+                .is_empty()
+        );
+        assert!(
+            detector
+                .on_transaction(&update(2, &[(10_000, 14_000), (5_100, 100)], false))
+                .is_empty()
+        );
+    }
+}
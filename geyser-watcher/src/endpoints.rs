@@ -0,0 +1,220 @@
+use {
+    crate::connection_pool::GeyserConnectionPool,
+    serde::{Deserialize, Serialize},
+    tracing::warn,
+};
+
+/// One Geyser endpoint and where its x-token comes from. The first of
+/// `x_token_file`, `x_token_env` and `x_token` holding a non-blank value is used;
+/// without any the endpoint is connected to with no token.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EndpointConfig {
+    /// Yellowstone gRPC endpoint (https://..., or http://... for plaintext)
+    pub url: String,
+    /// File holding this endpoint's x-token, e.g. a mounted secret
+    #[serde(default)]
+    pub x_token_file: Option<String>,
+    /// Environment variable holding this endpoint's x-token
+    #[serde(default = "default_x_token_env")]
+    pub x_token_env: String,
+    /// The x-token itself, for when neither of the above is set
+    #[serde(default)]
+    pub x_token: Option<String>,
+}
+
+fn default_x_token_env() -> String {
+    "GEYSER_X_TOKEN".to_string()
+}
+
+impl EndpointConfig {
+    /// An endpoint given on the command line, with its x-token from GEYSER_X_TOKEN
+    pub fn from_url(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            x_token_file: None,
+            x_token_env: default_x_token_env(),
+            x_token: None,
+        }
+    }
+
+    /// The x-token to send, if any. Only a configured `x_token_file` that can't be
+    /// read is an error; an endpoint that wants a token it didn't get says so when
+    /// subscribing.
+    pub fn resolve_x_token(&self) -> anyhow::Result<Option<String>> {
+        if let Some(path) = &self.x_token_file {
+            let contents = std::fs::read_to_string(path).map_err(|e| {
+                anyhow::anyhow!(
+                    "failed to read x-token file {} for {}: {}",
+                    path,
+                    self.url,
+                    e
+                )
+            })?;
+            if let Some(token) = non_blank(&contents) {
+                return Ok(Some(token));
+            }
+        }
+        if let Ok(value) = std::env::var(&self.x_token_env)
+            && let Some(token) = non_blank(&value)
+        {
+            return Ok(Some(token));
+        }
+        Ok(self.x_token.as_deref().and_then(non_blank))
+    }
+}
+
+// Tokens copied from a dashboard or written by `echo` end in a newline
+fn non_blank(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// A connection pool per endpoint; subscriptions use the active one and move to the
+/// next, in config order, after a failure
+pub struct EndpointRotation {
+    endpoints: Vec<(String, GeyserConnectionPool)>,
+    active: usize,
+}
+
+impl EndpointRotation {
+    /// `endpoints` pairs each url with its pool and must not be empty
+    pub fn new(endpoints: Vec<(String, GeyserConnectionPool)>) -> Self {
+        assert!(!endpoints.is_empty(), "at least one Geyser endpoint");
+        Self {
+            endpoints,
+            active: 0,
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.endpoints[self.active].0
+    }
+
+    pub fn pool(&mut self) -> &mut GeyserConnectionPool {
+        &mut self.endpoints[self.active].1
+    }
+
+    /// Make the next endpoint active; a single endpoint just stays active
+    pub fn fail_over(&mut self) {
+        if self.endpoints.len() < 2 {
+            return;
+        }
+        let failed = self.active;
+        self.active = (self.active + 1) % self.endpoints.len();
+        warn!(
+            failed = %self.endpoints[failed].0,
+            active = %self.url(),
+            "switching Geyser endpoint"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rotation(urls: &[&str]) -> EndpointRotation {
+        EndpointRotation::new(
+            urls.iter()
+                .map(|url| {
+                    (
+                        url.to_string(),
+                        GeyserConnectionPool::new(url.to_string(), None, 1, 1),
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_fail_over_cycles_through_endpoints() {
+        let mut endpoints = rotation(&["https://eu", "https://us"]);
+        assert_eq!(endpoints.url(), "https://eu");
+        endpoints.fail_over();
+        assert_eq!(endpoints.url(), "https://us");
+        endpoints.fail_over();
+        assert_eq!(endpoints.url(), "https://eu");
+
+        let mut single = rotation(&["https://eu"]);
+        single.fail_over();
+        assert_eq!(single.url(), "https://eu");
+    }
+
+    fn endpoint(
+        x_token_file: Option<String>,
+        x_token_env: &str,
+        x_token: Option<&str>,
+    ) -> EndpointConfig {
+        EndpointConfig {
+            url: "https://grpc.example.com".to_string(),
+            x_token_file,
+            x_token_env: x_token_env.to_string(),
+            x_token: x_token.map(str::to_string),
+        }
+    }
+
+    fn token_file(name: &str, contents: &str) -> String {
+        let path =
+            std::env::temp_dir().join(format!("geyser-x-token-{}-{}", name, std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_x_token_is_trimmed_and_blank_values_fall_through() {
+        let unset = "GEYSER_WATCHER_TEST_UNSET_X_TOKEN";
+        let file = token_file("trimmed", "file-token\n");
+        assert_eq!(
+            endpoint(Some(file), unset, Some("literal"))
+                .resolve_x_token()
+                .unwrap(),
+            Some("file-token".to_string())
+        );
+
+        let blank = token_file("blank", " \n");
+        assert_eq!(
+            endpoint(Some(blank), unset, Some("  literal\r\n"))
+                .resolve_x_token()
+                .unwrap(),
+            Some("literal".to_string())
+        );
+
+        assert_eq!(
+            endpoint(None, unset, Some("")).resolve_x_token().unwrap(),
+            None
+        );
+        assert_eq!(endpoint(None, unset, None).resolve_x_token().unwrap(), None);
+    }
+
+    #[test]
+    fn test_x_token_env_comes_before_the_literal() {
+        let var = "GEYSER_WATCHER_TEST_X_TOKEN";
+        // Only this test sets the variable
+        unsafe { std::env::set_var(var, "env-token\n") };
+        assert_eq!(
+            endpoint(None, var, Some("literal"))
+                .resolve_x_token()
+                .unwrap(),
+            Some("env-token".to_string())
+        );
+
+        unsafe { std::env::set_var(var, "") };
+        assert_eq!(
+            endpoint(None, var, Some("literal"))
+                .resolve_x_token()
+                .unwrap(),
+            Some("literal".to_string())
+        );
+    }
+
+    #[test]
+    fn test_unreadable_x_token_file_is_an_error() {
+        let missing = std::env::temp_dir().join("geyser-x-token-missing");
+        let endpoint = endpoint(
+            Some(missing.to_string_lossy().into_owned()),
+            "GEYSER_WATCHER_TEST_UNSET_X_TOKEN",
+            Some("literal"),
+        );
+        assert!(endpoint.resolve_x_token().is_err());
+    }
+}
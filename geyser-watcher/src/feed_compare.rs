@@ -0,0 +1,285 @@
+use {
+    crate::{
+        block_source::BlockInfo,
+        connection_pool::{GeyserConnectionPool, SubscribeStream},
+        keepalive::PingTracker,
+        reconnect::{ReconnectPolicy, Reconnector},
+    },
+    futures::{
+        sink::SinkExt,
+        stream::{self, StreamExt},
+    },
+    std::{
+        collections::{BTreeMap, HashMap},
+        fmt::Write,
+        time::{Duration, Instant},
+    },
+    tokio_util::sync::CancellationToken,
+    tracing::{info, warn},
+    yellowstone_grpc_proto::geyser::{
+        CommitmentLevel, SubscribeRequest, SubscribeRequestFilterBlocksMeta,
+        subscribe_update::UpdateOneof,
+    },
+};
+
+/// How far the newest slot must move past a slot before a feed that hasn't
+/// delivered it counts as having missed it (about a minute of slots)
+const MISSING_GRACE_SLOTS: u64 = 150;
+/// How often the running comparison is logged
+const REPORT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Which of two feeds delivers each block first, and which blocks only one of them
+/// delivers
+pub struct FeedComparison {
+    endpoints: [String; 2],
+    // Which feeds delivered each slot not yet settled
+    pending: BTreeMap<u64, [bool; 2]>,
+    // Slots at or below this are settled; copies arriving later are ignored
+    settled_through: Option<u64>,
+    first_arrivals: [u64; 2],
+    // Ranges of slots feed `i` never delivered but the other feed did
+    missing: [Vec<(u64, u64)>; 2],
+}
+
+impl FeedComparison {
+    pub fn new(endpoints: [String; 2]) -> Self {
+        Self {
+            endpoints,
+            pending: BTreeMap::new(),
+            settled_through: None,
+            first_arrivals: [0; 2],
+            missing: [Vec::new(), Vec::new()],
+        }
+    }
+
+    /// Record a block from `feed`; true for the first copy of its slot
+    pub fn on_block(&mut self, feed: usize, slot: u64) -> bool {
+        if self.settled_through.is_some_and(|settled| slot <= settled) {
+            return false;
+        }
+        let first = match self.pending.get_mut(&slot) {
+            Some(seen) => {
+                seen[feed] = true;
+                false
+            }
+            None => {
+                let mut seen = [false; 2];
+                seen[feed] = true;
+                self.pending.insert(slot, seen);
+                self.first_arrivals[feed] += 1;
+                true
+            }
+        };
+        self.settle(slot.saturating_sub(MISSING_GRACE_SLOTS));
+        first
+    }
+
+    // Decide every pending slot at or below `through`
+    fn settle(&mut self, through: u64) {
+        let later = self.pending.split_off(&(through + 1));
+        for (slot, seen) in std::mem::replace(&mut self.pending, later) {
+            if let Some(feed) = (0..2).find(|feed| !seen[*feed]) {
+                match self.missing[feed].last_mut() {
+                    Some((_, end)) if *end + 1 == slot => *end = slot,
+                    _ => self.missing[feed].push((slot, slot)),
+                }
+            }
+            self.settled_through = Some(slot);
+        }
+    }
+
+    pub fn log(&self) {
+        let total = self.first_arrivals[0] + self.first_arrivals[1];
+        if total == 0 {
+            return;
+        }
+        let percent = |feed: usize| self.first_arrivals[feed] as f64 * 100.0 / total as f64;
+        info!(
+            slots = total,
+            endpoint_a = %self.endpoints[0],
+            first_a_percent = format_args!("{:.1}", percent(0)),
+            missed_by_a = %format_ranges(&self.missing[0]),
+            endpoint_b = %self.endpoints[1],
+            first_b_percent = format_args!("{:.1}", percent(1)),
+            missed_by_b = %format_ranges(&self.missing[1]),
+            "feed comparison"
+        );
+    }
+}
+
+// "100-104, 230" or "none"
+fn format_ranges(ranges: &[(u64, u64)]) -> String {
+    if ranges.is_empty() {
+        return "none".to_string();
+    }
+    let mut formatted = String::new();
+    for (index, (start, end)) in ranges.iter().enumerate() {
+        if index > 0 {
+            formatted.push_str(", ");
+        }
+        match start == end {
+            true => write!(formatted, "{}", start),
+            false => write!(formatted, "{}-{}", start, end),
+        }
+        .expect("writing to a String");
+    }
+    formatted
+}
+
+/// Block headers only: enough to compare arrival order, far less data than blocks
+pub fn subscription_request(commitment: CommitmentLevel) -> SubscribeRequest {
+    SubscribeRequest {
+        blocks_meta: HashMap::from([("blocks".to_owned(), SubscribeRequestFilterBlocksMeta {})]),
+        commitment: Some(commitment as i32),
+        ..Default::default()
+    }
+}
+
+/// Both feeds and the comparison between them
+pub struct FeedRace {
+    pools: [GeyserConnectionPool; 2],
+    request: SubscribeRequest,
+    comparison: FeedComparison,
+    reconnector: Reconnector,
+    pings: PingTracker,
+    report_timer: tokio::time::Interval,
+}
+
+impl FeedRace {
+    pub fn new(
+        pools: [GeyserConnectionPool; 2],
+        endpoints: [String; 2],
+        request: SubscribeRequest,
+        reconnect: ReconnectPolicy,
+    ) -> Self {
+        Self {
+            pools,
+            request,
+            comparison: FeedComparison::new(endpoints),
+            reconnector: Reconnector::new(reconnect),
+            pings: PingTracker::default(),
+            report_timer: tokio::time::interval_at(
+                tokio::time::Instant::now() + REPORT_INTERVAL,
+                REPORT_INTERVAL,
+            ),
+        }
+    }
+
+    /// Subscribe to both endpoints at once and compare them until shutdown. Each
+    /// block is logged once, from whichever feed delivered it first. Losing either
+    /// feed resubscribes both after the usual backoff.
+    pub async fn run(mut self, shutdown: &CancellationToken) {
+        while !shutdown.is_cancelled() {
+            let result = self.compare(shutdown).await;
+            if shutdown.is_cancelled() {
+                break;
+            }
+            if let Err(e) = result {
+                warn!(error = %e, "feed comparison interrupted");
+            }
+            tokio::select! {
+                () = self.reconnector.wait("feed lost") => {}
+                () = shutdown.cancelled() => {}
+            }
+        }
+        self.comparison.log();
+    }
+
+    async fn compare(&mut self, shutdown: &CancellationToken) -> anyhow::Result<()> {
+        let (sink_a, stream_a, lease_a) = self.pools[0].subscribe(self.request.clone()).await?;
+        let (sink_b, stream_b, lease_b) = self.pools[1].subscribe(self.request.clone()).await?;
+        let leases = [lease_a, lease_b];
+        let mut sinks = [sink_a, sink_b];
+        self.reconnector.connected();
+        let endpoints = &self.comparison.endpoints;
+        info!(a = %endpoints[0], b = %endpoints[1], "comparing feeds");
+
+        // A `None` marks the end of that feed's stream
+        let tag = |feed: usize, stream: SubscribeStream| {
+            stream
+                .map(move |message| (feed, Some(message)))
+                .chain(stream::once(async move { (feed, None) }))
+        };
+        let merged = stream::select(tag(0, stream_a), tag(1, stream_b));
+        tokio::pin!(merged);
+
+        loop {
+            let (feed, message) = tokio::select! {
+                next = merged.next() => match next {
+                    Some(next) => next,
+                    None => return Ok(()),
+                },
+                _ = self.report_timer.tick() => {
+                    self.comparison.log();
+                    continue;
+                }
+                () = shutdown.cancelled() => return Ok(()),
+            };
+
+            match message {
+                Some(Ok(message)) => match message.update_oneof {
+                    Some(UpdateOneof::Ping(_)) => {
+                        sinks[feed].send(self.pings.ping(Instant::now())).await?;
+                    }
+                    Some(UpdateOneof::Pong(pong)) => {
+                        self.pings.on_pong(pong.id, Instant::now());
+                    }
+                    Some(UpdateOneof::BlockMeta(meta)) => {
+                        let block = BlockInfo::from(&meta);
+                        if self.comparison.on_block(feed, block.slot) {
+                            block.log();
+                        }
+                    }
+                    _ => {}
+                },
+                Some(Err(status)) => {
+                    self.pools[feed].discard(&leases[feed]);
+                    anyhow::bail!("{}: {}", self.comparison.endpoints[feed], status);
+                }
+                None => anyhow::bail!("{}: stream closed", self.comparison.endpoints[feed]),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comparison() -> FeedComparison {
+        FeedComparison::new(["https://eu".to_string(), "https://us".to_string()])
+    }
+
+    #[test]
+    fn test_each_slot_is_first_once() {
+        let mut comparison = comparison();
+        assert!(comparison.on_block(0, 100));
+        assert!(!comparison.on_block(1, 100));
+        assert!(comparison.on_block(1, 101));
+        assert!(!comparison.on_block(0, 101));
+        assert!(comparison.on_block(0, 102));
+        assert_eq!(comparison.first_arrivals, [2, 1]);
+    }
+
+    #[test]
+    fn test_slots_one_feed_never_delivered_become_ranges() {
+        let mut comparison = comparison();
+        for slot in 100..110 {
+            comparison.on_block(0, slot);
+            // Feed b skips 103..=105 and 108
+            if !(103..=105).contains(&slot) && slot != 108 {
+                comparison.on_block(1, slot);
+            }
+        }
+        // Nothing is settled until the newest slot is past the grace window
+        assert!(comparison.missing[1].is_empty());
+
+        comparison.on_block(0, 110 + MISSING_GRACE_SLOTS);
+        assert_eq!(comparison.missing[1], [(103, 105), (108, 108)]);
+        assert!(comparison.missing[0].is_empty());
+        assert_eq!(format_ranges(&comparison.missing[1]), "103-105, 108");
+
+        // A copy arriving after its slot settled is ignored
+        assert!(!comparison.on_block(1, 104));
+    }
+}
@@ -0,0 +1,229 @@
+use {
+    crate::watch_mode::WatchMode,
+    std::collections::{BTreeSet, HashMap},
+    tokio::sync::mpsc,
+    yellowstone_grpc_proto::geyser::{
+        CommitmentLevel, SubscribeRequest, SubscribeRequestFilterAccounts,
+        SubscribeRequestFilterTransactions,
+    },
+};
+
+/// New filters for the live subscription, sent to the stream reader
+pub struct FilterUpdate {
+    pub request: SubscribeRequest,
+    /// What the request covers, for the logs
+    pub streams: String,
+}
+
+/// What the worker needs to reload filters: the file to re-read, the subcommand
+/// narrowing it, the requests to do it and the way to the stream reader
+pub struct FilterReload {
+    pub config_path: String,
+    pub watch_mode: Option<WatchMode>,
+    pub requests: mpsc::Receiver<()>,
+    pub updates: mpsc::UnboundedSender<FilterUpdate>,
+}
+
+/// One reload request per SIGHUP; where there's no SIGHUP none ever come
+pub fn listen() -> mpsc::Receiver<()> {
+    let (requests, receiver) = mpsc::channel(1);
+    tokio::spawn(hangups(requests));
+    receiver
+}
+
+#[cfg(unix)]
+async fn hangups(requests: mpsc::Sender<()>) {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let Ok(mut hangup) = signal(SignalKind::hangup()) else {
+        tracing::warn!("can't listen for SIGHUP; filters won't be reloaded");
+        return;
+    };
+    while hangup.recv().await.is_some() {
+        // A reload still waiting picks up this change as well
+        let _ = requests.try_send(());
+    }
+}
+
+#[cfg(not(unix))]
+async fn hangups(_requests: mpsc::Sender<()>) {}
+
+/// What differs between two subscription requests, one line per change, e.g.
+/// `accounts filter 'accounts': account +Abc.., -Def..` or
+/// `commitment: confirmed -> processed`
+pub fn request_changes(old: &SubscribeRequest, new: &SubscribeRequest) -> Vec<String> {
+    let mut changes = Vec::new();
+    if old.commitment != new.commitment {
+        changes.push(format!(
+            "commitment: {} -> {}",
+            commitment_name(old.commitment),
+            commitment_name(new.commitment)
+        ));
+    }
+    changes.extend(filter_changes(
+        "accounts",
+        &old.accounts,
+        &new.accounts,
+        account_filter_changes,
+    ));
+    changes.extend(filter_changes(
+        "transactions",
+        &old.transactions,
+        &new.transactions,
+        transaction_filter_changes,
+    ));
+    changes.extend(filter_changes(
+        "blocks",
+        &old.blocks,
+        &new.blocks,
+        no_details,
+    ));
+    changes.extend(filter_changes(
+        "blocks_meta",
+        &old.blocks_meta,
+        &new.blocks_meta,
+        no_details,
+    ));
+    changes.extend(filter_changes("slots", &old.slots, &new.slots, no_details));
+    if old.accounts_data_slice != new.accounts_data_slice {
+        changes.push("account data slices changed".to_string());
+    }
+    changes
+}
+
+fn commitment_name(commitment: Option<i32>) -> String {
+    commitment
+        .and_then(|level| CommitmentLevel::try_from(level).ok())
+        .map_or("default".to_string(), |level| {
+            level.as_str_name().to_lowercase()
+        })
+}
+
+// Filters added, removed or changed under one kind. `details` names what changed
+// inside a filter; without any the filter is just reported as changed.
+fn filter_changes<F: PartialEq>(
+    kind: &str,
+    old: &HashMap<String, F>,
+    new: &HashMap<String, F>,
+    details: fn(&F, &F) -> Vec<String>,
+) -> Vec<String> {
+    let names: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    names
+        .into_iter()
+        .filter_map(|name| match (old.get(name), new.get(name)) {
+            (None, Some(_)) => Some(format!("{} filter '{}' added", kind, name)),
+            (Some(_), None) => Some(format!("{} filter '{}' removed", kind, name)),
+            (Some(old), Some(new)) if old != new => {
+                let details = details(old, new);
+                Some(if details.is_empty() {
+                    format!("{} filter '{}' changed", kind, name)
+                } else {
+                    format!("{} filter '{}': {}", kind, name, details.join("; "))
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn no_details<F>(_: &F, _: &F) -> Vec<String> {
+    Vec::new()
+}
+
+fn account_filter_changes(
+    old: &SubscribeRequestFilterAccounts,
+    new: &SubscribeRequestFilterAccounts,
+) -> Vec<String> {
+    let mut changes: Vec<String> = [
+        list_changes("account", &old.account, &new.account),
+        list_changes("owner", &old.owner, &new.owner),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    if old.filters != new.filters {
+        changes.push("data filters changed".to_string());
+    }
+    changes
+}
+
+fn transaction_filter_changes(
+    old: &SubscribeRequestFilterTransactions,
+    new: &SubscribeRequestFilterTransactions,
+) -> Vec<String> {
+    [
+        list_changes(
+            "account_include",
+            &old.account_include,
+            &new.account_include,
+        ),
+        list_changes(
+            "account_exclude",
+            &old.account_exclude,
+            &new.account_exclude,
+        ),
+        list_changes(
+            "account_required",
+            &old.account_required,
+            &new.account_required,
+        ),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+// `label +added, -removed`, or nothing when the lists hold the same entries
+fn list_changes(label: &str, old: &[String], new: &[String]) -> Option<String> {
+    let added = new.iter().filter(|entry| !old.contains(entry));
+    let removed = old.iter().filter(|entry| !new.contains(entry));
+    let entries: Vec<String> = added
+        .map(|entry| format!("+{}", entry))
+        .chain(removed.map(|entry| format!("-{}", entry)))
+        .collect();
+    (!entries.is_empty()).then(|| format!("{} {}", label, entries.join(", ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(accounts: &[&str], commitment: CommitmentLevel) -> SubscribeRequest {
+        SubscribeRequest {
+            accounts: HashMap::from([(
+                "accounts".to_string(),
+                SubscribeRequestFilterAccounts {
+                    account: accounts.iter().map(|account| account.to_string()).collect(),
+                    ..Default::default()
+                },
+            )]),
+            commitment: Some(commitment as i32),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_changes_name_accounts_and_commitment() {
+        let old = request(&["A", "B"], CommitmentLevel::Confirmed);
+        let mut new = request(&["B", "C"], CommitmentLevel::Processed);
+        new.transactions.insert(
+            "deposits".to_string(),
+            SubscribeRequestFilterTransactions::default(),
+        );
+
+        assert_eq!(
+            request_changes(&old, &new),
+            [
+                "commitment: confirmed -> processed",
+                "accounts filter 'accounts': account +C, -A",
+                "transactions filter 'deposits' added",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_same_request_has_no_changes() {
+        let request = request(&["A"], CommitmentLevel::Confirmed);
+        assert!(request_changes(&request, &request.clone()).is_empty());
+    }
+}
@@ -0,0 +1,170 @@
+use {
+    axum::{Router, extract::State, http::StatusCode, routing::get},
+    serde::{Deserialize, Serialize},
+    std::{
+        net::SocketAddr,
+        sync::{
+            Arc, Mutex,
+            atomic::{AtomicBool, Ordering},
+        },
+        time::{Duration, Instant},
+    },
+    tracing::error,
+};
+
+/// Liveness and readiness endpoints, e.g. for Kubernetes probes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthConfig {
+    /// Address `/healthz` and `/readyz` listen on
+    #[serde(default = "default_listen")]
+    pub listen: String,
+    /// Ready only while an update arrived within this many seconds. Finalized
+    /// subscriptions and quiet account filters need more than the default.
+    #[serde(default = "default_max_update_age_secs")]
+    pub max_update_age_secs: u64,
+}
+
+fn default_listen() -> String {
+    "0.0.0.0:8080".to_string()
+}
+
+fn default_max_update_age_secs() -> u64 {
+    30
+}
+
+#[derive(Default)]
+struct ReadinessState {
+    connected: AtomicBool,
+    last_update: Mutex<Option<Instant>>,
+}
+
+/// Stream state shared between the stream loop and the health server
+#[derive(Clone, Default)]
+pub struct Readiness(Arc<ReadinessState>);
+
+impl Readiness {
+    pub fn connected(&self) {
+        self.0.connected.store(true, Ordering::Relaxed);
+    }
+
+    /// Not ready again until the next subscription delivers an update
+    pub fn disconnected(&self) {
+        self.0.connected.store(false, Ordering::Relaxed);
+        *self.0.last_update.lock().unwrap() = None;
+    }
+
+    pub fn on_update(&self, now: Instant) {
+        *self.0.last_update.lock().unwrap() = Some(now);
+    }
+
+    /// Why the stream isn't ready, if it isn't
+    pub fn check(&self, now: Instant, max_update_age: Duration) -> Result<(), String> {
+        if !self.0.connected.load(Ordering::Relaxed) {
+            return Err("stream not connected".to_string());
+        }
+        match *self.0.last_update.lock().unwrap() {
+            None => Err("no update received yet".to_string()),
+            Some(at) if now.saturating_duration_since(at) > max_update_age => Err(format!(
+                "no update for {}s",
+                now.saturating_duration_since(at).as_secs()
+            )),
+            Some(_) => Ok(()),
+        }
+    }
+}
+
+async fn healthz() -> &'static str {
+    "ok"
+}
+
+async fn readyz(
+    State((readiness, max_update_age)): State<(Readiness, Duration)>,
+) -> (StatusCode, String) {
+    match readiness.check(Instant::now(), max_update_age) {
+        Ok(()) => (StatusCode::OK, "ready".to_string()),
+        Err(reason) => (StatusCode::SERVICE_UNAVAILABLE, reason),
+    }
+}
+
+/// Serve `/healthz` and `/readyz` in the background; returns the bound address
+pub fn serve(config: &HealthConfig, readiness: Readiness) -> anyhow::Result<SocketAddr> {
+    let address: SocketAddr = config
+        .listen
+        .parse()
+        .map_err(|e| anyhow::anyhow!("invalid health listen address {}: {}", config.listen, e))?;
+    let app = Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .with_state((readiness, Duration::from_secs(config.max_update_age_secs)));
+    let server = axum::Server::try_bind(&address)?.serve(app.into_make_service());
+    let bound = server.local_addr();
+    tokio::spawn(async move {
+        if let Err(e) = server.await {
+            error!(error = %e, "health server stopped");
+        }
+    });
+    Ok(bound)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_readiness_needs_a_connection_and_a_recent_update() {
+        let readiness = Readiness::default();
+        let max_age = Duration::from_secs(30);
+        let start = Instant::now();
+        assert!(readiness.check(start, max_age).is_err());
+
+        readiness.connected();
+        assert_eq!(
+            readiness.check(start, max_age).unwrap_err(),
+            "no update received yet"
+        );
+        readiness.on_update(start);
+        assert!(
+            readiness
+                .check(start + Duration::from_secs(30), max_age)
+                .is_ok()
+        );
+        assert_eq!(
+            readiness
+                .check(start + Duration::from_secs(31), max_age)
+                .unwrap_err(),
+            "no update for 31s"
+        );
+
+        readiness.on_update(start);
+        readiness.disconnected();
+        assert_eq!(
+            readiness.check(start, max_age).unwrap_err(),
+            "stream not connected"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_endpoints() {
+        let config = HealthConfig {
+            listen: "127.0.0.1:0".to_string(),
+            max_update_age_secs: 30,
+        };
+        let readiness = Readiness::default();
+        let address = serve(&config, readiness.clone()).unwrap();
+        let status = |path: &'static str| async move {
+            reqwest::get(format!("http://{}{}", address, path))
+                .await
+                .unwrap()
+                .status()
+        };
+
+        assert_eq!(status("/healthz").await, reqwest::StatusCode::OK);
+        assert_eq!(
+            status("/readyz").await,
+            reqwest::StatusCode::SERVICE_UNAVAILABLE
+        );
+        readiness.connected();
+        readiness.on_update(Instant::now());
+        assert_eq!(status("/readyz").await, reqwest::StatusCode::OK);
+    }
+}
@@ -0,0 +1,89 @@
+use {
+    std::{
+        collections::VecDeque,
+        time::{Duration, Instant},
+    },
+    yellowstone_grpc_proto::geyser::{SubscribeRequest, SubscribeRequestPing},
+};
+
+/// Pings still waiting for their pong; older ones are forgotten
+const MAX_OUTSTANDING_PINGS: usize = 16;
+
+/// Numbers our pings and times the server's pongs, matched by id
+#[derive(Default)]
+pub struct PingTracker {
+    next_id: i32,
+    outstanding: VecDeque<(i32, Instant)>,
+}
+
+impl PingTracker {
+    /// A ping request with the next id, sent at `now`
+    pub fn ping(&mut self, now: Instant) -> SubscribeRequest {
+        self.next_id = self.next_id.wrapping_add(1);
+        if self.outstanding.len() == MAX_OUTSTANDING_PINGS {
+            self.outstanding.pop_front();
+        }
+        self.outstanding.push_back((self.next_id, now));
+        SubscribeRequest {
+            ping: Some(SubscribeRequestPing { id: self.next_id }),
+            ..Default::default()
+        }
+    }
+
+    /// Round-trip time of the ping `id` answers, if we sent it
+    pub fn on_pong(&mut self, id: i32, now: Instant) -> Option<Duration> {
+        let index = self
+            .outstanding
+            .iter()
+            .position(|(sent_id, _)| *sent_id == id)?;
+        let (_, sent_at) = self.outstanding.remove(index)?;
+        Some(now.saturating_duration_since(sent_at))
+    }
+
+    /// Pings sent on a lost stream will never be answered
+    pub fn reset(&mut self) {
+        self.outstanding.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pongs_are_matched_by_id() {
+        let mut pings = PingTracker::default();
+        let start = Instant::now();
+        let first = pings.ping(start).ping.unwrap().id;
+        let second = pings
+            .ping(start + Duration::from_millis(10))
+            .ping
+            .unwrap()
+            .id;
+        assert_ne!(first, second);
+
+        assert_eq!(
+            pings.on_pong(second, start + Duration::from_millis(50)),
+            Some(Duration::from_millis(40))
+        );
+        // Each pong is only counted once, and unknown ids are ignored
+        assert_eq!(pings.on_pong(second, start), None);
+        assert_eq!(pings.on_pong(999, start), None);
+        assert_eq!(
+            pings.on_pong(first, start + Duration::from_millis(20)),
+            Some(Duration::from_millis(20))
+        );
+    }
+
+    #[test]
+    fn test_unanswered_pings_are_bounded() {
+        let mut pings = PingTracker::default();
+        let start = Instant::now();
+        let first = pings.ping(start).ping.unwrap().id;
+        for _ in 0..MAX_OUTSTANDING_PINGS {
+            pings.ping(start);
+        }
+        assert_eq!(pings.outstanding.len(), MAX_OUTSTANDING_PINGS);
+        assert_eq!(pings.on_pong(first, start), None);
+    }
+}
@@ -0,0 +1,26 @@
+use {
+    clap::ValueEnum,
+    tracing::Level,
+    tracing_subscriber::{EnvFilter, fmt},
+};
+
+/// How log events are written to stdout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// Human-readable lines
+    Pretty,
+    /// One JSON object per line, for log collectors
+    Json,
+}
+
+/// Install the global subscriber. `RUST_LOG` picks what is logged and falls back to
+/// `level`; e.g. `RUST_LOG=geyser_watcher=warn` drops the per-block events.
+pub fn init(format: LogFormat, level: Level) {
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(level.as_str().to_lowercase()));
+    let builder = fmt().with_env_filter(filter);
+    match format {
+        LogFormat::Pretty => builder.init(),
+        LogFormat::Json => builder.json().init(),
+    }
+}
@@ -1,248 +1,1410 @@
+mod account_change_detector;
+mod account_subscription;
+mod balance_triggers;
+mod block_source;
+mod block_stats;
+mod block_status;
+mod block_summary;
+mod bus_sink;
+mod config_migration;
+mod connection_pool;
+mod deposits;
+mod endpoints;
+mod feed_compare;
+mod filter_reload;
+mod health;
+mod keepalive;
+mod logging;
+mod metrics;
+mod postgres_sink;
+mod program_watch;
+mod reconnect;
+mod replay_dedup;
+mod shutdown;
+mod sink;
+mod slot_lag;
+mod slot_state;
+mod slot_tracker;
+mod stream_reader;
+mod system_transfers;
+mod transaction_watch;
+mod transfer_trigger;
+mod update_queue;
+mod watch_mode;
+mod webhook;
+
 use {
-    futures::{sink::SinkExt, stream::StreamExt},
+    account_change_detector::{AccountChangeDetector, AccountWatchConfig},
+    account_subscription::{AccountSubscriptionConfig, AccountUpdateTracker},
+    balance_triggers::{BalanceAction, BalanceTriggerConfig, BalanceTriggers},
+    block_source::{BlockFilterConfig, BlockInfo, BlockSubscriptionMode},
+    block_stats::{BLOCK_STATS_WINDOW, BlockStats},
+    block_status::{BlockStatusConfig, PendingBlocks},
+    block_summary::BlockSummary,
+    clap::{CommandFactory, Parser},
+    connection_pool::{GeyserConnectionConfig, GeyserConnectionPool},
+    deposits::{DepositDetector, DepositWatchConfig},
+    endpoints::{EndpointConfig, EndpointRotation},
+    feed_compare::FeedRace,
+    filter_reload::{FilterReload, FilterUpdate},
+    health::{HealthConfig, Readiness},
+    logging::LogFormat,
+    metrics::{Metrics, MetricsConfig},
+    program_watch::ProgramWatch,
+    reconnect::{ReconnectPolicy, Reconnector},
+    replay_dedup::{DedupConfig, ReplayDedup},
     serde::{Deserialize, Serialize},
-    // solana_client::rpc_client::RpcClient,
-    // solana_sdk::{
-    //     commitment_config::CommitmentConfig,
-    //     native_token::LAMPORTS_PER_SOL,
-    //     pubkey::Pubkey,
-    //     signature::{Keypair, Signer},
-    //     system_instruction,
-    //     transaction::Transaction,
-    // },
-    std::{collections::HashMap, fs, time::Duration},
-    tonic::transport::channel::ClientTlsConfig,
-    yellowstone_grpc_client::{GeyserGrpcClient, GeyserGrpcClientError},
-    yellowstone_grpc_proto::{
-        geyser::{
-            SubscribeRequest, SubscribeRequestFilterBlocks, SubscribeRequestPing,
-            subscribe_update::UpdateOneof,
-        },
-        tonic::service::Interceptor,
+    sink::{
+        AccountRecord, BlockRecord, BlockStatusRecord, Sink, SinkConfig, SinkUpdate,
+        TransactionRecord,
+    },
+    slot_lag::SlotLagMonitor,
+    slot_state::{SlotCheckpoint, StartMode},
+    slot_tracker::{SlotStatus, SlotTracker},
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_sdk::{
+        commitment_config::CommitmentConfig,
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        signature::{Keypair, Signer},
+    },
+    std::{
+        collections::HashMap,
+        fs,
+        str::FromStr,
+        time::{Duration, Instant},
+    },
+    stream_reader::{StreamReader, StreamSettings},
+    system_transfers::BlockTransferConfig,
+    tokio::sync::mpsc,
+    tokio_util::sync::CancellationToken,
+    tracing::{error, info, warn},
+    transaction_watch::{TransactionSummary, TransactionWatchConfig},
+    transfer_trigger::{TransferSender, TransferTrigger, TriggerConfig},
+    update_queue::{QueueConfig, QueueReceiver, Received, StreamEvent},
+    watch_mode::WatchMode,
+    webhook::{Webhook, WebhookConfig},
+    yellowstone_grpc_proto::geyser::{
+        CommitmentLevel, SubscribeRequest, SubscribeRequestFilterBlocksMeta,
+        SubscribeRequestFilterSlots, subscribe_update::UpdateOneof,
     },
 };
 
+#[derive(Debug, Parser)]
+#[command(
+    version,
+    about = "Watch blocks, transactions, accounts and slots over Yellowstone gRPC"
+)]
+struct Cli {
+    /// Config file to read. With a subcommand it's optional: a missing file means
+    /// defaults plus the subcommand's flags.
+    #[arg(
+        long,
+        value_name = "PATH",
+        default_value = "config.yaml",
+        global = true
+    )]
+    config: String,
+    /// Log triggered transfers instead of sending them, as `dry_run` does
+    #[arg(long)]
+    dry_run: bool,
+    /// Record every update to this JSONL file. Overrides `sink`.
+    #[arg(long, value_name = "PATH", global = true)]
+    output: Option<String>,
+    /// Lowest level logged when RUST_LOG isn't set: error, warn, info, debug or trace
+    #[arg(long, value_name = "LEVEL", default_value_t = tracing::Level::INFO)]
+    log_level: tracing::Level,
+    /// Shorthand for --log-level debug
+    #[arg(long, conflicts_with = "log_level")]
+    verbose: bool,
+    /// Print a shell completion script to stdout and exit
+    #[arg(long, value_name = "SHELL")]
+    completions: Option<clap_complete::Shell>,
+    /// Write a documented example config to PATH and exit
+    #[arg(long, value_name = "PATH")]
+    generate_config: Option<String>,
+    /// Log as human-readable lines or as one JSON object per line. Filter with
+    /// RUST_LOG, e.g. RUST_LOG=geyser_watcher=warn
+    #[arg(long, value_enum, default_value_t = LogFormat::Pretty)]
+    log_format: LogFormat,
+    /// Replay from this slot instead of the checkpoint or live, for a backfill
+    #[arg(long, value_name = "SLOT", value_parser = clap::value_parser!(u64).range(1..))]
+    from_slot: Option<u64>,
+
+    #[command(subcommand)]
+    mode: Option<WatchMode>,
+}
+
+// Example config with every field documented; kept parseable by a test
+const CONFIG_TEMPLATE: &str = include_str!("config_template.yaml");
+
+// How long shutdown waits for buffered sink rows and webhook deliveries
+const SHUTDOWN_DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct Config {
-    // /// Private key of the sender (base58 encoded)
-    // sender_private_key: String,
-    // /// Recipient wallet address
-    // recipient_address: String,
-    // /// Amount to transfer in SOL
-    // transfer_amount: f64,
-    // /// Solana RPC endpoint
-    // solana_rpc_url: String,
-    /// Geyser gRPC endpoint
-    geyser_endpoint: String,
-    /// X-Token for Geyser authentication
-    geyser_x_token: String,
+    /// Layout version the file was written for; older layouts are migrated on load
+    #[serde(default = "default_config_version")]
+    config_version: u32,
+    /// Private key of the sender (base58 encoded), for the transfer trigger
+    #[serde(default)]
+    sender_private_key: Option<String>,
+    /// Recipient wallet address, for the transfer trigger
+    #[serde(default)]
+    recipient_address: Option<String>,
+    /// Amount to transfer in SOL, for the transfer trigger
+    #[serde(default)]
+    transfer_amount: Option<f64>,
+    /// Send a SOL transfer on new blocks, rate limited
+    #[serde(default)]
+    trigger: Option<TriggerConfig>,
+    /// Actions fired when a watched account's balance changes
+    #[serde(default)]
+    triggers: Vec<BalanceTriggerConfig>,
+    /// Record processed updates to a file or database
+    #[serde(default)]
+    sink: Option<SinkConfig>,
+    /// POST every processed update to an HTTP endpoint
+    #[serde(default)]
+    webhook: Option<WebhookConfig>,
+    /// Serve Prometheus metrics over HTTP
+    #[serde(default)]
+    metrics: Option<MetricsConfig>,
+    /// Serve `/healthz` and `/readyz` over HTTP
+    #[serde(default)]
+    health: Option<HealthConfig>,
+    /// Geyser gRPC endpoints, in failover order; a failed connection or stream
+    /// moves to the next one
+    geyser_endpoint: Vec<EndpointConfig>,
+    /// Subscribe to the first two endpoints at once and report which delivers
+    /// blocks first, instead of watching
+    #[serde(default)]
+    compare_mode: bool,
+    /// Most Geyser connections kept open for subscriptions
+    #[serde(default = "default_pool_size")]
+    pool_size: usize,
+    /// PEM file with a CA certificate to trust besides the system roots, for
+    /// private endpoints with self-signed certificates
+    #[serde(default)]
+    tls_cert_pem_path: Option<String>,
+    /// Subscriptions carried by one connection before another is opened
+    #[serde(default = "default_max_streams_per_connection")]
+    max_streams_per_connection: usize,
+    /// Subscribe to block updates
+    #[serde(default = "default_watch_blocks")]
+    watch_blocks: bool,
+    /// Read blocks from full block updates or the lighter `blocks_meta`
+    #[serde(default)]
+    subscription_mode: BlockSubscriptionMode,
+    /// What full block updates carry, and which blocks are sent
+    #[serde(default)]
+    blocks: BlockFilterConfig,
+    /// Include transactions in full block updates and print the System Program
+    /// transfers in them
+    #[serde(default)]
+    block_transfers: Option<BlockTransferConfig>,
+    /// Commitment level for the subscription: processed, confirmed or finalized.
+    /// At processed, slot statuses are also streamed so printed blocks can be
+    /// marked confirmed, finalized or dead later.
+    #[serde(default = "default_commitment")]
+    commitment: String,
+    /// How many processed blocks are followed to a final status, and for how long
+    #[serde(default)]
+    block_status: BlockStatusConfig,
+    /// Optional transaction subscription, alongside or instead of blocks
+    #[serde(default)]
+    watch_transactions: Option<TransactionWatchConfig>,
+    /// Stream transactions calling this program and print its instructions
+    #[serde(default)]
+    program_filter: Option<String>,
+    /// Print program instruction data in base58 as well as hex
+    #[serde(default)]
+    program_data_base58: bool,
+    /// Report SOL received by these wallets as deposits, to the log, sink and webhook
+    #[serde(default)]
+    deposits: Option<DepositWatchConfig>,
+    /// Subscribe to slot status updates, reporting dead (forked-off) slots
+    #[serde(default)]
+    watch_slots: bool,
+    /// How often the slot head summary prints while watching slots
+    #[serde(default = "default_slot_summary_interval_secs")]
+    slot_summary_interval_secs: u64,
+    /// Log one block summary this often instead of a line per block, which drops
+    /// to debug level; unset keeps the per-block lines
+    #[serde(default)]
+    stats_interval_secs: Option<u64>,
+    /// Optional account update subscription by pubkey and/or owner
+    #[serde(default)]
+    accounts: Option<AccountSubscriptionConfig>,
+    /// Optional SOL balance tracking for a set of accounts
+    #[serde(default)]
+    account_watch: Option<AccountWatchConfig>,
+    /// Solana RPC endpoint: triggered transfers are sent through it, and the epoch
+    /// schedule is fetched from it to show epochs in block stats
+    #[serde(default)]
+    rpc_url: Option<String>,
+    /// How often the stream's slot is compared with `getSlot` from `rpc_url`; 0 turns
+    /// the check off
+    #[serde(default = "default_slot_lag_check_secs")]
+    slot_lag_check_secs: u64,
+    /// Backoff between reconnects after stream errors or failed connections
+    #[serde(default)]
+    reconnect: ReconnectPolicy,
+    /// Reconnect when no message at all, pings included, arrives for this long
+    #[serde(default = "default_stale_after_secs")]
+    stale_after_secs: u64,
+    /// Send our own ping this often, for proxies that drop connections without
+    /// client traffic; 0 only answers the server's pings
+    #[serde(default = "default_ping_interval_secs")]
+    ping_interval_secs: u64,
+    /// Buffer between reading the stream and processing its updates, and what to do
+    /// when processing falls that far behind
+    #[serde(default)]
+    queue: QueueConfig,
+    /// Recent slots and signatures remembered to skip what a resumed stream replays
+    #[serde(default)]
+    dedup: DedupConfig,
+    /// Timeouts, message size, flow control and keepalive of the gRPC connections
+    #[serde(default)]
+    connection: GeyserConnectionConfig,
+    /// File the last processed slot is saved to, so a restart resumes after it.
+    /// Reconnects resume from the last slot seen even without it.
+    #[serde(default)]
+    checkpoint_path: Option<String>,
+    /// Write the checkpoint once per this many processed slots
+    #[serde(default = "default_checkpoint_every_slots")]
+    checkpoint_every_slots: u64,
+    /// Log triggered transfers instead of sending them
+    #[serde(default)]
+    dry_run: bool,
+}
+
+fn default_config_version() -> u32 {
+    1
+}
+
+fn default_pool_size() -> usize {
+    1
+}
+
+fn default_max_streams_per_connection() -> usize {
+    100
+}
+
+fn default_watch_blocks() -> bool {
+    true
+}
+
+fn default_slot_summary_interval_secs() -> u64 {
+    10
+}
+
+fn default_slot_lag_check_secs() -> u64 {
+    30
+}
+
+fn default_stale_after_secs() -> u64 {
+    60
+}
+
+fn default_ping_interval_secs() -> u64 {
+    10
+}
+
+fn default_checkpoint_every_slots() -> u64 {
+    1
+}
+
+fn default_commitment() -> String {
+    "confirmed".to_string()
 }
 
 impl Config {
+    /// Write the documented example config, refusing to overwrite an existing file
+    fn generate_template(path: &str) -> anyhow::Result<()> {
+        if std::path::Path::new(path).exists() {
+            anyhow::bail!("{} already exists", path);
+        }
+        fs::write(path, CONFIG_TEMPLATE)?;
+        Ok(())
+    }
+
+    /// Every setting at its default and no endpoints, for a subcommand run without
+    /// a config file
+    fn defaults() -> anyhow::Result<Self> {
+        config_migration::parse_config(&format!(
+            "config_version: {}\ngeyser_endpoint: []\n",
+            config_migration::CURRENT_CONFIG_VERSION
+        ))
+    }
+
     fn load_from_file(path: &str) -> anyhow::Result<Self> {
         let content = fs::read_to_string(path)?;
 
-        let mut config: Config = serde_yaml::from_str(&content)?;
-        let geyser_x_token =
-            std::env::var("GEYSER_X_TOKEN").expect("env GEYSER_X_TOKEN must be set");
-        config.geyser_x_token = geyser_x_token;
+        let config: Config = config_migration::parse_config(&content)?;
+        if config.config_version < config_migration::CURRENT_CONFIG_VERSION {
+            warn!(
+                path,
+                from = config.config_version,
+                to = config_migration::CURRENT_CONFIG_VERSION,
+                "config uses an old layout version; it was migrated on load"
+            );
+        }
+        if config.geyser_endpoint.is_empty() {
+            anyhow::bail!("geyser_endpoint must list at least one endpoint");
+        }
 
         Ok(config)
     }
 
-    // fn get_sender_keypair(&self) -> anyhow::Result<Keypair> {
-    //     let private_key_bytes = bs58::decode(&self.sender_private_key).into_vec()?;
-    //     Ok(Keypair::from_bytes(&private_key_bytes)?)
-    // }
+    /// A connection pool for one endpoint, with the shared TLS and keepalive settings
+    fn connection_pool(&self, endpoint: &EndpointConfig) -> anyhow::Result<GeyserConnectionPool> {
+        let mut pool = GeyserConnectionPool::new(
+            endpoint.url.clone(),
+            endpoint.resolve_x_token()?,
+            self.pool_size,
+            self.max_streams_per_connection,
+        );
+        if let Some(path) = &self.tls_cert_pem_path {
+            let pem = fs::read(path)
+                .map_err(|e| anyhow::anyhow!("failed to read TLS certificate {}: {}", path, e))?;
+            pool = pool.with_ca_certificate(pem);
+        }
+        Ok(pool.with_connection_config(self.connection.clone()))
+    }
 
-    // fn get_recipient_pubkey(&self) -> anyhow::Result<Pubkey> {
-    //     Ok(Pubkey::from_str(&self.recipient_address)?)
-    // }
+    /// Every configured endpoint, in failover order
+    fn endpoint_rotation(&self) -> anyhow::Result<EndpointRotation> {
+        Ok(EndpointRotation::new(
+            self.geyser_endpoint
+                .iter()
+                .map(|endpoint| Ok((endpoint.url.clone(), self.connection_pool(endpoint)?)))
+                .collect::<anyhow::Result<_>>()?,
+        ))
+    }
+
+    /// This config with the subscription filters of `other`: the sections a SIGHUP
+    /// reloads without a restart
+    fn with_filters_of(&self, other: Config) -> Self {
+        Self {
+            commitment: other.commitment,
+            watch_transactions: other.watch_transactions,
+            program_filter: other.program_filter,
+            program_data_base58: other.program_data_base58,
+            deposits: other.deposits,
+            accounts: other.accounts,
+            blocks: other.blocks,
+            ..self.clone()
+        }
+    }
+
+    /// Reject settings that can't work together; a too-broad subscription only warns
+    fn validate(&self) -> anyhow::Result<()> {
+        self.connection.validate()?;
+        if self.stats_interval_secs == Some(0) {
+            anyhow::bail!(
+                "stats_interval_secs must be at least 1; leave it out for a line per block"
+            );
+        }
+        if self.checkpoint_every_slots == 0 {
+            anyhow::bail!("checkpoint_every_slots must be at least 1");
+        }
+
+        if !self.watch_blocks
+            && self.watch_transactions.is_none()
+            && self.program_filter.is_none()
+            && self.deposits.is_none()
+            && self.accounts.is_none()
+            && !self.watch_slots
+            && self.triggers.is_empty()
+        {
+            anyhow::bail!(
+                "nothing to watch: enable watch_blocks/watch_slots or configure watch_transactions/program_filter/deposits/accounts/triggers"
+            );
+        }
+
+        if self.block_transfers.is_some()
+            && (!self.watch_blocks || self.subscription_mode != BlockSubscriptionMode::Blocks)
+        {
+            anyhow::bail!("block_transfers needs watch_blocks with subscription_mode blocks");
+        }
+
+        if self.watch_blocks
+            && self.subscription_mode == BlockSubscriptionMode::Blocks
+            && self.blocks.is_broad(self.block_transfers.is_some())
+        {
+            warn!(
+                "every block is streamed with its transactions or accounts; expect heavy \
+                 bandwidth use, or narrow it with blocks.account_include"
+            );
+        }
+        Ok(())
+    }
+
+    fn commitment_level(&self) -> anyhow::Result<CommitmentLevel> {
+        match self.commitment.as_str() {
+            "processed" => Ok(CommitmentLevel::Processed),
+            "confirmed" => Ok(CommitmentLevel::Confirmed),
+            "finalized" => Ok(CommitmentLevel::Finalized),
+            other => anyhow::bail!(
+                "invalid commitment '{}': expected processed, confirmed or finalized",
+                other
+            ),
+        }
+    }
 
-    // fn get_transfer_amount_lamports(&self) -> u64 {
-    //     (self.transfer_amount * LAMPORTS_PER_SOL as f64) as u64
-    // }
+    fn get_sender_keypair(&self) -> anyhow::Result<Keypair> {
+        let private_key = self
+            .sender_private_key
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("sender_private_key is required for transfers"))?;
+        let private_key_bytes = bs58::decode(private_key).into_vec()?;
+        Ok(Keypair::from_bytes(&private_key_bytes)?)
+    }
+
+    fn get_recipient_pubkey(&self) -> anyhow::Result<Pubkey> {
+        let recipient = self
+            .recipient_address
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("recipient_address is required for transfers"))?;
+        Ok(Pubkey::from_str(recipient)?)
+    }
+
+    fn get_transfer_amount_lamports(&self) -> anyhow::Result<u64> {
+        let amount = self
+            .transfer_amount
+            .ok_or_else(|| anyhow::anyhow!("transfer_amount is required by the trigger"))?;
+        Ok((amount * LAMPORTS_PER_SOL as f64) as u64)
+    }
+
+    // The transfer trigger, when enabled and fully configured
+    fn transfer_trigger(&self) -> anyhow::Result<Option<TransferTrigger>> {
+        let Some(trigger) = self.trigger.as_ref().filter(|trigger| trigger.enabled) else {
+            return Ok(None);
+        };
+        if !self.watch_blocks {
+            anyhow::bail!("the transfer trigger needs watch_blocks");
+        }
+        let sender = self.transfer_sender()?;
+        let lamports = self.get_transfer_amount_lamports()?;
+
+        info!(
+            amount_sol = lamports as f64 / LAMPORTS_PER_SOL as f64,
+            condition = %trigger.condition,
+            every_n_blocks = trigger.every_n_blocks,
+            cooldown_secs = trigger.cooldown_secs,
+            max_transfers_per_hour = trigger.max_transfers_per_hour,
+            "transfer trigger enabled"
+        );
+        Ok(Some(TransferTrigger::new(trigger, sender, lamports)))
+    }
+
+    // Balance triggers, when any are configured
+    fn balance_triggers(&self) -> anyhow::Result<Option<BalanceTriggers>> {
+        if self.triggers.is_empty() {
+            return Ok(None);
+        }
+        let transfer_sender = self
+            .triggers
+            .iter()
+            .any(|trigger| trigger.action == BalanceAction::Transfer)
+            .then(|| self.transfer_sender())
+            .transpose()?;
+        Ok(Some(BalanceTriggers::new(
+            self.triggers.clone(),
+            transfer_sender,
+        )?))
+    }
+
+    // Sender and recipient shared by every configured transfer
+    fn transfer_sender(&self) -> anyhow::Result<TransferSender> {
+        let rpc_url = self
+            .rpc_url
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("rpc_url is required for transfers"))?;
+        let sender = self.get_sender_keypair()?;
+        let recipient = self.get_recipient_pubkey()?;
+
+        info!(sender = %sender.pubkey(), recipient = %recipient, "transfer wallets");
+        Ok(TransferSender::new(rpc_url, sender, recipient).with_dry_run(self.dry_run))
+    }
 }
 
 struct SolTransferBot {
     config: Config,
-    // solana_client: RpcClient,
+    account_detector: Option<AccountChangeDetector>,
+    account_tracker: Option<AccountUpdateTracker>,
+    slot_tracker: Option<SlotTracker>,
+    block_stats: BlockStats,
+    // Interval totals, when summaries replace the per-block lines
+    block_summary: Option<BlockSummary>,
+    commitment: CommitmentLevel,
+    // Blocks printed at processed commitment, until their slot is finalized or dies
+    pending_blocks: Option<PendingBlocks>,
+    checkpoint: SlotCheckpoint,
+    // Where the first subscription starts; later ones follow the checkpoint
+    start_mode: StartMode,
+    // First slot lost when the server refused to replay; the next subscription is
+    // live-only and reports the gap once its first block arrives
+    replay_gap_start: Option<u64>,
+    transfer_trigger: Option<TransferTrigger>,
+    balance_triggers: Option<BalanceTriggers>,
+    program_watch: Option<ProgramWatch>,
+    deposit_detector: Option<DepositDetector>,
+    // Blocks and transactions already processed, for the overlap a resume replays
+    dedup: ReplayDedup,
+    sink: Option<Sink>,
+    webhook: Option<Webhook>,
+    metrics: Metrics,
+    readiness: Readiness,
+    slot_lag: Option<SlotLagMonitor>,
 }
 
 impl SolTransferBot {
     fn new(config: Config) -> anyhow::Result<Self> {
-        // let solana_client = RpcClient::new_with_commitment(
-        //     config.solana_rpc_url.clone(),
-        //     CommitmentConfig::confirmed(),
-        // );
+        config.validate()?;
+        let commitment = config.commitment_level()?;
+        let transfer_trigger = config.transfer_trigger()?;
+        let balance_triggers = config.balance_triggers()?;
+        let program_watch = config
+            .program_filter
+            .as_deref()
+            .map(|program| ProgramWatch::new(program, config.program_data_base58))
+            .transpose()?;
+        let sink = config.sink.as_ref().map(Sink::open).transpose()?;
+        let webhook = config.webhook.as_ref().map(Webhook::new).transpose()?;
+        let deposit_detector = config.deposits.as_ref().map(DepositDetector::new);
+        let dedup = ReplayDedup::new(&config.dedup);
+
+        let account_detector = config
+            .account_watch
+            .as_ref()
+            .map(AccountChangeDetector::new)
+            .transpose()?;
+
+        let checkpoint = SlotCheckpoint::load(
+            config.checkpoint_path.as_deref(),
+            config.checkpoint_every_slots,
+        )?;
 
         Ok(Self {
+            start_mode: StartMode::new(None, &checkpoint),
+            checkpoint,
+            replay_gap_start: None,
+            block_stats: BlockStats::new(BLOCK_STATS_WINDOW),
+            block_summary: config
+                .stats_interval_secs
+                .map(|_| BlockSummary::new(Instant::now())),
+            commitment,
+            pending_blocks: (config.watch_blocks && commitment == CommitmentLevel::Processed)
+                .then(|| PendingBlocks::new(&config.block_status)),
+            account_tracker: config.accounts.as_ref().map(AccountUpdateTracker::new),
+            slot_tracker: config
+                .watch_slots
+                .then(|| SlotTracker::new(Duration::from_secs(config.slot_summary_interval_secs))),
             config,
-            // solana_client,
+            account_detector,
+            transfer_trigger,
+            balance_triggers,
+            program_watch,
+            deposit_detector,
+            dedup,
+            sink,
+            webhook,
+            metrics: Metrics::new()?,
+            readiness: Readiness::default(),
+            slot_lag: None,
         })
     }
 
-    async fn connect_geyser(&self) -> anyhow::Result<GeyserGrpcClient<impl Interceptor>> {
-        let client = GeyserGrpcClient::build_from_shared(self.config.geyser_endpoint.clone())?
-            .x_token(Some(self.config.geyser_x_token.clone()))?
-            .connect_timeout(Duration::from_secs(10))
-            .timeout(Duration::from_secs(10))
-            .tls_config(ClientTlsConfig::new().with_native_roots())?
-            .max_decoding_message_size(1024 * 1024 * 1024)
-            .connect()
-            .await?;
+    // Compare the stream's slot with the RPC endpoint's, when one is configured
+    fn start_slot_lag_monitor(&mut self) -> anyhow::Result<()> {
+        let Some(rpc_url) = &self.config.rpc_url else {
+            return Ok(());
+        };
+        if self.config.slot_lag_check_secs == 0 {
+            return Ok(());
+        }
+        let commitment = CommitmentConfig::from_str(&self.config.commitment)
+            .map_err(|e| anyhow::anyhow!("invalid commitment: {}", e))?;
+        self.slot_lag = Some(SlotLagMonitor::start(
+            rpc_url.clone(),
+            commitment,
+            Duration::from_secs(self.config.slot_lag_check_secs),
+            self.metrics.slot_lag(),
+        ));
+        Ok(())
+    }
+
+    // Start the metrics endpoint, when configured
+    fn serve_metrics(&self) -> anyhow::Result<()> {
+        if let Some(config) = &self.config.metrics {
+            let address = self.metrics.serve(&config.listen)?;
+            info!(%address, "serving metrics at /metrics");
+        }
+        Ok(())
+    }
+
+    // Start the health endpoints, when configured
+    fn serve_health(&self) -> anyhow::Result<()> {
+        if let Some(config) = &self.config.health {
+            let address = health::serve(config, self.readiness.clone())?;
+            info!(%address, "serving /healthz and /readyz");
+        }
+        Ok(())
+    }
 
-        Ok(client)
+    // Replay from `slot` rather than the checkpoint or live
+    fn start_from_slot(&mut self, slot: u64) {
+        self.start_mode = StartMode::Cli(slot);
     }
 
-    fn create_block_subscription_request(&self) -> SubscribeRequest {
+    // Drain the sinks and webhook, write any checkpoint still held back, then log
+    // the final block stats
+    async fn shutdown(mut self) {
+        let drain = async {
+            if let Some(sink) = self.sink {
+                let closed = sink.close().await;
+                if let Err(e) = closed {
+                    warn!(error = %e, "failed to flush the sink");
+                }
+            }
+            if let Some(webhook) = self.webhook {
+                webhook.close().await;
+            }
+        };
+        if tokio::time::timeout(SHUTDOWN_DRAIN_TIMEOUT, drain)
+            .await
+            .is_err()
+        {
+            warn!(
+                timeout_secs = SHUTDOWN_DRAIN_TIMEOUT.as_secs(),
+                "gave up waiting for the sink and webhook to drain"
+            );
+        }
+
+        if let Err(e) = self.checkpoint.flush().await {
+            warn!(error = %e, "failed to save slot checkpoint");
+        }
+
+        let report = self.block_stats.report();
+        if report.current_slot.is_some() {
+            report.log();
+        }
+        info!(slot = self.checkpoint.last_slot(), "stopped");
+    }
+
+    // Blocks, transactions, accounts and slots, whichever are configured, in one
+    // request; the stream reader fills in `from_slot`
+    fn create_subscription_request(&self) -> SubscribeRequest {
         let mut blocks = HashMap::new();
+        let mut blocks_meta = HashMap::new();
+        match (self.config.watch_blocks, self.config.subscription_mode) {
+            (false, _) => {}
+            (true, BlockSubscriptionMode::BlocksMeta) => {
+                blocks_meta.insert("blocks".to_owned(), SubscribeRequestFilterBlocksMeta {});
+            }
+            (true, BlockSubscriptionMode::Blocks) => {
+                blocks.insert(
+                    "blocks".to_owned(),
+                    self.config
+                        .blocks
+                        .filter(self.config.block_transfers.is_some()),
+                );
+            }
+        }
 
-        blocks.insert(
-            "blocks".to_owned(),
-            SubscribeRequestFilterBlocks {
-                account_include: vec![],
-                include_transactions: Some(false),
-                include_accounts: Some(false),
-                include_entries: Some(false),
-            },
-        );
+        let mut transactions = HashMap::new();
+        if let Some(watch) = &self.config.watch_transactions {
+            transactions.insert("transactions".to_owned(), watch.filter());
+        }
+        if let Some(watch) = &self.program_watch {
+            transactions.insert("program".to_owned(), watch.filter());
+        }
+        if let Some(deposits) = &self.config.deposits {
+            transactions.insert("deposits".to_owned(), deposits.filter());
+        }
+
+        let mut accounts = HashMap::new();
+        if let Some(watch) = &self.config.accounts {
+            accounts.insert("accounts".to_owned(), watch.filter());
+        }
+        // Kept apart so the data filters above don't hide trigger accounts
+        if let Some(triggers) = &self.balance_triggers {
+            accounts.insert(
+                "balance_triggers".to_owned(),
+                AccountSubscriptionConfig {
+                    pubkeys: triggers.accounts(),
+                    ..Default::default()
+                }
+                .filter(),
+            );
+        }
+
+        let mut slots = HashMap::new();
+        if self.watches_slots() {
+            slots.insert(
+                "slots".to_owned(),
+                SubscribeRequestFilterSlots {
+                    // Every status change, not just the request's commitment level
+                    filter_by_commitment: Some(false),
+                    ..Default::default()
+                },
+            );
+        }
 
         SubscribeRequest {
-            accounts: HashMap::default(),
-            slots: HashMap::default(),
-            transactions: HashMap::default(),
+            accounts,
+            slots,
+            transactions,
             transactions_status: HashMap::default(),
             blocks,
-            blocks_meta: HashMap::default(),
+            blocks_meta,
             entry: HashMap::default(),
-            commitment: Some(yellowstone_grpc_proto::geyser::CommitmentLevel::Confirmed as i32),
-            accounts_data_slice: Vec::default(),
+            commitment: Some(self.commitment as i32),
+            accounts_data_slice: self
+                .config
+                .accounts
+                .as_ref()
+                .map(AccountSubscriptionConfig::data_slices)
+                .unwrap_or_default(),
             ping: None,
             from_slot: None,
         }
     }
 
-    // async fn transfer_sol(&self) -> anyhow::Result<String> {
-    //     let sender_keypair = self.config.get_sender_keypair()?;
-    //     let recipient_pubkey = self.config.get_recipient_pubkey()?;
-    //     let amount_lamports = self.config.get_transfer_amount_lamports();
-
-    //     println!(
-    //         "Transferring {} SOL from {} to {}",
-    //         self.config.transfer_amount,
-    //         sender_keypair.pubkey(),
-    //         recipient_pubkey
-    //     );
-
-    //     // Get recent blockhash
-    //     let recent_blockhash = self.solana_client.get_latest_blockhash()?;
-
-    //     // Create transfer instruction
-    //     let transfer_instruction = system_instruction::transfer(
-    //         &sender_keypair.pubkey(),
-    //         &recipient_pubkey,
-    //         amount_lamports,
-    //     );
-
-    //     // Create and sign transaction
-    //     let transaction = Transaction::new_signed_with_payer(
-    //         &[transfer_instruction],
-    //         Some(&sender_keypair.pubkey()),
-    //         &[&sender_keypair],
-    //         recent_blockhash,
-    //     );
-
-    //     // Send transaction
-    //     let signature = self
-    //         .solana_client
-    //         .send_and_confirm_transaction(&transaction)?;
-
-    //     println!("Transfer successful! Signature: {}", signature);
-    //     Ok(signature.to_string())
-    // }
-
-    async fn run(&self) -> anyhow::Result<()> {
-        let mut geyser_client = self.connect_geyser().await?;
-        let request = self.create_block_subscription_request();
-        let (mut subscribe_tx, mut stream) =
-            geyser_client.subscribe_with_request(Some(request)).await?;
-
-        println!("Subscribed to new blocks. Waiting for blocks...");
-
-        while let Some(message) = stream.next().await {
-            match message {
-                Ok(msg) => match msg.update_oneof {
-                    Some(UpdateOneof::Block(block_update)) => {
-                        println!(
-                            "🆕 New block detected! Slot: {}, Hash: {}, Height: {:?}",
-                            block_update.slot, block_update.blockhash, block_update.block_height
-                        );
+    // What the stream reader needs to subscribe for this bot
+    fn stream_settings(&self) -> StreamSettings {
+        StreamSettings {
+            request: self.create_subscription_request(),
+            streams: self.subscription_name(),
+            stale_after: Duration::from_secs(self.config.stale_after_secs),
+            ping_interval_secs: self.config.ping_interval_secs,
+            watch_blocks: self.config.watch_blocks,
+            resume_after: self.start_mode.resume_after(),
+        }
+    }
 
-                        // Execute SOL transfer (commented out)
-                        // match self.transfer_sol().await {
-                        //     Ok(signature) => {
-                        //         println!("✅ SOL transfer completed: {}", signature);
-                        //     }
-                        //     Err(e) => {
-                        //         println!("❌ Failed to transfer SOL: {}", e);
-                        //     }
-                        // }
-                    }
-                    Some(UpdateOneof::Ping(_)) => {
-                        subscribe_tx
-                            .send(SubscribeRequest {
-                                ping: Some(SubscribeRequestPing { id: 1 }),
-                                ..Default::default()
-                            })
-                            .await?;
+    // Everything that happens per block, whichever update type it came from
+    async fn on_block(&mut self, block: BlockInfo, received: Received) {
+        match &mut self.block_summary {
+            Some(summary) => {
+                block.log_debug();
+                summary.record(&block, received.at);
+            }
+            None => block.log(),
+        }
+        let received_at = received.at;
+        let latency_ms = block.latency_ms(received.unix_ms);
+        self.metrics.on_block(latency_ms, received_at);
+        if let Some(pending) = &mut self.pending_blocks {
+            pending.on_block(block.slot, &block.blockhash, received_at);
+        }
+        self.on_slot_processed(block.slot).await;
+
+        self.block_stats.record_block(
+            block.slot,
+            block.transaction_count as u32,
+            received_at,
+            latency_ms,
+        );
+        if let Some(mut report) = self.block_stats.report_if_due() {
+            report.sink_dropped = self.sink.as_ref().and_then(Sink::dropped);
+            report.slot_lag = self.slot_lag.as_ref().and_then(SlotLagMonitor::lag);
+            self.metrics.on_block_stats(&report);
+            report.log();
+        }
+
+        if let Some(detector) = &mut self.account_detector {
+            detector.on_block(block.slot).await;
+        }
+        if let Some(detector) = &mut self.deposit_detector {
+            detector.on_block(block.slot, block.block_time);
+        }
+
+        if let Some(trigger) = &mut self.transfer_trigger {
+            trigger.on_block(&block);
+        }
+    }
+
+    // A failing sink or webhook is reported but never stops the stream
+    fn record(&mut self, update: SinkUpdate) {
+        if let Some(Err(e)) = self.sink.as_mut().map(|sink| sink.write(&update)) {
+            warn!(error = %e, "failed to write to the sink");
+        }
+        if let Some(webhook) = &mut self.webhook {
+            webhook.send(&update);
+        }
+    }
+
+    // Best effort: without the schedule block stats just leave epochs out
+    async fn load_epoch_schedule(&mut self) {
+        let Some(rpc_url) = &self.config.rpc_url else {
+            return;
+        };
+        match RpcClient::new(rpc_url.clone()).get_epoch_schedule().await {
+            Ok(schedule) => {
+                self.block_stats =
+                    BlockStats::new(BLOCK_STATS_WINDOW).with_epoch_schedule(schedule);
+            }
+            Err(e) => warn!(error = %e, "failed to fetch the epoch schedule"),
+        }
+    }
+
+    // Report any gap left by a refused replay, then advance the checkpoint
+    async fn on_slot_processed(&mut self, slot: u64) {
+        if let Some(gap_start) = self
+            .replay_gap_start
+            .take()
+            .filter(|gap_start| slot > *gap_start)
+        {
+            warn!(
+                first = gap_start,
+                last = slot - 1,
+                "missed slots (not replayed)"
+            );
+        }
+        if let Err(e) = self.checkpoint.record(slot).await {
+            warn!(error = %e, "failed to save slot checkpoint");
+        }
+        self.metrics.on_slot_processed(slot);
+        if let Some(monitor) = &self.slot_lag {
+            monitor.on_slot(slot);
+        }
+    }
+
+    // Slot statuses are needed for the slot report and to follow processed blocks
+    fn watches_slots(&self) -> bool {
+        self.config.watch_slots || self.pending_blocks.is_some()
+    }
+
+    fn subscription_name(&self) -> String {
+        [
+            (self.config.watch_blocks, "blocks"),
+            (
+                self.config.watch_transactions.is_some()
+                    || self.program_watch.is_some()
+                    || self.deposit_detector.is_some(),
+                "transactions",
+            ),
+            (
+                self.config.accounts.is_some() || self.balance_triggers.is_some(),
+                "accounts",
+            ),
+            (self.watches_slots(), "slots"),
+        ]
+        .into_iter()
+        .filter(|(enabled, _)| *enabled)
+        .map(|(_, name)| name)
+        .collect::<Vec<_>>()
+        .join(" and ")
+    }
+
+    // Re-read the config file and switch the subscription to its filters. A file
+    // that doesn't load or validate is reported and the current filters stay.
+    fn reload_filters(&mut self, reload: &FilterReload) {
+        info!(path = %reload.config_path, "reloading filters");
+        let changes = match self.apply_filters(&reload.config_path, reload.watch_mode.as_ref()) {
+            Ok(changes) => changes,
+            Err(e) => {
+                warn!(error = %e, "config reload rejected; keeping the current filters");
+                return;
+            }
+        };
+        if changes.is_empty() {
+            info!("config reloaded; filters unchanged");
+            return;
+        }
+        for change in &changes {
+            info!(change = %change, "filter changed");
+        }
+        let _ = reload.updates.send(FilterUpdate {
+            request: self.create_subscription_request(),
+            streams: self.subscription_name(),
+        });
+    }
+
+    // Take the filter sections of the config at `path`, narrowed by the subcommand
+    // if one was given, returning what that changes in the subscription
+    fn apply_filters(
+        &mut self,
+        path: &str,
+        watch_mode: Option<&WatchMode>,
+    ) -> anyhow::Result<Vec<String>> {
+        let mut file = Config::load_from_file(path)?;
+        if let Some(mode) = watch_mode {
+            mode.apply(&mut file);
+        }
+        let config = self.config.with_filters_of(file);
+        config.validate()?;
+        let commitment = config.commitment_level()?;
+        let program_watch = config
+            .program_filter
+            .as_deref()
+            .map(|program| ProgramWatch::new(program, config.program_data_base58))
+            .transpose()?;
+
+        let old_request = self.create_subscription_request();
+        self.commitment = commitment;
+        self.program_watch = program_watch;
+        self.deposit_detector = config.deposits.as_ref().map(DepositDetector::new);
+        self.account_tracker = config.accounts.as_ref().map(AccountUpdateTracker::new);
+        if !config.watch_blocks || commitment != CommitmentLevel::Processed {
+            self.pending_blocks = None;
+        } else if self.pending_blocks.is_none() {
+            self.pending_blocks = Some(PendingBlocks::new(&config.block_status));
+        }
+        self.config = config;
+        Ok(filter_reload::request_changes(
+            &old_request,
+            &self.create_subscription_request(),
+        ))
+    }
+
+    // Process everything the stream reader queues, until it stops and the queue is
+    // empty, then shut down. Reload requests swap in new filters meanwhile.
+    async fn work(mut self, queue: QueueReceiver, mut reload: FilterReload) {
+        let summary_interval = Duration::from_secs(self.config.stats_interval_secs.unwrap_or(60));
+        let mut summary_timer = tokio::time::interval_at(
+            tokio::time::Instant::now() + summary_interval,
+            summary_interval,
+        );
+        loop {
+            let event = tokio::select! {
+                event = queue.recv() => event,
+                Some(()) = reload.requests.recv() => {
+                    self.reload_filters(&reload);
+                    continue;
+                }
+                _ = summary_timer.tick(), if self.block_summary.is_some() => {
+                    self.log_block_summary();
+                    continue;
+                }
+            };
+            let Some(event) = event else {
+                break;
+            };
+            match event {
+                StreamEvent::Update(update, received) => {
+                    self.process_update(*update, received).await;
+                }
+                StreamEvent::Idle => {
+                    // Quiet streams still get their buffered lines written out
+                    if let Some(Err(e)) = self.sink.as_mut().map(Sink::flush) {
+                        warn!(error = %e, "failed to flush the sink");
                     }
-                    Some(UpdateOneof::Pong(_)) => {
-                        // Pong received, connection is healthy
+                }
+                StreamEvent::ReplayRefused(from_slot) => {
+                    self.replay_gap_start = Some(from_slot);
+                }
+            }
+        }
+        if queue.dropped() > 0 {
+            warn!(
+                dropped = queue.dropped(),
+                "updates dropped because processing fell behind"
+            );
+        }
+        self.shutdown().await;
+    }
+
+    // Log the interval's block summary and start the next one
+    fn log_block_summary(&mut self) {
+        let Some(summary) = &mut self.block_summary else {
+            return;
+        };
+        let mut snapshot = summary.snapshot(Instant::now());
+        snapshot.slot_lag = self.slot_lag.as_ref().and_then(SlotLagMonitor::lag);
+        snapshot.log();
+    }
+
+    // False for a block or transaction already processed; a stream resumed from an
+    // earlier slot delivers those again
+    fn first_delivery(&mut self, update: &UpdateOneof) -> bool {
+        match update {
+            UpdateOneof::Block(block) => self.dedup.first_block(block.slot),
+            UpdateOneof::BlockMeta(block_meta) => self.dedup.first_block(block_meta.slot),
+            UpdateOneof::Transaction(transaction_update) => transaction_update
+                .transaction
+                .as_ref()
+                .is_none_or(|info| self.dedup.first_transaction(&info.signature)),
+            _ => true,
+        }
+    }
+
+    // Handle one data update; pings, pongs and empty messages never reach the queue
+    async fn process_update(&mut self, update: UpdateOneof, received: Received) {
+        if let Some(summary) = &mut self.block_summary {
+            summary.record_bytes(update.encoded_len());
+        }
+        if !self.first_delivery(&update) {
+            self.metrics.on_duplicate_skipped();
+            return;
+        }
+        match update {
+            UpdateOneof::Block(block_update) => {
+                let block = BlockInfo::from(&block_update);
+                self.record(SinkUpdate::Block(BlockRecord::from(&block)));
+                if let Some(config) = &self.config.block_transfers {
+                    for transfer in system_transfers::block_transfers(config, &block_update) {
+                        transfer.log();
                     }
-                    None => {
-                        println!("❌ Empty update received");
-                        break;
+                }
+                self.on_block(block, received).await;
+            }
+            UpdateOneof::BlockMeta(block_meta) => {
+                let block = BlockInfo::from(&block_meta);
+                self.record(SinkUpdate::BlockMeta(BlockRecord::from(&block)));
+                self.on_block(block, received).await;
+            }
+            UpdateOneof::Transaction(transaction_update) => {
+                self.metrics.on_transaction();
+                if let Some(record) = TransactionRecord::from_update(&transaction_update) {
+                    self.record(SinkUpdate::Transaction(record));
+                }
+                if let Some(summary) =
+                    self.config.watch_transactions.as_ref().and_then(|watch| {
+                        TransactionSummary::from_update(watch, &transaction_update)
+                    })
+                {
+                    summary.log();
+                }
+                if let Some(watch) = &self.program_watch
+                    && let Some(activity) = watch.activity(&transaction_update)
+                {
+                    watch.log(&activity);
+                }
+                let deposits = self
+                    .deposit_detector
+                    .as_mut()
+                    .map(|detector| detector.on_transaction(&transaction_update))
+                    .unwrap_or_default();
+                for deposit in deposits {
+                    deposit.log();
+                    self.record(SinkUpdate::Deposit(deposit));
+                }
+
+                // More of this slot's transactions may still be coming, so only the
+                // slot before it is known to be complete
+                if !self.config.watch_blocks {
+                    self.on_slot_processed(transaction_update.slot.saturating_sub(1))
+                        .await;
+                }
+            }
+            UpdateOneof::Account(account_update) => {
+                self.metrics.on_account_update();
+                if let Some(record) = AccountRecord::from_update(&account_update) {
+                    self.record(SinkUpdate::Account(record));
+                }
+                if let Some(summary) = self
+                    .account_tracker
+                    .as_mut()
+                    .and_then(|tracker| tracker.on_update(&account_update))
+                {
+                    summary.log();
+                }
+                if let (Some(triggers), Some(account)) =
+                    (&mut self.balance_triggers, &account_update.account)
+                {
+                    triggers.on_update(
+                        &bs58::encode(&account.pubkey).into_string(),
+                        account.lamports,
+                        account_update.slot,
+                        account.write_version,
+                    );
+                }
+            }
+            UpdateOneof::Slot(slot_update) => {
+                let status =
+                    SlotStatus::from_update(slot_update.status, slot_update.dead_error.as_deref());
+                if let Some(change) = self
+                    .pending_blocks
+                    .as_mut()
+                    .and_then(|pending| pending.on_status(slot_update.slot, status, received.at))
+                {
+                    change.log();
+                    self.record(SinkUpdate::BlockStatus(BlockStatusRecord::from(&change)));
+                }
+                if let Some(tracker) = &mut self.slot_tracker {
+                    info!(
+                        slot = slot_update.slot,
+                        parent = slot_update.parent,
+                        status = %status,
+                        "slot status"
+                    );
+
+                    if let Some(abandoned) =
+                        tracker.on_update(slot_update.slot, slot_update.parent, status)
+                    {
+                        warn!(
+                            slot = abandoned.slot,
+                            parent = abandoned.parent,
+                            last_status = %abandoned.last_status,
+                            dead_error = slot_update.dead_error.as_deref(),
+                            "fork abandoned"
+                        );
                     }
-                    _ => {
-                        // Other update types (slots, transactions, etc.)
+                    if let Some(summary) = tracker.summary_if_due() {
+                        info!("{}", summary);
                     }
-                },
-                Err(error) => {
-                    println!("❌ Stream error: {:?}", error);
-                    println!("🔄 Attempting to reconnect...");
-                    tokio::time::sleep(Duration::from_secs(5)).await;
-                    break;
                 }
             }
+            _ => {
+                // Other update types (entries, etc.)
+            }
         }
-
-        println!("Block subscription stream closed");
-        Ok(())
     }
 }
 
+// `compare_mode`: race the first two endpoints against each other until shutdown
+async fn compare_feeds(config: &Config, shutdown: &CancellationToken) -> anyhow::Result<()> {
+    let [a, b, ..] = config.geyser_endpoint.as_slice() else {
+        anyhow::bail!("compare_mode needs two entries in geyser_endpoint");
+    };
+    FeedRace::new(
+        [config.connection_pool(a)?, config.connection_pool(b)?],
+        [a.url.clone(), b.url.clone()],
+        feed_compare::subscription_request(config.commitment_level()?),
+        config.reconnect.clone(),
+    )
+    .run(shutdown)
+    .await;
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // Load configuration
-    let config = Config::load_from_file("config.yaml")?;
-    println!("Configuration loaded from config.yaml");
+    let cli = Cli::parse();
+    if let Some(shell) = cli.completions {
+        clap_complete::generate(
+            shell,
+            &mut Cli::command(),
+            env!("CARGO_PKG_NAME"),
+            &mut std::io::stdout(),
+        );
+        return Ok(());
+    }
+    if let Some(path) = &cli.generate_config {
+        Config::generate_template(path)?;
+        println!("Example config written to {}", path);
+        return Ok(());
+    }
+    let log_level = if cli.verbose {
+        tracing::Level::DEBUG
+    } else {
+        cli.log_level
+    };
+    let log_format = cli
+        .mode
+        .as_ref()
+        .and_then(|mode| mode.args().format)
+        .unwrap_or(cli.log_format);
+    logging::init(log_format, log_level);
 
-    // Validate configuration (commented out)
-    // config.get_sender_keypair()?;
-    // config.get_recipient_pubkey()?;
+    // Load configuration; a subcommand narrows it to one kind of update
+    let mut config = match &cli.mode {
+        Some(_) if !std::path::Path::new(&cli.config).exists() => {
+            info!(path = %cli.config, "no config file; using defaults and the subcommand's flags");
+            Config::defaults()?
+        }
+        _ => {
+            let config = Config::load_from_file(&cli.config)?;
+            info!(path = %cli.config, "configuration loaded");
+            config
+        }
+    };
+    if let Some(mode) = &cli.mode {
+        mode.apply(&mut config);
+        if config.geyser_endpoint.is_empty() {
+            anyhow::bail!("{} not found; pass --endpoint", cli.config);
+        }
+    }
+    config.dry_run |= cli.dry_run;
+    if let Some(path) = cli.output {
+        config.sink = Some(SinkConfig::jsonl(path));
+    }
+    // Checked before branching so compare mode gets the same checks as the bot
+    config.validate()?;
+
+    let shutdown = shutdown::listen();
+    if config.compare_mode {
+        return compare_feeds(&config, &shutdown).await;
+    }
+
+    // Create the bot, which processes updates on a worker task while the stream is
+    // read here, so a slow sink or webhook never holds up reads
+    let endpoints = config.endpoint_rotation()?;
+    let reconnector = Reconnector::new(config.reconnect.clone());
+    let (queue, updates) = update_queue::channel(&config.queue);
+    let mut bot = SolTransferBot::new(config)?;
+    if let Some(slot) = cli.from_slot {
+        bot.start_from_slot(slot);
+    }
+    bot.start_mode.log();
+    bot.load_epoch_schedule().await;
+    bot.start_slot_lag_monitor()?;
+    bot.serve_metrics()?;
+    bot.serve_health()?;
+
+    // SIGHUP re-reads the config file and swaps its filters into the live stream
+    let (filter_updates, filter_changes) = mpsc::unbounded_channel();
+    let reload = FilterReload {
+        config_path: cli.config.clone(),
+        watch_mode: cli.mode,
+        requests: filter_reload::listen(),
+        updates: filter_updates,
+    };
+    let mut reader = StreamReader::new(
+        endpoints,
+        reconnector,
+        bot.readiness.clone(),
+        bot.metrics.clone(),
+        queue,
+        bot.stream_settings(),
+    )
+    .with_filter_updates(filter_changes);
+    let worker = tokio::spawn(bot.work(updates, reload));
+
+    while !shutdown.is_cancelled() && !worker.is_finished() {
+        if let Err(e) = reader.run(&shutdown).await {
+            error!(error = %e, "stream reader error");
+            reader.reconnect("reader error", &shutdown).await;
+        }
+    }
+    // Dropping the reader closes the queue; the worker finishes what's queued
+    drop(reader);
+    worker.await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_template_parses() {
+        let config: Config = serde_yaml::from_str(CONFIG_TEMPLATE).unwrap();
+        assert!(config.transfer_trigger().unwrap().is_none());
+        assert_eq!(config.triggers[0].action, BalanceAction::Webhook);
+        assert_eq!(
+            config.triggers[0].on,
+            balance_triggers::BalanceCondition::Increase
+        );
+        assert_eq!(config.geyser_endpoint[0].x_token_env, "GEYSER_X_TOKEN");
+        assert!(!config.compare_mode);
+        assert_eq!(config.ping_interval_secs, 10);
+        assert_eq!(config.queue.overflow, update_queue::QueueOverflow::Block);
+        assert_eq!(config.connection.http2_keep_alive_interval_secs, Some(30));
+        assert!(config.connection.keepalive_while_idle);
+        assert_eq!(config.connection.max_decoding_message_size_mib, 256);
+        assert!(config.connection.validate().is_ok());
+        assert!(config.sink.is_some());
+        assert_eq!(config.webhook.unwrap().max_retries, 5);
+        assert_eq!(config.metrics.unwrap().listen, "127.0.0.1:9090");
+        assert_eq!(config.health.unwrap().max_update_age_secs, 30);
+        assert!(!config.trigger.unwrap().enabled);
+        assert_eq!(config.account_watch.unwrap().accounts.len(), 1);
+        assert!(config.watch_blocks && config.watch_slots);
+        assert_eq!(config.watch_transactions.unwrap().account_include.len(), 1);
+        assert_eq!(config.accounts.unwrap().memcmp.len(), 1);
+    }
+
+    #[test]
+    fn test_reload_takes_only_the_filters() {
+        let current: Config = serde_yaml::from_str(CONFIG_TEMPLATE).unwrap();
+        let mut reloaded = current.clone();
+        reloaded.commitment = "processed".to_string();
+        reloaded.deposits = None;
+        reloaded.ping_interval_secs = 99;
+
+        let config = current.with_filters_of(reloaded);
+        assert_eq!(config.commitment, "processed");
+        assert!(config.deposits.is_none());
+        assert_eq!(config.ping_interval_secs, current.ping_interval_secs);
+    }
+
+    #[test]
+    fn test_commitment_level_parsing() {
+        let mut config: Config = serde_yaml::from_str(CONFIG_TEMPLATE).unwrap();
+        assert_eq!(
+            config.commitment_level().unwrap(),
+            CommitmentLevel::Confirmed
+        );
 
-    // println!("Sender address: {}", config.get_sender_keypair()?.pubkey());
-    // println!("Recipient address: {}", config.recipient_address);
-    // println!("Transfer amount: {} SOL", config.transfer_amount);
+        config.commitment = "processed".to_string();
+        assert_eq!(
+            config.commitment_level().unwrap(),
+            CommitmentLevel::Processed
+        );
+
+        config.commitment = "safe".to_string();
+        let error = config.commitment_level().unwrap_err().to_string();
+        assert!(error.contains("invalid commitment 'safe'"));
+    }
 
-    // Create and run the bot
-    let bot = SolTransferBot::new(config)?;
+    #[tokio::test]
+    async fn test_metrics_endpoint_counts_processed_updates() {
+        use yellowstone_grpc_proto::{
+            geyser::{
+                SubscribeUpdateAccount, SubscribeUpdateBlockMeta, SubscribeUpdateTransaction,
+            },
+            prelude::UnixTimestamp,
+        };
+
+        let config: Config =
+            serde_yaml::from_str("geyser_endpoint:\n  - url: \"http://localhost:10000\"\n")
+                .unwrap();
+        let mut bot = SolTransferBot::new(config).unwrap();
+        let address = bot.metrics.serve("127.0.0.1:0").unwrap();
+
+        for slot in [100, 101] {
+            bot.process_update(
+                UpdateOneof::BlockMeta(SubscribeUpdateBlockMeta {
+                    slot,
+                    parent_slot: slot - 1,
+                    block_time: Some(UnixTimestamp { timestamp: 1 }),
+                    ..Default::default()
+                }),
+                Received::now(),
+            )
+            .await;
+        }
+        bot.process_update(
+            UpdateOneof::Transaction(SubscribeUpdateTransaction::default()),
+            Received::now(),
+        )
+        .await;
+        bot.process_update(
+            UpdateOneof::Account(SubscribeUpdateAccount::default()),
+            Received::now(),
+        )
+        .await;
+        // Counters carry over into the next subscription
+        bot.metrics.on_reconnect("stale stream");
 
-    loop {
-        if let Err(e) = bot.run().await {
-            println!("❌ Bot error: {}. Restarting in 10 seconds...", e);
-            tokio::time::sleep(Duration::from_secs(10)).await;
+        let text = reqwest::get(format!("http://{}/metrics", address))
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        for expected in [
+            "blocks_received_total 2",
+            "transactions_received_total 1",
+            "account_updates_received_total 1",
+            "stream_reconnects_total{reason=\"stale_stream\"} 1",
+            "last_processed_slot 101",
+            "block_interval_seconds_count 1",
+        ] {
+            assert!(text.lines().any(|line| line == expected), "{}", expected);
         }
+        // The fake blocks are from 1970
+        let lag: f64 = text
+            .lines()
+            .find_map(|line| line.strip_prefix("block_receive_lag_seconds "))
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(lag > 1e9);
     }
 }
@@ -0,0 +1,316 @@
+use {
+    crate::block_stats::BlockStatsReport,
+    hyper::{
+        Body, Request, Response, Server, StatusCode,
+        header::CONTENT_TYPE,
+        service::{make_service_fn, service_fn},
+    },
+    prometheus::{
+        Encoder, Gauge, GaugeVec, Histogram, HistogramOpts, IntCounter, IntCounterVec, IntGauge,
+        Opts, Registry, TextEncoder,
+    },
+    serde::{Deserialize, Serialize},
+    std::{
+        convert::Infallible,
+        net::SocketAddr,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    },
+    tracing::error,
+};
+
+/// Buckets for the time between consecutive blocks, in seconds
+const BLOCK_INTERVAL_BUCKETS: &[f64] = &[0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.8, 1.0, 1.5, 2.0, 5.0];
+
+/// Serve Prometheus metrics over HTTP
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsConfig {
+    /// Address the `/metrics` endpoint listens on
+    #[serde(default = "default_listen")]
+    pub listen: String,
+}
+
+fn default_listen() -> String {
+    "127.0.0.1:9090".to_string()
+}
+
+/// Stream counters, kept by the bot for its whole life so reconnects don't reset them.
+/// Clones share the same counters, so the stream reader and the worker each hold one.
+#[derive(Clone)]
+pub struct Metrics {
+    registry: Registry,
+    blocks_received: IntCounter,
+    transactions_received: IntCounter,
+    account_updates_received: IntCounter,
+    stream_reconnects: IntCounterVec,
+    last_processed_slot: IntGauge,
+    block_receive_lag: Gauge,
+    block_latency: GaugeVec,
+    slot_lag: IntGauge,
+    ping_rtt: Gauge,
+    message_bytes: IntCounter,
+    block_interval: Histogram,
+    updates_dropped: IntCounter,
+    duplicates_skipped: IntCounter,
+    last_block_at: Arc<Mutex<Option<Instant>>>,
+}
+
+impl Metrics {
+    pub fn new() -> anyhow::Result<Self> {
+        let registry = Registry::new();
+        let counter = |name: &str, help: &str| -> anyhow::Result<IntCounter> {
+            let counter = IntCounter::new(name, help)?;
+            registry.register(Box::new(counter.clone()))?;
+            Ok(counter)
+        };
+        let blocks_received = counter("blocks_received_total", "Blocks and block metas received")?;
+        let transactions_received = counter(
+            "transactions_received_total",
+            "Transaction updates received",
+        )?;
+        let account_updates_received =
+            counter("account_updates_received_total", "Account updates received")?;
+        let message_bytes = counter(
+            "message_bytes_total",
+            "Encoded size of every stream message received",
+        )?;
+        let updates_dropped = counter(
+            "updates_dropped_total",
+            "Updates dropped because the worker queue was full",
+        )?;
+        let duplicates_skipped = counter(
+            "duplicates_skipped_total",
+            "Blocks and transactions skipped because they were already processed",
+        )?;
+
+        let stream_reconnects = IntCounterVec::new(
+            Opts::new(
+                "stream_reconnects_total",
+                "Times the subscription was lost and reconnected, by reason",
+            ),
+            &["reason"],
+        )?;
+        registry.register(Box::new(stream_reconnects.clone()))?;
+        let last_processed_slot =
+            IntGauge::new("last_processed_slot", "Highest slot fully processed")?;
+        registry.register(Box::new(last_processed_slot.clone()))?;
+        let block_receive_lag = Gauge::new(
+            "block_receive_lag_seconds",
+            "Wall clock time minus the latest block's block_time",
+        )?;
+        registry.register(Box::new(block_receive_lag.clone()))?;
+        let block_latency = GaugeVec::new(
+            Opts::new(
+                "block_latency_seconds",
+                "Block receive lag over the block stats window: min, p50 and p95",
+            ),
+            &["stat"],
+        )?;
+        registry.register(Box::new(block_latency.clone()))?;
+        let slot_lag = IntGauge::new(
+            "slot_lag",
+            "Slots the configured RPC endpoint is ahead of the stream",
+        )?;
+        registry.register(Box::new(slot_lag.clone()))?;
+        let ping_rtt = Gauge::new(
+            "ping_rtt_seconds",
+            "Round-trip time of the latest ping answered by the server",
+        )?;
+        registry.register(Box::new(ping_rtt.clone()))?;
+        let block_interval = Histogram::with_opts(
+            HistogramOpts::new(
+                "block_interval_seconds",
+                "Time between consecutive blocks arriving",
+            )
+            .buckets(BLOCK_INTERVAL_BUCKETS.to_vec()),
+        )?;
+        registry.register(Box::new(block_interval.clone()))?;
+
+        Ok(Self {
+            registry,
+            blocks_received,
+            transactions_received,
+            account_updates_received,
+            stream_reconnects,
+            last_processed_slot,
+            block_receive_lag,
+            block_latency,
+            slot_lag,
+            ping_rtt,
+            message_bytes,
+            block_interval,
+            updates_dropped,
+            duplicates_skipped,
+            last_block_at: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    pub fn on_message(&self, bytes: usize) {
+        self.message_bytes.inc_by(bytes as u64);
+    }
+
+    /// `latency_ms` is the block's receive lag, when it had a block time
+    pub fn on_block(&self, latency_ms: Option<f64>, now: Instant) {
+        self.blocks_received.inc();
+        if let Some(latency_ms) = latency_ms {
+            self.block_receive_lag.set(latency_ms / 1000.0);
+        }
+        let previous = self.last_block_at.lock().unwrap().replace(now);
+        if let Some(previous) = previous {
+            self.block_interval
+                .observe(now.saturating_duration_since(previous).as_secs_f64());
+        }
+    }
+
+    pub fn on_block_stats(&self, report: &BlockStatsReport) {
+        let Some(latency) = report.latency else {
+            return;
+        };
+        for (stat, ms) in [
+            ("min", latency.min_ms),
+            ("p50", latency.p50_ms),
+            ("p95", latency.p95_ms),
+        ] {
+            self.block_latency
+                .with_label_values(&[stat])
+                .set(ms / 1000.0);
+        }
+    }
+
+    /// Set by the slot lag monitor's task
+    pub fn slot_lag(&self) -> IntGauge {
+        self.slot_lag.clone()
+    }
+
+    pub fn on_ping_rtt(&self, rtt: Duration) {
+        self.ping_rtt.set(rtt.as_secs_f64());
+    }
+
+    pub fn on_transaction(&self) {
+        self.transactions_received.inc();
+    }
+
+    pub fn on_account_update(&self) {
+        self.account_updates_received.inc();
+    }
+
+    /// `reason` becomes the `reason` label, e.g. "stale stream" -> `stale_stream`
+    pub fn on_reconnect(&self, reason: &str) {
+        self.stream_reconnects
+            .with_label_values(&[&reason.replace(' ', "_")])
+            .inc();
+        // The gap while reconnecting isn't a block interval
+        *self.last_block_at.lock().unwrap() = None;
+    }
+
+    pub fn on_update_dropped(&self) {
+        self.updates_dropped.inc();
+    }
+
+    pub fn on_duplicate_skipped(&self) {
+        self.duplicates_skipped.inc();
+    }
+
+    pub fn on_slot_processed(&self, slot: u64) {
+        if slot as i64 > self.last_processed_slot.get() {
+            self.last_processed_slot.set(slot as i64);
+        }
+    }
+
+    /// Serve `/metrics` on `listen` in the background; returns the bound address
+    pub fn serve(&self, listen: &str) -> anyhow::Result<SocketAddr> {
+        let address: SocketAddr = listen
+            .parse()
+            .map_err(|e| anyhow::anyhow!("invalid metrics listen address {}: {}", listen, e))?;
+        let registry = self.registry.clone();
+        let server = Server::try_bind(&address)?.serve(make_service_fn(move |_| {
+            let registry = registry.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    let response = respond(&registry, &request);
+                    async move { Ok::<_, Infallible>(response) }
+                }))
+            }
+        }));
+        let bound = server.local_addr();
+        tokio::spawn(async move {
+            if let Err(e) = server.await {
+                error!(error = %e, "metrics server stopped");
+            }
+        });
+        Ok(bound)
+    }
+}
+
+/// Everything registered, in the Prometheus text format
+fn render(registry: &Registry) -> anyhow::Result<String> {
+    let mut buffer = Vec::new();
+    TextEncoder::new().encode(&registry.gather(), &mut buffer)?;
+    Ok(String::from_utf8(buffer)?)
+}
+
+fn respond(registry: &Registry, request: &Request<Body>) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    if request.uri().path() != "/metrics" {
+        *response.status_mut() = StatusCode::NOT_FOUND;
+        return response;
+    }
+    match render(registry) {
+        Ok(text) => {
+            response.headers_mut().insert(
+                CONTENT_TYPE,
+                TextEncoder::new()
+                    .format_type()
+                    .parse()
+                    .expect("static content type"),
+            );
+            *response.body_mut() = Body::from(text);
+        }
+        Err(e) => {
+            *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+            *response.body_mut() = Body::from(e.to_string());
+        }
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Value of an unlabelled sample in the text format
+    fn sample(text: &str, name: &str) -> Option<f64> {
+        text.lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(' '))
+            .and_then(|value| value.parse().ok())
+    }
+
+    #[test]
+    fn test_block_interval_skips_reconnect_gaps() {
+        let metrics = Metrics::new().unwrap();
+        let start = Instant::now();
+        metrics.on_block(None, start);
+        metrics.on_block(None, start + Duration::from_millis(400));
+        metrics.on_reconnect("stream error");
+        metrics.on_block(None, start + Duration::from_secs(30));
+
+        let text = render(&metrics.registry).unwrap();
+        assert_eq!(sample(&text, "blocks_received_total"), Some(3.0));
+        assert_eq!(sample(&text, "block_interval_seconds_count"), Some(1.0));
+        assert_eq!(
+            sample(&text, "stream_reconnects_total{reason=\"stream_error\"}"),
+            Some(1.0)
+        );
+    }
+
+    #[test]
+    fn test_last_processed_slot_only_moves_forward() {
+        let metrics = Metrics::new().unwrap();
+        metrics.on_slot_processed(100);
+        metrics.on_slot_processed(90);
+        assert_eq!(
+            sample(&render(&metrics.registry).unwrap(), "last_processed_slot"),
+            Some(100.0)
+        );
+    }
+}
@@ -0,0 +1,307 @@
+use {
+    crate::sink::{BlockRecord, BlockStatusRecord, SinkUpdate, TransactionRecord},
+    sqlx::{PgPool, postgres::PgPoolOptions},
+    std::{
+        sync::{
+            Arc,
+            atomic::{AtomicU64, Ordering},
+        },
+        time::Duration,
+    },
+    tokio::{sync::mpsc, task::JoinHandle, time::Instant},
+    tracing::warn,
+};
+
+// Pause before retrying a batch Postgres rejected or couldn't take
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+// Report dropped rows at the first drop, then once per this many more
+const DROP_REPORT_EVERY: u64 = 1000;
+
+/// Settings of the `postgres` sink
+#[derive(Debug, Clone)]
+pub struct PostgresSinkSettings {
+    pub url: String,
+    pub schema: String,
+    pub batch_size: usize,
+    pub max_flush_latency: Duration,
+    pub max_buffered_rows: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Row {
+    Block(BlockRecord, u64),
+    Transaction(TransactionRecord, u64),
+    BlockStatus(BlockStatusRecord, u64),
+}
+
+/// Upserts blocks, block statuses and transactions into Postgres from a background task. Rows wait
+/// in a bounded buffer; while Postgres is unreachable the buffer fills and further
+/// rows are dropped and counted, so the stream is never held up.
+pub struct PostgresSink {
+    rows: mpsc::Sender<Row>,
+    writer: JoinHandle<()>,
+    dropped: Arc<AtomicU64>,
+    next_drop_report: u64,
+}
+
+impl PostgresSink {
+    /// Start the writer task; the connection is made (and remade) by that task
+    pub fn start(settings: PostgresSinkSettings) -> anyhow::Result<Self> {
+        if settings.schema.is_empty()
+            || !settings
+                .schema
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            anyhow::bail!(
+                "invalid postgres schema name {:?}: use letters, digits and underscores",
+                settings.schema
+            );
+        }
+        let pool = PgPoolOptions::new()
+            .max_connections(1)
+            .acquire_timeout(Duration::from_secs(5))
+            .connect_lazy(&settings.url)?;
+
+        let (rows, receiver) = mpsc::channel(settings.max_buffered_rows.max(1));
+        let writer = tokio::spawn(write_rows(pool, settings, receiver));
+        Ok(Self {
+            rows,
+            writer,
+            dropped: Arc::new(AtomicU64::new(0)),
+            next_drop_report: 1,
+        })
+    }
+
+    /// Queue an update; account updates and deposits aren't stored
+    pub fn write(&mut self, update: &SinkUpdate, received_at_ms: u64) {
+        let row = match update {
+            SinkUpdate::Block(block) | SinkUpdate::BlockMeta(block) => {
+                Row::Block(block.clone(), received_at_ms)
+            }
+            SinkUpdate::Transaction(transaction) => {
+                Row::Transaction(transaction.clone(), received_at_ms)
+            }
+            SinkUpdate::BlockStatus(status) => Row::BlockStatus(status.clone(), received_at_ms),
+            SinkUpdate::Account(_) | SinkUpdate::Deposit(_) => return,
+        };
+
+        if self.rows.try_send(row).is_err() {
+            let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+            if dropped >= self.next_drop_report {
+                warn!(dropped, "Postgres sink buffer is full; dropping rows");
+                self.next_drop_report = dropped + DROP_REPORT_EVERY;
+            }
+        }
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Stop accepting rows and wait until the buffered ones are written
+    pub async fn close(self) {
+        drop(self.rows);
+        let _ = self.writer.await;
+    }
+}
+
+// Collect rows into batches of `batch_size`, or whatever arrived within
+// `max_flush_latency` of the first, and write each until it succeeds
+async fn write_rows(pool: PgPool, settings: PostgresSinkSettings, mut rows: mpsc::Receiver<Row>) {
+    let mut schema_ready = false;
+    let mut batch = Vec::with_capacity(settings.batch_size);
+    loop {
+        match rows.recv().await {
+            Some(row) => batch.push(row),
+            None => return,
+        }
+        let deadline = Instant::now() + settings.max_flush_latency;
+        while batch.len() < settings.batch_size {
+            match tokio::time::timeout_at(deadline, rows.recv()).await {
+                Ok(Some(row)) => batch.push(row),
+                // Latency reached, or the watcher is shutting down: write what we have
+                Ok(None) | Err(_) => break,
+            }
+        }
+
+        loop {
+            let result = match schema_ready {
+                true => Ok(()),
+                false => create_tables(&pool, &settings.schema).await,
+            };
+            match result {
+                Ok(()) => schema_ready = true,
+                Err(e) => {
+                    warn!(error = %e, "Postgres sink can't prepare tables");
+                    tokio::time::sleep(RETRY_DELAY).await;
+                    continue;
+                }
+            }
+            match insert_batch(&pool, &settings.schema, &batch).await {
+                Ok(()) => break,
+                Err(e) => {
+                    warn!(
+                        rows = batch.len(),
+                        error = %e,
+                        "Postgres sink failed to write rows, retrying"
+                    );
+                    tokio::time::sleep(RETRY_DELAY).await;
+                }
+            }
+        }
+        batch.clear();
+    }
+}
+
+async fn create_tables(pool: &PgPool, schema: &str) -> Result<(), sqlx::Error> {
+    for statement in [
+        format!("CREATE SCHEMA IF NOT EXISTS {schema}"),
+        format!(
+            "CREATE TABLE IF NOT EXISTS {schema}.blocks (
+                slot BIGINT PRIMARY KEY,
+                blockhash TEXT NOT NULL,
+                parent_slot BIGINT NOT NULL,
+                block_time BIGINT,
+                block_height BIGINT,
+                received_at TIMESTAMPTZ NOT NULL
+            )"
+        ),
+        format!(
+            "CREATE TABLE IF NOT EXISTS {schema}.block_statuses (
+                slot BIGINT PRIMARY KEY,
+                blockhash TEXT NOT NULL,
+                status TEXT NOT NULL,
+                elapsed_ms BIGINT NOT NULL,
+                updated_at TIMESTAMPTZ NOT NULL
+            )"
+        ),
+        format!(
+            "CREATE TABLE IF NOT EXISTS {schema}.transactions (
+                signature TEXT PRIMARY KEY,
+                slot BIGINT NOT NULL,
+                success BOOLEAN NOT NULL,
+                fee BIGINT,
+                account_keys TEXT[] NOT NULL
+            )"
+        ),
+    ] {
+        sqlx::query(&statement).execute(pool).await?;
+    }
+    Ok(())
+}
+
+// One transaction per batch; replayed rows overwrite what's there
+async fn insert_batch(pool: &PgPool, schema: &str, batch: &[Row]) -> Result<(), sqlx::Error> {
+    let insert_block = format!(
+        "INSERT INTO {schema}.blocks
+            (slot, blockhash, parent_slot, block_time, block_height, received_at)
+         VALUES ($1, $2, $3, $4, $5, to_timestamp($6::DOUBLE PRECISION / 1000))
+         ON CONFLICT (slot) DO UPDATE SET
+            blockhash = EXCLUDED.blockhash,
+            parent_slot = EXCLUDED.parent_slot,
+            block_time = EXCLUDED.block_time,
+            block_height = EXCLUDED.block_height,
+            received_at = EXCLUDED.received_at"
+    );
+    // A slot keeps only its latest status: confirmed, then finalized or dead
+    let upsert_block_status = format!(
+        "INSERT INTO {schema}.block_statuses (slot, blockhash, status, elapsed_ms, updated_at)
+         VALUES ($1, $2, $3, $4, to_timestamp($5::DOUBLE PRECISION / 1000))
+         ON CONFLICT (slot) DO UPDATE SET
+            blockhash = EXCLUDED.blockhash,
+            status = EXCLUDED.status,
+            elapsed_ms = EXCLUDED.elapsed_ms,
+            updated_at = EXCLUDED.updated_at"
+    );
+    let insert_transaction = format!(
+        "INSERT INTO {schema}.transactions (signature, slot, success, fee, account_keys)
+         VALUES ($1, $2, $3, $4, $5)
+         ON CONFLICT (signature) DO UPDATE SET
+            slot = EXCLUDED.slot,
+            success = EXCLUDED.success,
+            fee = EXCLUDED.fee,
+            account_keys = EXCLUDED.account_keys"
+    );
+
+    let mut transaction = pool.begin().await?;
+    for row in batch {
+        match row {
+            Row::Block(block, received_at_ms) => {
+                sqlx::query(&insert_block)
+                    .bind(block.slot as i64)
+                    .bind(&block.blockhash)
+                    .bind(block.parent_slot as i64)
+                    .bind(block.block_time)
+                    .bind(block.block_height.map(|height| height as i64))
+                    .bind(*received_at_ms as i64)
+                    .execute(&mut *transaction)
+                    .await?;
+            }
+            Row::Transaction(record, _) => {
+                sqlx::query(&insert_transaction)
+                    .bind(&record.signature)
+                    .bind(record.slot as i64)
+                    .bind(record.success)
+                    .bind(record.fee.map(|fee| fee as i64))
+                    .bind(&record.account_keys)
+                    .execute(&mut *transaction)
+                    .await?;
+            }
+            Row::BlockStatus(status, received_at_ms) => {
+                sqlx::query(&upsert_block_status)
+                    .bind(status.slot as i64)
+                    .bind(&status.blockhash)
+                    .bind(&status.status)
+                    .bind(status.elapsed_ms as i64)
+                    .bind(*received_at_ms as i64)
+                    .execute(&mut *transaction)
+                    .await?;
+            }
+        }
+    }
+    transaction.commit().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(schema: &str, max_buffered_rows: usize) -> PostgresSinkSettings {
+        PostgresSinkSettings {
+            // Nothing listens here; rows just stay queued
+            url: "postgres://watcher@127.0.0.1:1/geyser".to_string(),
+            schema: schema.to_string(),
+            batch_size: 100,
+            max_flush_latency: Duration::from_millis(500),
+            max_buffered_rows,
+        }
+    }
+
+    fn block(slot: u64) -> SinkUpdate {
+        SinkUpdate::Block(BlockRecord {
+            slot,
+            blockhash: "hash".to_string(),
+            parent_slot: slot - 1,
+            block_height: None,
+            block_time: None,
+            transaction_count: 0,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_schema_name_is_checked() {
+        assert!(PostgresSink::start(settings("geyser; DROP TABLE x", 10)).is_err());
+        assert!(PostgresSink::start(settings("geyser_1", 10)).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_full_buffer_drops_and_counts() {
+        let mut sink = PostgresSink::start(settings("geyser", 2)).unwrap();
+        // The writer holds at most one batch; the channel takes two more
+        for slot in 1..=20 {
+            sink.write(&block(slot), 0);
+        }
+        assert!(sink.dropped.load(Ordering::Relaxed) >= 17);
+    }
+}
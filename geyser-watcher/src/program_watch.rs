@@ -0,0 +1,245 @@
+use {
+    solana_sdk::pubkey::Pubkey,
+    std::str::FromStr,
+    tracing::info,
+    yellowstone_grpc_proto::geyser::{
+        SubscribeRequestFilterTransactions, SubscribeUpdateTransaction,
+    },
+};
+
+/// Streams the transactions that reference one program and prints its instructions
+pub struct ProgramWatch {
+    program: Pubkey,
+    // Print instruction data in base58 as well as hex
+    base58: bool,
+}
+
+impl ProgramWatch {
+    pub fn new(program: &str, base58: bool) -> anyhow::Result<Self> {
+        let program = Pubkey::from_str(program)
+            .map_err(|e| anyhow::anyhow!("invalid program_filter '{}': {}", program, e))?;
+        Ok(Self { program, base58 })
+    }
+
+    pub fn filter(&self) -> SubscribeRequestFilterTransactions {
+        SubscribeRequestFilterTransactions {
+            vote: Some(false),
+            // Failed calls are activity too; they print with success = false
+            failed: None,
+            signature: None,
+            account_include: vec![],
+            account_exclude: vec![],
+            account_required: vec![self.program.to_string()],
+        }
+    }
+
+    /// The program's instructions in `update`, top-level and inner; `None` when the
+    /// transaction only references the program as an account
+    pub fn activity(&self, update: &SubscribeUpdateTransaction) -> Option<ProgramActivity> {
+        let info = update.transaction.as_ref()?;
+        let message = info.transaction.as_ref()?.message.as_ref()?;
+        let meta = info.meta.as_ref();
+
+        // Inner instructions index into the static keys followed by the loaded ones
+        let mut keys: Vec<&[u8]> = message.account_keys.iter().map(Vec::as_slice).collect();
+        if let Some(meta) = meta {
+            keys.extend(meta.loaded_writable_addresses.iter().map(Vec::as_slice));
+            keys.extend(meta.loaded_readonly_addresses.iter().map(Vec::as_slice));
+        }
+        let targets_program = |program_id_index: u32| {
+            keys.get(program_id_index as usize)
+                .is_some_and(|key| *key == self.program.as_ref())
+        };
+
+        let mut instructions = Vec::new();
+        for (index, instruction) in message.instructions.iter().enumerate() {
+            if targets_program(instruction.program_id_index) {
+                instructions.push(ProgramInstruction {
+                    index: index.to_string(),
+                    data: instruction.data.clone(),
+                });
+            }
+            let inner = meta
+                .filter(|meta| !meta.inner_instructions_none)
+                .and_then(|meta| {
+                    meta.inner_instructions
+                        .iter()
+                        .find(|inner| inner.index as usize == index)
+                });
+            for (inner_index, inner) in inner
+                .map(|inner| inner.instructions.as_slice())
+                .unwrap_or_default()
+                .iter()
+                .enumerate()
+            {
+                if targets_program(inner.program_id_index) {
+                    instructions.push(ProgramInstruction {
+                        index: format!("{}.{}", index, inner_index),
+                        data: inner.data.clone(),
+                    });
+                }
+            }
+        }
+        if instructions.is_empty() {
+            return None;
+        }
+
+        Some(ProgramActivity {
+            slot: update.slot,
+            signature: bs58::encode(&info.signature).into_string(),
+            success: meta.is_none_or(|meta| meta.err.is_none()),
+            compute_units: meta.and_then(|meta| meta.compute_units_consumed),
+            instructions,
+        })
+    }
+
+    pub fn log(&self, activity: &ProgramActivity) {
+        info!(
+            slot = activity.slot,
+            sig = %activity.signature,
+            success = activity.success,
+            compute_units = ?activity.compute_units,
+            instructions = activity.instructions.len(),
+            program = %self.program,
+            "program transaction"
+        );
+        for instruction in &activity.instructions {
+            if self.base58 {
+                info!(
+                    index = %instruction.index,
+                    bytes = instruction.data.len(),
+                    data = %hex(&instruction.data),
+                    data_base58 = %bs58::encode(&instruction.data).into_string(),
+                    "program instruction"
+                );
+            } else {
+                info!(
+                    index = %instruction.index,
+                    bytes = instruction.data.len(),
+                    data = %hex(&instruction.data),
+                    "program instruction"
+                );
+            }
+        }
+    }
+}
+
+/// One transaction calling the watched program
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgramActivity {
+    pub slot: u64,
+    pub signature: String,
+    pub success: bool,
+    pub compute_units: Option<u64>,
+    pub instructions: Vec<ProgramInstruction>,
+}
+
+/// An instruction targeting the program. `index` is the top-level position, or
+/// `outer.inner` for an instruction it made through CPI.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgramInstruction {
+    pub index: String,
+    pub data: Vec<u8>,
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        yellowstone_grpc_proto::{
+            geyser::SubscribeUpdateTransactionInfo,
+            prelude::{
+                CompiledInstruction, InnerInstruction, InnerInstructions, Message, Transaction,
+                TransactionStatusMeta,
+            },
+        },
+    };
+
+    const PROGRAM: &str = "JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4";
+
+    fn update(
+        instructions: Vec<CompiledInstruction>,
+        inner: Vec<InnerInstructions>,
+    ) -> SubscribeUpdateTransaction {
+        let program = Pubkey::from_str(PROGRAM).unwrap();
+        SubscribeUpdateTransaction {
+            slot: 12,
+            transaction: Some(SubscribeUpdateTransactionInfo {
+                signature: vec![1; 64],
+                transaction: Some(Transaction {
+                    message: Some(Message {
+                        account_keys: vec![vec![2; 32], program.to_bytes().to_vec()],
+                        instructions,
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
+                meta: Some(TransactionStatusMeta {
+                    compute_units_consumed: Some(42_000),
+                    inner_instructions: inner,
+                    // The program is also loaded through a lookup table at index 2
+                    loaded_readonly_addresses: vec![program.to_bytes().to_vec()],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+        }
+    }
+
+    fn instruction(program_id_index: u32, data: Vec<u8>) -> CompiledInstruction {
+        CompiledInstruction {
+            program_id_index,
+            accounts: vec![],
+            data,
+        }
+    }
+
+    #[test]
+    fn test_finds_top_level_and_inner_instructions() {
+        let watch = ProgramWatch::new(PROGRAM, false).unwrap();
+        let activity = watch
+            .activity(&update(
+                vec![instruction(0, vec![9]), instruction(1, vec![0xab, 0xcd])],
+                vec![InnerInstructions {
+                    index: 0,
+                    instructions: vec![
+                        InnerInstruction {
+                            program_id_index: 0,
+                            ..Default::default()
+                        },
+                        InnerInstruction {
+                            program_id_index: 2,
+                            data: vec![7],
+                            ..Default::default()
+                        },
+                    ],
+                }],
+            ))
+            .unwrap();
+
+        assert_eq!(activity.compute_units, Some(42_000));
+        assert!(activity.success);
+        let indexes: Vec<&str> = activity
+            .instructions
+            .iter()
+            .map(|instruction| instruction.index.as_str())
+            .collect();
+        assert_eq!(indexes, ["0.1", "1"]);
+        assert_eq!(hex(&activity.instructions[1].data), "abcd");
+    }
+
+    #[test]
+    fn test_referencing_the_program_as_an_account_is_not_activity() {
+        let watch = ProgramWatch::new(PROGRAM, false).unwrap();
+        assert!(
+            watch
+                .activity(&update(vec![instruction(0, vec![1])], vec![]))
+                .is_none()
+        );
+        assert_eq!(watch.filter().account_required, [PROGRAM]);
+    }
+}
@@ -0,0 +1,175 @@
+use {
+    backoff::{ExponentialBackoff, backoff::Backoff},
+    serde::{Deserialize, Serialize},
+    std::time::{Duration, Instant},
+    tracing::warn,
+};
+
+/// How long to wait between reconnects: exponential with jitter, starting over
+/// once a connection has stayed up for `healthy_reset_secs`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReconnectPolicy {
+    /// Delay before the first reconnect
+    #[serde(default = "default_initial_delay_ms")]
+    pub initial_delay_ms: u64,
+    /// Each further attempt waits this many times longer...
+    #[serde(default = "default_multiplier")]
+    pub multiplier: f64,
+    /// ...up to this delay
+    #[serde(default = "default_max_delay_ms")]
+    pub max_delay_ms: u64,
+    /// Randomize each delay by up to this fraction either way (0.2 = ±20%)
+    #[serde(default = "default_jitter")]
+    pub jitter: f64,
+    /// A connection up at least this long counts as healthy and resets the backoff
+    #[serde(default = "default_healthy_reset_secs")]
+    pub healthy_reset_secs: u64,
+}
+
+fn default_initial_delay_ms() -> u64 {
+    500
+}
+
+fn default_multiplier() -> f64 {
+    2.0
+}
+
+fn default_max_delay_ms() -> u64 {
+    60_000
+}
+
+fn default_jitter() -> f64 {
+    0.2
+}
+
+fn default_healthy_reset_secs() -> u64 {
+    60
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay_ms: default_initial_delay_ms(),
+            multiplier: default_multiplier(),
+            max_delay_ms: default_max_delay_ms(),
+            jitter: default_jitter(),
+            healthy_reset_secs: default_healthy_reset_secs(),
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    fn backoff(&self) -> ExponentialBackoff {
+        let initial = Duration::from_millis(self.initial_delay_ms);
+        ExponentialBackoff {
+            current_interval: initial,
+            initial_interval: initial,
+            randomization_factor: self.jitter.clamp(0.0, 1.0),
+            multiplier: self.multiplier.max(1.0),
+            max_interval: Duration::from_millis(self.max_delay_ms),
+            // Keep retrying for as long as the watcher runs
+            max_elapsed_time: None,
+            ..ExponentialBackoff::default()
+        }
+    }
+}
+
+/// Backoff state shared by every reconnect path
+pub struct Reconnector {
+    policy: ReconnectPolicy,
+    backoff: ExponentialBackoff,
+    attempt: u32,
+    connected_at: Option<Instant>,
+}
+
+impl Reconnector {
+    pub fn new(policy: ReconnectPolicy) -> Self {
+        Self {
+            backoff: policy.backoff(),
+            policy,
+            attempt: 0,
+            connected_at: None,
+        }
+    }
+
+    /// Note that a subscription is up; if it lasts it resets the backoff
+    pub fn connected(&mut self) {
+        self.connected_at = Some(Instant::now());
+    }
+
+    /// Delay before the next attempt, starting over after a healthy connection
+    fn next_delay(&mut self) -> Duration {
+        let healthy = Duration::from_secs(self.policy.healthy_reset_secs);
+        if self
+            .connected_at
+            .take()
+            .is_some_and(|connected_at| connected_at.elapsed() >= healthy)
+        {
+            self.backoff.reset();
+            self.attempt = 0;
+        }
+
+        self.attempt += 1;
+        self.backoff
+            .next_backoff()
+            .unwrap_or(self.backoff.max_interval)
+    }
+
+    /// Log why we're reconnecting and the backoff state, then wait
+    pub async fn wait(&mut self, reason: &str) {
+        let delay = self.next_delay();
+        warn!(
+            attempt = self.attempt,
+            delay = ?delay,
+            reason,
+            next_base_delay = ?self.backoff.current_interval,
+            max_delay = ?self.backoff.max_interval,
+            "reconnecting"
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(jitter: f64) -> ReconnectPolicy {
+        ReconnectPolicy {
+            initial_delay_ms: 100,
+            multiplier: 2.0,
+            max_delay_ms: 500,
+            jitter,
+            healthy_reset_secs: 0,
+        }
+    }
+
+    #[test]
+    fn test_delays_grow_to_the_cap() {
+        let mut reconnector = Reconnector::new(policy(0.0));
+        let delays: Vec<u128> = (0..5)
+            .map(|_| reconnector.next_delay().as_millis())
+            .collect();
+
+        assert_eq!(delays, [100, 200, 400, 500, 500]);
+        assert_eq!(reconnector.attempt, 5);
+    }
+
+    #[test]
+    fn test_healthy_connection_resets_backoff() {
+        let mut reconnector = Reconnector::new(policy(0.0));
+        reconnector.next_delay();
+        reconnector.next_delay();
+
+        reconnector.connected();
+        assert_eq!(reconnector.next_delay(), Duration::from_millis(100));
+        assert_eq!(reconnector.attempt, 1);
+    }
+
+    #[test]
+    fn test_jitter_stays_within_fraction() {
+        let mut reconnector = Reconnector::new(policy(0.5));
+        let delay = reconnector.next_delay().as_millis();
+        assert!((50..=150).contains(&delay), "{} ms", delay);
+    }
+}
@@ -0,0 +1,127 @@
+use {
+    serde::{Deserialize, Serialize},
+    std::{
+        collections::{HashSet, VecDeque},
+        hash::Hash,
+    },
+};
+
+/// How much recent history is remembered to skip updates replayed after a
+/// reconnect resumes from an earlier slot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DedupConfig {
+    /// Block slots remembered
+    #[serde(default = "default_slot_window")]
+    pub slot_window: usize,
+    /// Transaction signatures remembered; a busy slot has a few thousand
+    #[serde(default = "default_signature_window")]
+    pub signature_window: usize,
+}
+
+impl Default for DedupConfig {
+    fn default() -> Self {
+        Self {
+            slot_window: default_slot_window(),
+            signature_window: default_signature_window(),
+        }
+    }
+}
+
+fn default_slot_window() -> usize {
+    1000
+}
+
+fn default_signature_window() -> usize {
+    100_000
+}
+
+// The last `capacity` distinct keys, forgetting the oldest first
+struct RecentKeys<K> {
+    seen: HashSet<K>,
+    order: VecDeque<K>,
+    capacity: usize,
+}
+
+impl<K: Hash + Eq + Clone> RecentKeys<K> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            seen: HashSet::new(),
+            order: VecDeque::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    // False when the key is already remembered
+    fn insert(&mut self, key: &K) -> bool {
+        if !self.seen.insert(key.clone()) {
+            return false;
+        }
+        self.order.push_back(key.clone());
+        if self.order.len() > self.capacity
+            && let Some(oldest) = self.order.pop_front()
+        {
+            self.seen.remove(&oldest);
+        }
+        true
+    }
+}
+
+/// Recently processed block slots and transaction signatures, so each is handled
+/// once even when the stream delivers it again
+pub struct ReplayDedup {
+    slots: RecentKeys<u64>,
+    signatures: RecentKeys<Vec<u8>>,
+}
+
+impl ReplayDedup {
+    pub fn new(config: &DedupConfig) -> Self {
+        Self {
+            slots: RecentKeys::new(config.slot_window),
+            signatures: RecentKeys::new(config.signature_window),
+        }
+    }
+
+    /// False when a block (or block meta) for `slot` was already processed
+    pub fn first_block(&mut self, slot: u64) -> bool {
+        self.slots.insert(&slot)
+    }
+
+    /// False when the transaction was already processed
+    pub fn first_transaction(&mut self, signature: &[u8]) -> bool {
+        self.signatures.insert(&signature.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overlap_after_reconnect_is_processed_once() {
+        let mut dedup = ReplayDedup::new(&DedupConfig::default());
+        // Slots 1-10 arrive, the stream drops and resumes from slot 7
+        let processed: Vec<u64> = (1..=10)
+            .chain(7..=15)
+            .filter(|slot| dedup.first_block(*slot))
+            .collect();
+        assert_eq!(processed, (1..=15).collect::<Vec<_>>());
+
+        assert!(dedup.first_transaction(&[1; 64]));
+        assert!(!dedup.first_transaction(&[1; 64]));
+    }
+
+    #[test]
+    fn test_window_is_bounded() {
+        let mut dedup = ReplayDedup::new(&DedupConfig {
+            slot_window: 3,
+            ..Default::default()
+        });
+        for slot in 1..=4 {
+            assert!(dedup.first_block(slot));
+        }
+        assert_eq!(dedup.slots.order.len(), 3);
+        // Slot 1 was forgotten; 2 is still remembered
+        assert!(dedup.first_block(1));
+        assert!(!dedup.first_block(4));
+    }
+}
@@ -0,0 +1,40 @@
+use {
+    tokio_util::sync::CancellationToken,
+    tracing::{info, warn},
+};
+
+/// Cancelled on the first SIGINT or SIGTERM so the watcher can flush its sinks and
+/// exit cleanly. A second signal exits immediately.
+pub fn listen() -> CancellationToken {
+    let shutdown = CancellationToken::new();
+    let token = shutdown.clone();
+    tokio::spawn(async move {
+        signal().await;
+        info!("shutting down; signal again to exit immediately");
+        token.cancel();
+        signal().await;
+        warn!("second signal received; exiting without cleanup");
+        std::process::exit(1);
+    });
+    shutdown
+}
+
+#[cfg(unix)]
+async fn signal() {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let Ok(mut terminate) = signal(SignalKind::terminate()) else {
+        // Without a SIGTERM handler Ctrl-C still works
+        let _ = tokio::signal::ctrl_c().await;
+        return;
+    };
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {}
+        _ = terminate.recv() => {}
+    }
+}
+
+#[cfg(not(unix))]
+async fn signal() {
+    let _ = tokio::signal::ctrl_c().await;
+}
@@ -0,0 +1,590 @@
+use {
+    crate::{
+        block_source::BlockInfo,
+        block_status::BlockStatusChange,
+        bus_sink::{BusSink, BusTarget},
+        deposits::DepositEvent,
+        postgres_sink::{PostgresSink, PostgresSinkSettings},
+    },
+    serde::{Deserialize, Serialize},
+    std::{
+        fs::{self, File, OpenOptions},
+        io::{BufWriter, Write},
+        path::{Path, PathBuf},
+        time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+    },
+    yellowstone_grpc_proto::geyser::{SubscribeUpdateAccount, SubscribeUpdateTransaction},
+};
+
+/// Where processed updates are recorded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SinkConfig {
+    /// One JSON object per line. When the file would grow past `rotate_mb` it is
+    /// renamed with the next numeric suffix (`blocks.jsonl.1`, `.2`, ...) and a new
+    /// one started.
+    Jsonl {
+        path: String,
+        #[serde(default = "default_rotate_mb")]
+        rotate_mb: u64,
+        /// Buffered lines are written out at least this often
+        #[serde(default = "default_flush_interval_ms")]
+        flush_interval_ms: u64,
+    },
+    /// Blocks, block statuses and transactions upserted into `<schema>.blocks`,
+    /// `<schema>.block_statuses` and `<schema>.transactions`, created on first
+    /// connect. Rows are written in
+    /// batches of `batch_size` or after `max_flush_latency_ms`, whichever comes
+    /// first; while Postgres is down up to `max_buffered_rows` wait and the rest
+    /// are dropped.
+    Postgres {
+        url: String,
+        #[serde(default = "default_schema")]
+        schema: String,
+        #[serde(default = "default_batch_size")]
+        batch_size: usize,
+        #[serde(default = "default_max_flush_latency_ms")]
+        max_flush_latency_ms: u64,
+        #[serde(default = "default_max_buffered_rows")]
+        max_buffered_rows: usize,
+    },
+    /// The jsonl envelope of every update, published to NATS subjects
+    /// `<subject_prefix>.<kind>`. Needs the `nats` cargo feature.
+    Nats {
+        url: String,
+        #[serde(default = "default_topic_prefix")]
+        subject_prefix: String,
+        /// Messages waiting for the publisher; beyond this they are dropped
+        #[serde(default = "default_buffer_size")]
+        buffer_size: usize,
+    },
+    /// The jsonl envelope of every update, appended to Redis streams
+    /// `<key_prefix>:<kind>` as a `data` field. Needs the `redis-stream` cargo
+    /// feature.
+    #[serde(rename = "redis-stream")]
+    RedisStream {
+        url: String,
+        #[serde(default = "default_topic_prefix")]
+        key_prefix: String,
+        /// Messages waiting for the publisher; beyond this they are dropped
+        #[serde(default = "default_buffer_size")]
+        buffer_size: usize,
+    },
+}
+
+fn default_topic_prefix() -> String {
+    "geyser".to_string()
+}
+
+fn default_buffer_size() -> usize {
+    10_000
+}
+
+fn default_schema() -> String {
+    "geyser".to_string()
+}
+
+fn default_batch_size() -> usize {
+    100
+}
+
+fn default_max_flush_latency_ms() -> u64 {
+    500
+}
+
+fn default_max_buffered_rows() -> usize {
+    10_000
+}
+
+impl SinkConfig {
+    /// A JSONL file sink at `path` with the default rotation and flush settings
+    pub fn jsonl(path: String) -> Self {
+        Self::Jsonl {
+            path,
+            rotate_mb: default_rotate_mb(),
+            flush_interval_ms: default_flush_interval_ms(),
+        }
+    }
+}
+
+fn default_rotate_mb() -> u64 {
+    100
+}
+
+fn default_flush_interval_ms() -> u64 {
+    1000
+}
+
+/// Serializable mirror of a block or block meta update
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BlockRecord {
+    pub slot: u64,
+    pub blockhash: String,
+    pub parent_slot: u64,
+    pub block_height: Option<u64>,
+    pub block_time: Option<i64>,
+    pub transaction_count: u64,
+}
+
+impl From<&BlockInfo> for BlockRecord {
+    fn from(block: &BlockInfo) -> Self {
+        Self {
+            slot: block.slot,
+            blockhash: block.blockhash.clone(),
+            parent_slot: block.parent_slot,
+            block_height: block.block_height,
+            block_time: block.block_time,
+            transaction_count: block.transaction_count,
+        }
+    }
+}
+
+/// Serializable mirror of an account update; data is hex encoded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AccountRecord {
+    pub slot: u64,
+    pub pubkey: String,
+    pub owner: String,
+    pub lamports: u64,
+    pub executable: bool,
+    pub rent_epoch: u64,
+    pub write_version: u64,
+    pub data: String,
+    pub txn_signature: Option<String>,
+    pub is_startup: bool,
+}
+
+impl AccountRecord {
+    pub fn from_update(update: &SubscribeUpdateAccount) -> Option<Self> {
+        let account = update.account.as_ref()?;
+        Some(Self {
+            slot: update.slot,
+            pubkey: bs58::encode(&account.pubkey).into_string(),
+            owner: bs58::encode(&account.owner).into_string(),
+            lamports: account.lamports,
+            executable: account.executable,
+            rent_epoch: account.rent_epoch,
+            write_version: account.write_version,
+            data: account
+                .data
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect(),
+            txn_signature: account
+                .txn_signature
+                .as_ref()
+                .map(|signature| bs58::encode(signature).into_string()),
+            is_startup: update.is_startup,
+        })
+    }
+}
+
+/// Serializable mirror of a transaction update
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TransactionRecord {
+    pub slot: u64,
+    pub signature: String,
+    pub is_vote: bool,
+    pub success: bool,
+    pub fee: Option<u64>,
+    pub compute_units_consumed: Option<u64>,
+    pub account_keys: Vec<String>,
+}
+
+impl TransactionRecord {
+    pub fn from_update(update: &SubscribeUpdateTransaction) -> Option<Self> {
+        let info = update.transaction.as_ref()?;
+        let meta = info.meta.as_ref();
+        Some(Self {
+            slot: update.slot,
+            signature: bs58::encode(&info.signature).into_string(),
+            is_vote: info.is_vote,
+            success: meta.is_none_or(|meta| meta.err.is_none()),
+            fee: meta.map(|meta| meta.fee),
+            compute_units_consumed: meta.and_then(|meta| meta.compute_units_consumed),
+            account_keys: info
+                .transaction
+                .as_ref()
+                .and_then(|transaction| transaction.message.as_ref())
+                .map(|message| {
+                    message
+                        .account_keys
+                        .iter()
+                        .map(|key| bs58::encode(key).into_string())
+                        .collect()
+                })
+                .unwrap_or_default(),
+        })
+    }
+}
+
+/// A processed-commitment block's slot reaching confirmed, finalized or dead;
+/// consumers match it to the block record by slot
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BlockStatusRecord {
+    pub slot: u64,
+    pub blockhash: String,
+    pub status: String,
+    /// Milliseconds from the block arriving to this status
+    pub elapsed_ms: u64,
+}
+
+impl From<&BlockStatusChange> for BlockStatusRecord {
+    fn from(change: &BlockStatusChange) -> Self {
+        Self {
+            slot: change.slot,
+            blockhash: change.blockhash.clone(),
+            status: change.status.to_string(),
+            elapsed_ms: change.elapsed.as_millis() as u64,
+        }
+    }
+}
+
+/// One recorded update
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SinkUpdate {
+    Block(BlockRecord),
+    BlockMeta(BlockRecord),
+    Account(AccountRecord),
+    Transaction(TransactionRecord),
+    Deposit(DepositEvent),
+    BlockStatus(BlockStatusRecord),
+}
+
+impl SinkUpdate {
+    /// The `kind` tag of the serialized update
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Block(_) => "block",
+            Self::BlockMeta(_) => "block_meta",
+            Self::Account(_) => "account",
+            Self::Transaction(_) => "transaction",
+            Self::Deposit(_) => "deposit",
+            Self::BlockStatus(_) => "block_status",
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct SinkLine<'a> {
+    /// Milliseconds since the Unix epoch when the watcher received the update
+    received_at_ms: u64,
+    #[serde(flatten)]
+    update: &'a SinkUpdate,
+}
+
+/// An opened `SinkConfig`
+pub enum Sink {
+    Jsonl(JsonlSink),
+    Postgres(PostgresSink),
+    Bus(BusSink),
+}
+
+impl Sink {
+    pub fn open(config: &SinkConfig) -> anyhow::Result<Self> {
+        match config {
+            SinkConfig::Jsonl { .. } => Ok(Self::Jsonl(JsonlSink::open(config)?)),
+            SinkConfig::Postgres {
+                url,
+                schema,
+                batch_size,
+                max_flush_latency_ms,
+                max_buffered_rows,
+            } => Ok(Self::Postgres(PostgresSink::start(PostgresSinkSettings {
+                url: url.clone(),
+                schema: schema.clone(),
+                batch_size: (*batch_size).max(1),
+                max_flush_latency: Duration::from_millis(*max_flush_latency_ms),
+                max_buffered_rows: *max_buffered_rows,
+            })?)),
+            SinkConfig::Nats {
+                url,
+                subject_prefix,
+                buffer_size,
+            } => Ok(Self::Bus(BusSink::start(
+                BusTarget::Nats {
+                    url: url.clone(),
+                    subject_prefix: subject_prefix.clone(),
+                },
+                *buffer_size,
+            )?)),
+            SinkConfig::RedisStream {
+                url,
+                key_prefix,
+                buffer_size,
+            } => Ok(Self::Bus(BusSink::start(
+                BusTarget::RedisStream {
+                    url: url.clone(),
+                    key_prefix: key_prefix.clone(),
+                },
+                *buffer_size,
+            )?)),
+        }
+    }
+
+    pub fn write(&mut self, update: &SinkUpdate) -> anyhow::Result<()> {
+        match self {
+            Self::Jsonl(sink) => sink.write(update),
+            Self::Postgres(sink) => {
+                sink.write(update, received_at_ms());
+                Ok(())
+            }
+            Self::Bus(sink) => {
+                sink.write(update, envelope(update)?);
+                Ok(())
+            }
+        }
+    }
+
+    /// Updates dropped because the sink's buffer was full; files never drop
+    pub fn dropped(&self) -> Option<u64> {
+        match self {
+            Self::Jsonl(_) => None,
+            Self::Postgres(sink) => Some(sink.dropped()),
+            Self::Bus(sink) => Some(sink.dropped()),
+        }
+    }
+
+    /// Postgres rows and bus messages go out on their own schedule, so only files
+    /// need this
+    pub fn flush(&mut self) -> anyhow::Result<()> {
+        match self {
+            Self::Jsonl(sink) => sink.flush(),
+            Self::Postgres(_) | Self::Bus(_) => Ok(()),
+        }
+    }
+
+    /// Write out everything buffered, waiting for the background writers to finish
+    pub async fn close(self) -> anyhow::Result<()> {
+        match self {
+            Self::Jsonl(mut sink) => sink.flush(),
+            Self::Postgres(sink) => {
+                sink.close().await;
+                Ok(())
+            }
+            Self::Bus(sink) => {
+                sink.close().await;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Milliseconds since the Unix epoch
+pub fn received_at_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+/// The JSON object written for an update: its fields, `kind` and `received_at_ms`
+pub fn envelope(update: &SinkUpdate) -> serde_json::Result<Vec<u8>> {
+    serde_json::to_vec(&SinkLine {
+        received_at_ms: received_at_ms(),
+        update,
+    })
+}
+
+/// Appends updates to a JSON Lines file with size-based rotation
+pub struct JsonlSink {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    rotate_bytes: u64,
+    bytes_written: u64,
+    flush_interval: Duration,
+    last_flush: Instant,
+}
+
+impl JsonlSink {
+    pub fn open(config: &SinkConfig) -> anyhow::Result<Self> {
+        let SinkConfig::Jsonl {
+            path,
+            rotate_mb,
+            flush_interval_ms,
+        } = config
+        else {
+            anyhow::bail!("not a jsonl sink config");
+        };
+        let path = PathBuf::from(path);
+        let (writer, bytes_written) = open_append(&path)?;
+        Ok(Self {
+            path,
+            writer,
+            rotate_bytes: rotate_mb.saturating_mul(1024 * 1024).max(1),
+            bytes_written,
+            flush_interval: Duration::from_millis(*flush_interval_ms),
+            last_flush: Instant::now(),
+        })
+    }
+
+    pub fn write(&mut self, update: &SinkUpdate) -> anyhow::Result<()> {
+        let mut line = envelope(update)?;
+        line.push(b'\n');
+
+        if self.bytes_written > 0 && self.bytes_written + line.len() as u64 > self.rotate_bytes {
+            self.rotate()?;
+        }
+        self.writer.write_all(&line)?;
+        self.bytes_written += line.len() as u64;
+
+        if self.last_flush.elapsed() >= self.flush_interval {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> anyhow::Result<()> {
+        self.writer.flush()?;
+        self.last_flush = Instant::now();
+        Ok(())
+    }
+
+    // Move the full file aside under the first free numeric suffix and start over
+    fn rotate(&mut self) -> anyhow::Result<()> {
+        self.flush()?;
+        let rotated = (1..)
+            .map(|index| suffixed(&self.path, index))
+            .find(|candidate| !candidate.exists())
+            .expect("some suffix is free");
+        fs::rename(&self.path, &rotated)?;
+        (self.writer, self.bytes_written) = open_append(&self.path)?;
+        Ok(())
+    }
+}
+
+fn open_append(path: &Path) -> anyhow::Result<(BufWriter<File>, u64)> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let len = file.metadata()?.len();
+    Ok((BufWriter::new(file), len))
+}
+
+fn suffixed(path: &Path, index: u32) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(slot: u64) -> SinkUpdate {
+        SinkUpdate::Block(BlockRecord {
+            slot,
+            blockhash: "hash".to_string(),
+            parent_slot: slot - 1,
+            block_height: None,
+            block_time: Some(1_700_000_000),
+            transaction_count: 10,
+        })
+    }
+
+    fn sink(dir: &Path, rotate_mb: u64) -> JsonlSink {
+        JsonlSink::open(&SinkConfig::Jsonl {
+            path: dir.join("blocks.jsonl").to_string_lossy().into_owned(),
+            rotate_mb,
+            flush_interval_ms: 0,
+        })
+        .unwrap()
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("geyser-sink-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_lines_are_tagged_json() {
+        let dir = temp_dir("lines");
+        let mut sink = sink(&dir, 100);
+        sink.write(&block(10)).unwrap();
+        sink.write(&block(11)).unwrap();
+
+        let contents = fs::read_to_string(dir.join("blocks.jsonl")).unwrap();
+        let lines: Vec<serde_json::Value> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["kind"], "block");
+        assert_eq!(lines[1]["slot"], 11);
+        assert!(lines[0]["received_at_ms"].as_u64().unwrap() > 0);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_rotation_uses_numeric_suffixes() {
+        let dir = temp_dir("rotate");
+        let mut sink = sink(&dir, 0);
+        // With the minimum size every line after the first rotates the file
+        for slot in 10..13 {
+            sink.write(&block(slot)).unwrap();
+        }
+        sink.flush().unwrap();
+
+        let read = |name: &str| fs::read_to_string(dir.join(name)).unwrap();
+        assert!(read("blocks.jsonl.1").contains("\"slot\":10"));
+        assert!(read("blocks.jsonl.2").contains("\"slot\":11"));
+        assert!(read("blocks.jsonl").contains("\"slot\":12"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_close_writes_buffered_lines() {
+        let dir = temp_dir("close");
+        let path = dir.join("blocks.jsonl");
+        let mut sink = Sink::open(&SinkConfig::Jsonl {
+            path: path.to_string_lossy().into_owned(),
+            rotate_mb: 100,
+            flush_interval_ms: 60_000,
+        })
+        .unwrap();
+        sink.write(&block(10)).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "");
+
+        sink.close().await.unwrap();
+        assert!(fs::read_to_string(&path).unwrap().contains("\"slot\":10"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_postgres_config_defaults() {
+        let config: SinkConfig =
+            serde_yaml::from_str("type: postgres\nurl: \"postgres://localhost/watcher\"\n")
+                .unwrap();
+        let SinkConfig::Postgres {
+            schema,
+            batch_size,
+            max_flush_latency_ms,
+            max_buffered_rows,
+            ..
+        } = config
+        else {
+            panic!("expected a postgres sink");
+        };
+        assert_eq!(schema, "geyser");
+        assert_eq!(batch_size, 100);
+        assert_eq!(max_flush_latency_ms, 500);
+        assert_eq!(max_buffered_rows, 10_000);
+    }
+
+    #[test]
+    fn test_kind_matches_serialized_tag() {
+        let update = block(10);
+        let json = serde_json::to_value(&update).unwrap();
+        assert_eq!(json["kind"], update.kind());
+    }
+
+    #[test]
+    fn test_redis_stream_type_name() {
+        let config: SinkConfig =
+            serde_yaml::from_str("type: redis-stream\nurl: \"redis://localhost\"\n").unwrap();
+        assert!(matches!(
+            config,
+            SinkConfig::RedisStream { key_prefix, buffer_size: 10_000, .. } if key_prefix == "geyser"
+        ));
+    }
+}
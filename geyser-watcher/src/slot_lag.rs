@@ -0,0 +1,84 @@
+use {
+    prometheus::IntGauge,
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_sdk::commitment_config::CommitmentConfig,
+    std::{
+        sync::{
+            Arc, Mutex,
+            atomic::{AtomicU64, Ordering},
+        },
+        time::Duration,
+    },
+    tracing::warn,
+};
+
+/// Slots the RPC endpoint is ahead of the stream, once the stream has delivered a slot
+pub fn slot_lag(rpc_slot: u64, stream_slot: u64) -> Option<i64> {
+    (stream_slot > 0).then(|| rpc_slot as i64 - stream_slot as i64)
+}
+
+/// Compares the newest slot seen on the stream with `getSlot` from an RPC endpoint on
+/// an interval. Unlike block latency this doesn't depend on `block_time` accuracy.
+pub struct SlotLagMonitor {
+    stream_slot: Arc<AtomicU64>,
+    lag: Arc<Mutex<Option<i64>>>,
+}
+
+impl SlotLagMonitor {
+    /// Poll `rpc_url` every `interval` in the background, at the stream's commitment
+    pub fn start(
+        rpc_url: String,
+        commitment: CommitmentConfig,
+        interval: Duration,
+        gauge: IntGauge,
+    ) -> Self {
+        let stream_slot = Arc::new(AtomicU64::new(0));
+        let lag = Arc::new(Mutex::new(None));
+        let monitor = Self {
+            stream_slot: stream_slot.clone(),
+            lag: lag.clone(),
+        };
+
+        tokio::spawn(async move {
+            let rpc = RpcClient::new_with_commitment(rpc_url, commitment);
+            let mut ticks = tokio::time::interval(interval);
+            loop {
+                ticks.tick().await;
+                let rpc_slot = match rpc.get_slot().await {
+                    Ok(slot) => slot,
+                    Err(e) => {
+                        warn!(error = %e, "slot lag check failed");
+                        continue;
+                    }
+                };
+                if let Some(current) = slot_lag(rpc_slot, stream_slot.load(Ordering::Relaxed)) {
+                    gauge.set(current);
+                    *lag.lock().unwrap() = Some(current);
+                }
+            }
+        });
+        monitor
+    }
+
+    pub fn on_slot(&self, slot: u64) {
+        self.stream_slot.fetch_max(slot, Ordering::Relaxed);
+    }
+
+    /// The result of the latest check
+    pub fn lag(&self) -> Option<i64> {
+        *self.lag.lock().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slot_lag() {
+        assert_eq!(slot_lag(1_000, 0), None);
+        assert_eq!(slot_lag(1_000, 995), Some(5));
+        // The stream can be ahead of a slow RPC node
+        assert_eq!(slot_lag(1_000, 1_002), Some(-2));
+    }
+}
@@ -0,0 +1,261 @@
+use {
+    crate::sink::received_at_ms,
+    serde::{Deserialize, Serialize},
+    std::{
+        fs,
+        io::ErrorKind,
+        path::{Path, PathBuf},
+    },
+    tracing::{info, warn},
+};
+
+/// Highest slot processed so far, optionally persisted so a reconnect or restart
+/// can ask the server to replay everything after it
+pub struct SlotCheckpoint {
+    path: Option<PathBuf>,
+    last_slot: Option<u64>,
+    // Write the file once per this many advances rather than on every slot
+    write_every: u64,
+    unwritten: u64,
+}
+
+/// What the checkpoint file holds
+#[derive(Debug, Serialize, Deserialize)]
+struct CheckpointFile {
+    slot: u64,
+    /// Milliseconds since the Unix epoch
+    updated_at_ms: u64,
+}
+
+/// Where the first subscription starts, in order of precedence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartMode {
+    /// `--from-slot`: replay from this slot for a backfill
+    Cli(u64),
+    /// After the slot saved in the checkpoint file
+    Checkpoint(u64),
+    /// Only new blocks
+    Live,
+}
+
+impl StartMode {
+    pub fn new(from_slot: Option<u64>, checkpoint: &SlotCheckpoint) -> Self {
+        match (from_slot, checkpoint.last_slot()) {
+            (Some(slot), _) => Self::Cli(slot),
+            (None, Some(slot)) => Self::Checkpoint(slot),
+            (None, None) => Self::Live,
+        }
+    }
+
+    /// Last slot treated as already processed, so the stream starts right after it
+    pub fn resume_after(self) -> Option<u64> {
+        match self {
+            Self::Cli(slot) => slot.checked_sub(1),
+            Self::Checkpoint(slot) => Some(slot),
+            Self::Live => None,
+        }
+    }
+
+    pub fn log(self) {
+        match self {
+            Self::Cli(slot) => info!(from_slot = slot, "start mode: CLI slot"),
+            Self::Checkpoint(slot) => {
+                info!(slot, "start mode: checkpoint, resuming after its slot")
+            }
+            Self::Live => info!("start mode: live"),
+        }
+    }
+}
+
+impl SlotCheckpoint {
+    /// Read the last slot from `path`. A missing file means nothing has been
+    /// processed yet; one that can't be parsed is ignored with a warning, so the
+    /// stream starts live instead of the service failing to start.
+    pub fn load(path: Option<&str>, write_every: u64) -> anyhow::Result<Self> {
+        let last_slot = match path {
+            Some(path) => read_slot(Path::new(path))?,
+            None => None,
+        };
+
+        Ok(Self {
+            path: path.map(PathBuf::from),
+            last_slot,
+            write_every,
+            unwritten: 0,
+        })
+    }
+
+    pub fn last_slot(&self) -> Option<u64> {
+        self.last_slot
+    }
+
+    /// Remember `slot` if it's the highest seen, writing it out every `write_every`
+    /// advances. Replayed blocks older than the checkpoint leave it alone.
+    pub async fn record(&mut self, slot: u64) -> anyhow::Result<()> {
+        if self.last_slot.is_some_and(|last| slot <= last) {
+            return Ok(());
+        }
+        self.last_slot = Some(slot);
+        self.unwritten += 1;
+
+        if self.unwritten >= self.write_every {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Write out a slot that `record` is still holding back
+    pub async fn flush(&mut self) -> anyhow::Result<()> {
+        let (Some(path), Some(slot)) = (&self.path, self.last_slot) else {
+            return Ok(());
+        };
+        if self.unwritten == 0 {
+            return Ok(());
+        }
+
+        let contents = serde_json::to_string(&CheckpointFile {
+            slot,
+            updated_at_ms: received_at_ms(),
+        })?;
+        // Write then rename so a crash mid-write can't leave a truncated file
+        let tmp = path.with_extension("tmp");
+        tokio::fs::write(&tmp, contents).await?;
+        tokio::fs::rename(&tmp, path).await?;
+        self.unwritten = 0;
+        Ok(())
+    }
+}
+
+/// Whether the server turned down `from_slot`, typically because the slot is older
+/// than what it keeps for replay
+pub fn is_replay_rejection(status: &tonic::Status) -> bool {
+    matches!(
+        status.code(),
+        tonic::Code::InvalidArgument | tonic::Code::OutOfRange
+    )
+}
+
+fn read_slot(path: &Path) -> anyhow::Result<Option<u64>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    // Files written before the timestamp was added hold just the slot
+    let slot = serde_json::from_str::<CheckpointFile>(&contents)
+        .map(|file| file.slot)
+        .or_else(|_| contents.trim().parse());
+    match slot {
+        Ok(slot) => Ok(Some(slot)),
+        Err(e) => {
+            warn!(
+                path = %path.display(),
+                error = %e,
+                "checkpoint file is unreadable; starting live"
+            );
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "geyser-watcher-{}-{}.slot",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_round_trips_highest_slot() {
+        let path = state_path("round-trip");
+        let path_str = path.to_str().unwrap();
+
+        let mut checkpoint = SlotCheckpoint::load(Some(path_str), 1).unwrap();
+        assert_eq!(checkpoint.last_slot(), None);
+
+        checkpoint.record(100).await.unwrap();
+        checkpoint.record(99).await.unwrap();
+
+        let reloaded = SlotCheckpoint::load(Some(path_str), 1).unwrap();
+        assert_eq!(reloaded.last_slot(), Some(100));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_replay_rejection_codes() {
+        assert!(is_replay_rejection(&tonic::Status::invalid_argument(
+            "broadcast from 100 is not available"
+        )));
+        assert!(!is_replay_rejection(&tonic::Status::unavailable(
+            "connection reset"
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_writes_every_n_slots() {
+        let path = state_path("every-n");
+        let path_str = path.to_str().unwrap();
+
+        let mut checkpoint = SlotCheckpoint::load(Some(path_str), 3).unwrap();
+        checkpoint.record(100).await.unwrap();
+        checkpoint.record(101).await.unwrap();
+        assert!(!path.exists());
+
+        checkpoint.record(102).await.unwrap();
+        checkpoint.record(103).await.unwrap();
+        let saved: CheckpointFile =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved.slot, 102);
+        assert!(saved.updated_at_ms > 0);
+
+        checkpoint.flush().await.unwrap();
+        let reloaded = SlotCheckpoint::load(Some(path_str), 3).unwrap();
+        assert_eq!(reloaded.last_slot(), Some(103));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_plain_slot_file_still_loads() {
+        let path = state_path("plain");
+        fs::write(&path, "4242\n").unwrap();
+        let checkpoint = SlotCheckpoint::load(path.to_str(), 1).unwrap();
+        assert_eq!(checkpoint.last_slot(), Some(4242));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_garbage_state_file_starts_live() {
+        let path = state_path("garbage");
+        fs::write(&path, "not a slot").unwrap();
+        let checkpoint = SlotCheckpoint::load(path.to_str(), 1).unwrap();
+        assert_eq!(checkpoint.last_slot(), None);
+        assert_eq!(StartMode::new(None, &checkpoint), StartMode::Live);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_cli_slot_overrides_the_checkpoint() {
+        let checkpoint = SlotCheckpoint {
+            path: None,
+            last_slot: Some(500),
+            write_every: 1,
+            unwritten: 0,
+        };
+
+        let checkpointed = StartMode::new(None, &checkpoint);
+        assert_eq!(checkpointed, StartMode::Checkpoint(500));
+        assert_eq!(checkpointed.resume_after(), Some(500));
+
+        let backfill = StartMode::new(Some(200), &checkpoint);
+        assert_eq!(backfill, StartMode::Cli(200));
+        assert_eq!(backfill.resume_after(), Some(199));
+    }
+}
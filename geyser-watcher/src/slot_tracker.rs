@@ -0,0 +1,191 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    time::{Duration, Instant},
+};
+
+/// A slot's commitment progress, from `SubscribeUpdateSlot::status`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlotStatus {
+    Processed,
+    Confirmed,
+    Finalized,
+    Dead,
+    /// Intermediate states (first shred received, completed, bank created)
+    Other(i32),
+}
+
+impl SlotStatus {
+    // Values of the `SlotStatus` enum in geyser.proto
+    pub fn from_update(status: i32, dead_error: Option<&str>) -> Self {
+        match (status, dead_error) {
+            (_, Some(_)) | (6, _) => Self::Dead,
+            (0, _) => Self::Processed,
+            (1, _) => Self::Confirmed,
+            (2, _) => Self::Finalized,
+            (other, _) => Self::Other(other),
+        }
+    }
+}
+
+impl fmt::Display for SlotStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Processed => write!(f, "processed"),
+            Self::Confirmed => write!(f, "confirmed"),
+            Self::Finalized => write!(f, "finalized"),
+            Self::Dead => write!(f, "dead"),
+            Self::Other(status) => write!(f, "status {}", status),
+        }
+    }
+}
+
+/// A slot that was announced and then died, i.e. its fork was abandoned
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AbandonedSlot {
+    pub slot: u64,
+    pub parent: Option<u64>,
+    /// Furthest status the slot reached before dying
+    pub last_status: SlotStatus,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SlotInfo {
+    parent: Option<u64>,
+    status: SlotStatus,
+}
+
+/// Recent slots and the processed/confirmed/finalized heads
+pub struct SlotTracker {
+    slots: BTreeMap<u64, SlotInfo>,
+    processed: Option<u64>,
+    confirmed: Option<u64>,
+    finalized: Option<u64>,
+    summary_interval: Duration,
+    last_summary: Instant,
+}
+
+impl SlotTracker {
+    pub fn new(summary_interval: Duration) -> Self {
+        Self {
+            slots: BTreeMap::new(),
+            processed: None,
+            confirmed: None,
+            finalized: None,
+            summary_interval,
+            last_summary: Instant::now(),
+        }
+    }
+
+    /// Record a status change; returns the abandoned slot when one just died
+    pub fn on_update(
+        &mut self,
+        slot: u64,
+        parent: Option<u64>,
+        status: SlotStatus,
+    ) -> Option<AbandonedSlot> {
+        let head = match status {
+            SlotStatus::Processed => Some(&mut self.processed),
+            SlotStatus::Confirmed => Some(&mut self.confirmed),
+            SlotStatus::Finalized => Some(&mut self.finalized),
+            _ => None,
+        };
+        if let Some(head) = head {
+            *head = Some(head.map_or(slot, |current| current.max(slot)));
+        }
+
+        // Only the first update for a slot is guaranteed to carry its parent
+        let parent = parent.or_else(|| self.slots.get(&slot).and_then(|info| info.parent));
+        let previous = self.slots.insert(slot, SlotInfo { parent, status });
+
+        // Anything at or below the finalized head can no longer fork
+        if let Some(finalized) = self.finalized {
+            self.slots = self.slots.split_off(&finalized);
+        }
+
+        match (status, previous) {
+            (SlotStatus::Dead, Some(previous)) if previous.status != SlotStatus::Dead => {
+                Some(AbandonedSlot {
+                    slot,
+                    parent,
+                    last_status: previous.status,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// One line with the heads and how far confirmed and finalized trail processed
+    pub fn summary(&self) -> String {
+        let head = |slot: Option<u64>| slot.map_or("-".to_string(), |slot| slot.to_string());
+        let lag = |slot: Option<u64>| match (self.processed, slot) {
+            (Some(processed), Some(slot)) => format!(" (-{})", processed.saturating_sub(slot)),
+            _ => String::new(),
+        };
+        format!(
+            "📊 Slots: processed {}, confirmed {}{}, finalized {}{}",
+            head(self.processed),
+            head(self.confirmed),
+            lag(self.confirmed),
+            head(self.finalized),
+            lag(self.finalized)
+        )
+    }
+
+    /// The summary, once every `summary_interval`
+    pub fn summary_if_due(&mut self) -> Option<String> {
+        if self.last_summary.elapsed() < self.summary_interval {
+            return None;
+        }
+        self.last_summary = Instant::now();
+        Some(self.summary())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_mapping() {
+        assert_eq!(SlotStatus::from_update(1, None), SlotStatus::Confirmed);
+        assert_eq!(SlotStatus::from_update(6, None), SlotStatus::Dead);
+        assert_eq!(
+            SlotStatus::from_update(0, Some("bad block")),
+            SlotStatus::Dead
+        );
+        assert_eq!(SlotStatus::from_update(4, None), SlotStatus::Other(4));
+    }
+
+    #[test]
+    fn test_dead_slot_reports_abandoned_fork() {
+        let mut tracker = SlotTracker::new(Duration::from_secs(10));
+        tracker.on_update(100, Some(99), SlotStatus::Processed);
+        tracker.on_update(101, Some(100), SlotStatus::Processed);
+
+        assert_eq!(
+            tracker.on_update(101, None, SlotStatus::Dead),
+            Some(AbandonedSlot {
+                slot: 101,
+                parent: Some(100),
+                last_status: SlotStatus::Processed,
+            })
+        );
+        // Only the transition is reported
+        assert_eq!(tracker.on_update(101, None, SlotStatus::Dead), None);
+    }
+
+    #[test]
+    fn test_summary_shows_heads_and_lag() {
+        let mut tracker = SlotTracker::new(Duration::from_secs(10));
+        tracker.on_update(120, Some(119), SlotStatus::Processed);
+        tracker.on_update(118, Some(117), SlotStatus::Confirmed);
+        tracker.on_update(90, Some(89), SlotStatus::Finalized);
+
+        assert_eq!(
+            tracker.summary(),
+            "📊 Slots: processed 120, confirmed 118 (-2), finalized 90 (-30)"
+        );
+        assert!(tracker.slots.keys().all(|slot| *slot >= 90));
+    }
+}
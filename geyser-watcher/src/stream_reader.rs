@@ -0,0 +1,306 @@
+use {
+    crate::{
+        endpoints::EndpointRotation,
+        filter_reload::FilterUpdate,
+        health::Readiness,
+        keepalive::PingTracker,
+        metrics::Metrics,
+        reconnect::Reconnector,
+        slot_state::is_replay_rejection,
+        update_queue::{QueueSender, Received, StreamEvent},
+    },
+    futures::{sink::SinkExt, stream::StreamExt},
+    prost::Message,
+    std::time::{Duration, Instant},
+    tokio::sync::mpsc,
+    tokio_util::sync::CancellationToken,
+    tracing::{debug, error, info, warn},
+    yellowstone_grpc_client::GeyserGrpcClientError,
+    yellowstone_grpc_proto::geyser::{SubscribeRequest, subscribe_update::UpdateOneof},
+};
+
+/// Owns the subscription: subscribes, reads and decodes updates, answers pings and
+/// reconnects. Updates go to the worker through the queue, so a slow sink or
+/// webhook never holds up reads.
+pub struct StreamReader {
+    endpoints: EndpointRotation,
+    reconnector: Reconnector,
+    // Ids for the pings we send, shared by every subscription
+    pings: PingTracker,
+    readiness: Readiness,
+    metrics: Metrics,
+    queue: QueueSender,
+    // Every subscription asks for this, plus `from_slot` when resuming
+    request: SubscribeRequest,
+    // What the request covers, e.g. "blocks and slots", for the logs
+    streams: String,
+    stale_after: Duration,
+    ping_interval_secs: u64,
+    // Without blocks, a transaction's slot marks the slot before it as complete
+    watch_blocks: bool,
+    // Highest complete slot handed to the worker; a reconnect resumes after it
+    last_queued_slot: Option<u64>,
+    // Filters reloaded by the worker, swapped into the live subscription
+    filter_updates: mpsc::UnboundedReceiver<FilterUpdate>,
+}
+
+/// Fixed settings for a `StreamReader`
+pub struct StreamSettings {
+    pub request: SubscribeRequest,
+    pub streams: String,
+    pub stale_after: Duration,
+    pub ping_interval_secs: u64,
+    pub watch_blocks: bool,
+    /// Last slot processed before this run, from the checkpoint
+    pub resume_after: Option<u64>,
+}
+
+impl StreamReader {
+    pub fn new(
+        endpoints: EndpointRotation,
+        reconnector: Reconnector,
+        readiness: Readiness,
+        metrics: Metrics,
+        queue: QueueSender,
+        settings: StreamSettings,
+    ) -> Self {
+        Self {
+            endpoints,
+            reconnector,
+            pings: PingTracker::default(),
+            readiness,
+            metrics,
+            queue,
+            request: settings.request,
+            streams: settings.streams,
+            stale_after: settings.stale_after,
+            ping_interval_secs: settings.ping_interval_secs,
+            watch_blocks: settings.watch_blocks,
+            last_queued_slot: settings.resume_after,
+            // Nothing sends on this until `with_filter_updates` replaces it
+            filter_updates: mpsc::unbounded_channel().1,
+        }
+    }
+
+    /// Take new filters from `updates` while running
+    pub fn with_filter_updates(mut self, updates: mpsc::UnboundedReceiver<FilterUpdate>) -> Self {
+        self.filter_updates = updates;
+        self
+    }
+
+    // Later subscriptions, reconnects included, ask for the new filters
+    fn use_filters(&mut self, update: FilterUpdate) {
+        self.request = update.request;
+        self.streams = update.streams;
+    }
+
+    // Count a lost subscription, move to the next endpoint, then back off before the
+    // next subscription unless shutting down
+    pub async fn reconnect(&mut self, reason: &str, shutdown: &CancellationToken) {
+        self.readiness.disconnected();
+        self.metrics.on_reconnect(reason);
+        self.endpoints.fail_over();
+        tokio::select! {
+            () = self.reconnector.wait(reason) => {}
+            () = shutdown.cancelled() => {}
+        }
+    }
+
+    // The server refused to replay, so the next subscription is live-only; the
+    // worker reports the gap once blocks resume
+    async fn replay_rejected(&mut self, from_slot: u64, reason: &str) -> anyhow::Result<()> {
+        warn!(
+            from_slot,
+            reason,
+            "server refused to replay; falling back to a live subscription, blocks from \
+             slot {} until it starts will be missed",
+            from_slot
+        );
+        self.last_queued_slot = None;
+        self.queue
+            .send(StreamEvent::ReplayRefused(from_slot))
+            .await?;
+        Ok(())
+    }
+
+    // Queue an event for the worker, counting any older event dropped for it
+    async fn enqueue(&self, event: StreamEvent) -> anyhow::Result<()> {
+        if self.queue.send(event).await? {
+            self.metrics.on_update_dropped();
+        }
+        Ok(())
+    }
+
+    // Slot known to be complete once this update is processed
+    fn completed_slot(&self, update: &UpdateOneof) -> Option<u64> {
+        match update {
+            UpdateOneof::Block(block) => Some(block.slot),
+            UpdateOneof::BlockMeta(meta) => Some(meta.slot),
+            // More of this slot's transactions may still be coming
+            UpdateOneof::Transaction(transaction) if !self.watch_blocks => {
+                Some(transaction.slot.saturating_sub(1))
+            }
+            _ => None,
+        }
+    }
+
+    #[tracing::instrument(
+        name = "subscription",
+        skip_all,
+        fields(
+            endpoint = tracing::field::Empty,
+            streams = tracing::field::Empty,
+            from_slot = tracing::field::Empty
+        )
+    )]
+    pub async fn run(&mut self, shutdown: &CancellationToken) -> anyhow::Result<()> {
+        // Filters reloaded while there was no stream to send them on
+        while let Ok(update) = self.filter_updates.try_recv() {
+            self.use_filters(update);
+        }
+        // Replay the gap since the last queued block, unless the server just refused to
+        let from_slot = self.last_queued_slot.map(|slot| slot + 1);
+        let request = SubscribeRequest {
+            from_slot,
+            ..self.request.clone()
+        };
+        let subscription = tokio::select! {
+            subscription = self.endpoints.pool().subscribe(request) => subscription,
+            () = shutdown.cancelled() => return Ok(()),
+        };
+        let (mut subscribe_tx, mut stream, lease) = match subscription {
+            Ok(subscription) => subscription,
+            Err(e) => match e.downcast_ref::<GeyserGrpcClientError>() {
+                Some(GeyserGrpcClientError::TonicStatus(status))
+                    if from_slot.is_some() && is_replay_rejection(status) =>
+                {
+                    return self
+                        .replay_rejected(from_slot.unwrap_or_default(), status.message())
+                        .await;
+                }
+                _ => return Err(e),
+            },
+        };
+
+        let span = tracing::Span::current();
+        span.record("endpoint", self.endpoints.url());
+        span.record("streams", self.streams.as_str());
+        if let Some(slot) = from_slot {
+            span.record("from_slot", slot);
+        }
+        info!("subscribed");
+        self.reconnector.connected();
+        self.readiness.connected();
+        let mut received_update = false;
+
+        // A stream can stop delivering without erroring, e.g. after a NAT timeout. The
+        // server pings regularly, so silence this long means the stream is gone.
+        let stale_after = self.stale_after;
+        let watchdog = tokio::time::sleep(stale_after);
+        tokio::pin!(watchdog);
+        let mut stale = false;
+
+        self.pings.reset();
+        let ping_interval = Duration::from_secs(self.ping_interval_secs.max(1));
+        let mut ping_timer =
+            tokio::time::interval_at(tokio::time::Instant::now() + ping_interval, ping_interval);
+        let send_pings = self.ping_interval_secs > 0;
+
+        loop {
+            let message = tokio::select! {
+                message = stream.next() => message,
+                () = &mut watchdog => {
+                    stale = true;
+                    None
+                }
+                () = shutdown.cancelled() => None,
+                _ = ping_timer.tick(), if send_pings => {
+                    subscribe_tx.send(self.pings.ping(Instant::now())).await?;
+                    continue;
+                }
+                Some(update) = self.filter_updates.recv() => {
+                    // The server swaps the filters on the open stream
+                    subscribe_tx.send(update.request.clone()).await?;
+                    info!(streams = %update.streams, "subscription filters updated");
+                    self.use_filters(update);
+                    continue;
+                }
+            };
+            let Some(message) = message else {
+                break;
+            };
+            watchdog
+                .as_mut()
+                .reset(tokio::time::Instant::now() + stale_after);
+
+            match message {
+                Ok(msg) => {
+                    self.metrics.on_message(msg.encoded_len());
+                    match msg.update_oneof {
+                        Some(UpdateOneof::Ping(_)) => {
+                            // Quiet streams still get their buffered lines written out
+                            self.enqueue(StreamEvent::Idle).await?;
+                            subscribe_tx.send(self.pings.ping(Instant::now())).await?;
+                        }
+                        Some(UpdateOneof::Pong(pong)) => {
+                            if let Some(rtt) = self.pings.on_pong(pong.id, Instant::now()) {
+                                self.metrics.on_ping_rtt(rtt);
+                                debug!(id = pong.id, rtt_ms = rtt.as_millis() as u64, "pong");
+                            }
+                        }
+                        None => {
+                            error!("empty update received");
+                            break;
+                        }
+                        Some(update) => {
+                            let received = Received::now();
+                            received_update = true;
+                            self.readiness.on_update(received.at);
+                            if let Some(slot) = self.completed_slot(&update) {
+                                self.last_queued_slot = self.last_queued_slot.max(Some(slot));
+                            }
+                            self.enqueue(StreamEvent::Update(Box::new(update), received))
+                                .await?;
+                        }
+                    }
+                }
+                // The replay request is refused as soon as the stream starts
+                Err(error)
+                    if from_slot.is_some() && !received_update && is_replay_rejection(&error) =>
+                {
+                    self.readiness.disconnected();
+                    return self
+                        .replay_rejected(from_slot.unwrap_or_default(), error.message())
+                        .await;
+                }
+                Err(error) => {
+                    error!(error = ?error, "stream error");
+                    self.endpoints.pool().discard(&lease);
+                    self.reconnect("stream error", shutdown).await;
+                    return Ok(());
+                }
+            }
+        }
+
+        if shutdown.is_cancelled() {
+            // Let the server end the subscription instead of seeing the connection drop
+            let _ = subscribe_tx.close().await;
+            self.readiness.disconnected();
+            return Ok(());
+        }
+
+        if stale {
+            warn!(
+                stale_after_secs = stale_after.as_secs(),
+                "nothing received; dropping the stream"
+            );
+            self.endpoints.pool().discard(&lease);
+            self.reconnect("stale stream", shutdown).await;
+            return Ok(());
+        }
+
+        warn!("subscription stream closed");
+        self.reconnect("stream closed", shutdown).await;
+        Ok(())
+    }
+}
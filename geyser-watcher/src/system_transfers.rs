@@ -0,0 +1,253 @@
+use {
+    serde::{Deserialize, Serialize},
+    solana_sdk::native_token::LAMPORTS_PER_SOL,
+    std::fmt,
+    tracing::info,
+    yellowstone_grpc_proto::{
+        geyser::{SubscribeUpdateBlock, SubscribeUpdateTransactionInfo},
+        prelude::CompiledInstruction,
+    },
+};
+
+const SYSTEM_PROGRAM_ID: [u8; 32] = [0; 32];
+// SystemInstruction discriminators, encoded as a little-endian u32
+const TRANSFER: u32 = 2;
+const TRANSFER_WITH_SEED: u32 = 11;
+
+/// Decode System Program transfers from the transactions of full block updates
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BlockTransferConfig {
+    /// Skip transfers smaller than this
+    #[serde(default)]
+    pub min_transfer_sol: f64,
+    /// Only transfers from or to one of these wallets; empty prints every transfer
+    #[serde(default)]
+    pub watch_addresses: Vec<String>,
+}
+
+impl BlockTransferConfig {
+    fn matches(&self, transfer: &SystemTransfer) -> bool {
+        let touches =
+            |address: &String| *address == transfer.source || *address == transfer.destination;
+        transfer.lamports as f64 >= self.min_transfer_sol * LAMPORTS_PER_SOL as f64
+            && (self.watch_addresses.is_empty() || self.watch_addresses.iter().any(touches))
+    }
+}
+
+/// One SOL transfer made by a top-level System Program instruction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SystemTransfer {
+    pub slot: u64,
+    pub signature: String,
+    pub source: String,
+    pub destination: String,
+    pub lamports: u64,
+}
+
+impl SystemTransfer {
+    pub fn log(&self) {
+        info!(
+            slot = self.slot,
+            sig = %self.signature,
+            lamports = self.lamports,
+            "{}",
+            self
+        );
+    }
+}
+
+impl fmt::Display for SystemTransfer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:.4} SOL from {} to {} in slot {} (sig {})",
+            self.lamports as f64 / LAMPORTS_PER_SOL as f64,
+            self.source,
+            self.destination,
+            self.slot,
+            self.signature
+        )
+    }
+}
+
+/// Every transfer in `block` passing `config`'s filters, in block order
+pub fn block_transfers(
+    config: &BlockTransferConfig,
+    block: &SubscribeUpdateBlock,
+) -> Vec<SystemTransfer> {
+    block
+        .transactions
+        .iter()
+        .flat_map(|transaction| transaction_transfers(block.slot, transaction))
+        .filter(|transfer| config.matches(transfer))
+        .collect()
+}
+
+fn transaction_transfers(slot: u64, info: &SubscribeUpdateTransactionInfo) -> Vec<SystemTransfer> {
+    let Some(message) = info
+        .transaction
+        .as_ref()
+        .and_then(|transaction| transaction.message.as_ref())
+    else {
+        return Vec::new();
+    };
+    // Lookup-table accounts follow the static keys: writable ones, then readonly
+    let loaded: Vec<&[u8]> = info
+        .meta
+        .as_ref()
+        .map(|meta| {
+            meta.loaded_writable_addresses
+                .iter()
+                .chain(&meta.loaded_readonly_addresses)
+                .map(Vec::as_slice)
+                .collect()
+        })
+        .unwrap_or_default();
+    let account = |index: u8| -> String {
+        let index = index as usize;
+        match message.account_keys.get(index) {
+            Some(key) => bs58::encode(key).into_string(),
+            None => match loaded.get(index - message.account_keys.len()) {
+                Some(key) => bs58::encode(key).into_string(),
+                None => format!("<lookup table account #{}>", index),
+            },
+        }
+    };
+
+    let signature = bs58::encode(&info.signature).into_string();
+    message
+        .instructions
+        .iter()
+        .filter(|instruction| {
+            // Programs are always static keys, never loaded from a lookup table
+            message
+                .account_keys
+                .get(instruction.program_id_index as usize)
+                .is_some_and(|program| *program == SYSTEM_PROGRAM_ID)
+        })
+        .filter_map(|instruction| {
+            let (source, destination, lamports) = decode_transfer(instruction)?;
+            Some(SystemTransfer {
+                slot,
+                signature: signature.clone(),
+                source: account(source),
+                destination: account(destination),
+                lamports,
+            })
+        })
+        .collect()
+}
+
+// (source index, destination index, lamports) of a transfer instruction
+fn decode_transfer(instruction: &CompiledInstruction) -> Option<(u8, u8, u64)> {
+    let data = &instruction.data;
+    let discriminator = u32::from_le_bytes(data.get(..4)?.try_into().ok()?);
+    let lamports = u64::from_le_bytes(data.get(4..12)?.try_into().ok()?);
+    let accounts = &instruction.accounts;
+    match discriminator {
+        // [from, to]
+        TRANSFER => Some((*accounts.first()?, *accounts.get(1)?, lamports)),
+        // [from (derived), base, to]
+        TRANSFER_WITH_SEED => Some((*accounts.first()?, *accounts.get(2)?, lamports)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        yellowstone_grpc_proto::prelude::{Message, Transaction, TransactionStatusMeta},
+    };
+
+    const FROM: [u8; 32] = [1; 32];
+    const TO: [u8; 32] = [2; 32];
+
+    fn instruction_data(discriminator: u32, lamports: u64) -> Vec<u8> {
+        let mut data = discriminator.to_le_bytes().to_vec();
+        data.extend(lamports.to_le_bytes());
+        data
+    }
+
+    fn block(instructions: Vec<CompiledInstruction>, loaded: Vec<Vec<u8>>) -> SubscribeUpdateBlock {
+        SubscribeUpdateBlock {
+            slot: 9,
+            transactions: vec![SubscribeUpdateTransactionInfo {
+                signature: vec![3; 64],
+                transaction: Some(Transaction {
+                    message: Some(Message {
+                        account_keys: vec![FROM.to_vec(), SYSTEM_PROGRAM_ID.to_vec()],
+                        instructions,
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
+                meta: Some(TransactionStatusMeta {
+                    loaded_writable_addresses: loaded,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn transfer(discriminator: u32, accounts: Vec<u8>, lamports: u64) -> CompiledInstruction {
+        CompiledInstruction {
+            program_id_index: 1,
+            accounts,
+            data: instruction_data(discriminator, lamports),
+        }
+    }
+
+    #[test]
+    fn test_decodes_transfers_and_resolves_loaded_accounts() {
+        let block = block(
+            vec![
+                transfer(TRANSFER, vec![0, 2], 1_500_000_000),
+                transfer(TRANSFER_WITH_SEED, vec![0, 0, 2], 7),
+                // CreateAccount is not a transfer
+                transfer(0, vec![0, 2], 1),
+            ],
+            vec![TO.to_vec()],
+        );
+
+        let transfers = block_transfers(&BlockTransferConfig::default(), &block);
+        assert_eq!(transfers.len(), 2);
+        assert_eq!(transfers[0].destination, bs58::encode(TO).into_string());
+        assert_eq!(transfers[1].lamports, 7);
+        assert!(transfers[0].to_string().starts_with(&format!(
+            "1.5000 SOL from {} to ",
+            bs58::encode(FROM).into_string()
+        )));
+    }
+
+    #[test]
+    fn test_unresolved_lookup_accounts_are_labeled() {
+        let block = block(vec![transfer(TRANSFER, vec![0, 5], 1)], vec![]);
+        let transfers = block_transfers(&BlockTransferConfig::default(), &block);
+        assert_eq!(transfers[0].destination, "<lookup table account #5>");
+    }
+
+    #[test]
+    fn test_filters_by_amount_and_address() {
+        let block = block(
+            vec![
+                transfer(TRANSFER, vec![0, 2], LAMPORTS_PER_SOL),
+                transfer(TRANSFER, vec![0, 2], 10),
+            ],
+            vec![TO.to_vec()],
+        );
+        let config = BlockTransferConfig {
+            min_transfer_sol: 0.5,
+            watch_addresses: vec![bs58::encode(TO).into_string()],
+        };
+        assert_eq!(block_transfers(&config, &block).len(), 1);
+
+        let config = BlockTransferConfig {
+            watch_addresses: vec!["11111111111111111111111111111112".to_string()],
+            ..Default::default()
+        };
+        assert!(block_transfers(&config, &block).is_empty());
+    }
+}
@@ -0,0 +1,185 @@
+use {
+    serde::{Deserialize, Serialize},
+    tracing::info,
+    yellowstone_grpc_proto::geyser::{
+        SubscribeRequestFilterTransactions, SubscribeUpdateTransaction,
+    },
+};
+
+/// Which transactions to stream, mapped onto `SubscribeRequestFilterTransactions`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TransactionWatchConfig {
+    /// Transactions touching any of these accounts
+    #[serde(default)]
+    pub account_include: Vec<String>,
+    /// Skip transactions touching any of these
+    #[serde(default)]
+    pub account_exclude: Vec<String>,
+    /// Only transactions touching all of these
+    #[serde(default)]
+    pub account_required: Vec<String>,
+    /// Include vote transactions
+    #[serde(default)]
+    pub vote: bool,
+    /// Include failed transactions
+    #[serde(default)]
+    pub failed: bool,
+}
+
+impl TransactionWatchConfig {
+    pub fn filter(&self) -> SubscribeRequestFilterTransactions {
+        SubscribeRequestFilterTransactions {
+            // `Some(false)` excludes them; `None` would let them through
+            vote: Some(self.vote),
+            failed: Some(self.failed),
+            signature: None,
+            account_include: self.account_include.clone(),
+            account_exclude: self.account_exclude.clone(),
+            account_required: self.account_required.clone(),
+        }
+    }
+}
+
+/// What gets printed for one streamed transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionSummary {
+    pub slot: u64,
+    pub signature: String,
+    pub success: bool,
+    pub fee: Option<u64>,
+    /// Configured include/required accounts the transaction references
+    pub matched_accounts: Vec<String>,
+}
+
+impl TransactionSummary {
+    pub fn from_update(
+        config: &TransactionWatchConfig,
+        update: &SubscribeUpdateTransaction,
+    ) -> Option<Self> {
+        let info = update.transaction.as_ref()?;
+        let meta = info.meta.as_ref();
+
+        // Static keys plus whatever address lookup tables loaded
+        let mut keys: Vec<String> = info
+            .transaction
+            .as_ref()
+            .and_then(|transaction| transaction.message.as_ref())
+            .map(|message| message.account_keys.iter().map(bs58_key).collect())
+            .unwrap_or_default();
+        if let Some(meta) = meta {
+            keys.extend(meta.loaded_writable_addresses.iter().map(bs58_key));
+            keys.extend(meta.loaded_readonly_addresses.iter().map(bs58_key));
+        }
+
+        let mut matched_accounts: Vec<String> = Vec::new();
+        for account in config
+            .account_include
+            .iter()
+            .chain(&config.account_required)
+        {
+            if keys.contains(account) && !matched_accounts.contains(account) {
+                matched_accounts.push(account.clone());
+            }
+        }
+
+        Some(Self {
+            slot: update.slot,
+            signature: bs58::encode(&info.signature).into_string(),
+            success: meta.is_none_or(|meta| meta.err.is_none()),
+            fee: meta.map(|meta| meta.fee),
+            matched_accounts,
+        })
+    }
+
+    pub fn log(&self) {
+        info!(
+            slot = self.slot,
+            sig = %self.signature,
+            success = self.success,
+            fee = ?self.fee,
+            matched = %self.matched_accounts.join(","),
+            "transaction"
+        );
+    }
+}
+
+fn bs58_key(key: impl AsRef<[u8]>) -> String {
+    bs58::encode(key).into_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        yellowstone_grpc_proto::{
+            geyser::SubscribeUpdateTransactionInfo,
+            prelude::{Message, Transaction, TransactionError, TransactionStatusMeta},
+        },
+    };
+
+    fn update(keys: &[&str], loaded: &[&str], err: bool) -> SubscribeUpdateTransaction {
+        let decode = |key: &&str| bs58::decode(key).into_vec().unwrap();
+        SubscribeUpdateTransaction {
+            slot: 42,
+            transaction: Some(SubscribeUpdateTransactionInfo {
+                signature: vec![1; 64],
+                transaction: Some(Transaction {
+                    message: Some(Message {
+                        account_keys: keys.iter().map(decode).collect(),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
+                meta: Some(TransactionStatusMeta {
+                    fee: 5_000,
+                    err: err.then(|| TransactionError { err: vec![1] }),
+                    loaded_readonly_addresses: loaded.iter().map(decode).collect(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+        }
+    }
+
+    #[test]
+    fn test_filter_maps_config() {
+        let config = TransactionWatchConfig {
+            account_include: vec!["A".to_string()],
+            failed: true,
+            ..Default::default()
+        };
+        let filter = config.filter();
+        assert_eq!(filter.account_include, ["A"]);
+        assert_eq!(filter.vote, Some(false));
+        assert_eq!(filter.failed, Some(true));
+    }
+
+    #[test]
+    fn test_summary_matches_static_and_loaded_accounts() {
+        let watched = "Vote111111111111111111111111111111111111111";
+        let loaded = "SysvarC1ock11111111111111111111111111111111";
+        let config = TransactionWatchConfig {
+            account_include: vec![
+                watched.to_string(),
+                "Unseen1111111111111111111111111111111111111".to_string(),
+            ],
+            account_required: vec![loaded.to_string()],
+            ..Default::default()
+        };
+
+        let summary = TransactionSummary::from_update(
+            &config,
+            &update(
+                &[watched, "11111111111111111111111111111111"],
+                &[loaded],
+                true,
+            ),
+        )
+        .unwrap();
+
+        assert_eq!(summary.slot, 42);
+        assert!(!summary.success);
+        assert_eq!(summary.fee, Some(5_000));
+        assert_eq!(summary.matched_accounts, [watched, loaded]);
+    }
+}
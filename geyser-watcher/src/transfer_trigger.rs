@@ -0,0 +1,349 @@
+use {
+    crate::block_source::BlockInfo,
+    serde::{Deserialize, Serialize},
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_sdk::{
+        commitment_config::CommitmentConfig,
+        pubkey::Pubkey,
+        signature::{Keypair, Signature, Signer},
+        system_instruction,
+        transaction::Transaction,
+    },
+    std::{
+        collections::VecDeque,
+        fmt,
+        sync::Arc,
+        time::{Duration, Instant},
+    },
+    tracing::{Instrument, info, warn},
+};
+
+const HOUR: Duration = Duration::from_secs(60 * 60);
+
+/// When new blocks trigger a SOL transfer. A transfer fires once every
+/// `every_n_blocks` blocks meeting `condition`, but never within `cooldown_secs` of
+/// the previous one and never more than `max_transfers_per_hour` times in a rolling
+/// hour.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TriggerConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Written as `{slot_multiple: N}` rather than a YAML tag
+    #[serde(default, with = "serde_yaml::with::singleton_map")]
+    pub condition: TriggerCondition,
+    #[serde(default = "default_every_n_blocks")]
+    pub every_n_blocks: u64,
+    #[serde(default = "default_max_transfers_per_hour")]
+    pub max_transfers_per_hour: usize,
+    #[serde(default = "default_cooldown_secs")]
+    pub cooldown_secs: u64,
+}
+
+/// Which blocks count towards a transfer
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerCondition {
+    #[default]
+    EveryBlock,
+    /// Blocks whose slot is a multiple of this
+    SlotMultiple(u64),
+    /// Blocks with at least this many transactions
+    MinTransactionCount(u32),
+    /// The block with this blockhash
+    OnBlockhash(String),
+}
+
+impl TriggerCondition {
+    pub fn matches(&self, block: &BlockInfo) -> bool {
+        match self {
+            Self::EveryBlock => true,
+            Self::SlotMultiple(multiple) => block.slot.is_multiple_of((*multiple).max(1)),
+            Self::MinTransactionCount(count) => block.transaction_count >= u64::from(*count),
+            Self::OnBlockhash(blockhash) => block.blockhash == *blockhash,
+        }
+    }
+
+    /// Conditions only one block can meet, which fire on that block instead of
+    /// waiting for `every_n_blocks` matches
+    pub fn is_one_shot(&self) -> bool {
+        matches!(self, Self::OnBlockhash(_))
+    }
+}
+
+impl fmt::Display for TriggerCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EveryBlock => write!(f, "every block"),
+            Self::SlotMultiple(multiple) => write!(f, "slot multiple of {}", multiple),
+            Self::MinTransactionCount(count) => write!(f, "at least {} transactions", count),
+            Self::OnBlockhash(blockhash) => write!(f, "blockhash {}", blockhash),
+        }
+    }
+}
+
+fn default_every_n_blocks() -> u64 {
+    10
+}
+
+fn default_max_transfers_per_hour() -> usize {
+    6
+}
+
+fn default_cooldown_secs() -> u64 {
+    300
+}
+
+// Block counter, cooldown and hourly cap, all of which must allow a transfer
+struct RateLimiter {
+    every_n_blocks: u64,
+    max_per_hour: usize,
+    cooldown: Duration,
+    blocks_since_transfer: u64,
+    last_transfer: Option<Instant>,
+    recent_transfers: VecDeque<Instant>,
+}
+
+impl RateLimiter {
+    fn new(config: &TriggerConfig) -> Self {
+        Self {
+            every_n_blocks: if config.condition.is_one_shot() {
+                1
+            } else {
+                config.every_n_blocks.max(1)
+            },
+            max_per_hour: config.max_transfers_per_hour,
+            cooldown: Duration::from_secs(config.cooldown_secs),
+            blocks_since_transfer: 0,
+            last_transfer: None,
+            recent_transfers: VecDeque::new(),
+        }
+    }
+
+    // Count a block; true when a transfer should fire for it
+    fn on_block(&mut self, now: Instant) -> bool {
+        self.blocks_since_transfer += 1;
+        if self.blocks_since_transfer < self.every_n_blocks {
+            return false;
+        }
+        if self
+            .last_transfer
+            .is_some_and(|last| now.saturating_duration_since(last) < self.cooldown)
+        {
+            return false;
+        }
+        while self
+            .recent_transfers
+            .front()
+            .is_some_and(|sent| now.saturating_duration_since(*sent) >= HOUR)
+        {
+            self.recent_transfers.pop_front();
+        }
+        if self.recent_transfers.len() >= self.max_per_hour {
+            return false;
+        }
+
+        self.blocks_since_transfer = 0;
+        self.last_transfer = Some(now);
+        self.recent_transfers.push_back(now);
+        true
+    }
+}
+
+/// Sends SOL from the configured sender to the configured recipient
+pub struct TransferSender {
+    rpc: Arc<RpcClient>,
+    sender: Arc<Keypair>,
+    recipient: Pubkey,
+    dry_run: bool,
+}
+
+impl TransferSender {
+    pub fn new(rpc_url: String, sender: Keypair, recipient: Pubkey) -> Self {
+        Self {
+            rpc: Arc::new(RpcClient::new_with_commitment(
+                rpc_url,
+                CommitmentConfig::confirmed(),
+            )),
+            sender: Arc::new(sender),
+            recipient,
+            dry_run: false,
+        }
+    }
+
+    /// Log triggered transfers without sending them
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Send `lamports` on a spawned task so a slow RPC doesn't hold up the stream.
+    /// `cause` says what triggered it in the log lines.
+    pub fn spawn(&self, lamports: u64, cause: String) {
+        let rpc = self.rpc.clone();
+        let sender = self.sender.clone();
+        let recipient = self.recipient;
+        info!(
+            cause = %cause,
+            lamports,
+            from = %sender.pubkey(),
+            to = %recipient,
+            "transfer triggered"
+        );
+        if self.dry_run {
+            info!(cause = %cause, "dry run: transfer not sent");
+            return;
+        }
+        tokio::spawn(
+            async move {
+                match transfer_sol(&rpc, &sender, &recipient, lamports).await {
+                    Ok(signature) => info!(cause = %cause, sig = %signature, "transfer completed"),
+                    Err(e) => warn!(cause = %cause, error = %e, "transfer failed"),
+                }
+            }
+            .in_current_span(),
+        );
+    }
+}
+
+/// Sends a fixed SOL transfer when a block meets the condition and the rate limits
+/// allow, off the stream loop
+pub struct TransferTrigger {
+    condition: TriggerCondition,
+    limiter: RateLimiter,
+    sender: TransferSender,
+    lamports: u64,
+}
+
+impl TransferTrigger {
+    pub fn new(config: &TriggerConfig, sender: TransferSender, lamports: u64) -> Self {
+        Self {
+            condition: config.condition.clone(),
+            limiter: RateLimiter::new(config),
+            sender,
+            lamports,
+        }
+    }
+
+    /// Count a block meeting the condition and send the transfer if it triggers one
+    pub fn on_block(&mut self, block: &BlockInfo) {
+        if !self.condition.matches(block) {
+            return;
+        }
+        if self.limiter.on_block(Instant::now()) {
+            info!(
+                slot = block.slot,
+                blockhash = %block.blockhash,
+                transactions = block.transaction_count,
+                condition = %self.condition,
+                "trigger fired"
+            );
+            self.sender
+                .spawn(self.lamports, format!("block {}", block.slot));
+        }
+    }
+}
+
+// `SolTransfer::execute_transfers` lives in the sol-transfer binary, which has no
+// library target to depend on, so the trigger sends its single transfer itself
+async fn transfer_sol(
+    rpc: &RpcClient,
+    sender: &Keypair,
+    recipient: &Pubkey,
+    lamports: u64,
+) -> anyhow::Result<Signature> {
+    let recent_blockhash = rpc.get_latest_blockhash().await?;
+    let transaction = Transaction::new_signed_with_payer(
+        &[system_instruction::transfer(
+            &sender.pubkey(),
+            recipient,
+            lamports,
+        )],
+        Some(&sender.pubkey()),
+        &[sender],
+        recent_blockhash,
+    );
+    Ok(rpc.send_and_confirm_transaction(&transaction).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(
+        every_n_blocks: u64,
+        max_transfers_per_hour: usize,
+        cooldown_secs: u64,
+    ) -> RateLimiter {
+        RateLimiter::new(&TriggerConfig {
+            enabled: true,
+            condition: TriggerCondition::EveryBlock,
+            every_n_blocks,
+            max_transfers_per_hour,
+            cooldown_secs,
+        })
+    }
+
+    #[test]
+    fn test_fires_every_n_blocks() {
+        let mut limiter = limiter(3, 100, 0);
+        let now = Instant::now();
+        let fired: Vec<bool> = (0..6).map(|_| limiter.on_block(now)).collect();
+        assert_eq!(fired, [false, false, true, false, false, true]);
+    }
+
+    #[test]
+    fn test_cooldown_delays_the_next_transfer() {
+        let mut limiter = limiter(1, 100, 300);
+        let start = Instant::now();
+        assert!(limiter.on_block(start));
+        assert!(!limiter.on_block(start + Duration::from_secs(299)));
+        // The block count is already met, so the first block after the cooldown fires
+        assert!(limiter.on_block(start + Duration::from_secs(300)));
+    }
+
+    #[test]
+    fn test_conditions() {
+        let block = BlockInfo {
+            slot: 300,
+            blockhash: "hash".to_string(),
+            parent_slot: 299,
+            block_height: None,
+            block_time: None,
+            transaction_count: 1_000,
+        };
+        assert!(TriggerCondition::EveryBlock.matches(&block));
+        assert!(TriggerCondition::SlotMultiple(100).matches(&block));
+        assert!(!TriggerCondition::SlotMultiple(7).matches(&block));
+        assert!(TriggerCondition::MinTransactionCount(1_000).matches(&block));
+        assert!(!TriggerCondition::MinTransactionCount(1_001).matches(&block));
+        assert!(TriggerCondition::OnBlockhash("hash".to_string()).matches(&block));
+
+        let config: TriggerConfig =
+            serde_yaml::from_str("condition:\n  slot_multiple: 100\n").unwrap();
+        assert_eq!(config.condition, TriggerCondition::SlotMultiple(100));
+        let config: TriggerConfig = serde_yaml::from_str("condition: every_block\n").unwrap();
+        assert_eq!(config.condition, TriggerCondition::EveryBlock);
+    }
+
+    #[test]
+    fn test_one_shot_condition_skips_the_block_count() {
+        let mut limiter = RateLimiter::new(&TriggerConfig {
+            enabled: true,
+            condition: TriggerCondition::OnBlockhash("hash".to_string()),
+            every_n_blocks: 10,
+            max_transfers_per_hour: 6,
+            cooldown_secs: 300,
+        });
+        assert!(limiter.on_block(Instant::now()));
+    }
+
+    #[test]
+    fn test_hourly_cap() {
+        let mut limiter = limiter(1, 2, 0);
+        let start = Instant::now();
+        assert!(limiter.on_block(start));
+        assert!(limiter.on_block(start + Duration::from_secs(60)));
+        assert!(!limiter.on_block(start + Duration::from_secs(120)));
+        assert!(limiter.on_block(start + HOUR));
+    }
+}
@@ -0,0 +1,203 @@
+use {
+    serde::{Deserialize, Serialize},
+    std::{
+        sync::{
+            Arc,
+            atomic::{AtomicU64, Ordering},
+        },
+        time::Instant,
+    },
+    tokio::sync::{
+        Mutex,
+        mpsc::{self, error::TrySendError},
+    },
+    yellowstone_grpc_proto::geyser::subscribe_update::UpdateOneof,
+};
+
+/// What the stream reader does when the workers fall a full queue behind
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueOverflow {
+    /// Stop reading until there's room; the server buffers meanwhile
+    #[default]
+    Block,
+    /// Drop the oldest queued event to make room, counting it
+    DropOldest,
+}
+
+/// Buffer between the stream reader and the worker processing updates
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueConfig {
+    /// Most events waiting for the worker
+    #[serde(default = "default_capacity")]
+    pub capacity: usize,
+    #[serde(default)]
+    pub overflow: QueueOverflow,
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self {
+            capacity: default_capacity(),
+            overflow: QueueOverflow::default(),
+        }
+    }
+}
+
+fn default_capacity() -> usize {
+    10_000
+}
+
+/// When the reader took an update off the stream, so queueing time counts towards
+/// latency
+#[derive(Debug, Clone, Copy)]
+pub struct Received {
+    pub at: Instant,
+    pub unix_ms: u64,
+}
+
+impl Received {
+    pub fn now() -> Self {
+        Self {
+            at: Instant::now(),
+            unix_ms: crate::sink::received_at_ms(),
+        }
+    }
+}
+
+/// Everything the reader hands to the worker, in stream order
+pub enum StreamEvent {
+    Update(Box<UpdateOneof>, Received),
+    /// The server pinged: the stream is quiet, a good time to flush
+    Idle,
+    /// The server refused to replay from this slot; report the gap once blocks resume
+    ReplayRefused(u64),
+}
+
+/// Queue an event for the worker, applying the overflow policy when it's full
+pub struct QueueSender {
+    sender: mpsc::Sender<StreamEvent>,
+    // Shared with the receiver so the oldest event can be dropped from this side
+    receiver: Arc<Mutex<mpsc::Receiver<StreamEvent>>>,
+    overflow: QueueOverflow,
+    dropped: Arc<AtomicU64>,
+}
+
+pub struct QueueReceiver {
+    receiver: Arc<Mutex<mpsc::Receiver<StreamEvent>>>,
+    dropped: Arc<AtomicU64>,
+}
+
+pub fn channel(config: &QueueConfig) -> (QueueSender, QueueReceiver) {
+    let (sender, receiver) = mpsc::channel(config.capacity.max(1));
+    let receiver = Arc::new(Mutex::new(receiver));
+    let dropped = Arc::new(AtomicU64::new(0));
+    (
+        QueueSender {
+            sender,
+            receiver: receiver.clone(),
+            overflow: config.overflow,
+            dropped: dropped.clone(),
+        },
+        QueueReceiver { receiver, dropped },
+    )
+}
+
+impl QueueSender {
+    /// Queue `event`; true when an older event was dropped to make room. Fails once
+    /// the worker has stopped.
+    pub async fn send(&self, event: StreamEvent) -> anyhow::Result<bool> {
+        match self.overflow {
+            QueueOverflow::Block => {
+                self.sender
+                    .send(event)
+                    .await
+                    .map_err(|_| anyhow::anyhow!("update worker stopped"))?;
+                Ok(false)
+            }
+            QueueOverflow::DropOldest => {
+                let mut event = event;
+                let mut dropped = false;
+                loop {
+                    match self.sender.try_send(event) {
+                        Ok(()) => return Ok(dropped),
+                        Err(TrySendError::Closed(_)) => {
+                            anyhow::bail!("update worker stopped")
+                        }
+                        Err(TrySendError::Full(rejected)) => {
+                            event = rejected;
+                            // The worker may empty a slot first; then nothing is dropped
+                            if self.receiver.lock().await.try_recv().is_ok() {
+                                self.dropped.fetch_add(1, Ordering::Relaxed);
+                                dropped = true;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl QueueReceiver {
+    /// The next event; `None` once the reader is gone and the queue is empty
+    pub async fn recv(&self) -> Option<StreamEvent> {
+        self.receiver.lock().await.recv().await
+    }
+
+    /// Events dropped so far under `drop_oldest`
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue(overflow: QueueOverflow) -> (QueueSender, QueueReceiver) {
+        channel(&QueueConfig {
+            capacity: 2,
+            overflow,
+        })
+    }
+
+    fn refused(event: StreamEvent) -> u64 {
+        match event {
+            StreamEvent::ReplayRefused(slot) => slot,
+            _ => panic!("unexpected event"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_drop_oldest_keeps_the_newest_events() {
+        let (sender, receiver) = queue(QueueOverflow::DropOldest);
+        for slot in 1..=4 {
+            sender.send(StreamEvent::ReplayRefused(slot)).await.unwrap();
+        }
+        drop(sender);
+
+        assert_eq!(receiver.dropped(), 2);
+        assert_eq!(refused(receiver.recv().await.unwrap()), 3);
+        assert_eq!(refused(receiver.recv().await.unwrap()), 4);
+        assert!(receiver.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_block_waits_for_the_worker() {
+        let (sender, receiver) = queue(QueueOverflow::Block);
+        sender.send(StreamEvent::Idle).await.unwrap();
+        sender.send(StreamEvent::Idle).await.unwrap();
+
+        let full = tokio::time::timeout(
+            std::time::Duration::from_millis(50),
+            sender.send(StreamEvent::Idle),
+        )
+        .await;
+        assert!(full.is_err());
+
+        receiver.recv().await.unwrap();
+        assert!(!sender.send(StreamEvent::Idle).await.unwrap());
+        assert_eq!(receiver.dropped(), 0);
+    }
+}
@@ -0,0 +1,173 @@
+use {
+    crate::{
+        Config, account_subscription::AccountSubscriptionConfig, endpoints::EndpointConfig,
+        logging::LogFormat, transaction_watch::TransactionWatchConfig,
+    },
+    clap::{Args, Subcommand},
+};
+
+/// Flags every watch mode takes; each one overrides its config setting
+#[derive(Debug, Args)]
+pub struct ModeArgs {
+    /// Geyser gRPC endpoint to use instead of the configured ones
+    #[arg(long, value_name = "URL")]
+    pub endpoint: Option<String>,
+    /// Commitment level of the subscription
+    #[arg(long, value_parser = ["processed", "confirmed", "finalized"])]
+    pub commitment: Option<String>,
+    /// Log as human-readable lines or as one JSON object per line, like --log-format
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    pub format: Option<LogFormat>,
+}
+
+/// Watch one kind of update, built from flags on top of the config file. Without a
+/// subcommand the config file alone decides what is watched.
+#[derive(Debug, Subcommand)]
+pub enum WatchMode {
+    /// Watch blocks
+    Blocks {
+        #[command(flatten)]
+        args: ModeArgs,
+    },
+    /// Watch transactions touching any of the given accounts
+    Transactions {
+        /// Account to stream transactions of; repeat for more
+        #[arg(long = "account", value_name = "PUBKEY", required = true)]
+        accounts: Vec<String>,
+        #[command(flatten)]
+        args: ModeArgs,
+    },
+    /// Watch every account owned by the given programs
+    Accounts {
+        /// Program whose accounts are streamed; repeat for more
+        #[arg(long = "owner", value_name = "PUBKEY", required = true)]
+        owners: Vec<String>,
+        #[command(flatten)]
+        args: ModeArgs,
+    },
+    /// Watch slot status changes
+    Slots {
+        #[command(flatten)]
+        args: ModeArgs,
+    },
+}
+
+impl WatchMode {
+    pub fn args(&self) -> &ModeArgs {
+        match self {
+            Self::Blocks { args }
+            | Self::Transactions { args, .. }
+            | Self::Accounts { args, .. }
+            | Self::Slots { args } => args,
+        }
+    }
+
+    /// Narrow `config` to this mode's subscription and apply the shared flags. The
+    /// mode's own config section, if any, still supplies the settings without a
+    /// flag, e.g. `watch_transactions.failed` or `accounts.memcmp`.
+    pub fn apply(&self, config: &mut Config) {
+        let args = self.args();
+        if let Some(url) = &args.endpoint {
+            config.geyser_endpoint = vec![EndpointConfig::from_url(url)];
+        }
+        if let Some(commitment) = &args.commitment {
+            config.commitment = commitment.clone();
+        }
+
+        let watch_transactions = config.watch_transactions.take().unwrap_or_default();
+        let accounts = config.accounts.take().unwrap_or_default();
+        config.watch_blocks = matches!(self, Self::Blocks { .. });
+        config.watch_slots = matches!(self, Self::Slots { .. });
+        if !config.watch_blocks {
+            config.block_transfers = None;
+        }
+        config.program_filter = None;
+        config.deposits = None;
+        config.compare_mode = false;
+
+        match self {
+            Self::Transactions {
+                accounts: include, ..
+            } => {
+                config.watch_transactions = Some(TransactionWatchConfig {
+                    account_include: include.clone(),
+                    ..watch_transactions
+                });
+            }
+            Self::Accounts { owners, .. } => {
+                config.accounts = Some(AccountSubscriptionConfig {
+                    pubkeys: Vec::new(),
+                    owners: owners.clone(),
+                    ..accounts
+                });
+            }
+            Self::Blocks { .. } | Self::Slots { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::Cli, clap::Parser};
+
+    fn config() -> Config {
+        crate::config_migration::parse_config(
+            "geyser_endpoint:\n  - url: \"https://grpc.example.com\"\nwatch_slots: true\nwatch_transactions:\n  account_include: [\"Old1111\"]\n  failed: true\ndeposits:\n  wallets: [\"Dep1111\"]\n",
+        )
+        .unwrap()
+    }
+
+    fn mode(args: &[&str]) -> WatchMode {
+        let cli = Cli::try_parse_from([&["geyser-watcher"][..], args].concat()).unwrap();
+        cli.mode.unwrap()
+    }
+
+    #[test]
+    fn test_transactions_mode_replaces_the_other_subscriptions() {
+        let mut config = config();
+        mode(&[
+            "transactions",
+            "--account",
+            "Acct1111",
+            "--account",
+            "Acct2222",
+            "--endpoint",
+            "http://127.0.0.1:10000",
+            "--commitment",
+            "processed",
+        ])
+        .apply(&mut config);
+
+        let watch = config.watch_transactions.as_ref().unwrap();
+        assert_eq!(watch.account_include, ["Acct1111", "Acct2222"]);
+        assert!(watch.failed);
+        assert!(!config.watch_blocks && !config.watch_slots);
+        assert!(config.deposits.is_none());
+        assert_eq!(config.geyser_endpoint[0].url, "http://127.0.0.1:10000");
+        assert_eq!(config.commitment, "processed");
+        config.validate().unwrap();
+    }
+
+    #[test]
+    fn test_accounts_mode_streams_by_owner() {
+        let mut config = config();
+        mode(&["accounts", "--owner", "Prog1111"]).apply(&mut config);
+
+        let accounts = config.accounts.as_ref().unwrap();
+        assert_eq!(accounts.owners, ["Prog1111"]);
+        assert!(accounts.pubkeys.is_empty());
+        assert!(config.watch_transactions.is_none());
+        assert_eq!(config.geyser_endpoint[0].url, "https://grpc.example.com");
+    }
+
+    #[test]
+    fn test_mode_flags_are_checked() {
+        let parse = |args: &[&str]| Cli::try_parse_from([&["geyser-watcher"][..], args].concat());
+        assert!(parse(&["transactions"]).is_err());
+        assert!(parse(&["blocks", "--commitment", "rooted"]).is_err());
+        assert!(parse(&["slots", "--format", "json"]).is_ok());
+        let cli = parse(&["blocks", "--output", "out.jsonl"]).unwrap();
+        assert_eq!(cli.output.as_deref(), Some("out.jsonl"));
+        assert!(parse(&[]).unwrap().mode.is_none());
+    }
+}
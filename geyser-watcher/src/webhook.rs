@@ -0,0 +1,261 @@
+use {
+    crate::sink::{SinkUpdate, envelope},
+    hmac::{Hmac, Mac},
+    reqwest::header::{CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue},
+    serde::{Deserialize, Serialize},
+    sha2::Sha256,
+    std::{collections::HashMap, sync::Arc, time::Duration},
+    tokio::{io::AsyncWriteExt, sync::Semaphore},
+    tracing::warn,
+};
+
+/// Wait before the first retry; doubled for every retry after it
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+/// Report dropped updates at the first drop, then once per this many more
+const DROP_REPORT_EVERY: u64 = 1000;
+
+/// POST every recorded update (the sink's JSON envelope) to an HTTP endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// Extra request headers, e.g. `Authorization`
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    /// Retries after the first attempt, with exponential backoff
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// Signs each body with HMAC-SHA256, sent as `X-Signature: sha256=<hex>`
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// Deliveries in flight or waiting to retry; updates beyond this are dropped
+    #[serde(default = "default_queue_size")]
+    pub queue_size: usize,
+    /// Payloads that ran out of retries are appended here, one per line
+    #[serde(default = "default_dead_letter_path")]
+    pub dead_letter_path: String,
+}
+
+fn default_timeout_ms() -> u64 {
+    5000
+}
+
+fn default_max_retries() -> u32 {
+    5
+}
+
+fn default_queue_size() -> usize {
+    1000
+}
+
+fn default_dead_letter_path() -> String {
+    "webhook-dead-letter.jsonl".to_string()
+}
+
+/// `sha256=<hex>` HMAC of `body` under `secret`
+pub fn signature_header(secret: &[u8], body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC takes keys of any size");
+    mac.update(body);
+    let digest: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    format!("sha256={}", digest)
+}
+
+fn retry_delay(retry: u32) -> Duration {
+    RETRY_BASE_DELAY
+        .saturating_mul(2u32.saturating_pow(retry))
+        .min(MAX_RETRY_DELAY)
+}
+
+// What every delivery task needs
+struct Delivery {
+    http: reqwest::Client,
+    url: String,
+    secret: Option<Vec<u8>>,
+    timeout: Duration,
+    max_retries: u32,
+    dead_letter_path: String,
+}
+
+impl Delivery {
+    async fn post(&self, body: &[u8]) -> Result<(), reqwest::Error> {
+        let mut request = self
+            .http
+            .post(&self.url)
+            .timeout(self.timeout)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body.to_vec());
+        if let Some(secret) = &self.secret {
+            request = request.header("X-Signature", signature_header(secret, body));
+        }
+        request.send().await?.error_for_status()?;
+        Ok(())
+    }
+
+    async fn deliver(&self, body: Vec<u8>) {
+        let mut retries = 0;
+        loop {
+            match self.post(&body).await {
+                Ok(()) => return,
+                Err(_) if retries < self.max_retries => {
+                    tokio::time::sleep(retry_delay(retries)).await;
+                    retries += 1;
+                }
+                Err(e) => {
+                    warn!(
+                        url = %self.url,
+                        attempts = retries + 1,
+                        error = %e,
+                        "webhook delivery failed"
+                    );
+                    if let Err(e) = self.dead_letter(&body).await {
+                        warn!(
+                            path = %self.dead_letter_path,
+                            error = %e,
+                            "failed to write to the dead-letter file"
+                        );
+                    }
+                    return;
+                }
+            }
+        }
+    }
+
+    async fn dead_letter(&self, body: &[u8]) -> std::io::Result<()> {
+        let mut line = body.to_vec();
+        line.push(b'\n');
+        tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.dead_letter_path)
+            .await?
+            .write_all(&line)
+            .await
+    }
+}
+
+/// Delivers updates on spawned tasks so the stream never waits on the endpoint.
+/// At most `queue_size` deliveries are pending at once, retries included.
+pub struct Webhook {
+    delivery: Arc<Delivery>,
+    pending: Arc<Semaphore>,
+    queue_size: u32,
+    dropped: u64,
+    next_drop_report: u64,
+}
+
+impl Webhook {
+    pub fn new(config: &WebhookConfig) -> anyhow::Result<Self> {
+        reqwest::Url::parse(&config.url)
+            .map_err(|e| anyhow::anyhow!("invalid webhook url {}: {}", config.url, e))?;
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in &config.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| anyhow::anyhow!("invalid webhook header name '{}'", name))?;
+            let mut value = HeaderValue::from_str(value)
+                .map_err(|_| anyhow::anyhow!("webhook header '{}' has an invalid value", name))?;
+            value.set_sensitive(true);
+            headers.insert(name, value);
+        }
+
+        Ok(Self {
+            delivery: Arc::new(Delivery {
+                http: reqwest::Client::builder()
+                    .default_headers(headers)
+                    .build()?,
+                url: config.url.clone(),
+                secret: config
+                    .secret
+                    .as_ref()
+                    .map(|secret| secret.as_bytes().to_vec()),
+                timeout: Duration::from_millis(config.timeout_ms),
+                max_retries: config.max_retries,
+                dead_letter_path: config.dead_letter_path.clone(),
+            }),
+            pending: Arc::new(Semaphore::new(config.queue_size.max(1))),
+            queue_size: config.queue_size.clamp(1, u32::MAX as usize) as u32,
+            dropped: 0,
+            next_drop_report: 1,
+        })
+    }
+
+    pub fn send(&mut self, update: &SinkUpdate) {
+        let body = match envelope(update) {
+            Ok(body) => body,
+            Err(e) => {
+                warn!(error = %e, "failed to serialize webhook payload");
+                return;
+            }
+        };
+        let Ok(permit) = self.pending.clone().try_acquire_owned() else {
+            self.dropped += 1;
+            if self.dropped >= self.next_drop_report {
+                warn!(
+                    dropped = self.dropped,
+                    "webhook queue is full; dropping updates"
+                );
+                self.next_drop_report = self.dropped + DROP_REPORT_EVERY;
+            }
+            return;
+        };
+
+        let delivery = self.delivery.clone();
+        tokio::spawn(async move {
+            delivery.deliver(body).await;
+            drop(permit);
+        });
+    }
+
+    /// Wait until every pending delivery has succeeded or been dead-lettered
+    pub async fn close(self) {
+        let _ = self.pending.acquire_many(self.queue_size).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature_header() {
+        assert_eq!(
+            signature_header(b"key", b"The quick brown fox jumps over the lazy dog"),
+            "sha256=f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+
+    #[test]
+    fn test_retry_delay_backs_off_to_a_cap() {
+        assert_eq!(retry_delay(0), Duration::from_millis(500));
+        assert_eq!(retry_delay(2), Duration::from_secs(2));
+        assert_eq!(retry_delay(40), MAX_RETRY_DELAY);
+    }
+
+    #[test]
+    fn test_config_defaults_and_validation() {
+        let config: WebhookConfig =
+            serde_yaml::from_str("url: \"https://hooks.example.com/geyser\"\n").unwrap();
+        assert_eq!(config.timeout_ms, 5000);
+        assert_eq!(config.max_retries, 5);
+        assert!(Webhook::new(&config).is_ok());
+
+        let bad_header = WebhookConfig {
+            headers: HashMap::from([("bad header".to_string(), "x".to_string())]),
+            ..config.clone()
+        };
+        assert!(Webhook::new(&bad_header).is_err());
+
+        let bad_url = WebhookConfig {
+            url: "not a url".to_string(),
+            ..config
+        };
+        assert!(Webhook::new(&bad_url).is_err());
+    }
+}
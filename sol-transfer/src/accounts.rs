@@ -0,0 +1,301 @@
+use clap::Args;
+use serde::Deserialize;
+use solana_sdk::{
+    hash::Hash,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    system_instruction::{self, MAX_PERMITTED_DATA_LENGTH},
+    transaction::Transaction,
+};
+use std::str::FromStr;
+
+use crate::{Config, SolTransfer};
+
+// Defaults for `account-create`, overridden by CLI flags
+#[derive(Debug, Deserialize, Default, Clone)]
+pub(crate) struct AccountCreateConfig {
+    owner: Option<String>,
+    space: Option<usize>,
+    lamports: Option<u64>,
+    seed: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct AccountCreateArgs {
+    /// Program that will own the new account
+    #[arg(long)]
+    owner: Option<String>,
+    /// Account data size in bytes
+    #[arg(long)]
+    space: Option<usize>,
+    /// Lamports to fund the account with (default: rent-exempt minimum for `space`)
+    #[arg(long)]
+    lamports: Option<u64>,
+    /// Derive the address from the funder and this seed instead of a new keypair
+    #[arg(long, conflicts_with_all = ["allocate_and_assign", "new_account_key"])]
+    seed: Option<String>,
+    /// Allocate and assign an existing, already funded system account (it pays the fee)
+    #[arg(long, requires = "new_account_key")]
+    allocate_and_assign: bool,
+    /// Base58 private key of the new account (default: generate one)
+    #[arg(long)]
+    new_account_key: Option<String>,
+    /// Index into `sender_wallets` of the wallet that funds the account
+    #[arg(long, default_value_t = 0)]
+    funder: usize,
+}
+
+// Minimum balance for an account of `space` bytes to be rent exempt
+pub(crate) fn rent_exempt_minimum(space: usize) -> u64 {
+    Rent::default().minimum_balance(space)
+}
+
+fn check_space(space: usize) -> Result<(), Box<dyn std::error::Error>> {
+    if space as u64 > MAX_PERMITTED_DATA_LENGTH {
+        return Err(format!(
+            "space {} exceeds the maximum account size of {} bytes",
+            space, MAX_PERMITTED_DATA_LENGTH
+        )
+        .into());
+    }
+    Ok(())
+}
+
+impl SolTransfer {
+    // Fund and assign a brand-new account to `owner` in one instruction
+    pub fn build_create_account_transaction(
+        &self,
+        funder: &Keypair,
+        new_account: &Keypair,
+        owner: &Pubkey,
+        space: usize,
+        lamports: u64,
+        recent_blockhash: Hash,
+    ) -> Result<Transaction, Box<dyn std::error::Error>> {
+        check_space(space)?;
+
+        let instruction = system_instruction::create_account(
+            &funder.pubkey(),
+            &new_account.pubkey(),
+            lamports,
+            space as u64,
+            owner,
+        );
+
+        Ok(Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&funder.pubkey()),
+            &[funder, new_account],
+            recent_blockhash,
+        ))
+    }
+
+    // Give an existing system account data space and hand it to `owner`.
+    // The account must already hold enough lamports for rent and the fee.
+    pub fn build_allocate_and_assign_transaction(
+        &self,
+        account: &Keypair,
+        owner: &Pubkey,
+        space: usize,
+        recent_blockhash: Hash,
+    ) -> Result<Transaction, Box<dyn std::error::Error>> {
+        check_space(space)?;
+
+        let instructions = [
+            system_instruction::allocate(&account.pubkey(), space as u64),
+            system_instruction::assign(&account.pubkey(), owner),
+        ];
+
+        Ok(Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&account.pubkey()),
+            &[account],
+            recent_blockhash,
+        ))
+    }
+
+    // Create an account at an address derived from the funder, `seed` and `owner`,
+    // so no extra keypair has to be generated or stored
+    pub fn build_create_account_with_seed_transaction(
+        &self,
+        funder: &Keypair,
+        seed: &str,
+        owner: &Pubkey,
+        space: usize,
+        lamports: u64,
+        recent_blockhash: Hash,
+    ) -> Result<(Pubkey, Transaction), Box<dyn std::error::Error>> {
+        check_space(space)?;
+
+        let address = Pubkey::create_with_seed(&funder.pubkey(), seed, owner)?;
+        let instruction = system_instruction::create_account_with_seed(
+            &funder.pubkey(),
+            &address,
+            &funder.pubkey(),
+            seed,
+            lamports,
+            space as u64,
+            owner,
+        );
+
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&funder.pubkey()),
+            &[funder],
+            recent_blockhash,
+        );
+
+        Ok((address, transaction))
+    }
+}
+
+// `account-create` subcommand
+pub(crate) async fn run(
+    sol_transfer: &SolTransfer,
+    config: &Config,
+    args: AccountCreateArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let defaults = config.account_create.clone().unwrap_or_default();
+
+    let owner = args
+        .owner
+        .or(defaults.owner)
+        .ok_or("missing owner (--owner or account_create.owner)")?;
+    let owner = Pubkey::from_str(&owner).map_err(|e| format!("Invalid owner: {}", e))?;
+    let space = args
+        .space
+        .or(defaults.space)
+        .ok_or("missing space (--space or account_create.space)")?;
+    let lamports = args
+        .lamports
+        .or(defaults.lamports)
+        .unwrap_or_else(|| rent_exempt_minimum(space));
+    let seed = args.seed.or(defaults.seed);
+
+    let funder = config
+        .sender_wallets
+        .get(args.funder)
+        .ok_or_else(|| format!("No sender wallet at index {}", args.funder))?;
+    let funder = SolTransfer::parse_keypair(&funder.private_key)?;
+
+    let blockhash = sol_transfer.get_recent_blockhash().await?;
+
+    let (address, transaction) = if args.allocate_and_assign {
+        let key = args.new_account_key.as_deref().unwrap_or_default();
+        let account = SolTransfer::parse_keypair(key)?;
+        let transaction = sol_transfer
+            .build_allocate_and_assign_transaction(&account, &owner, space, blockhash)?;
+        (account.pubkey(), transaction)
+    } else if let Some(seed) = &seed {
+        sol_transfer.build_create_account_with_seed_transaction(
+            &funder, seed, &owner, space, lamports, blockhash,
+        )?
+    } else {
+        let new_account = match &args.new_account_key {
+            Some(key) => SolTransfer::parse_keypair(key)?,
+            None => {
+                let keypair = Keypair::new();
+                println!("🔑 Generated new account keypair — store this secret, it is not saved:");
+                println!("   {}", bs58::encode(keypair.to_bytes()).into_string());
+                keypair
+            }
+        };
+        let transaction = sol_transfer.build_create_account_transaction(
+            &funder,
+            &new_account,
+            &owner,
+            space,
+            lamports,
+            blockhash,
+        )?;
+        (new_account.pubkey(), transaction)
+    };
+
+    println!("Account: {}", address);
+    println!("Owner: {}", owner);
+    println!("Space: {} bytes", space);
+    if !args.allocate_and_assign {
+        println!(
+            "Lamports: {} (rent-exempt minimum: {})",
+            lamports,
+            rent_exempt_minimum(space)
+        );
+    }
+
+    let (signature, level) = sol_transfer.send_and_confirm(&transaction).await?;
+    println!("Signature: {}", signature);
+    println!("✅ Account created ({})", level);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rent_exempt_minimum() {
+        assert_eq!(rent_exempt_minimum(0), 890_880);
+        assert_eq!(rent_exempt_minimum(82), 1_461_600);
+        assert_eq!(rent_exempt_minimum(165), 2_039_280);
+    }
+
+    #[test]
+    fn test_create_account_transaction_is_signed_by_both_keys() {
+        let sol_transfer = SolTransfer::new("http://127.0.0.1:8899".to_string());
+        let funder = Keypair::new();
+        let new_account = Keypair::new();
+
+        let transaction = sol_transfer
+            .build_create_account_transaction(
+                &funder,
+                &new_account,
+                &Pubkey::new_unique(),
+                165,
+                rent_exempt_minimum(165),
+                Hash::new_unique(),
+            )
+            .unwrap();
+
+        assert_eq!(transaction.message.header.num_required_signatures, 2);
+        assert!(transaction.verify().is_ok());
+    }
+
+    #[test]
+    fn test_create_account_with_seed_derives_address() {
+        let sol_transfer = SolTransfer::new("http://127.0.0.1:8899".to_string());
+        let funder = Keypair::new();
+        let owner = Pubkey::new_unique();
+
+        let (address, transaction) = sol_transfer
+            .build_create_account_with_seed_transaction(
+                &funder,
+                "vault",
+                &owner,
+                0,
+                rent_exempt_minimum(0),
+                Hash::new_unique(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            address,
+            Pubkey::create_with_seed(&funder.pubkey(), "vault", &owner).unwrap()
+        );
+        assert!(transaction.message.account_keys.contains(&address));
+    }
+
+    #[test]
+    fn test_oversized_account_is_rejected() {
+        let sol_transfer = SolTransfer::new("http://127.0.0.1:8899".to_string());
+        let account = Keypair::new();
+
+        let result = sol_transfer.build_allocate_and_assign_transaction(
+            &account,
+            &Pubkey::new_unique(),
+            MAX_PERMITTED_DATA_LENGTH as usize + 1,
+            Hash::new_unique(),
+        );
+        assert!(result.is_err());
+    }
+}
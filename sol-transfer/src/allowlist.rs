@@ -0,0 +1,147 @@
+use serde::Deserialize;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::fs;
+use std::str::FromStr;
+
+use crate::recipients::{Recipient, RowError};
+
+// What to do with recipients that aren't on the allowlist
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum AllowlistPolicy {
+    // Refuse to run at all
+    #[default]
+    Abort,
+    // Leave them out of the run
+    Skip,
+}
+
+// Approved recipient addresses, each with an optional label
+#[derive(Debug)]
+pub(crate) struct Allowlist {
+    entries: HashMap<String, Option<String>>,
+}
+
+// Outcome of checking a recipient list against the allowlist
+#[derive(Debug)]
+pub(crate) struct AllowlistCheck {
+    pub(crate) approved: Vec<Recipient>,
+    pub(crate) rejected: Vec<Recipient>,
+}
+
+impl Allowlist {
+    // One address per line, optionally followed by a label; `#` starts a comment
+    pub(crate) fn parse(source: &str, contents: &str) -> Result<Self, Vec<RowError>> {
+        let mut entries = HashMap::new();
+        let mut errors = Vec::new();
+
+        for (index, line) in contents.lines().enumerate() {
+            let line = line
+                .split_once('#')
+                .map_or(line, |(before, _)| before)
+                .trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (address, label) = match line.split_once(char::is_whitespace) {
+                Some((address, label)) => (address, Some(label.trim().to_string())),
+                None => (line, None),
+            };
+            match Pubkey::from_str(address) {
+                Ok(_) => {
+                    entries.insert(address.to_string(), label);
+                }
+                Err(e) => errors.push(RowError {
+                    source: source.to_string(),
+                    line: index + 1,
+                    message: format!("invalid address '{}': {}", address, e),
+                }),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(Self { entries })
+        } else {
+            Err(errors)
+        }
+    }
+
+    pub(crate) fn load(path: &str) -> Result<Self, Vec<RowError>> {
+        let contents = fs::read_to_string(path).map_err(|e| {
+            vec![RowError {
+                source: path.to_string(),
+                line: 0,
+                message: e.to_string(),
+            }]
+        })?;
+        Self::parse(path, &contents)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub(crate) fn label(&self, address: &str) -> Option<&str> {
+        self.entries.get(address)?.as_deref()
+    }
+
+    // Approved recipients without a label of their own take the allowlist's
+    pub(crate) fn check(&self, recipients: Vec<Recipient>) -> AllowlistCheck {
+        let (mut approved, rejected): (Vec<Recipient>, Vec<Recipient>) = recipients
+            .into_iter()
+            .partition(|recipient| self.entries.contains_key(&recipient.address));
+
+        for recipient in &mut approved {
+            if recipient.label.is_none() {
+                recipient.label = self.label(&recipient.address).map(str::to_string);
+            }
+        }
+
+        AllowlistCheck { approved, rejected }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_comments_and_labels() {
+        let a = Pubkey::new_unique().to_string();
+        let b = Pubkey::new_unique().to_string();
+        let contents = format!(
+            "# treasury wallets\n{}   Cold storage  # checked 2024-12\n\n{}\n",
+            a, b
+        );
+
+        let allowlist = Allowlist::parse("allowlist.txt", &contents).unwrap();
+        assert_eq!(allowlist.len(), 2);
+        assert_eq!(allowlist.label(&a), Some("Cold storage"));
+        assert_eq!(allowlist.label(&b), None);
+    }
+
+    #[test]
+    fn test_check_splits_unapproved_recipients() {
+        let approved = Pubkey::new_unique().to_string();
+        let typo = Pubkey::new_unique().to_string();
+        let allowlist =
+            Allowlist::parse("allowlist.txt", &format!("{} payroll", approved)).unwrap();
+
+        let check = allowlist.check(vec![
+            Recipient::from_address(approved.clone()),
+            Recipient::from_address(typo.clone()),
+        ]);
+        assert_eq!(check.approved.len(), 1);
+        assert_eq!(check.rejected.len(), 1);
+        assert_eq!(check.rejected[0].address, typo);
+        assert_eq!(check.approved[0].label.as_deref(), Some("payroll"));
+    }
+
+    #[test]
+    fn test_invalid_allowlist_line_is_reported() {
+        let errors = Allowlist::parse("allowlist.txt", "not-an-address label\n").unwrap_err();
+        assert_eq!(errors[0].line, 1);
+    }
+}
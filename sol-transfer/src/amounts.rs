@@ -0,0 +1,208 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{Config, PlannedTransfer, SenderWallet, SolTransfer};
+
+// Group name shown for senders without a `group`
+const UNGROUPED: &str = "(ungrouped)";
+
+// SOL each transfer from `sender` carries: the sender's own amount, then its
+// group's, then the global `amount_sol`
+fn sender_amount(config: &Config, sender: &SenderWallet) -> Result<f64, String> {
+    if let Some(amount) = sender.amount_sol {
+        return Ok(amount);
+    }
+    if let Some(group) = &sender.group {
+        return config.groups.get(group).copied().ok_or_else(|| {
+            format!(
+                "sender {} is in group '{}', which isn't defined under `groups`",
+                sender.address, group
+            )
+        });
+    }
+    config.amount_sol.ok_or_else(|| {
+        format!(
+            "sender {} has no amount: set its amount_sol, a group, or a global amount_sol",
+            sender.address
+        )
+    })
+}
+
+// Lamports per transfer for every sender, keyed by address, or every sender that
+// doesn't resolve to a usable amount
+pub(crate) fn resolve_sender_amounts(config: &Config) -> Result<HashMap<String, u64>, Vec<String>> {
+    let mut amounts = HashMap::new();
+    let mut errors = Vec::new();
+
+    for sender in &config.sender_wallets {
+        match sender_amount(config, sender) {
+            Ok(amount) if amount.is_finite() && amount > 0.0 => {
+                amounts.insert(sender.address.clone(), SolTransfer::sol_to_lamports(amount));
+            }
+            Ok(amount) => errors.push(format!(
+                "sender {} resolves to an invalid amount {}",
+                sender.address, amount
+            )),
+            Err(e) => errors.push(e),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(amounts)
+    } else {
+        Err(errors)
+    }
+}
+
+// Per-group sender count, transfer count and total, in group name order
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct GroupTotals {
+    pub(crate) senders: usize,
+    pub(crate) transfers: usize,
+    pub(crate) lamports: u64,
+}
+
+pub(crate) fn group_totals(planned: &[PlannedTransfer]) -> BTreeMap<String, GroupTotals> {
+    let mut totals: BTreeMap<String, GroupTotals> = BTreeMap::new();
+    let mut seen_senders = HashMap::new();
+
+    for transfer in planned {
+        let group = transfer
+            .sender
+            .group
+            .clone()
+            .unwrap_or_else(|| UNGROUPED.to_string());
+        let entry = totals.entry(group.clone()).or_default();
+        if seen_senders
+            .insert(transfer.sender.address.clone(), group)
+            .is_none()
+        {
+            entry.senders += 1;
+        }
+        entry.transfers += 1;
+        entry.lamports += transfer.lamports;
+    }
+
+    totals
+}
+
+// Pre-flight breakdown of what each sender tier is about to send
+pub(crate) fn print_group_totals(planned: &[PlannedTransfer], unit: &str) {
+    let totals = group_totals(planned);
+    // A single ungrouped tier adds nothing to the summary above
+    if totals.len() == 1 && totals.contains_key(UNGROUPED) {
+        return;
+    }
+
+    println!("Totals by sender group:");
+    for (group, totals) in &totals {
+        println!(
+            "  {}: {} sender(s), {} transfers, {} {}",
+            group,
+            totals.senders,
+            totals.transfers,
+            totals.lamports as f64 / 1_000_000_000.0,
+            unit
+        );
+    }
+    println!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sender(address: &str, amount_sol: Option<f64>, group: Option<&str>) -> SenderWallet {
+        SenderWallet {
+            address: address.to_string(),
+            private_key: String::new(),
+            private_key_source: None,
+            amount_sol,
+            group: group.map(str::to_string),
+        }
+    }
+
+    fn config(yaml: &str) -> Config {
+        crate::config_migration::parse_config(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_sender_beats_group_beats_global() {
+        let config = config(
+            r#"
+solana_rpc_url: "http://127.0.0.1:8899"
+amount_sol: 1
+groups:
+  validators: 10
+  community: 0.5
+sender_wallets:
+  - address: "own"
+    amount_sol: 2
+    group: "validators"
+  - address: "validator"
+    group: "validators"
+  - address: "member"
+    group: "community"
+  - address: "plain"
+"#,
+        );
+
+        let amounts = resolve_sender_amounts(&config).unwrap();
+        assert_eq!(amounts["own"], 2_000_000_000);
+        assert_eq!(amounts["validator"], 10_000_000_000);
+        assert_eq!(amounts["member"], 500_000_000);
+        assert_eq!(amounts["plain"], 1_000_000_000);
+    }
+
+    #[test]
+    fn test_every_sender_must_resolve() {
+        let config = config(
+            r#"
+solana_rpc_url: "http://127.0.0.1:8899"
+groups:
+  validators: 10
+sender_wallets:
+  - address: "grouped"
+    group: "validators"
+  - address: "no-amount"
+  - address: "typo"
+    group: "validator"
+"#,
+        );
+
+        let errors = resolve_sender_amounts(&config).unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].contains("no-amount"));
+        assert!(errors[1].contains("'validator'"));
+    }
+
+    #[test]
+    fn test_group_totals() {
+        let planned: Vec<PlannedTransfer> = [
+            sender("a", None, Some("validators")),
+            sender("a", None, Some("validators")),
+            sender("b", None, Some("community")),
+            sender("c", None, None),
+        ]
+        .into_iter()
+        .map(|sender| PlannedTransfer {
+            sender,
+            recipient: "r".to_string(),
+            lamports: 5,
+            label: None,
+            create_token_account: false,
+        })
+        .collect();
+
+        let totals = group_totals(&planned);
+        assert_eq!(
+            totals["validators"],
+            GroupTotals {
+                senders: 1,
+                transfers: 2,
+                lamports: 10
+            }
+        );
+        assert_eq!(totals["community"].senders, 1);
+        assert_eq!(totals[UNGROUPED].transfers, 1);
+    }
+}
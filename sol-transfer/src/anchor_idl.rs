@@ -0,0 +1,434 @@
+use borsh::BorshDeserialize;
+use serde::Deserialize;
+use serde_json::Value;
+use solana_sdk::{hash::hashv, pubkey::Pubkey};
+use std::fs;
+
+// Anchor prefixes instruction data with the first 8 bytes of sha256("global:<name>")
+const DISCRIMINATOR_LEN: usize = 8;
+
+// An Anchor IDL, in either the legacy (pre-0.30) layout or the current one
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct AnchorIdl {
+    // Program id; legacy IDLs keep it under `metadata.address`
+    #[serde(default)]
+    address: Option<String>,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    metadata: Option<IdlMetadata>,
+    pub(crate) instructions: Vec<IdlInstruction>,
+    #[serde(default)]
+    pub(crate) types: Vec<IdlTypeDef>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct IdlMetadata {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    address: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct IdlInstruction {
+    pub(crate) name: String,
+    // Only current IDLs list it; legacy ones derive it from the name
+    #[serde(default)]
+    discriminator: Option<Vec<u8>>,
+    #[serde(default)]
+    pub(crate) accounts: Vec<IdlAccountItem>,
+    #[serde(default)]
+    pub(crate) args: Vec<IdlField>,
+}
+
+// An instruction account, or a named group of them
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct IdlAccountItem {
+    pub(crate) name: String,
+    #[serde(default)]
+    accounts: Vec<IdlAccountItem>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct IdlField {
+    pub(crate) name: String,
+    #[serde(rename = "type")]
+    ty: Value,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct IdlTypeDef {
+    pub(crate) name: String,
+    #[serde(rename = "type")]
+    ty: IdlTypeDefBody,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+enum IdlTypeDefBody {
+    // Fields are `{name, type}` objects, or bare types for tuple structs
+    Struct {
+        #[serde(default)]
+        fields: Vec<Value>,
+    },
+    Enum {
+        variants: Vec<IdlVariant>,
+    },
+    Type {
+        alias: Value,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct IdlVariant {
+    name: String,
+    #[serde(default)]
+    fields: Vec<Value>,
+}
+
+// An instruction matched against an IDL, with its arguments decoded
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct DecodedInstruction {
+    pub(crate) name: String,
+    pub(crate) args: Vec<(String, Value)>,
+    // Names of the accounts the instruction expects, in order
+    pub(crate) account_names: Vec<String>,
+}
+
+// Read a JSON IDL file
+pub(crate) fn load_idl(path: &str) -> Result<AnchorIdl, Box<dyn std::error::Error>> {
+    let contents =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read IDL {}: {}", path, e))?;
+    let idl = serde_json::from_str(&contents)
+        .map_err(|e| format!("Failed to parse IDL {}: {}", path, e))?;
+    Ok(idl)
+}
+
+// camelCase -> snake_case, the form legacy discriminators are computed from
+fn snake_case(name: &str) -> String {
+    let mut snake = String::with_capacity(name.len() + 4);
+    for (index, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if index > 0 {
+                snake.push('_');
+            }
+            snake.push(c.to_ascii_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+impl AnchorIdl {
+    pub(crate) fn program_id(&self) -> Option<&str> {
+        self.address
+            .as_deref()
+            .or_else(|| self.metadata.as_ref()?.address.as_deref())
+    }
+
+    pub(crate) fn program_name(&self) -> &str {
+        self.name
+            .as_deref()
+            .or_else(|| self.metadata.as_ref()?.name.as_deref())
+            .unwrap_or("anchor program")
+    }
+
+    fn type_def(&self, name: &str) -> Option<&IdlTypeDef> {
+        self.types.iter().find(|def| def.name == name)
+    }
+}
+
+impl IdlInstruction {
+    fn discriminator(&self) -> Vec<u8> {
+        match &self.discriminator {
+            Some(discriminator) => discriminator.clone(),
+            None => {
+                let preimage = format!("global:{}", snake_case(&self.name));
+                hashv(&[preimage.as_bytes()]).to_bytes()[..DISCRIMINATOR_LEN].to_vec()
+            }
+        }
+    }
+}
+
+impl IdlAccountItem {
+    // Group members are listed as "group.member", matching their position in the instruction
+    fn flatten(&self, prefix: &str, names: &mut Vec<String>) {
+        let name = if prefix.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{}.{}", prefix, self.name)
+        };
+        if self.accounts.is_empty() {
+            names.push(name);
+        } else {
+            for account in &self.accounts {
+                account.flatten(&name, names);
+            }
+        }
+    }
+}
+
+// Match `data` to an instruction by discriminator and borsh-decode its arguments
+pub(crate) fn decode_instruction(
+    idl: &AnchorIdl,
+    data: &[u8],
+) -> Result<DecodedInstruction, Box<dyn std::error::Error>> {
+    if data.len() < DISCRIMINATOR_LEN {
+        return Err(format!("instruction data is only {} bytes", data.len()).into());
+    }
+    let (discriminator, mut reader) = data.split_at(DISCRIMINATOR_LEN);
+
+    let instruction = idl
+        .instructions
+        .iter()
+        .find(|instruction| instruction.discriminator() == discriminator)
+        .ok_or_else(|| {
+            format!(
+                "no {} instruction has discriminator {:?}",
+                idl.program_name(),
+                discriminator
+            )
+        })?;
+
+    let mut args = Vec::with_capacity(instruction.args.len());
+    for arg in &instruction.args {
+        let value = decode_value(idl, &arg.ty, &mut reader)
+            .map_err(|e| format!("argument `{}`: {}", arg.name, e))?;
+        args.push((arg.name.clone(), value));
+    }
+
+    let mut account_names = Vec::new();
+    for account in &instruction.accounts {
+        account.flatten("", &mut account_names);
+    }
+
+    Ok(DecodedInstruction {
+        name: instruction.name.clone(),
+        args,
+        account_names,
+    })
+}
+
+fn read<T: BorshDeserialize>(reader: &mut &[u8]) -> Result<T, String> {
+    T::deserialize(reader).map_err(|e| e.to_string())
+}
+
+// Borsh-decode one value of IDL type `ty` into JSON for display. 128-bit integers
+// become strings, public keys base58 and byte strings hex.
+fn decode_value(idl: &AnchorIdl, ty: &Value, reader: &mut &[u8]) -> Result<Value, String> {
+    if let Some(primitive) = ty.as_str() {
+        return Ok(match primitive {
+            "bool" => Value::from(read::<bool>(reader)?),
+            "u8" => Value::from(read::<u8>(reader)?),
+            "i8" => Value::from(read::<i8>(reader)?),
+            "u16" => Value::from(read::<u16>(reader)?),
+            "i16" => Value::from(read::<i16>(reader)?),
+            "u32" => Value::from(read::<u32>(reader)?),
+            "i32" => Value::from(read::<i32>(reader)?),
+            "u64" => Value::from(read::<u64>(reader)?),
+            "i64" => Value::from(read::<i64>(reader)?),
+            "u128" => Value::from(read::<u128>(reader)?.to_string()),
+            "i128" => Value::from(read::<i128>(reader)?.to_string()),
+            "f32" => Value::from(read::<f32>(reader)?),
+            "f64" => Value::from(read::<f64>(reader)?),
+            "string" => Value::from(read::<String>(reader)?),
+            "bytes" => {
+                let bytes = read::<Vec<u8>>(reader)?;
+                Value::from(
+                    bytes
+                        .iter()
+                        .map(|b| format!("{:02x}", b))
+                        .collect::<String>(),
+                )
+            }
+            "publicKey" | "pubkey" => {
+                Value::from(Pubkey::from(read::<[u8; 32]>(reader)?).to_string())
+            }
+            other => return Err(format!("unsupported type `{}`", other)),
+        });
+    }
+
+    if let Some(inner) = ty.get("vec") {
+        let len = read::<u32>(reader)?;
+        return (0..len)
+            .map(|_| decode_value(idl, inner, reader))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::from);
+    }
+    if let Some(inner) = ty.get("option") {
+        return match read::<u8>(reader)? {
+            0 => Ok(Value::Null),
+            1 => decode_value(idl, inner, reader),
+            tag => Err(format!("invalid option tag {}", tag)),
+        };
+    }
+    if let Some(array) = ty.get("array").and_then(Value::as_array) {
+        let (inner, len) = match array.as_slice() {
+            [inner, len] => (inner, len.as_u64().ok_or("array length must be a number")?),
+            _ => return Err("array type needs [type, length]".to_string()),
+        };
+        return (0..len)
+            .map(|_| decode_value(idl, inner, reader))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::from);
+    }
+    if let Some(defined) = ty.get("defined") {
+        let name = defined
+            .as_str()
+            .or_else(|| defined.get("name").and_then(Value::as_str))
+            .ok_or("defined type without a name")?;
+        let def = idl
+            .type_def(name)
+            .ok_or_else(|| format!("type `{}` isn't defined in the IDL", name))?;
+        return decode_defined(idl, def, reader);
+    }
+
+    Err(format!("unsupported type {}", ty))
+}
+
+// Named fields become an object, tuple fields an array
+fn decode_fields(idl: &AnchorIdl, fields: &[Value], reader: &mut &[u8]) -> Result<Value, String> {
+    let named = fields.iter().all(|field| field.get("name").is_some());
+    if named {
+        let mut object = serde_json::Map::new();
+        for field in fields {
+            let name = field["name"].as_str().unwrap_or_default().to_string();
+            let value = decode_value(idl, &field["type"], reader)?;
+            object.insert(name, value);
+        }
+        Ok(Value::Object(object))
+    } else {
+        fields
+            .iter()
+            .map(|field| decode_value(idl, field, reader))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::from)
+    }
+}
+
+fn decode_defined(idl: &AnchorIdl, def: &IdlTypeDef, reader: &mut &[u8]) -> Result<Value, String> {
+    match &def.ty {
+        IdlTypeDefBody::Struct { fields } => decode_fields(idl, fields, reader),
+        IdlTypeDefBody::Enum { variants } => {
+            let index = read::<u8>(reader)?;
+            let variant = variants
+                .get(index as usize)
+                .ok_or_else(|| format!("`{}` has no variant {}", def.name, index))?;
+            if variant.fields.is_empty() {
+                Ok(Value::from(variant.name.clone()))
+            } else {
+                let fields = decode_fields(idl, &variant.fields, reader)?;
+                Ok(serde_json::json!({ variant.name.clone(): fields }))
+            }
+        }
+        IdlTypeDefBody::Type { alias } => decode_value(idl, alias, reader),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Legacy-format IDL: camelCase names, no discriminators, address in metadata
+    const LEGACY_IDL: &str = r#"{
+        "version": "0.1.0",
+        "name": "vault",
+        "instructions": [
+            {
+                "name": "depositFunds",
+                "accounts": [
+                    {"name": "owner", "isMut": true, "isSigner": true},
+                    {"name": "vault", "accounts": [{"name": "state", "isMut": true, "isSigner": false}]}
+                ],
+                "args": [
+                    {"name": "amount", "type": "u64"},
+                    {"name": "memo", "type": {"option": "string"}},
+                    {"name": "kind", "type": {"defined": "DepositKind"}}
+                ]
+            }
+        ],
+        "accounts": [{"name": "Vault", "type": {"kind": "struct", "fields": []}}],
+        "types": [
+            {"name": "DepositKind", "type": {"kind": "enum", "variants": [
+                {"name": "Spot"},
+                {"name": "Locked", "fields": [{"name": "until", "type": "i64"}]}
+            ]}}
+        ],
+        "metadata": {"address": "Vau1t11111111111111111111111111111111111111"}
+    }"#;
+
+    fn legacy_data() -> Vec<u8> {
+        let mut data = hashv(&[b"global:deposit_funds"]).to_bytes()[..8].to_vec();
+        data.extend_from_slice(&1_500u64.to_le_bytes());
+        data.push(1);
+        data.extend_from_slice(&2u32.to_le_bytes());
+        data.extend_from_slice(b"hi");
+        data.push(1);
+        data.extend_from_slice(&(-7i64).to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_decode_legacy_instruction() {
+        let idl: AnchorIdl = serde_json::from_str(LEGACY_IDL).unwrap();
+        assert_eq!(
+            idl.program_id(),
+            Some("Vau1t11111111111111111111111111111111111111")
+        );
+
+        let decoded = decode_instruction(&idl, &legacy_data()).unwrap();
+        assert_eq!(decoded.name, "depositFunds");
+        assert_eq!(
+            decoded.args,
+            vec![
+                ("amount".to_string(), serde_json::json!(1500)),
+                ("memo".to_string(), serde_json::json!("hi")),
+                (
+                    "kind".to_string(),
+                    serde_json::json!({"Locked": {"until": -7}})
+                ),
+            ]
+        );
+        assert_eq!(decoded.account_names, ["owner", "vault.state"]);
+    }
+
+    #[test]
+    fn test_decode_uses_listed_discriminator() {
+        let idl: AnchorIdl = serde_json::from_str(
+            r#"{
+                "address": "Vau1t11111111111111111111111111111111111111",
+                "metadata": {"name": "vault", "version": "0.1.0", "spec": "0.1.0"},
+                "instructions": [
+                    {"name": "close", "discriminator": [1, 2, 3, 4, 5, 6, 7, 8], "accounts": [], "args": [
+                        {"name": "targets", "type": {"vec": "pubkey"}}
+                    ]}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let target = Pubkey::new_unique();
+        let mut data = vec![1, 2, 3, 4, 5, 6, 7, 8];
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(target.as_ref());
+
+        let decoded = decode_instruction(&idl, &data).unwrap();
+        assert_eq!(idl.program_name(), "vault");
+        assert_eq!(
+            decoded.args,
+            vec![(
+                "targets".to_string(),
+                serde_json::json!([target.to_string()])
+            )]
+        );
+    }
+
+    #[test]
+    fn test_unknown_discriminator_and_short_data_fail() {
+        let idl: AnchorIdl = serde_json::from_str(LEGACY_IDL).unwrap();
+        assert!(decode_instruction(&idl, &[0; 8]).is_err());
+        assert!(decode_instruction(&idl, &[0; 4]).is_err());
+        assert!(decode_instruction(&idl, &legacy_data()[..12]).is_err());
+    }
+}
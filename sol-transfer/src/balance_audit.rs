@@ -0,0 +1,245 @@
+use serde::Deserialize;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{BTreeSet, HashMap};
+use std::str::FromStr;
+
+use crate::{PlannedTransfer, SolTransfer, recipients::MAX_MULTIPLE_ACCOUNTS, spl};
+
+#[derive(Debug, Deserialize)]
+struct TokenAccountsResult {
+    value: Vec<Option<spl::TokenAccountData>>,
+}
+
+// A wallet whose token balance moved between two snapshots
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct BalanceDiff {
+    pub(crate) wallet: Pubkey,
+    pub(crate) before_amount: u64,
+    pub(crate) after_amount: u64,
+    // Negative when the balance went down
+    pub(crate) delta: i128,
+}
+
+impl SolTransfer {
+    // Each wallet's balance in its associated token account for `mint`, in base
+    // units; a wallet without that account holds 0
+    pub(crate) async fn snapshot_token_balances(
+        &self,
+        wallets: &[Pubkey],
+        mint: &Pubkey,
+    ) -> Result<HashMap<Pubkey, u64>, Box<dyn std::error::Error>> {
+        let mut balances = HashMap::with_capacity(wallets.len());
+
+        for chunk in wallets.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let token_accounts: Vec<String> = chunk
+                .iter()
+                .map(|wallet| spl::associated_token_address(wallet, mint).to_string())
+                .collect();
+            let result: TokenAccountsResult = self
+                .rpc_call(
+                    "getMultipleAccounts",
+                    vec![
+                        serde_json::json!(token_accounts),
+                        serde_json::json!({ "encoding": "jsonParsed", "commitment": "confirmed" }),
+                    ],
+                )
+                .await?;
+            if result.value.len() != chunk.len() {
+                return Err("getMultipleAccounts returned the wrong number of accounts".into());
+            }
+
+            for (wallet, account) in chunk.iter().zip(result.value) {
+                let amount = match account {
+                    Some(account) => account
+                        .amount()
+                        .map_err(|e| format!("Invalid token balance of {}: {}", wallet, e))?,
+                    None => 0,
+                };
+                balances.insert(*wallet, amount);
+            }
+        }
+
+        Ok(balances)
+    }
+}
+
+// Wallets whose balance differs between the snapshots, ordered by address. A
+// wallet missing from one snapshot counts as holding 0 there.
+pub(crate) fn diff_token_balances(
+    before: &HashMap<Pubkey, u64>,
+    after: &HashMap<Pubkey, u64>,
+) -> Vec<BalanceDiff> {
+    let wallets: BTreeSet<&Pubkey> = before.keys().chain(after.keys()).collect();
+    wallets
+        .into_iter()
+        .filter_map(|wallet| {
+            let before_amount = before.get(wallet).copied().unwrap_or_default();
+            let after_amount = after.get(wallet).copied().unwrap_or_default();
+            (before_amount != after_amount).then(|| BalanceDiff {
+                wallet: *wallet,
+                before_amount,
+                after_amount,
+                delta: i128::from(after_amount) - i128::from(before_amount),
+            })
+        })
+        .collect()
+}
+
+// Sender token balances taken before a transfer batch, with the most each sender
+// was planned to send
+pub(crate) struct BalanceAudit {
+    mint: spl::SplMint,
+    before: HashMap<Pubkey, u64>,
+    planned_outflow: HashMap<Pubkey, u64>,
+}
+
+impl BalanceAudit {
+    // Snapshot every sender of `planned` before anything is sent
+    pub(crate) async fn start(
+        sol_transfer: &SolTransfer,
+        mint: spl::SplMint,
+        planned: &[PlannedTransfer],
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut planned_outflow: HashMap<Pubkey, u64> = HashMap::new();
+        for transfer in planned {
+            // Bad sender addresses fail their transfers; there's nothing to audit
+            if let Ok(sender) = Pubkey::from_str(&transfer.sender.address) {
+                let outflow = planned_outflow.entry(sender).or_default();
+                *outflow = outflow.saturating_add(mint.base_units(transfer.lamports));
+            }
+        }
+
+        let senders: Vec<Pubkey> = planned_outflow.keys().copied().collect();
+        let before = sol_transfer
+            .snapshot_token_balances(&senders, &mint.mint)
+            .await?;
+        println!(
+            "🔍 Balance audit: recorded token balances of {} sender(s)\n",
+            senders.len()
+        );
+        Ok(Self {
+            mint,
+            before,
+            planned_outflow,
+        })
+    }
+
+    // Senders whose balance dropped by more than they were planned to send
+    fn unexpected<'a>(&self, diffs: &'a [BalanceDiff]) -> Vec<&'a BalanceDiff> {
+        diffs
+            .iter()
+            .filter(|diff| {
+                let planned = self
+                    .planned_outflow
+                    .get(&diff.wallet)
+                    .copied()
+                    .unwrap_or_default();
+                -diff.delta > i128::from(planned)
+            })
+            .collect()
+    }
+
+    // Snapshot the senders again and report any withdrawal the batch doesn't explain
+    pub(crate) async fn finish(&self, sol_transfer: &SolTransfer) {
+        let senders: Vec<Pubkey> = self.before.keys().copied().collect();
+        let after = match sol_transfer
+            .snapshot_token_balances(&senders, &self.mint.mint)
+            .await
+        {
+            Ok(after) => after,
+            Err(e) => {
+                println!(
+                    "⚠️  Warning: Balance audit could not re-read balances: {}",
+                    e
+                );
+                return;
+            }
+        };
+
+        let diffs = diff_token_balances(&self.before, &after);
+        let unexpected = self.unexpected(&diffs);
+        if unexpected.is_empty() {
+            println!(
+                "🔍 Balance audit: {} sender balance(s) changed, all within the planned transfers",
+                diffs.len()
+            );
+            return;
+        }
+
+        println!(
+            "🚨 Balance audit: {} sender(s) lost more than the batch sent:",
+            unexpected.len()
+        );
+        for diff in unexpected {
+            let planned = self
+                .planned_outflow
+                .get(&diff.wallet)
+                .copied()
+                .unwrap_or_default();
+            println!(
+                "  {}: {} -> {} ({}), planned to send at most {}",
+                diff.wallet, diff.before_amount, diff.after_amount, diff.delta, planned
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_lists_changed_wallets_only() {
+        let unchanged = Pubkey::new_unique();
+        let sender = Pubkey::new_unique();
+        let emptied = Pubkey::new_unique();
+        let new = Pubkey::new_unique();
+        let before = HashMap::from([(unchanged, 10), (sender, 500), (emptied, 7)]);
+        let after = HashMap::from([(unchanged, 10), (sender, 300), (new, 25)]);
+
+        let mut expected = vec![
+            BalanceDiff {
+                wallet: sender,
+                before_amount: 500,
+                after_amount: 300,
+                delta: -200,
+            },
+            BalanceDiff {
+                wallet: emptied,
+                before_amount: 7,
+                after_amount: 0,
+                delta: -7,
+            },
+            BalanceDiff {
+                wallet: new,
+                before_amount: 0,
+                after_amount: 25,
+                delta: 25,
+            },
+        ];
+        expected.sort_by_key(|diff| diff.wallet);
+        assert_eq!(diff_token_balances(&before, &after), expected);
+    }
+
+    #[test]
+    fn test_only_withdrawals_beyond_the_plan_are_unexpected() {
+        let paid = Pubkey::new_unique();
+        let drained = Pubkey::new_unique();
+        let audit = BalanceAudit {
+            mint: spl::SplMint {
+                mint: Pubkey::new_unique(),
+                decimals: 9,
+            },
+            before: HashMap::new(),
+            planned_outflow: HashMap::from([(paid, 200), (drained, 200)]),
+        };
+        let diffs = diff_token_balances(
+            &HashMap::from([(paid, 1_000), (drained, 1_000)]),
+            &HashMap::from([(paid, 800), (drained, 100)]),
+        );
+
+        let unexpected = audit.unexpected(&diffs);
+        assert_eq!(unexpected.len(), 1);
+        assert_eq!(unexpected[0].wallet, drained);
+    }
+}
@@ -0,0 +1,325 @@
+use solana_sdk::{
+    hash::Hash, instruction::Instruction, message::Message, packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey, signature::Signer, transaction::Transaction,
+};
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::{PlannedTransfer, PreparedTransfer, SenderWallet, SolTransfer, TransferLeg};
+
+// Space kept free in every packed transaction for a dedup memo
+const DEDUP_MEMO_RESERVE: usize = 64;
+
+// Bytes each signature adds to the wire transaction
+const SIGNATURE_SIZE: usize = 64;
+
+// Serialized size of a transaction carrying `instructions`, signatures included.
+// Building the message applies the same account-key dedup the real transaction gets.
+pub(crate) fn transaction_wire_size(payer: &Pubkey, instructions: &[Instruction]) -> usize {
+    let message = Message::new(instructions, Some(payer));
+    let signers = message.header.num_required_signatures as usize;
+    skeleton_size(message, signers)
+}
+
+// Predicted wire size of a transaction carrying `instructions` once `signers`
+// signatures are added. No payer is named; the fee payer is the first signer
+// the instructions name.
+pub(crate) fn estimate_wire_size(instructions: &[Instruction], signers: usize) -> usize {
+    skeleton_size(Message::new(instructions, None), signers)
+}
+
+// The unsigned transaction plus the signatures it will carry. Their count prefix
+// takes one byte either way for fewer than 128 signers.
+fn skeleton_size(message: Message, signers: usize) -> usize {
+    let skeleton = Transaction {
+        signatures: Vec::new(),
+        message,
+    };
+    bincode::serialized_size(&skeleton)
+        .map_or(usize::MAX, |size| size as usize + SIGNATURE_SIZE * signers)
+}
+
+// Split items into groups whose transaction, signed by `signers` keypairs, stays
+// within `max_size` bytes, measuring after each item's instructions are added. An
+// item too big to share a transaction still gets one of its own.
+pub(crate) fn batch_transfers_per_tx<T>(
+    signers: usize,
+    items: Vec<(T, Vec<Instruction>)>,
+    max_size: usize,
+) -> Vec<Vec<(T, Vec<Instruction>)>> {
+    let mut batches = Vec::new();
+    let mut current: Vec<(T, Vec<Instruction>)> = Vec::new();
+    let mut instructions: Vec<Instruction> = Vec::new();
+
+    for (item, item_instructions) in items {
+        for instruction in &item_instructions {
+            let size = estimate_wire_size(std::slice::from_ref(instruction), signers);
+            if size > PACKET_DATA_SIZE {
+                println!(
+                    "⚠️  Warning: A {} instruction alone makes a {}-byte transaction, over the \
+                     {}-byte limit; it can't be sent",
+                    instruction.program_id, size, PACKET_DATA_SIZE
+                );
+            }
+        }
+        instructions.extend(item_instructions.iter().cloned());
+        if !current.is_empty() && estimate_wire_size(&instructions, signers) > max_size {
+            batches.push(std::mem::take(&mut current));
+            instructions = item_instructions.clone();
+        }
+        current.push((item, item_instructions));
+    }
+    if !current.is_empty() {
+        batches.push(current);
+    }
+
+    batches
+}
+
+impl SolTransfer {
+    // Bytes the run memo adds to every transaction: its data, the memo program key and
+    // the compiled instruction header
+    fn run_memo_reserve(&self) -> usize {
+        self.run_memo
+            .as_ref()
+            .map_or(0, |memo| memo.len() + Pubkey::default().as_ref().len() + 4)
+    }
+
+    // Group planned transfers by sender and pack each group into size-limited transactions
+    pub(crate) fn prepare_batched_transfers(
+        &self,
+        planned: Vec<PlannedTransfer>,
+        blockhash: Hash,
+    ) -> Vec<PreparedTransfer> {
+        let mut seen_messages = HashSet::new();
+        let mut prepared = Vec::new();
+
+        // Keep senders in the order they were planned
+        let mut groups: Vec<(SenderWallet, Vec<PlannedTransfer>)> = Vec::new();
+        for transfer in planned {
+            match groups
+                .iter_mut()
+                .find(|(sender, _)| sender.address == transfer.sender.address)
+            {
+                Some((_, group)) => group.push(transfer),
+                None => groups.push((transfer.sender.clone(), vec![transfer])),
+            }
+        }
+
+        for (sender, group) in groups {
+            let start_time = Instant::now();
+
+            let keypair = match Self::parse_keypair(&sender.private_key) {
+                Ok(keypair) => Arc::new(keypair),
+                Err(e) => {
+                    prepared.push(PreparedTransfer {
+                        from_address: sender.address,
+                        legs: group.iter().map(TransferLeg::from_planned).collect(),
+                        transaction: Err(format!("Failed to parse keypair: {}", e)),
+                        signer: None,
+                        start_time,
+                    });
+                    continue;
+                }
+            };
+
+            // Invalid recipients fail on their own; everything else gets packed
+            let mut items = Vec::new();
+            for transfer in &group {
+                let leg = TransferLeg::from_planned(transfer);
+                match Pubkey::from_str(&transfer.recipient) {
+                    Ok(recipient) => {
+                        let instructions =
+                            self.transfer_instructions(&keypair.pubkey(), &recipient, transfer);
+                        items.push((leg, instructions));
+                    }
+                    Err(e) => prepared.push(PreparedTransfer {
+                        from_address: sender.address.clone(),
+                        legs: vec![leg],
+                        transaction: Err(format!("Invalid recipient address: {}", e)),
+                        signer: None,
+                        start_time,
+                    }),
+                }
+            }
+
+            let max_size = PACKET_DATA_SIZE - DEDUP_MEMO_RESERVE - self.run_memo_reserve();
+            for batch in batch_transfers_per_tx(1, items, max_size) {
+                let (legs, instructions): (Vec<_>, Vec<Vec<_>>) = batch.into_iter().unzip();
+                let transaction = self.sign_unique_transaction(
+                    instructions.concat(),
+                    &keypair,
+                    blockhash,
+                    &mut seen_messages,
+                );
+
+                prepared.push(PreparedTransfer {
+                    from_address: sender.address.clone(),
+                    legs,
+                    signer: transaction.is_ok().then(|| keypair.clone()),
+                    transaction,
+                    start_time,
+                });
+            }
+        }
+
+        prepared
+    }
+}
+
+// How planned transfers were packed into transactions, one line per transaction
+pub(crate) fn batch_plan(prepared: &[PreparedTransfer], planned_count: usize) -> String {
+    let transactions = prepared.iter().filter(|p| p.transaction.is_ok()).count();
+    let mut plan = format!(
+        "📦 Packed {} transfers into {} transactions:\n",
+        planned_count, transactions
+    );
+
+    for (index, transfer) in prepared.iter().enumerate() {
+        if let Ok(transaction) = &transfer.transaction {
+            let size = bincode::serialized_size(transaction).unwrap_or_default();
+            plan.push_str(&format!(
+                "  Tx {}: {} -> {} recipients ({} bytes)\n",
+                index + 1,
+                transfer.from_address,
+                transfer.legs.len(),
+                size
+            ));
+        }
+    }
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{instruction::AccountMeta, signature::Keypair, system_instruction};
+
+    fn plan_for(recipients: usize) -> Vec<PlannedTransfer> {
+        let keypair = Keypair::new();
+        let sender = SenderWallet {
+            address: keypair.pubkey().to_string(),
+            private_key: bs58::encode(keypair.to_bytes()).into_string(),
+            private_key_source: None,
+            amount_sol: None,
+            group: None,
+        };
+
+        (0..recipients)
+            .map(|_| PlannedTransfer {
+                sender: sender.clone(),
+                recipient: Pubkey::new_unique().to_string(),
+                lamports: 1_000_000,
+                label: None,
+                create_token_account: false,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_packed_transactions_fit_size_limit() {
+        let sol_transfer =
+            SolTransfer::new("http://127.0.0.1:8899".to_string()).with_recipient_batching(true);
+
+        for (recipients, expected_min_txs) in [(1, 1), (15, 1), (40, 2), (100, 4)] {
+            let prepared = sol_transfer.prepare_transfers(plan_for(recipients), Hash::new_unique());
+
+            let legs: usize = prepared.iter().map(|p| p.legs.len()).sum();
+            assert_eq!(legs, recipients);
+            assert!(prepared.len() >= expected_min_txs);
+
+            for transfer in &prepared {
+                let transaction = transfer.transaction.as_ref().unwrap();
+                let size = bincode::serialized_size(transaction).unwrap() as usize;
+                assert!(
+                    size <= PACKET_DATA_SIZE,
+                    "{} recipients: transaction of {} bytes exceeds {}",
+                    recipients,
+                    size,
+                    PACKET_DATA_SIZE
+                );
+                assert_eq!(transaction.message.instructions.len(), transfer.legs.len());
+            }
+        }
+    }
+
+    #[test]
+    fn test_small_batch_uses_single_transaction() {
+        let sol_transfer =
+            SolTransfer::new("http://127.0.0.1:8899".to_string()).with_recipient_batching(true);
+
+        let prepared = sol_transfer.prepare_transfers(plan_for(15), Hash::new_unique());
+        assert_eq!(prepared.len(), 1);
+    }
+
+    #[test]
+    fn test_wire_size_matches_signed_transaction() {
+        let payer = Keypair::new();
+        let instructions: Vec<Instruction> = (0..5)
+            .map(|_| system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1))
+            .collect();
+
+        let signed = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::new_unique(),
+        );
+
+        assert_eq!(
+            transaction_wire_size(&payer.pubkey(), &instructions),
+            bincode::serialized_size(&signed).unwrap() as usize
+        );
+        assert_eq!(
+            estimate_wire_size(&instructions, 1),
+            bincode::serialized_size(&signed).unwrap() as usize
+        );
+    }
+
+    #[test]
+    fn test_estimate_of_known_instruction_sizes() {
+        let from = Pubkey::new_unique();
+        let transfer = system_instruction::transfer(&from, &Pubkey::new_unique(), 1);
+        // 64 signature + 1 count, 3 header, 1 + 3 * 32 keys, 32 blockhash, then one
+        // instruction of 1 + 1 + 2 accounts + 1 + 12 data bytes
+        assert_eq!(estimate_wire_size(std::slice::from_ref(&transfer), 1), 215);
+        // Another transfer from the same sender only adds its new key and instruction
+        let second = system_instruction::transfer(&from, &Pubkey::new_unique(), 1);
+        assert_eq!(
+            estimate_wire_size(&[transfer.clone(), second], 1),
+            215 + 32 + 17
+        );
+        // A second signer adds its signature and key
+        let other = system_instruction::transfer(&Pubkey::new_unique(), &from, 1);
+        assert_eq!(
+            estimate_wire_size(&[transfer, other], 2),
+            215 + 64 + 32 + 17
+        );
+    }
+
+    #[test]
+    fn test_oversized_instruction_gets_its_own_transaction() {
+        let from = Pubkey::new_unique();
+        let oversized = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[0; PACKET_DATA_SIZE],
+            vec![AccountMeta::new(from, true)],
+        );
+        assert!(estimate_wire_size(std::slice::from_ref(&oversized), 1) > PACKET_DATA_SIZE);
+
+        let transfer = || system_instruction::transfer(&from, &Pubkey::new_unique(), 1);
+        let items = vec![
+            (0, vec![transfer()]),
+            (1, vec![oversized]),
+            (2, vec![transfer()]),
+        ];
+        let batches = batch_transfers_per_tx(1, items, PACKET_DATA_SIZE);
+        let grouped: Vec<Vec<i32>> = batches
+            .iter()
+            .map(|batch| batch.iter().map(|(item, _)| *item).collect())
+            .collect();
+        assert_eq!(grouped, [vec![0], vec![1], vec![2]]);
+    }
+}
@@ -0,0 +1,355 @@
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+use crate::{PlannedTransfer, SolTransfer, TransferOutcome, TransferResult, progress, report};
+
+pub(crate) const DEFAULT_FAILURE_THRESHOLD_PERCENT: f64 = 10.0;
+
+// How a large run is split up and when it should stop to ask
+#[derive(Debug, Clone)]
+pub(crate) struct ChunkSettings {
+    pub(crate) size: usize,
+    pub(crate) failure_threshold_percent: f64,
+    // Results so far are written here after every chunk
+    pub(crate) checkpoint_path: Option<String>,
+}
+
+// Runs larger than `max_concurrent` are sent in sub-batches of `batch_size`,
+// one after another with `delay` in between, to stay under RPC rate limits
+#[derive(Debug, Clone)]
+pub(crate) struct SplitSettings {
+    pub(crate) max_concurrent: usize,
+    pub(crate) batch_size: usize,
+    pub(crate) delay: Duration,
+}
+
+// Partition planned transfers into the sub-batches `split_large_batch` sends
+fn split_into_sub_batches(
+    planned: Vec<PlannedTransfer>,
+    settings: &SplitSettings,
+) -> Vec<Vec<PlannedTransfer>> {
+    if planned.len() <= settings.max_concurrent {
+        return vec![planned];
+    }
+
+    let mut remaining = planned.into_iter();
+    let mut batches = Vec::new();
+    loop {
+        let batch: Vec<PlannedTransfer> = remaining.by_ref().take(settings.batch_size).collect();
+        if batch.is_empty() {
+            return batches;
+        }
+        batches.push(batch);
+    }
+}
+
+// Totals for one finished chunk
+#[derive(Debug, Clone)]
+pub(crate) struct ChunkStats {
+    pub(crate) number: usize,
+    // Position of the chunk's first transfer in the planned run
+    pub(crate) first_transfer: usize,
+    pub(crate) transfers: usize,
+    pub(crate) succeeded: usize,
+    pub(crate) duration: Duration,
+}
+
+impl ChunkStats {
+    pub(crate) fn failed(&self) -> usize {
+        self.transfers - self.succeeded
+    }
+
+    pub(crate) fn failure_percent(&self) -> f64 {
+        if self.transfers == 0 {
+            return 0.0;
+        }
+        self.failed() as f64 * 100.0 / self.transfers as f64
+    }
+}
+
+// Ask on the terminal whether to carry on; without one the answer is no
+async fn confirm_continue(prompt: String, interactive: bool) -> bool {
+    if !interactive || !io::stdin().is_terminal() {
+        return false;
+    }
+
+    tokio::task::spawn_blocking(move || {
+        print!("{} [y/N] ", prompt);
+        io::stdout().flush().ok();
+        let mut answer = String::new();
+        io::stdin().lock().read_line(&mut answer).ok();
+        matches!(answer.trim(), "y" | "Y" | "yes")
+    })
+    .await
+    .unwrap_or(false)
+}
+
+impl SolTransfer {
+    // Dispatch the run chunk by chunk, waiting for each chunk's confirmations and writing
+    // a checkpoint before the next. A chunk over the failure threshold pauses for
+    // confirmation, or stops the run when nobody can be asked.
+    pub(crate) async fn execute_in_chunks(
+        &self,
+        planned: Vec<PlannedTransfer>,
+        settings: &ChunkSettings,
+    ) -> (Vec<TransferResult>, Vec<ChunkStats>) {
+        let total = planned.len();
+        let mut remaining = planned.into_iter();
+        let mut results = Vec::with_capacity(total);
+        let mut chunks: Vec<ChunkStats> = Vec::new();
+        // Prompting would fight the status table for the terminal
+        let interactive = self.events.is_none();
+
+        let stop_reason = loop {
+            let chunk: Vec<PlannedTransfer> = remaining.by_ref().take(settings.size).collect();
+            if chunk.is_empty() {
+                break None;
+            }
+
+            let number = chunks.len() + 1;
+            let first_transfer = total - remaining.len() - chunk.len();
+            self.notice(format!(
+                "📦 Chunk {} (transfers {}-{} of {})",
+                number,
+                first_transfer + 1,
+                first_transfer + chunk.len(),
+                total
+            ));
+
+            let started = Instant::now();
+            let chunk_results = self.dispatch(chunk, first_transfer).await;
+            let stats = ChunkStats {
+                number,
+                first_transfer,
+                transfers: chunk_results.len(),
+                succeeded: chunk_results
+                    .iter()
+                    .filter(|result| self.outcome(result) == TransferOutcome::Success)
+                    .count(),
+                duration: started.elapsed(),
+            };
+            results.extend(chunk_results);
+
+            self.notice(format!(
+                "📦 Chunk {} done: {}/{} succeeded in {:?}\n",
+                number, stats.succeeded, stats.transfers, stats.duration
+            ));
+            if let Some(path) = &settings.checkpoint_path {
+                match report::export_csv(self, &results, path) {
+                    Ok(()) => self.notice(format!("💾 Checkpoint written to {}\n", path)),
+                    Err(e) => self.notice(format!(
+                        "⚠️  Warning: Failed to write checkpoint {}: {}",
+                        path, e
+                    )),
+                }
+            }
+
+            let over_threshold = stats.failure_percent() > settings.failure_threshold_percent;
+            let failure_percent = stats.failure_percent();
+            chunks.push(stats);
+
+            if self.is_cancelled() {
+                break Some("Cancelled before sending".to_string());
+            }
+            if over_threshold && !remaining.as_slice().is_empty() {
+                let prompt = format!(
+                    "⚠️  Chunk {} failure rate {:.1}% exceeds {:.1}%. Continue with the remaining {} transfers?",
+                    number,
+                    failure_percent,
+                    settings.failure_threshold_percent,
+                    remaining.len()
+                );
+                if !confirm_continue(prompt, interactive).await {
+                    self.notice(format!("🛑 Stopping after chunk {}\n", number));
+                    break Some(format!(
+                        "Not sent: run stopped after chunk {} exceeded the failure threshold",
+                        number
+                    ));
+                }
+            }
+        };
+
+        // Transfers that were never dispatched still get a result so the report adds up
+        if let Some(reason) = stop_reason {
+            let start_time = Instant::now();
+            results.extend(remaining.map(|transfer| {
+                TransferResult::failed(
+                    transfer.sender.address,
+                    transfer.recipient,
+                    transfer.label,
+                    reason.clone(),
+                    start_time,
+                )
+            }));
+        }
+
+        self.emit(progress::TransferEvent::Finished);
+        (results, chunks)
+    }
+
+    // Send a run too large for one concurrent burst as sequential sub-batches,
+    // pausing between them, and merge their results
+    pub(crate) async fn split_large_batch(
+        &self,
+        planned: Vec<PlannedTransfer>,
+        settings: &SplitSettings,
+    ) -> Vec<TransferResult> {
+        let total = planned.len();
+        let batches = split_into_sub_batches(planned, settings);
+        let count = batches.len();
+        let mut results = Vec::with_capacity(total);
+        let mut first_transfer = 0;
+
+        for (index, batch) in batches.into_iter().enumerate() {
+            if self.is_cancelled() {
+                let start_time = Instant::now();
+                results.extend(batch.into_iter().map(|transfer| {
+                    TransferResult::failed(
+                        transfer.sender.address,
+                        transfer.recipient,
+                        transfer.label,
+                        "Cancelled before sending".to_string(),
+                        start_time,
+                    )
+                }));
+                continue;
+            }
+
+            if count > 1 {
+                if index > 0 && !settings.delay.is_zero() {
+                    tokio::time::sleep(settings.delay).await;
+                }
+                self.notice(format!(
+                    "🔀 Sub-batch {}/{} (transfers {}-{} of {})",
+                    index + 1,
+                    count,
+                    first_transfer + 1,
+                    first_transfer + batch.len(),
+                    total
+                ));
+            }
+
+            let batch_len = batch.len();
+            results.extend(self.dispatch(batch, first_transfer).await);
+            first_transfer += batch_len;
+        }
+
+        self.emit(progress::TransferEvent::Finished);
+        results
+    }
+}
+
+pub(crate) fn print_chunk_summary(chunks: &[ChunkStats]) {
+    if chunks.is_empty() {
+        return;
+    }
+
+    println!("\n=== Chunks ===");
+    for chunk in chunks {
+        println!(
+            "Chunk {} (transfers {}-{}): {} succeeded, {} failed ({:.1}%) in {:?}",
+            chunk.number,
+            chunk.first_transfer + 1,
+            chunk.first_transfer + chunk.transfers,
+            chunk.succeeded,
+            chunk.failed(),
+            chunk.failure_percent(),
+            chunk.duration
+        );
+    }
+}
+
+pub(crate) fn chunks_json(chunks: &[ChunkStats]) -> serde_json::Value {
+    chunks
+        .iter()
+        .map(|chunk| {
+            serde_json::json!({
+                "chunk": chunk.number,
+                "first_transfer": chunk.first_transfer + 1,
+                "transfers": chunk.transfers,
+                "succeeded": chunk.succeeded,
+                "failed": chunk.failed(),
+                "duration_ms": chunk.duration.as_millis() as u64,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SenderWallet;
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn planned(count: usize) -> Vec<PlannedTransfer> {
+        let sender = SenderWallet {
+            address: "sender".to_string(),
+            private_key: String::new(),
+            private_key_source: None,
+            amount_sol: None,
+            group: None,
+        };
+        (0..count)
+            .map(|index| PlannedTransfer {
+                sender: sender.clone(),
+                recipient: index.to_string(),
+                lamports: 1,
+                label: None,
+                create_token_account: false,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_sub_batches_only_above_threshold() {
+        let settings = SplitSettings {
+            max_concurrent: 10,
+            batch_size: 4,
+            delay: Duration::ZERO,
+        };
+
+        assert_eq!(split_into_sub_batches(planned(10), &settings).len(), 1);
+
+        let batches = split_into_sub_batches(planned(11), &settings);
+        let sizes: Vec<usize> = batches.iter().map(Vec::len).collect();
+        assert_eq!(sizes, [4, 4, 3]);
+        assert_eq!(batches[2][2].recipient, "10");
+    }
+
+    #[tokio::test]
+    async fn test_chunk_without_blockhash_fails_every_transfer() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+
+        let settings = ChunkSettings {
+            size: 2,
+            failure_threshold_percent: 100.0,
+            checkpoint_path: None,
+        };
+        let (results, chunks) = SolTransfer::new(server.uri())
+            .execute_in_chunks(planned(3), &settings)
+            .await;
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|result| result.error.is_some()));
+        let sizes: Vec<usize> = chunks.iter().map(|chunk| chunk.transfers).collect();
+        assert_eq!(sizes, [2, 1]);
+    }
+
+    #[test]
+    fn test_failure_percent() {
+        let chunk = ChunkStats {
+            number: 1,
+            first_transfer: 0,
+            transfers: 40,
+            succeeded: 34,
+            duration: Duration::ZERO,
+        };
+        assert_eq!(chunk.failed(), 6);
+        assert_eq!(chunk.failure_percent(), 15.0);
+        assert!(chunk.failure_percent() > DEFAULT_FAILURE_THRESHOLD_PERCENT);
+    }
+}
@@ -0,0 +1,166 @@
+use futures::StreamExt;
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+
+use crate::SolTransfer;
+
+// How long `--auto-rpc` waits for each node to answer `getHealth`
+pub(crate) const AUTO_RPC_TIMEOUT: Duration = Duration::from_secs(2);
+
+// Nodes `--auto-rpc` pings at once; mainnet advertises thousands
+const PING_CONCURRENCY: usize = 32;
+
+// One validator or RPC node as advertised in gossip
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ClusterNode {
+    pub(crate) pubkey: String,
+    pub(crate) gossip: Option<String>,
+    // `host:port` of the JSON RPC service; most validators don't expose one
+    pub(crate) rpc: Option<String>,
+    pub(crate) tpu: Option<String>,
+    pub(crate) version: Option<String>,
+    pub(crate) feature_set: Option<u32>,
+}
+
+impl SolTransfer {
+    pub(crate) async fn get_cluster_nodes(
+        &self,
+    ) -> Result<Vec<ClusterNode>, Box<dyn std::error::Error>> {
+        self.rpc_call("getClusterNodes", vec![]).await
+    }
+}
+
+// Time a `getHealth` call to `rpc_url`; `None` if the node is unhealthy or too slow
+async fn ping_rpc(rpc_url: String, timeout: Duration) -> Option<(String, Duration)> {
+    let started = Instant::now();
+    let health = tokio::time::timeout(
+        timeout,
+        SolTransfer::new(rpc_url.clone()).rpc_call::<String>("getHealth", vec![]),
+    )
+    .await;
+    match health {
+        Ok(Ok(status)) if status == "ok" => Some((rpc_url, started.elapsed())),
+        _ => None,
+    }
+}
+
+// Ping every node that exposes RPC, a few at a time, and return the URL of the fastest
+// healthy one
+pub(crate) async fn select_fastest_rpc(
+    nodes: &[ClusterNode],
+    timeout: Duration,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let pings = nodes
+        .iter()
+        .filter_map(|node| node.rpc.as_ref())
+        .map(|rpc| ping_rpc(format!("http://{}", rpc), timeout));
+
+    futures::stream::iter(pings)
+        .buffer_unordered(PING_CONCURRENCY)
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .flatten()
+        .min_by_key(|(_, elapsed)| *elapsed)
+        .map(|(rpc_url, _)| rpc_url)
+        .ok_or_else(|| {
+            format!(
+                "none of {} cluster node(s) answered getHealth within {:?}",
+                nodes.len(),
+                timeout
+            )
+            .into()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn healthy_node(delay: Duration) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(
+                serde_json::json!({ "method": "getHealth" }),
+            ))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "id": 1,
+                        "result": "ok"
+                    }))
+                    .set_delay(delay),
+            )
+            .mount(&server)
+            .await;
+        server
+    }
+
+    fn node(rpc: Option<String>) -> ClusterNode {
+        ClusterNode {
+            pubkey: "node".to_string(),
+            gossip: None,
+            rpc,
+            tpu: None,
+            version: None,
+            feature_set: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fastest_healthy_node_is_selected() {
+        let slow = healthy_node(Duration::from_millis(300)).await;
+        let fast = healthy_node(Duration::ZERO).await;
+        let too_slow = healthy_node(Duration::from_secs(5)).await;
+        let nodes = [
+            node(Some(slow.address().to_string())),
+            node(None),
+            node(Some(fast.address().to_string())),
+            node(Some(too_slow.address().to_string())),
+        ];
+
+        let selected = select_fastest_rpc(&nodes, Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert_eq!(selected, fast.uri());
+
+        assert!(
+            select_fastest_rpc(&nodes[1..2], Duration::from_secs(1))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cluster_nodes_parse() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": [{
+                    "pubkey": "9QxCLckBiJc783jnMvXZubK4wH86Eqqvashtrwvcsgkv",
+                    "gossip": "10.239.6.48:8001",
+                    "rpc": "10.239.6.48:8899",
+                    "tpu": "10.239.6.48:8856",
+                    "version": "2.1.21",
+                    "featureSet": 1142275233,
+                    "shredVersion": 50093
+                }]
+            })))
+            .mount(&server)
+            .await;
+
+        let nodes = SolTransfer::new(server.uri())
+            .get_cluster_nodes()
+            .await
+            .unwrap();
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(nodes[0].rpc.as_deref(), Some("10.239.6.48:8899"));
+        assert_eq!(nodes[0].feature_set, Some(1142275233));
+    }
+}
@@ -0,0 +1,39 @@
+use config_migration::{Migrations, nest_rpc_url};
+use serde::de::DeserializeOwned;
+
+// Each layout version's upgrade to the next, oldest first
+const MIGRATIONS: Migrations = Migrations::new(&[nest_rpc_url]);
+
+// Layout version written by `--generate-config`; older files are migrated on load
+pub(crate) const CURRENT_CONFIG_VERSION: u32 = MIGRATIONS.current_version();
+
+// Parse a config file of any supported version into the current layout
+pub(crate) fn parse_config<T: DeserializeOwned>(
+    contents: &str,
+) -> Result<T, Box<dyn std::error::Error>> {
+    Ok(MIGRATIONS.parse(contents)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    const V1_CONFIG: &str = r#"
+solana_rpc_url: "http://127.0.0.1:8899"
+sender_wallets: []
+"#;
+
+    #[test]
+    fn test_v1_file_loads_as_current_config() {
+        let config: Config = parse_config(V1_CONFIG).unwrap();
+        assert_eq!(config.config_version, 1);
+        assert_eq!(config.rpc.url, "http://127.0.0.1:8899");
+    }
+
+    #[test]
+    fn test_newer_version_is_rejected() {
+        let result: Result<Config, _> = parse_config("config_version: 3\n");
+        assert!(result.unwrap_err().to_string().contains("newer"));
+    }
+}
@@ -0,0 +1,266 @@
+use serde::Deserialize;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::str::FromStr;
+
+use crate::recipients::{Recipient, RowError, split_csv_line};
+
+// Split `total_sol` across the recipients in `file` in proportion to their weights
+#[derive(Debug, Deserialize, Clone)]
+pub(crate) struct DistributionConfig {
+    // YAML mapping of address -> weight, or CSV rows of address,weight
+    pub(crate) file: String,
+    pub(crate) total_sol: f64,
+}
+
+impl DistributionConfig {
+    // Rounded rather than truncated so e.g. 0.3 SOL is exactly 300_000_000 lamports
+    pub(crate) fn total_lamports(&self) -> u64 {
+        (self.total_sol * 1_000_000_000.0).round() as u64
+    }
+}
+
+// One recipient's weight and the lamports it was allotted
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Share {
+    pub(crate) address: String,
+    pub(crate) weight: f64,
+    pub(crate) lamports: u64,
+}
+
+fn check_weight(address: &str, weight: f64) -> Result<(), String> {
+    Pubkey::from_str(address).map_err(|e| format!("invalid address '{}': {}", address, e))?;
+    if !weight.is_finite() || weight <= 0.0 {
+        return Err(format!(
+            "weight for {} must be positive, got {}",
+            address, weight
+        ));
+    }
+    Ok(())
+}
+
+fn parse_csv(source: &str, contents: &str) -> Result<Vec<(String, f64)>, Vec<RowError>> {
+    let mut weights = Vec::new();
+    let mut errors = Vec::new();
+    // Line each address first appeared on; a repeat would get two shares
+    let mut first_lines: HashMap<String, usize> = HashMap::new();
+
+    for (index, line) in contents.lines().enumerate() {
+        let line_number = index + 1;
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        let fields = split_csv_line(trimmed);
+        if line_number == 1 && fields[0].eq_ignore_ascii_case("address") {
+            continue;
+        }
+
+        let row = match fields.as_slice() {
+            [address, weight] => weight
+                .parse::<f64>()
+                .map_err(|_| format!("invalid weight '{}'", weight))
+                .and_then(|weight| {
+                    check_weight(address, weight).map(|_| (address.clone(), weight))
+                }),
+            _ => Err(format!(
+                "expected 2 columns (address, weight), got {}",
+                fields.len()
+            )),
+        };
+        let row = row.and_then(|(address, weight)| {
+            match first_lines.insert(address.clone(), line_number) {
+                Some(first_line) => Err(format!(
+                    "duplicate address {}, first listed on line {}",
+                    address, first_line
+                )),
+                None => Ok((address, weight)),
+            }
+        });
+        match row {
+            Ok(row) => weights.push(row),
+            Err(message) => errors.push(RowError {
+                source: source.to_string(),
+                line: line_number,
+                message,
+            }),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(weights)
+    } else {
+        Err(errors)
+    }
+}
+
+fn parse_yaml(source: &str, contents: &str) -> Result<Vec<(String, f64)>, Vec<RowError>> {
+    let error = |message: String| {
+        vec![RowError {
+            source: source.to_string(),
+            line: 0,
+            message,
+        }]
+    };
+
+    // BTreeMap keeps the order deterministic regardless of the file's key order
+    let weights: BTreeMap<String, f64> =
+        serde_yaml::from_str(contents).map_err(|e| error(e.to_string()))?;
+    let errors: Vec<RowError> = weights
+        .iter()
+        .filter_map(|(address, weight)| check_weight(address, *weight).err())
+        .flat_map(error)
+        .collect();
+
+    if errors.is_empty() {
+        Ok(weights.into_iter().collect())
+    } else {
+        Err(errors)
+    }
+}
+
+// Read weights from a `.csv` file, or a YAML mapping otherwise
+pub(crate) fn load_weights(path: &str) -> Result<Vec<(String, f64)>, Vec<RowError>> {
+    let contents = fs::read_to_string(path).map_err(|e| {
+        vec![RowError {
+            source: path.to_string(),
+            line: 0,
+            message: e.to_string(),
+        }]
+    })?;
+
+    if path.ends_with(".csv") {
+        parse_csv(path, &contents)
+    } else {
+        parse_yaml(path, &contents)
+    }
+}
+
+// Split `total_lamports` pro rata. Each share is rounded down and the leftover goes to
+// the largest weight (the first one on ties), so the shares always sum to the total.
+pub(crate) fn split_lamports(total_lamports: u64, weights: &[(String, f64)]) -> Vec<Share> {
+    let weight_sum: f64 = weights.iter().map(|(_, weight)| weight).sum();
+    let mut shares: Vec<Share> = weights
+        .iter()
+        .map(|(address, weight)| Share {
+            address: address.clone(),
+            weight: *weight,
+            lamports: (total_lamports as f64 * weight / weight_sum).floor() as u64,
+        })
+        .collect();
+
+    let Some(largest) = shares
+        .iter()
+        .enumerate()
+        .fold(
+            None,
+            |best: Option<(usize, f64)>, (index, share)| match best {
+                Some((_, weight)) if weight >= share.weight => best,
+                _ => Some((index, share.weight)),
+            },
+        )
+        .map(|(index, _)| index)
+    else {
+        return shares;
+    };
+
+    // Float rounding can leave the sum a little over as well as under the total
+    let allotted: u64 = shares.iter().map(|share| share.lamports).sum();
+    let largest = &mut shares[largest];
+    if allotted <= total_lamports {
+        largest.lamports += total_lamports - allotted;
+    } else {
+        largest.lamports -= allotted - total_lamports;
+    }
+
+    shares
+}
+
+pub(crate) fn into_recipients(shares: &[Share]) -> Vec<Recipient> {
+    shares
+        .iter()
+        .map(|share| Recipient {
+            address: share.address.clone(),
+            amount_lamports: Some(share.lamports),
+            label: None,
+        })
+        .collect()
+}
+
+pub(crate) fn print_table(total_lamports: u64, shares: &[Share]) {
+    let weight_sum: f64 = shares.iter().map(|share| share.weight).sum();
+
+    println!(
+        "- Distribution: {} SOL ({} lamports) across {} recipients",
+        total_lamports as f64 / 1_000_000_000.0,
+        total_lamports,
+        shares.len()
+    );
+    println!(
+        "    {:<44} {:>10} {:>8} {:>14}",
+        "Address", "Weight", "Share", "Lamports"
+    );
+    for share in shares {
+        println!(
+            "    {:<44} {:>10} {:>7.3}% {:>14}",
+            share.address,
+            share.weight,
+            share.weight * 100.0 / weight_sum,
+            share.lamports
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn weights(values: &[f64]) -> Vec<(String, f64)> {
+        values
+            .iter()
+            .map(|weight| (Pubkey::new_unique().to_string(), *weight))
+            .collect()
+    }
+
+    #[test]
+    fn test_equal_thirds_of_one_sol_sum_exactly() {
+        let shares = split_lamports(1_000_000_000, &weights(&[1.0, 1.0, 1.0]));
+
+        let lamports: Vec<u64> = shares.iter().map(|share| share.lamports).collect();
+        assert_eq!(lamports, vec![333_333_334, 333_333_333, 333_333_333]);
+        assert_eq!(lamports.iter().sum::<u64>(), 1_000_000_000);
+    }
+
+    #[test]
+    fn test_remainder_goes_to_largest_weight() {
+        let shares = split_lamports(100, &weights(&[1.0, 3.0, 3.0]));
+
+        let lamports: Vec<u64> = shares.iter().map(|share| share.lamports).collect();
+        assert_eq!(lamports, vec![14, 44, 42]);
+        assert_eq!(lamports.iter().sum::<u64>(), 100);
+    }
+
+    #[test]
+    fn test_awkward_weights_always_sum_to_total() {
+        for total in [1, 7, 999_999_999, 1_000_000_000, 123_456_789_012] {
+            let shares = split_lamports(total, &weights(&[0.1, 0.2, 0.3, 1e-9, 7.0, 1.0 / 3.0]));
+            assert_eq!(
+                shares.iter().map(|share| share.lamports).sum::<u64>(),
+                total
+            );
+        }
+    }
+
+    #[test]
+    fn test_csv_weights() {
+        let a = Pubkey::new_unique().to_string();
+        let contents = format!("address,weight\n{},2.5\nbad,1\n{},0\n{},1\n", a, a, a);
+
+        let errors = parse_csv("weights.csv", &contents).unwrap_err();
+        let lines: Vec<usize> = errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![3, 4, 5]);
+        assert!(errors[2].message.contains("first listed on line 2"));
+    }
+}
@@ -0,0 +1,108 @@
+use serde::Deserialize;
+
+use crate::SolTransfer;
+
+// Length of the first epoch when the schedule warms up; epochs double from here
+const MINIMUM_SLOTS_PER_EPOCH: u64 = 32;
+
+// Epoch layout as returned by getEpochSchedule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct EpochSchedule {
+    pub(crate) slots_per_epoch: u64,
+    pub(crate) leader_schedule_slot_offset: u64,
+    // Whether epochs start short and double in length until `first_normal_epoch`
+    pub(crate) warmup: bool,
+    pub(crate) first_normal_epoch: u64,
+    pub(crate) first_normal_slot: u64,
+}
+
+impl SolTransfer {
+    pub(crate) async fn get_epoch_schedule(
+        &self,
+    ) -> Result<EpochSchedule, Box<dyn std::error::Error>> {
+        self.rpc_call("getEpochSchedule", vec![]).await
+    }
+}
+
+// Epoch containing `slot`
+pub(crate) fn slot_to_epoch(slot: u64, schedule: &EpochSchedule) -> u64 {
+    if schedule.warmup && slot < schedule.first_normal_slot {
+        // Warmup epoch n covers slots [32 * (2^n - 1), 32 * (2^(n+1) - 1))
+        let epoch_len_exponent = (slot + MINIMUM_SLOTS_PER_EPOCH + 1)
+            .next_power_of_two()
+            .trailing_zeros();
+        (epoch_len_exponent - MINIMUM_SLOTS_PER_EPOCH.trailing_zeros() - 1) as u64
+    } else {
+        (slot - schedule.first_normal_slot) / schedule.slots_per_epoch + schedule.first_normal_epoch
+    }
+}
+
+// First slot of `epoch`
+pub(crate) fn epoch_to_first_slot(epoch: u64, schedule: &EpochSchedule) -> u64 {
+    if schedule.warmup && epoch < schedule.first_normal_epoch {
+        ((1 << epoch) - 1) * MINIMUM_SLOTS_PER_EPOCH
+    } else {
+        (epoch - schedule.first_normal_epoch) * schedule.slots_per_epoch
+            + schedule.first_normal_slot
+    }
+}
+
+// Slots from `slot` to the end of its epoch, `slot` included
+pub(crate) fn slots_left_in_epoch(slot: u64, schedule: &EpochSchedule) -> u64 {
+    epoch_to_first_slot(slot_to_epoch(slot, schedule) + 1, schedule) - slot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Mainnet launched without warmup
+    const MAINNET: EpochSchedule = EpochSchedule {
+        slots_per_epoch: 432_000,
+        leader_schedule_slot_offset: 432_000,
+        warmup: false,
+        first_normal_epoch: 0,
+        first_normal_slot: 0,
+    };
+
+    // Devnet and testnet style schedule with warmup
+    const WARMUP: EpochSchedule = EpochSchedule {
+        slots_per_epoch: 432_000,
+        leader_schedule_slot_offset: 432_000,
+        warmup: true,
+        first_normal_epoch: 14,
+        first_normal_slot: 524_256,
+    };
+
+    #[test]
+    fn test_mainnet_epoch_boundaries() {
+        assert_eq!(slot_to_epoch(0, &MAINNET), 0);
+        assert_eq!(slot_to_epoch(302_399_999, &MAINNET), 699);
+        assert_eq!(slot_to_epoch(302_400_000, &MAINNET), 700);
+        assert_eq!(epoch_to_first_slot(700, &MAINNET), 302_400_000);
+        assert_eq!(slots_left_in_epoch(302_400_000, &MAINNET), 432_000);
+        assert_eq!(slots_left_in_epoch(302_831_999, &MAINNET), 1);
+    }
+
+    #[test]
+    fn test_warmup_epochs_double() {
+        assert_eq!(slot_to_epoch(31, &WARMUP), 0);
+        assert_eq!(slot_to_epoch(32, &WARMUP), 1);
+        assert_eq!(slot_to_epoch(95, &WARMUP), 1);
+        assert_eq!(slot_to_epoch(96, &WARMUP), 2);
+        assert_eq!(epoch_to_first_slot(2, &WARMUP), 96);
+        assert_eq!(slot_to_epoch(524_255, &WARMUP), 13);
+        assert_eq!(slot_to_epoch(524_256, &WARMUP), 14);
+        assert_eq!(epoch_to_first_slot(15, &WARMUP), 956_256);
+    }
+
+    #[test]
+    fn test_schedule_parses_rpc_result() {
+        let schedule: EpochSchedule = serde_json::from_str(
+            r#"{"firstNormalEpoch":0,"firstNormalSlot":0,"leaderScheduleSlotOffset":432000,"slotsPerEpoch":432000,"warmup":false}"#,
+        )
+        .unwrap();
+        assert_eq!(schedule, MAINNET);
+    }
+}
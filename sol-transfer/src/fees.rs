@@ -0,0 +1,223 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::Deserialize;
+use solana_sdk::{compute_budget, message::Message, transaction::Transaction};
+
+use crate::simulation::MAX_COMPUTE_UNIT_LIMIT;
+use crate::{PlannedTransfer, SenderWallet, SolTransfer};
+
+// Base fee charged for each signature
+const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+// Compute units each instruction is budgeted when a transaction sets no limit
+const DEFAULT_INSTRUCTION_COMPUTE_UNITS: u64 = 200_000;
+// ComputeBudgetInstruction discriminators
+const SET_COMPUTE_UNIT_LIMIT: u8 = 2;
+const SET_COMPUTE_UNIT_PRICE: u8 = 3;
+
+#[derive(Debug, Deserialize)]
+struct FeeForMessageResult {
+    value: Option<u64>,
+}
+
+// Expected fees for a set of planned transfers
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct BatchFeeEstimate {
+    pub(crate) transactions: usize,
+    // Transactions the cluster returned no fee for (their blockhash had expired);
+    // their fee is worked out locally from the message instead
+    pub(crate) unavailable: usize,
+    pub(crate) total_fee_lamports: u64,
+    pub(crate) total_transfer_lamports: u64,
+}
+
+impl BatchFeeEstimate {
+    fn from_fees(
+        transactions: &[Transaction],
+        fees: &[Option<u64>],
+        total_transfer_lamports: u64,
+    ) -> Self {
+        Self {
+            transactions: fees.len(),
+            unavailable: fees.iter().filter(|fee| fee.is_none()).count(),
+            total_fee_lamports: transactions
+                .iter()
+                .zip(fees)
+                .map(|(transaction, fee)| fee.unwrap_or_else(|| message_fee(&transaction.message)))
+                .sum(),
+            total_transfer_lamports,
+        }
+    }
+}
+
+// What `message` is charged: the base fee for each signature plus the priority fee,
+// its compute unit price times its compute unit limit
+pub(crate) fn message_fee(message: &Message) -> u64 {
+    let mut unit_limit = None;
+    let mut unit_price = 0;
+    let mut other_instructions = 0;
+    for instruction in &message.instructions {
+        if message.account_keys[instruction.program_id_index as usize] != compute_budget::id() {
+            other_instructions += 1;
+            continue;
+        }
+        match instruction.data.split_first() {
+            Some((&SET_COMPUTE_UNIT_LIMIT, rest)) => {
+                unit_limit = rest
+                    .get(..4)
+                    .map(|bytes| u64::from(u32::from_le_bytes(bytes.try_into().unwrap())));
+            }
+            Some((&SET_COMPUTE_UNIT_PRICE, rest)) => {
+                unit_price = rest
+                    .get(..8)
+                    .map_or(0, |bytes| u64::from_le_bytes(bytes.try_into().unwrap()));
+            }
+            _ => {}
+        }
+    }
+
+    let units = unit_limit.unwrap_or(
+        (other_instructions * DEFAULT_INSTRUCTION_COMPUTE_UNITS).min(MAX_COMPUTE_UNIT_LIMIT),
+    );
+    // The price is in micro-lamports per compute unit
+    let priority_fee = (u128::from(unit_price) * u128::from(units)).div_ceil(1_000_000) as u64;
+    u64::from(message.header.num_required_signatures) * LAMPORTS_PER_SIGNATURE + priority_fee
+}
+
+impl SolTransfer {
+    // Fee for each transaction's message, fetched concurrently.
+    // `None` means the cluster no longer recognises the message's blockhash.
+    pub async fn get_multiple_transaction_fees(
+        &self,
+        transactions: &[Transaction],
+    ) -> Result<Vec<Option<u64>>, Box<dyn std::error::Error>> {
+        let requests = transactions.iter().map(|transaction| async move {
+            let message = STANDARD.encode(transaction.message_data());
+            self.rpc_call::<FeeForMessageResult>(
+                "getFeeForMessage",
+                vec![
+                    serde_json::json!(message),
+                    serde_json::json!({ "commitment": "confirmed" }),
+                ],
+            )
+            .await
+            .map(|result| result.value)
+        });
+
+        futures::future::join_all(requests)
+            .await
+            .into_iter()
+            .collect()
+    }
+
+    // Build the transactions every sender would send to every recipient and price them.
+    // The cluster's fee covers every signature and any priority fee; a transaction it
+    // can't price gets the same sum worked out from its message.
+    pub(crate) async fn estimate_batch_fees(
+        &self,
+        senders: &[SenderWallet],
+        recipients: &[String],
+        amount: u64,
+    ) -> Result<BatchFeeEstimate, Box<dyn std::error::Error>> {
+        let planned: Vec<PlannedTransfer> = senders
+            .iter()
+            .flat_map(|sender| {
+                recipients.iter().map(|recipient| PlannedTransfer {
+                    sender: sender.clone(),
+                    recipient: recipient.clone(),
+                    lamports: amount,
+                    label: None,
+                    create_token_account: false,
+                })
+            })
+            .collect();
+        let total_transfer_lamports = amount * planned.len() as u64;
+
+        let blockhash = self.get_recent_blockhash().await?;
+        let transactions: Vec<Transaction> = self
+            .prepare_transfers(planned, blockhash)
+            .into_iter()
+            .filter_map(|prepared| prepared.transaction.ok())
+            .collect();
+
+        let fees = self.get_multiple_transaction_fees(&transactions).await?;
+        Ok(BatchFeeEstimate::from_fees(
+            &transactions,
+            &fees,
+            total_transfer_lamports,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{
+        compute_budget::ComputeBudgetInstruction,
+        hash::Hash,
+        instruction::Instruction,
+        signature::{Keypair, Signer},
+        system_instruction,
+    };
+
+    fn transaction(signers: &[&Keypair], budget: Vec<Instruction>) -> Transaction {
+        let mut instructions = budget;
+        instructions.extend(signers.iter().map(|signer| {
+            system_instruction::transfer(&signer.pubkey(), &Keypair::new().pubkey(), 1)
+        }));
+        Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&signers[0].pubkey()),
+            signers,
+            Hash::new_unique(),
+        )
+    }
+
+    #[test]
+    fn test_unavailable_fees_are_worked_out_locally() {
+        let payer = Keypair::new();
+        let transactions = vec![
+            transaction(&[&payer], Vec::new()),
+            transaction(&[&payer, &Keypair::new()], Vec::new()),
+            transaction(&[&payer], Vec::new()),
+        ];
+        let estimate =
+            BatchFeeEstimate::from_fees(&transactions, &[Some(5000), None, Some(10000)], 3_000_000);
+
+        assert_eq!(
+            estimate,
+            BatchFeeEstimate {
+                transactions: 3,
+                unavailable: 1,
+                total_fee_lamports: 25000,
+                total_transfer_lamports: 3_000_000,
+            }
+        );
+    }
+
+    #[test]
+    fn test_message_fee_counts_signatures_and_priority_fee() {
+        let payer = Keypair::new();
+        let second = Keypair::new();
+        assert_eq!(
+            message_fee(&transaction(&[&payer, &second], Vec::new()).message),
+            10_000
+        );
+
+        // 300_000 CU at 2.5 lamports (2_500_000 micro-lamports) each
+        let priced = transaction(
+            &[&payer],
+            vec![
+                ComputeBudgetInstruction::set_compute_unit_limit(300_000),
+                ComputeBudgetInstruction::set_compute_unit_price(2_500_000),
+            ],
+        );
+        assert_eq!(message_fee(&priced.message), 5_000 + 750_000);
+
+        // Without a limit each instruction is budgeted 200_000 CU
+        let unlimited = transaction(
+            &[&payer],
+            vec![ComputeBudgetInstruction::set_compute_unit_price(1_000_000)],
+        );
+        assert_eq!(message_fee(&unlimited.message), 5_000 + 200_000);
+    }
+}
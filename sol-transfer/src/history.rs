@@ -0,0 +1,326 @@
+use clap::Args;
+use futures::{Stream, StreamExt};
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::SolTransfer;
+
+// Largest page `getSignaturesForAddress` will return
+const MAX_PAGE_SIZE: usize = 1000;
+
+// One signature returned by `getSignaturesForAddress`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct HistoryEntry {
+    pub(crate) signature: String,
+    pub(crate) slot: u64,
+    pub(crate) err: Option<serde_json::Value>,
+    pub(crate) memo: Option<String>,
+    pub(crate) block_time: Option<i64>,
+    pub(crate) confirmation_status: Option<String>,
+}
+
+// Source of signature pages: the RPC in production, canned pages in tests
+pub(crate) trait SignaturePageFetcher {
+    async fn fetch_page(
+        &self,
+        address: &str,
+        before: Option<&str>,
+        until: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<HistoryEntry>, String>;
+}
+
+impl SignaturePageFetcher for SolTransfer {
+    async fn fetch_page(
+        &self,
+        address: &str,
+        before: Option<&str>,
+        until: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<HistoryEntry>, String> {
+        let mut options = serde_json::json!({
+            "limit": limit,
+            "commitment": "confirmed",
+        });
+        if let Some(before) = before {
+            options["before"] = serde_json::json!(before);
+        }
+        if let Some(until) = until {
+            options["until"] = serde_json::json!(until);
+        }
+
+        self.rpc_call(
+            "getSignaturesForAddress",
+            vec![serde_json::json!(address), options],
+        )
+        .await
+        .map_err(|e| e.to_string())
+    }
+}
+
+// Newest-first signature history for an address, fetched lazily page by page
+pub(crate) struct TransactionHistory<'a, F> {
+    fetcher: &'a F,
+    address: String,
+    until: Option<String>,
+    page_size: usize,
+}
+
+struct PageState<'a, F> {
+    fetcher: &'a F,
+    address: String,
+    until: Option<String>,
+    page_size: usize,
+    before: Option<String>,
+    buffer: VecDeque<HistoryEntry>,
+    exhausted: bool,
+}
+
+// Stream of history entries; the next page is requested only once the current one is drained
+pub(crate) struct SignatureIterator<'a> {
+    inner: Pin<Box<dyn Stream<Item = Result<HistoryEntry, String>> + 'a>>,
+}
+
+impl Stream for SignatureIterator<'_> {
+    type Item = Result<HistoryEntry, String>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.as_mut().poll_next(cx)
+    }
+}
+
+impl<'a, F: SignaturePageFetcher + 'a> TransactionHistory<'a, F> {
+    pub(crate) fn new(fetcher: &'a F, address: String) -> Self {
+        Self {
+            fetcher,
+            address,
+            until: None,
+            page_size: MAX_PAGE_SIZE,
+        }
+    }
+
+    // Stop at (and exclude) this signature
+    pub(crate) fn until(mut self, signature: Option<String>) -> Self {
+        self.until = signature;
+        self
+    }
+
+    pub(crate) fn page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.clamp(1, MAX_PAGE_SIZE);
+        self
+    }
+
+    pub(crate) fn iter(&self) -> SignatureIterator<'a> {
+        let state = PageState {
+            fetcher: self.fetcher,
+            address: self.address.clone(),
+            until: self.until.clone(),
+            page_size: self.page_size,
+            before: None,
+            buffer: VecDeque::new(),
+            exhausted: false,
+        };
+
+        let stream = futures::stream::unfold(state, |mut state| async move {
+            loop {
+                if let Some(entry) = state.buffer.pop_front() {
+                    return Some((Ok(entry), state));
+                }
+                if state.exhausted {
+                    return None;
+                }
+
+                let page = state
+                    .fetcher
+                    .fetch_page(
+                        &state.address,
+                        state.before.as_deref(),
+                        state.until.as_deref(),
+                        state.page_size,
+                    )
+                    .await;
+
+                match page {
+                    Ok(page) => {
+                        // A short page means there is nothing older to fetch
+                        state.exhausted = page.len() < state.page_size;
+                        if let Some(last) = page.last() {
+                            state.before = Some(last.signature.clone());
+                        }
+                        state.buffer.extend(page);
+                    }
+                    Err(e) => {
+                        state.exhausted = true;
+                        return Some((Err(e), state));
+                    }
+                }
+            }
+        });
+
+        SignatureIterator {
+            inner: Box::pin(stream),
+        }
+    }
+
+    // Drain the history, stopping early once `limit` entries have been collected
+    pub(crate) async fn collect_all(
+        &self,
+        limit: Option<usize>,
+    ) -> Result<Vec<HistoryEntry>, String> {
+        let mut entries = Vec::new();
+        if limit == Some(0) {
+            return Ok(entries);
+        }
+
+        let mut stream = self.iter();
+        while let Some(entry) = stream.next().await {
+            entries.push(entry?);
+            if limit.is_some_and(|limit| entries.len() >= limit) {
+                break;
+            }
+        }
+
+        Ok(entries)
+    }
+}
+
+impl SolTransfer {
+    pub(crate) fn get_transaction_history(&self, address: &str) -> TransactionHistory<'_, Self> {
+        TransactionHistory::new(self, address.to_string())
+    }
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct HistoryArgs {
+    /// Address whose signatures to list
+    address: String,
+    /// Maximum number of signatures to print
+    #[arg(long)]
+    limit: Option<usize>,
+    /// Stop at this signature (exclusive)
+    #[arg(long)]
+    until: Option<String>,
+}
+
+// `history` subcommand
+pub(crate) async fn run(
+    sol_transfer: &SolTransfer,
+    args: HistoryArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let history = sol_transfer
+        .get_transaction_history(&args.address)
+        .until(args.until)
+        .page_size(args.limit.unwrap_or(MAX_PAGE_SIZE));
+
+    let entries = history.collect_all(args.limit).await?;
+
+    println!("=== Transaction History: {} ===\n", args.address);
+    for entry in &entries {
+        let status = if entry.err.is_some() { "❌" } else { "✅" };
+        println!("{} {} (slot {})", status, entry.signature, entry.slot);
+        if let Some(confirmation_status) = &entry.confirmation_status {
+            println!("   Confirmation: {}", confirmation_status);
+        }
+        if let Some(block_time) = entry.block_time {
+            println!("   Block time: {}", block_time);
+        }
+        if let Some(memo) = &entry.memo {
+            println!("   Memo: {}", memo);
+        }
+    }
+    println!("\nTotal: {}", entries.len());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    // Three pages of two, two and one entries, newest first
+    const FIXTURE_PAGES: [&str; 3] = [
+        r#"[
+            {"signature": "sig5", "slot": 105, "err": null, "memo": null, "blockTime": 1700000005, "confirmationStatus": "finalized"},
+            {"signature": "sig4", "slot": 104, "err": null, "memo": "[5] payroll", "blockTime": 1700000004, "confirmationStatus": "finalized"}
+        ]"#,
+        r#"[
+            {"signature": "sig3", "slot": 103, "err": {"InstructionError": [0, {"Custom": 1}]}, "memo": null, "blockTime": null, "confirmationStatus": "finalized"},
+            {"signature": "sig2", "slot": 102, "err": null, "memo": null, "blockTime": 1700000002, "confirmationStatus": "finalized"}
+        ]"#,
+        r#"[
+            {"signature": "sig1", "slot": 101, "err": null, "memo": null, "blockTime": 1700000001, "confirmationStatus": "finalized"}
+        ]"#,
+    ];
+
+    struct MockFetcher {
+        pages: Vec<Vec<HistoryEntry>>,
+        requested_before: RefCell<Vec<Option<String>>>,
+    }
+
+    impl MockFetcher {
+        fn new() -> Self {
+            Self {
+                pages: FIXTURE_PAGES
+                    .iter()
+                    .map(|page| serde_json::from_str(page).unwrap())
+                    .collect(),
+                requested_before: RefCell::new(Vec::new()),
+            }
+        }
+    }
+
+    impl SignaturePageFetcher for MockFetcher {
+        async fn fetch_page(
+            &self,
+            _address: &str,
+            before: Option<&str>,
+            _until: Option<&str>,
+            limit: usize,
+        ) -> Result<Vec<HistoryEntry>, String> {
+            assert_eq!(limit, 2);
+            self.requested_before
+                .borrow_mut()
+                .push(before.map(str::to_string));
+
+            let page = match before {
+                None => 0,
+                Some("sig4") => 1,
+                Some("sig2") => 2,
+                Some(other) => return Err(format!("unexpected cursor {}", other)),
+            };
+            Ok(self.pages[page].clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_collect_all_follows_before_cursor() {
+        let fetcher = MockFetcher::new();
+        let history = TransactionHistory::new(&fetcher, "addr".to_string()).page_size(2);
+
+        let entries = history.collect_all(None).await.unwrap();
+        let signatures: Vec<&str> = entries.iter().map(|e| e.signature.as_str()).collect();
+
+        assert_eq!(signatures, vec!["sig5", "sig4", "sig3", "sig2", "sig1"]);
+        assert_eq!(
+            *fetcher.requested_before.borrow(),
+            vec![None, Some("sig4".to_string()), Some("sig2".to_string())]
+        );
+        assert_eq!(entries[1].memo.as_deref(), Some("[5] payroll"));
+        assert!(entries[2].err.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_collect_all_fetches_pages_lazily() {
+        let fetcher = MockFetcher::new();
+        let history = TransactionHistory::new(&fetcher, "addr".to_string()).page_size(2);
+
+        let entries = history.collect_all(Some(3)).await.unwrap();
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(fetcher.requested_before.borrow().len(), 2);
+    }
+}
@@ -0,0 +1,206 @@
+use futures::future::BoxFuture;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use std::time::Instant;
+
+use crate::{PlannedTransfer, SolTransfer, TransferResult};
+
+// Custom checks and notifications around every transfer, e.g. compliance screening,
+// balance pre-verification or alerting, registered with `SolTransfer::with_hook`.
+// Futures are boxed so hooks can be stored as trait objects.
+pub(crate) trait TransferHook: Send + Sync {
+    // Runs before the transaction is built; an error stops this transfer and becomes
+    // its failure reason
+    fn pre_transfer<'a>(
+        &'a self,
+        from: &'a Pubkey,
+        to: &'a Pubkey,
+        lamports: u64,
+    ) -> BoxFuture<'a, Result<(), String>>;
+
+    // Runs once the transfer has an outcome, including transfers a hook rejected
+    fn post_transfer<'a>(&'a self, result: &'a TransferResult) -> BoxFuture<'a, ()>;
+}
+
+// Prints each transfer before it is sent and its outcome afterwards
+pub(crate) struct LoggingHook;
+
+impl TransferHook for LoggingHook {
+    fn pre_transfer<'a>(
+        &'a self,
+        from: &'a Pubkey,
+        to: &'a Pubkey,
+        lamports: u64,
+    ) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            println!("🪝 {} -> {}: {} lamports", from, to, lamports);
+            Ok(())
+        })
+    }
+
+    fn post_transfer<'a>(&'a self, result: &'a TransferResult) -> BoxFuture<'a, ()> {
+        Box::pin(async move {
+            match &result.error {
+                Some(error) => println!(
+                    "🪝 {} -> {} failed: {}",
+                    result.from_address, result.to_address, error
+                ),
+                None => println!(
+                    "🪝 {} -> {} sent: {}",
+                    result.from_address, result.to_address, result.signature
+                ),
+            }
+        })
+    }
+}
+
+impl SolTransfer {
+    // Split off the transfers a pre-transfer hook rejected, as failed results.
+    // Addresses that don't parse are kept so they fail with the usual error later.
+    pub(crate) async fn run_pre_transfer_hooks(
+        &self,
+        planned: Vec<PlannedTransfer>,
+    ) -> (Vec<PlannedTransfer>, Vec<TransferResult>) {
+        if self.hooks.is_empty() {
+            return (planned, Vec::new());
+        }
+
+        let mut kept = Vec::with_capacity(planned.len());
+        let mut rejected = Vec::new();
+        for transfer in planned {
+            let (Ok(from), Ok(to)) = (
+                Pubkey::from_str(&transfer.sender.address),
+                Pubkey::from_str(&transfer.recipient),
+            ) else {
+                kept.push(transfer);
+                continue;
+            };
+
+            let mut rejection = None;
+            for hook in &self.hooks {
+                if let Err(e) = hook.pre_transfer(&from, &to, transfer.lamports).await {
+                    rejection = Some(e);
+                    break;
+                }
+            }
+            match rejection {
+                Some(reason) => rejected.push(TransferResult::failed(
+                    transfer.sender.address,
+                    transfer.recipient,
+                    transfer.label,
+                    format!("Rejected by transfer hook: {}", reason),
+                    Instant::now(),
+                )),
+                None => kept.push(transfer),
+            }
+        }
+        (kept, rejected)
+    }
+
+    pub(crate) async fn run_post_transfer_hooks(&self, result: &TransferResult) {
+        for hook in &self.hooks {
+            hook.post_transfer(result).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SenderWallet;
+    use std::sync::{Arc, Mutex};
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    // Rejects transfers above a limit and remembers every outcome it saw
+    struct LimitHook {
+        max_lamports: u64,
+        seen: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl TransferHook for LimitHook {
+        fn pre_transfer<'a>(
+            &'a self,
+            _from: &'a Pubkey,
+            _to: &'a Pubkey,
+            lamports: u64,
+        ) -> BoxFuture<'a, Result<(), String>> {
+            Box::pin(async move {
+                if lamports > self.max_lamports {
+                    return Err(format!("{} lamports is over the limit", lamports));
+                }
+                Ok(())
+            })
+        }
+
+        fn post_transfer<'a>(&'a self, result: &'a TransferResult) -> BoxFuture<'a, ()> {
+            Box::pin(async move {
+                self.seen.lock().unwrap().push(result.to_address.clone());
+            })
+        }
+    }
+
+    fn planned(lamports: u64) -> PlannedTransfer {
+        PlannedTransfer {
+            sender: SenderWallet {
+                address: Pubkey::new_unique().to_string(),
+                private_key: String::new(),
+                private_key_source: None,
+                amount_sol: None,
+                group: None,
+            },
+            recipient: Pubkey::new_unique().to_string(),
+            lamports,
+            label: None,
+            create_token_account: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hooks_reject_and_observe_transfers() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sol_transfer = SolTransfer::new("http://127.0.0.1:8899".to_string())
+            .with_hook(Box::new(LoggingHook))
+            .with_hook(Box::new(LimitHook {
+                max_lamports: 1_000,
+                seen: seen.clone(),
+            }));
+
+        let (kept, rejected) = sol_transfer
+            .run_pre_transfer_hooks(vec![planned(500), planned(5_000)])
+            .await;
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].lamports, 500);
+        assert_eq!(rejected.len(), 1);
+        assert_eq!(
+            rejected[0].error.as_deref(),
+            Some("Rejected by transfer hook: 5000 lamports is over the limit")
+        );
+
+        sol_transfer.run_post_transfer_hooks(&rejected[0]).await;
+        assert_eq!(*seen.lock().unwrap(), vec![rejected[0].to_address.clone()]);
+    }
+
+    #[tokio::test]
+    async fn test_rejections_survive_a_failed_blockhash_fetch() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sol_transfer = SolTransfer::new(server.uri()).with_hook(Box::new(LimitHook {
+            max_lamports: 1_000,
+            seen: seen.clone(),
+        }));
+
+        let results = sol_transfer
+            .dispatch(vec![planned(500), planned(5_000)], 0)
+            .await;
+        assert_eq!(results.len(), 2);
+        assert!(results[0].error.as_ref().unwrap().contains("blockhash"));
+        assert!(results[1].error.as_ref().unwrap().contains("Rejected"));
+        assert_eq!(seen.lock().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,289 @@
+use solana_sdk::signature::Signer;
+use std::fmt;
+use std::fs;
+
+use crate::{SenderWallet, SolTransfer};
+
+// Where a sender's private key comes from. Display shows only the location, never the secret.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum KeySource {
+    Env(String),
+    File(String),
+    AwsSecretsManager(String),
+    GcpSecretManager(String),
+}
+
+impl KeySource {
+    pub(crate) fn parse(source: &str) -> Result<Self, String> {
+        let (kind, location) = source.split_once(':').ok_or_else(|| {
+            format!(
+                "invalid key source '{}': expected <kind>:<location>",
+                source
+            )
+        })?;
+        if location.is_empty() {
+            return Err(format!("invalid key source '{}': empty location", source));
+        }
+
+        match kind {
+            "env" => Ok(Self::Env(location.to_string())),
+            "file" => Ok(Self::File(location.to_string())),
+            "aws-sm" => Ok(Self::AwsSecretsManager(location.to_string())),
+            "gcp-sm" => Ok(Self::GcpSecretManager(location.to_string())),
+            _ => Err(format!(
+                "unknown key source kind '{}' (expected env, file, aws-sm or gcp-sm)",
+                kind
+            )),
+        }
+    }
+
+    // Fetch the raw secret. Errors describe the failure but never include secret material.
+    async fn fetch(&self) -> Result<String, String> {
+        match self {
+            Self::Env(var) => std::env::var(var).map_err(|e| e.to_string()),
+            Self::File(path) => fs::read_to_string(path).map_err(|e| e.to_string()),
+            Self::AwsSecretsManager(name) => fetch_aws_secret(name).await,
+            Self::GcpSecretManager(resource) => fetch_gcp_secret(resource).await,
+        }
+    }
+}
+
+impl fmt::Display for KeySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Env(var) => write!(f, "env:{}", var),
+            Self::File(path) => write!(f, "file:{}", path),
+            Self::AwsSecretsManager(name) => write!(f, "aws-sm:{}", name),
+            Self::GcpSecretManager(resource) => write!(f, "gcp-sm:{}", resource),
+        }
+    }
+}
+
+#[cfg(feature = "aws-sm")]
+async fn fetch_aws_secret(name: &str) -> Result<String, String> {
+    let config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+    let client = aws_sdk_secretsmanager::Client::new(&config);
+
+    let response = client
+        .get_secret_value()
+        .secret_id(name)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    response
+        .secret_string()
+        .map(str::to_string)
+        .ok_or_else(|| "secret has no string value".to_string())
+}
+
+#[cfg(not(feature = "aws-sm"))]
+async fn fetch_aws_secret(_name: &str) -> Result<String, String> {
+    Err("AWS Secrets Manager support not compiled in (rebuild with --features aws-sm)".to_string())
+}
+
+#[cfg(feature = "gcp-sm")]
+async fn fetch_gcp_secret(resource: &str) -> Result<String, String> {
+    use base64::Engine;
+
+    #[derive(serde::Deserialize)]
+    struct AccessResponse {
+        payload: Payload,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct Payload {
+        data: String,
+    }
+
+    // Accept a bare secret resource and default to its latest version
+    let resource = if resource.contains("/versions/") {
+        resource.to_string()
+    } else {
+        format!("{}/versions/latest", resource)
+    };
+
+    let provider = gcp_auth::provider().await.map_err(|e| e.to_string())?;
+    let token = provider
+        .token(&["https://www.googleapis.com/auth/cloud-platform"])
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let response = reqwest::Client::new()
+        .get(format!(
+            "https://secretmanager.googleapis.com/v1/{}:access",
+            resource
+        ))
+        .bearer_auth(token.as_str())
+        .send()
+        .await
+        .map_err(|e| e.to_string())?
+        .error_for_status()
+        .map_err(|e| e.to_string())?;
+
+    let body: AccessResponse = response.json().await.map_err(|e| e.to_string())?;
+    let data = base64::engine::general_purpose::STANDARD
+        .decode(body.payload.data)
+        .map_err(|_| "secret payload is not valid base64".to_string())?;
+
+    String::from_utf8(data).map_err(|_| "secret payload is not valid UTF-8".to_string())
+}
+
+#[cfg(not(feature = "gcp-sm"))]
+async fn fetch_gcp_secret(_resource: &str) -> Result<String, String> {
+    Err("GCP Secret Manager support not compiled in (rebuild with --features gcp-sm)".to_string())
+}
+
+// Accept either a base58 string or a solana-keygen style JSON byte array
+fn normalize_secret(secret: &str) -> Result<String, String> {
+    let secret = secret.trim();
+    if secret.starts_with('[') {
+        let bytes: Vec<u8> = serde_json::from_str(secret)
+            .map_err(|_| "secret looks like a JSON keypair but could not be parsed".to_string())?;
+        return Ok(bs58::encode(bytes).into_string());
+    }
+    Ok(secret.to_string())
+}
+
+// Resolve every sender's key and check it matches the declared address.
+// All senders are checked so every problem is reported at once, before anything is sent.
+pub(crate) async fn resolve_sender_keys(wallets: &mut [SenderWallet]) -> Result<(), Vec<String>> {
+    let mut errors = Vec::new();
+
+    for wallet in wallets.iter_mut() {
+        let source = match (&wallet.private_key_source, wallet.private_key.is_empty()) {
+            (Some(_), false) => {
+                errors.push(format!(
+                    "sender {}: set either private_key or private_key_source, not both",
+                    wallet.address
+                ));
+                continue;
+            }
+            (None, true) => {
+                errors.push(format!(
+                    "sender {}: no private_key or private_key_source configured",
+                    wallet.address
+                ));
+                continue;
+            }
+            (Some(source), true) => match KeySource::parse(source) {
+                Ok(source) => Some(source),
+                Err(e) => {
+                    errors.push(format!("sender {}: {}", wallet.address, e));
+                    continue;
+                }
+            },
+            (None, false) => None,
+        };
+
+        if let Some(source) = &source {
+            let secret = source.fetch().await.and_then(|s| normalize_secret(&s));
+            match secret {
+                Ok(secret) => wallet.private_key = secret,
+                Err(e) => {
+                    errors.push(format!(
+                        "sender {}: failed to load key from {}: {}",
+                        wallet.address, source, e
+                    ));
+                    continue;
+                }
+            }
+        }
+
+        let origin = source
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| "inline private_key".to_string());
+        match SolTransfer::parse_keypair(&wallet.private_key) {
+            Ok(keypair) if keypair.pubkey().to_string() == wallet.address => {}
+            Ok(keypair) => errors.push(format!(
+                "sender {}: key from {} belongs to {}",
+                wallet.address,
+                origin,
+                keypair.pubkey()
+            )),
+            Err(_) => errors.push(format!(
+                "sender {}: key from {} is not a valid 64-byte keypair",
+                wallet.address, origin
+            )),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::Keypair;
+
+    #[test]
+    fn test_parse_key_source() {
+        assert_eq!(
+            KeySource::parse("env:SENDER_KEY"),
+            Ok(KeySource::Env("SENDER_KEY".to_string()))
+        );
+        assert_eq!(
+            KeySource::parse("gcp-sm:projects/p/secrets/s"),
+            Ok(KeySource::GcpSecretManager(
+                "projects/p/secrets/s".to_string()
+            ))
+        );
+        assert!(KeySource::parse("vault:foo").is_err());
+        assert!(KeySource::parse("env:").is_err());
+        assert!(KeySource::parse("plain").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_reports_mismatch_without_leaking_secret() {
+        let keypair = Keypair::new();
+        let secret = bs58::encode(keypair.to_bytes()).into_string();
+        let var = "SOL_TRANSFER_TEST_KEY_MISMATCH";
+        // SAFETY: the variable name is unique to this test
+        unsafe { std::env::set_var(var, format!("{}\n", secret)) };
+
+        let mut wallets = vec![SenderWallet {
+            address: Keypair::new().pubkey().to_string(),
+            private_key: String::new(),
+            private_key_source: Some(format!("env:{}", var)),
+            amount_sol: None,
+            group: None,
+        }];
+
+        let errors = resolve_sender_keys(&mut wallets).await.unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains(&format!("env:{}", var)));
+        assert!(!errors[0].contains(&secret));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_json_keypair_file() {
+        let keypair = Keypair::new();
+        let path = std::env::temp_dir().join(format!("sol-transfer-key-{}.json", keypair.pubkey()));
+        fs::write(
+            &path,
+            serde_json::to_string(&keypair.to_bytes().to_vec()).unwrap(),
+        )
+        .unwrap();
+
+        let mut wallets = vec![SenderWallet {
+            address: keypair.pubkey().to_string(),
+            private_key: String::new(),
+            private_key_source: Some(format!("file:{}", path.display())),
+            amount_sol: None,
+            group: None,
+        }];
+
+        let result = resolve_sender_keys(&mut wallets).await;
+        fs::remove_file(&path).unwrap();
+
+        assert!(result.is_ok());
+        assert_eq!(
+            wallets[0].private_key,
+            bs58::encode(keypair.to_bytes()).into_string()
+        );
+    }
+}
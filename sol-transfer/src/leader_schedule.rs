@@ -0,0 +1,148 @@
+use clap::Args;
+use std::collections::HashMap;
+
+use crate::SolTransfer;
+use crate::epochs;
+
+// Nominal slot length, for the rough wait estimate
+const SLOT_DURATION_MS: u64 = 400;
+
+impl SolTransfer {
+    // Leader schedule for the epoch containing `slot` (the current epoch if `None`):
+    // validator identity -> slot indices relative to the epoch's first slot
+    pub(crate) async fn get_leader_schedule(
+        &self,
+        slot: Option<u64>,
+    ) -> Result<HashMap<String, Vec<u64>>, Box<dyn std::error::Error>> {
+        self.rpc_call(
+            "getLeaderSchedule",
+            vec![
+                serde_json::json!(slot),
+                serde_json::json!({ "commitment": "processed" }),
+            ],
+        )
+        .await
+    }
+
+    // Latest processed slot, the reference point for upcoming leader slots
+    async fn get_processed_slot(&self) -> Result<u64, Box<dyn std::error::Error>> {
+        self.rpc_call(
+            "getSlot",
+            vec![serde_json::json!({ "commitment": "processed" })],
+        )
+        .await
+    }
+
+    // First slot after `current_slot` that `validator_identity` is scheduled to lead,
+    // looking into the next epoch once the current one has no slots left for it
+    pub(crate) async fn next_leader_slot(
+        &self,
+        validator_identity: &str,
+        current_slot: u64,
+    ) -> Result<Option<u64>, Box<dyn std::error::Error>> {
+        let schedule = self.get_epoch_schedule().await?;
+        let epoch = epochs::slot_to_epoch(current_slot, &schedule);
+        let epoch_start = epochs::epoch_to_first_slot(epoch, &schedule);
+
+        let leaders = self.get_leader_schedule(Some(current_slot)).await?;
+        if let Some(slot) = leaders
+            .get(validator_identity)
+            .and_then(|indices| next_slot_in_schedule(indices, epoch_start, current_slot))
+        {
+            return Ok(Some(slot));
+        }
+
+        // The next epoch's schedule may not be published yet
+        let next_epoch_start = epochs::epoch_to_first_slot(epoch + 1, &schedule);
+        Ok(self
+            .get_leader_schedule(Some(next_epoch_start))
+            .await
+            .ok()
+            .and_then(|leaders| {
+                leaders.get(validator_identity).and_then(|indices| {
+                    next_slot_in_schedule(indices, next_epoch_start, current_slot)
+                })
+            }))
+    }
+}
+
+// Earliest absolute slot after `current_slot` among an epoch's leader slot indices
+fn next_slot_in_schedule(indices: &[u64], epoch_start: u64, current_slot: u64) -> Option<u64> {
+    indices
+        .iter()
+        .map(|index| epoch_start + index)
+        .filter(|slot| *slot > current_slot)
+        .min()
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct NextLeaderArgs {
+    /// Validator identity pubkey
+    validator: String,
+    /// Look ahead from this slot instead of the current processed slot
+    #[arg(long)]
+    slot: Option<u64>,
+}
+
+// `next-leader` subcommand
+pub(crate) async fn run(
+    sol_transfer: &SolTransfer,
+    args: NextLeaderArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let current_slot = match args.slot {
+        Some(slot) => slot,
+        None => sol_transfer.get_processed_slot().await?,
+    };
+
+    match sol_transfer
+        .next_leader_slot(&args.validator, current_slot)
+        .await?
+    {
+        Some(slot) => {
+            let slots_away = slot - current_slot;
+            println!(
+                "🗓️  {} leads slot {} next ({} slots after {}, ~{:.1}s)",
+                args.validator,
+                slot,
+                slots_away,
+                current_slot,
+                (slots_away * SLOT_DURATION_MS) as f64 / 1000.0
+            );
+        }
+        None => println!(
+            "❌ {} has no leader slots after {} in the known schedule",
+            args.validator, current_slot
+        ),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_slot_in_schedule() {
+        // Leader slots come in groups of four consecutive indices
+        let indices = [8, 9, 10, 11, 400, 401, 402, 403];
+        let epoch_start = 302_400_000;
+
+        assert_eq!(
+            next_slot_in_schedule(&indices, epoch_start, 302_400_000),
+            Some(302_400_008)
+        );
+        assert_eq!(
+            next_slot_in_schedule(&indices, epoch_start, 302_400_009),
+            Some(302_400_010)
+        );
+        assert_eq!(
+            next_slot_in_schedule(&indices, epoch_start, 302_400_011),
+            Some(302_400_400)
+        );
+        assert_eq!(
+            next_slot_in_schedule(&indices, epoch_start, 302_400_403),
+            None
+        );
+    }
+}
@@ -0,0 +1,232 @@
+use clap::{Args, Subcommand};
+use solana_sdk::{
+    address_lookup_table::instruction::{
+        close_lookup_table, create_lookup_table, deactivate_lookup_table, extend_lookup_table,
+    },
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use std::str::FromStr;
+
+use crate::{Config, SolTransfer};
+
+// Addresses added per `extend_lookup_table` transaction; 20 keeps each one well under the packet limit
+const EXTEND_BATCH_SIZE: usize = 20;
+
+// One extend instruction per chunk of `EXTEND_BATCH_SIZE` addresses, paid for by the authority
+fn extend_instructions(
+    authority: &Pubkey,
+    table: &Pubkey,
+    addresses: Vec<Pubkey>,
+) -> Vec<Instruction> {
+    addresses
+        .chunks(EXTEND_BATCH_SIZE)
+        .map(|chunk| extend_lookup_table(*table, *authority, Some(*authority), chunk.to_vec()))
+        .collect()
+}
+
+impl SolTransfer {
+    // Latest finalized slot; lookup table addresses are derived from a recent one
+    async fn get_slot(&self) -> Result<u64, Box<dyn std::error::Error>> {
+        self.rpc_call(
+            "getSlot",
+            vec![serde_json::json!({ "commitment": "finalized" })],
+        )
+        .await
+    }
+
+    // Sign a single instruction for `authority`, send it and wait for the configured confirmation
+    async fn send_authority_instruction(
+        &self,
+        authority: &Keypair,
+        instruction: Instruction,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let blockhash = self.get_recent_blockhash().await?;
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&authority.pubkey()),
+            &[authority],
+            blockhash,
+        );
+
+        let (signature, _) = self.send_and_confirm(&transaction).await?;
+        Ok(signature)
+    }
+
+    // Create a lookup table owned and paid for by `authority`.
+    // Returns the table address and the creation signature.
+    pub async fn create_address_lookup_table(
+        &self,
+        authority: &Keypair,
+        recent_slot: u64,
+    ) -> Result<(Pubkey, String), Box<dyn std::error::Error>> {
+        let (instruction, table) =
+            create_lookup_table(authority.pubkey(), authority.pubkey(), recent_slot);
+        let signature = self
+            .send_authority_instruction(authority, instruction)
+            .await?;
+        Ok((table, signature))
+    }
+
+    // Append `addresses` to `table`, 20 per transaction, each confirmed before the next is sent.
+    // Returns the signature of the last transaction.
+    pub async fn extend_lookup_table(
+        &self,
+        authority: &Keypair,
+        table: &Pubkey,
+        addresses: Vec<Pubkey>,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let instructions = extend_instructions(&authority.pubkey(), table, addresses);
+        let total = instructions.len();
+        let mut last_signature = None;
+
+        for (index, instruction) in instructions.into_iter().enumerate() {
+            let signature = self
+                .send_authority_instruction(authority, instruction)
+                .await?;
+            println!("  Extend {}/{}: {}", index + 1, total, signature);
+            last_signature = Some(signature);
+        }
+
+        last_signature.ok_or_else(|| "no addresses to add".into())
+    }
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct LookupTableArgs {
+    /// Index into `sender_wallets` of the table authority (also pays fees)
+    #[arg(long, default_value_t = 0, global = true)]
+    authority: usize,
+
+    #[command(subcommand)]
+    command: LookupTableCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum LookupTableCommand {
+    /// Create a new lookup table
+    Create,
+    /// Add addresses to a lookup table
+    Extend {
+        /// Lookup table address
+        table: String,
+        /// Addresses to add
+        #[arg(required = true)]
+        addresses: Vec<String>,
+    },
+    /// Deactivate a lookup table so it can later be closed
+    Deactivate {
+        /// Lookup table address
+        table: String,
+    },
+    /// Close a deactivated lookup table and reclaim its rent
+    Close {
+        /// Lookup table address
+        table: String,
+        /// Account to receive the reclaimed lamports (default: the authority)
+        #[arg(long)]
+        recipient: Option<String>,
+    },
+}
+
+fn parse_pubkey(value: &str, what: &str) -> Result<Pubkey, Box<dyn std::error::Error>> {
+    Pubkey::from_str(value).map_err(|e| format!("Invalid {} '{}': {}", what, value, e).into())
+}
+
+// `lookup-table` subcommand
+pub(crate) async fn run(
+    sol_transfer: &SolTransfer,
+    config: &Config,
+    args: LookupTableArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let authority = config
+        .sender_wallets
+        .get(args.authority)
+        .ok_or_else(|| format!("No sender wallet at index {}", args.authority))?;
+    let authority = SolTransfer::parse_keypair(&authority.private_key)?;
+
+    match args.command {
+        LookupTableCommand::Create => {
+            let recent_slot = sol_transfer.get_slot().await?;
+            let (table, signature) = sol_transfer
+                .create_address_lookup_table(&authority, recent_slot)
+                .await?;
+            println!("✅ Created lookup table {}", table);
+            println!("Authority: {}", authority.pubkey());
+            println!("Signature: {}", signature);
+        }
+        LookupTableCommand::Extend { table, addresses } => {
+            let table = parse_pubkey(&table, "lookup table")?;
+            let addresses = addresses
+                .iter()
+                .map(|address| parse_pubkey(address, "address"))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            println!("Adding {} addresses to {}", addresses.len(), table);
+            let count = addresses.len();
+            sol_transfer
+                .extend_lookup_table(&authority, &table, addresses)
+                .await?;
+            println!("✅ Extended lookup table with {} addresses", count);
+        }
+        LookupTableCommand::Deactivate { table } => {
+            let table = parse_pubkey(&table, "lookup table")?;
+            let instruction = deactivate_lookup_table(table, authority.pubkey());
+            let signature = sol_transfer
+                .send_authority_instruction(&authority, instruction)
+                .await?;
+            println!("✅ Deactivated lookup table {}", table);
+            println!("Signature: {}", signature);
+        }
+        LookupTableCommand::Close { table, recipient } => {
+            let table = parse_pubkey(&table, "lookup table")?;
+            let recipient = match recipient {
+                Some(recipient) => parse_pubkey(&recipient, "recipient")?,
+                None => authority.pubkey(),
+            };
+            let instruction = close_lookup_table(table, authority.pubkey(), recipient);
+            let signature = sol_transfer
+                .send_authority_instruction(&authority, instruction)
+                .await?;
+            println!(
+                "✅ Closed lookup table {} (rent sent to {})",
+                table, recipient
+            );
+            println!("Signature: {}", signature);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extend_splits_into_batches_of_twenty() {
+        let authority = Pubkey::new_unique();
+        let table = Pubkey::new_unique();
+
+        for (count, expected) in [(1, 1), (20, 1), (21, 2), (45, 3)] {
+            let addresses: Vec<Pubkey> = (0..count).map(|_| Pubkey::new_unique()).collect();
+            let instructions = extend_instructions(&authority, &table, addresses);
+            assert_eq!(instructions.len(), expected, "{} addresses", count);
+        }
+    }
+
+    #[test]
+    fn test_extend_transactions_fit_packet_limit() {
+        let authority = Keypair::new();
+        let addresses: Vec<Pubkey> = (0..EXTEND_BATCH_SIZE)
+            .map(|_| Pubkey::new_unique())
+            .collect();
+        let instructions =
+            extend_instructions(&authority.pubkey(), &Pubkey::new_unique(), addresses);
+
+        let size = crate::batching::transaction_wire_size(&authority.pubkey(), &instructions[..1]);
+        assert!(size <= solana_sdk::packet::PACKET_DATA_SIZE);
+    }
+}
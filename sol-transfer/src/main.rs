@@ -1,34 +1,393 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use clap::{CommandFactory, Parser, Subcommand};
 use reqwest::Client;
-use serde::{Deserialize, Serialize};
-use serde_yaml;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
 use std::time::{Duration, Instant};
-use tokio;
+
+mod accounts;
+mod allowlist;
+mod amounts;
+mod anchor_idl;
+mod balance_audit;
+mod batching;
+mod chunking;
+mod cluster_nodes;
+mod config_migration;
+mod distribution;
+mod epochs;
+mod fees;
+mod history;
+mod hooks;
+mod keys;
+mod leader_schedule;
+mod lookup_tables;
+mod message_signing;
+mod mint_info;
+mod pacing;
+mod preflight;
+mod program_logs;
+mod progress;
+mod recipients;
+mod replay;
+mod report;
+mod resubmit;
+mod rpc_headers;
+mod rpc_logger;
+mod run_marker;
+mod simulation;
+mod spl;
+mod supply;
+mod swap;
+mod sweep;
+mod transfer_store;
+mod tui;
+mod tx_decode;
+
+use recipients::Recipient;
 
 // Solana SDK imports
 use solana_sdk::{
-    hash::Hash,
+    hash::{Hash, hash},
+    instruction::Instruction,
+    pubkey,
     pubkey::Pubkey,
-    signature::{Keypair, Signature, Signer},
+    signature::{Keypair, Signer},
     system_instruction,
     transaction::Transaction,
 };
 
+// SPL Memo program, used to make otherwise identical transactions unique
+const MEMO_PROGRAM_ID: Pubkey = pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Concurrent SOL transfers driven by config.yaml")]
+struct Cli {
+    /// Config file to read
+    #[arg(long, value_name = "PATH", default_value = "config.yaml")]
+    config: String,
+
+    /// Plan the run, estimate fees and list every transfer, but send nothing.
+    /// Refused for subcommands that send transactions.
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Log each transfer before it is sent and its outcome, as `log_transfers` does
+    #[arg(long)]
+    verbose: bool,
+
+    /// Write per-transfer results to this CSV file. Overrides `results_csv`.
+    #[arg(long, value_name = "PATH")]
+    output: Option<String>,
+
+    /// Print a shell completion script to stdout and exit
+    #[arg(long, value_name = "SHELL")]
+    completions: Option<clap_complete::Shell>,
+
+    /// Extra recipients: a CSV file (address, amount_sol, label) or `-` for addresses on stdin.
+    /// Overrides `recipients_file` from the config.
+    #[arg(long, value_name = "PATH")]
+    recipients: Option<String>,
+
+    /// Show a live status table instead of log output (ignored when not run in a terminal).
+    /// Press `q` to stop sending new transfers.
+    #[arg(long)]
+    tui: bool,
+
+    /// Run even if an identical run happened within `duplicate_window_hours`
+    #[arg(long)]
+    allow_duplicate_run: bool,
+
+    /// Write a documented example config to PATH and exit
+    #[arg(long, value_name = "PATH")]
+    generate_config: Option<String>,
+
+    /// Ask the configured RPC for the cluster's nodes and use whichever public RPC
+    /// answers fastest instead. `rpc_headers` are not sent to the chosen node.
+    #[arg(long)]
+    auto_rpc: bool,
+
+    /// Close each sender wallet's wrapped SOL account back into the wallet before
+    /// the transfers are planned
+    #[arg(long)]
+    unwrap_wsol: bool,
+
+    /// Log every RPC call with its method, outcome and latency to stderr. With
+    /// `--verbose` each request's id and params are logged too.
+    #[arg(long)]
+    log_rpc: bool,
+
+    /// Log diagnostic events at this level and above to stderr: error, warn, info,
+    /// debug or trace. `info` includes every RPC call, as `--log-rpc` does.
+    #[arg(long, value_name = "LEVEL")]
+    log_level: Option<tracing::Level>,
+
+    /// Record each sender's token balance before the batch and warn about any that
+    /// dropped by more than the batch sent (SPL token transfers only)
+    #[arg(long)]
+    balance_audit: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+// Standalone operations; without a subcommand the configured transfer batch runs
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Create a new account owned by a program
+    AccountCreate(accounts::AccountCreateArgs),
+    /// Swap SOL between two sender wallets in one all-or-nothing transaction, as in a
+    /// trustless peer-to-peer exchange
+    AtomicSwap(swap::AtomicSwapArgs),
+    /// Create associated token accounts for many wallets, skipping existing ones
+    AtaCreate(spl::AtaCreateArgs),
+    /// Send every sender wallet's balance, less fees, to one address
+    Drain(sweep::DrainArgs),
+    /// List an address's transaction signatures, newest first
+    History(history::HistoryArgs),
+    /// Write the recorded transfer history to a CSV file
+    ExportHistory(transfer_store::ExportHistoryArgs),
+    /// Create, extend, deactivate or close address lookup tables
+    LookupTable(lookup_tables::LookupTableArgs),
+    /// Show a token mint's supply, decimals, authorities and Metaplex metadata
+    MintInfo(mint_info::MintInfoArgs),
+    /// Find the next slot a validator is scheduled to lead
+    NextLeader(leader_schedule::NextLeaderArgs),
+    /// List every transfer recorded in `transfer_history_db`, oldest first
+    ShowHistory(transfer_store::ShowHistoryArgs),
+    /// Sign a message off-chain with a sender wallet
+    Sign(message_signing::SignArgs),
+    /// Poll the cluster's supply and log when circulating supply moves past a threshold
+    SupplyMonitor(supply::SupplyMonitorArgs),
+    /// Send a wallet's whole balance to another address, optionally closing the wallet
+    SweepClose(sweep::SweepCloseArgs),
+    /// Fetch a transaction and print its decoded instructions
+    TxDecode(tx_decode::TxDecodeArgs),
+    /// Check an off-chain message signature
+    Verify(message_signing::VerifyArgs),
+}
+
+impl Command {
+    // Subcommands that send transactions, which `--dry-run` can't preview
+    fn sends_transactions(&self) -> bool {
+        matches!(
+            self,
+            Self::AccountCreate(_)
+                | Self::AtomicSwap(_)
+                | Self::AtaCreate(_)
+                | Self::Drain(_)
+                | Self::LookupTable(_)
+                | Self::SweepClose(_)
+        )
+    }
+}
+
 // Configuration structures
 #[derive(Debug, Deserialize)]
 struct Config {
-    solana_rpc_url: String,
+    // Layout version the file was written for; older layouts are migrated on load
+    #[serde(default = "default_config_version")]
+    config_version: u32,
+    rpc: RpcConfig,
+    // Extra headers for every RPC request; values may use `${ENV_VAR}`
+    #[serde(default)]
+    rpc_headers: HashMap<String, String>,
     sender_wallets: Vec<SenderWallet>,
+    #[serde(default)]
     recipient_addresses: Vec<String>,
-    amount_sol: f64,
+    // CSV with columns: address, optional amount_sol, optional label
+    recipients_file: Option<String>,
+    // Split a total pro rata across weighted recipients, paid by the single sender wallet
+    distribution: Option<distribution::DistributionConfig>,
+    // Approved recipient addresses; anyone else aborts the run (or is skipped)
+    recipient_allowlist: Option<String>,
+    #[serde(default)]
+    allowlist_policy: allowlist::AllowlistPolicy,
+    // Default SOL per transfer; senders and groups can override it
+    amount_sol: Option<f64>,
+    // Amount per transfer for each sender group, by group name
+    #[serde(default)]
+    groups: HashMap<String, f64>,
+    // Send this SPL token instead of SOL; amounts are then in whole tokens
+    spl_token: Option<spl::SplTokenConfig>,
+    #[serde(default)]
+    confirmation_level: ConfirmationLevel,
+    // Overrides the per-level default from `ConfirmationLevel::default_timeout`
+    confirmation_timeout_secs: Option<u64>,
+    // Purpose tags keyed by "sender->recipient" or by sender address alone
+    #[serde(default)]
+    labels: HashMap<String, String>,
+    // Optional per-transfer CSV export and JSON run summary
+    results_csv: Option<String>,
+    summary_json: Option<String>,
+    // sled database every submitted transfer is recorded in, for auditing
+    transfer_history_db: Option<String>,
+    // Print every transfer before it is sent and its outcome after (the built-in LoggingHook)
+    #[serde(default)]
+    log_transfers: bool,
+    // Submit at most this many transactions per observed block
+    pace_per_block: Option<usize>,
+    // Pack each sender's transfers into as few transactions as fit the size limit
+    #[serde(default)]
+    batch_recipients: bool,
+    // Leave out recipients whose account holds no lamports yet (e.g. uninitialized PDAs)
+    #[serde(default)]
+    skip_nonexistent_recipients: bool,
+    // Send without RPC preflight simulation
+    #[serde(default)]
+    skip_preflight: bool,
+    // Simulate the whole batch client-side first and drop transactions that would fail
+    #[serde(default)]
+    pre_send_simulation: bool,
+    // Request only the compute units each transaction needs, measured by simulation
+    #[serde(default)]
+    estimate_compute_units: bool,
+    // Send this many transfers at a time, waiting for each chunk to confirm
+    chunk_size: Option<usize>,
+    // Pause (or stop, when not interactive) after a chunk with more failures than this
+    chunk_failure_threshold_percent: Option<f64>,
+    // Results so far are written here after every chunk
+    checkpoint_file: Option<String>,
+    // Runs with more transfers than this are sent in sequential sub-batches
+    max_concurrent_transfers: Option<usize>,
+    // Transfers per sub-batch (default: `max_concurrent_transfers`)
+    batch_size: Option<usize>,
+    // Pause between sub-batches
+    #[serde(default)]
+    batch_delay_ms: u64,
+    // Name for this run, shown when a duplicate run is refused
+    run_label: Option<String>,
+    // Tag transfers with the run label and skip recipients an earlier tagged run already paid
+    #[serde(default)]
+    verify_on_chain_dedup: bool,
+    // How many recent signatures of each recipient that check looks through
+    on_chain_dedup_lookback: Option<usize>,
+    // Where the last run's fingerprint and outcome are recorded
+    run_marker_path: Option<String>,
+    // Refuse to repeat an identical run started less than this many hours ago
+    duplicate_window_hours: Option<u64>,
+    // Defaults for the `account-create` subcommand
+    account_create: Option<accounts::AccountCreateConfig>,
+}
+
+// Files from before `config_version` existed use the first layout
+fn default_config_version() -> u32 {
+    1
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcConfig {
+    url: String,
+}
+
+// Example config with every field documented; kept parseable by a test
+const CONFIG_TEMPLATE: &str = include_str!("config_template.yaml");
+
+impl Config {
+    // Write the documented example config, refusing to overwrite an existing file
+    fn generate_template(path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if std::path::Path::new(path).exists() {
+            return Err(format!("{} already exists", path).into());
+        }
+        fs::write(path, CONFIG_TEMPLATE)?;
+        Ok(())
+    }
+
+    // A "sender->recipient" entry wins over the recipient's own label, then a sender-wide one
+    fn label_for(&self, sender: &str, recipient: &Recipient) -> Option<String> {
+        self.labels
+            .get(&format!("{}->{}", sender, recipient.address))
+            .or(recipient.label.as_ref())
+            .or_else(|| self.labels.get(sender))
+            .cloned()
+    }
+
+    // Chunk and sub-batch sizes split the run with `take`, so a zero would drop transfers
+    fn check_batch_sizes(&self) -> Result<(), String> {
+        for (field, size) in [
+            ("chunk_size", self.chunk_size),
+            ("max_concurrent_transfers", self.max_concurrent_transfers),
+            ("batch_size", self.batch_size),
+        ] {
+            if size == Some(0) {
+                return Err(format!("{} must be at least 1", field));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Commitment a transfer must reach before the polling loop considers it done
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+enum ConfirmationLevel {
+    Processed,
+    #[default]
+    Confirmed,
+    Finalized,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+impl ConfirmationLevel {
+    // Parse the `confirmationStatus` string reported by the RPC
+    fn from_status(status: &str) -> Option<Self> {
+        match status {
+            "processed" => Some(Self::Processed),
+            "confirmed" => Some(Self::Confirmed),
+            "finalized" => Some(Self::Finalized),
+            _ => None,
+        }
+    }
+
+    // Processed/confirmed either land within the blockhash validity window (~150 blocks)
+    // or not at all. Finalization can lag well behind that, so it gets a longer wait.
+    fn default_timeout(self) -> Duration {
+        match self {
+            Self::Processed | Self::Confirmed => Duration::from_secs(90),
+            Self::Finalized => Duration::from_secs(180),
+        }
+    }
+}
+
+impl std::fmt::Display for ConfirmationLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Processed => "processed",
+            Self::Confirmed => "confirmed",
+            Self::Finalized => "finalized",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Deserialize, Clone)]
 struct SenderWallet {
     address: String,
+    #[serde(default)]
     private_key: String, // Base58 encoded private key
+    // env:<VAR>, file:<path>, aws-sm:<secret-name> or gcp-sm:<resource>; replaces private_key
+    private_key_source: Option<String>,
+    // Overrides the group and global amount for this sender's transfers
+    amount_sol: Option<f64>,
+    // Name of an entry in `groups`
+    group: Option<String>,
+}
+
+// Keys must never end up in logs, so Debug only shows where they come from
+impl std::fmt::Debug for SenderWallet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SenderWallet")
+            .field("address", &self.address)
+            .field("private_key", &"<redacted>")
+            .field("private_key_source", &self.private_key_source)
+            .field("amount_sol", &self.amount_sol)
+            .field("group", &self.group)
+            .finish()
+    }
 }
 
 // JSON RPC structures
@@ -42,8 +401,6 @@ struct JsonRpcRequest {
 
 #[derive(Debug, Deserialize)]
 struct JsonRpcResponse<T> {
-    jsonrpc: String,
-    id: u64,
     result: Option<T>,
     error: Option<JsonRpcError>,
 }
@@ -52,6 +409,8 @@ struct JsonRpcResponse<T> {
 struct JsonRpcError {
     code: i32,
     message: String,
+    // Extra detail; for failed preflights this carries the simulation `err` and `logs`
+    data: Option<serde_json::Value>,
 }
 
 // Blockhash result structure
@@ -63,17 +422,15 @@ struct BlockhashResult {
 #[derive(Debug, Deserialize)]
 struct BlockhashValue {
     blockhash: String,
-    #[serde(rename = "lastValidBlockHeight")]
-    last_valid_block_height: u64,
 }
 
 // Transaction status structures
 #[derive(Debug, Deserialize)]
 struct SignatureStatusResult {
-    value: Option<SignatureStatus>,
+    value: Vec<Option<SignatureStatus>>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct SignatureStatus {
     slot: u64,
     confirmations: Option<u64>,
@@ -82,27 +439,350 @@ struct SignatureStatus {
     confirmation_status: Option<String>,
 }
 
-#[derive(Debug)]
+impl SignatureStatus {
+    fn level(&self) -> Option<ConfirmationLevel> {
+        self.confirmation_status
+            .as_deref()
+            .and_then(ConfirmationLevel::from_status)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 struct TransferResult {
     from_address: String,
     to_address: String,
+    label: Option<String>,
     signature: String,
     status: Option<SignatureStatus>,
     processing_time: Duration,
+    // Highest commitment observed and how long after sending it was first seen
+    reached_level: Option<ConfirmationLevel>,
+    confirmation_time: Option<Duration>,
     error: Option<String>,
+    // Program logs from a pre-send simulation that stopped this transfer
+    simulation_logs: Vec<String>,
+    // The transaction also created the recipient's associated token account
+    created_token_account: bool,
+    // Not sent: an earlier run already paid this recipient (see `signature`)
+    already_paid: bool,
+    // Unix timestamp of the block the transfer landed in
+    block_time: Option<i64>,
+}
+
+impl PreparedTransfer {
+    // One result per recipient, all sharing the transaction's outcome
+    fn into_results(
+        self,
+        sent: Result<(String, ConfirmationOutcome), String>,
+    ) -> Vec<TransferResult> {
+        let from_address = self.from_address;
+        let start_time = self.start_time;
+
+        self.legs
+            .into_iter()
+            .map(|leg| match &sent {
+                Ok((signature, outcome)) => TransferResult {
+                    from_address: from_address.clone(),
+                    to_address: leg.to_address,
+                    label: leg.label,
+                    signature: signature.clone(),
+                    status: outcome.status.clone(),
+                    processing_time: start_time.elapsed(),
+                    reached_level: outcome.reached_level,
+                    confirmation_time: outcome.confirmation_time,
+                    error: None,
+                    simulation_logs: Vec::new(),
+                    created_token_account: leg.create_token_account,
+                    already_paid: false,
+                    block_time: None,
+                },
+                Err(e) => TransferResult {
+                    created_token_account: leg.create_token_account,
+                    ..TransferResult::failed(
+                        from_address.clone(),
+                        leg.to_address,
+                        leg.label,
+                        e.clone(),
+                        start_time,
+                    )
+                },
+            })
+            .collect()
+    }
+}
+
+impl TransferResult {
+    fn failed(
+        from_address: String,
+        to_address: String,
+        label: Option<String>,
+        error: String,
+        start_time: Instant,
+    ) -> Self {
+        Self {
+            from_address,
+            to_address,
+            label,
+            signature: String::new(),
+            status: None,
+            processing_time: start_time.elapsed(),
+            reached_level: None,
+            confirmation_time: None,
+            error: Some(error),
+            simulation_logs: Vec::new(),
+            created_token_account: false,
+            already_paid: false,
+            block_time: None,
+        }
+    }
+}
+
+// "YYYY-MM-DD HH:MM:SS UTC" for a Unix timestamp
+fn format_utc(timestamp: i64) -> String {
+    let days = timestamp.div_euclid(86_400);
+    let seconds = timestamp.rem_euclid(86_400);
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        seconds / 3_600,
+        seconds % 3_600 / 60,
+        seconds % 60
+    )
+}
+
+// A single sender -> recipient transfer, before any transaction is built
+#[derive(Debug, Clone)]
+struct PlannedTransfer {
+    sender: SenderWallet,
+    recipient: String,
+    lamports: u64,
+    label: Option<String>,
+    // SPL sends only: create the recipient's associated token account first
+    create_token_account: bool,
+}
+
+// A signed transaction (or the reason it couldn't be built) and the recipients it credits.
+// Without recipient batching there is exactly one leg per transaction.
+struct PreparedTransfer {
+    from_address: String,
+    legs: Vec<TransferLeg>,
+    transaction: Result<Transaction, String>,
+    // Keeps a stale transaction re-signable; absent when it couldn't be built
+    signer: Option<Arc<Keypair>>,
+    start_time: Instant,
+}
+
+// One recipient credited by a prepared transaction
+struct TransferLeg {
+    to_address: String,
+    label: Option<String>,
+    create_token_account: bool,
+}
+
+impl TransferLeg {
+    fn from_planned(transfer: &PlannedTransfer) -> Self {
+        Self {
+            to_address: transfer.recipient.clone(),
+            label: transfer.label.clone(),
+            create_token_account: transfer.create_token_account,
+        }
+    }
+}
+
+// Largest single airdrop the devnet faucet will grant
+#[cfg(any(test, feature = "devnet-utils"))]
+const MAX_AIRDROP_LAMPORTS: u64 = 2_000_000_000;
+
+#[cfg(any(test, feature = "devnet-utils"))]
+#[derive(Debug)]
+pub struct AirdropResult {
+    wallet: Pubkey,
+    lamports: u64,
+    signatures: Vec<String>,
+    error: Option<String>,
+}
+
+// Result of polling a sent transaction until it reaches the target commitment
+#[derive(Clone)]
+struct ConfirmationOutcome {
+    status: Option<SignatureStatus>,
+    reached_level: Option<ConfirmationLevel>,
+    confirmation_time: Option<Duration>,
 }
 
 pub struct SolTransfer {
-    client: Client,
+    rpc_logger: rpc_logger::RpcLogger,
     rpc_url: String,
+    confirmation_level: ConfirmationLevel,
+    confirmation_timeout: Duration,
+    pacer: Option<pacing::BlockPacer>,
+    batch_recipients: bool,
+    skip_nonexistent_recipients: bool,
+    skip_preflight: bool,
+    pre_send_simulation: bool,
+    estimate_compute_units: bool,
+    spl_token: Option<spl::SplMint>,
+    run_memo: Option<String>,
+    events: Option<progress::EventSender>,
+    cancelled: Arc<AtomicBool>,
+    transfer_store: Option<transfer_store::TransferStore>,
+    hooks: Vec<Box<dyn hooks::TransferHook>>,
 }
 
 impl SolTransfer {
     pub fn new(rpc_url: String) -> Self {
+        let confirmation_level = ConfirmationLevel::default();
         Self {
-            client: Client::new(),
+            rpc_logger: rpc_logger::RpcLogger::new(Client::new()),
             rpc_url,
+            confirmation_level,
+            confirmation_timeout: confirmation_level.default_timeout(),
+            pacer: None,
+            batch_recipients: false,
+            skip_nonexistent_recipients: false,
+            skip_preflight: false,
+            pre_send_simulation: false,
+            estimate_compute_units: false,
+            spl_token: None,
+            run_memo: None,
+            events: None,
+            cancelled: Arc::new(AtomicBool::new(false)),
+            transfer_store: None,
+            hooks: Vec::new(),
+        }
+    }
+
+    // Pack each sender's transfers into multi-recipient transactions
+    pub fn with_recipient_batching(mut self, enabled: bool) -> Self {
+        self.batch_recipients = enabled;
+        self
+    }
+
+    // Check recipients on chain first and skip those without an account
+    pub fn with_skip_nonexistent_recipients(mut self, enabled: bool) -> Self {
+        self.skip_nonexistent_recipients = enabled;
+        self
+    }
+
+    // Send without RPC preflight; pair with `with_pre_send_simulation` to keep a safety net
+    pub fn with_skip_preflight(mut self, enabled: bool) -> Self {
+        self.skip_preflight = enabled;
+        self
+    }
+
+    // Simulate every transaction before sending and drop the ones that would fail
+    pub fn with_pre_send_simulation(mut self, enabled: bool) -> Self {
+        self.pre_send_simulation = enabled;
+        self
+    }
+
+    // Prefix transactions with a `SetComputeUnitLimit` sized from their simulation
+    pub fn with_compute_unit_estimation(mut self, enabled: bool) -> Self {
+        self.estimate_compute_units = enabled;
+        self
+    }
+
+    // Record every submitted transfer in a local database
+    pub(crate) fn with_transfer_store(
+        mut self,
+        store: Option<transfer_store::TransferStore>,
+    ) -> Self {
+        self.transfer_store = store;
+        self
+    }
+
+    // Run `hook` around every transfer, after any hooks added before it
+    pub(crate) fn with_hook(mut self, hook: Box<dyn hooks::TransferHook>) -> Self {
+        self.hooks.push(hook);
+        self
+    }
+
+    // Move this SPL token instead of SOL
+    pub(crate) fn with_spl_token(mut self, mint: Option<spl::SplMint>) -> Self {
+        self.spl_token = mint;
+        self
+    }
+
+    // Add this memo to every transfer transaction
+    pub(crate) fn with_run_memo(mut self, memo: Option<String>) -> Self {
+        self.run_memo = memo;
+        self
+    }
+
+    // Limit submissions to `per_block` transactions per observed block
+    pub fn with_pace_per_block(mut self, per_block: usize) -> Self {
+        self.pacer = Some(pacing::BlockPacer::new(per_block));
+        self
+    }
+
+    // Issue a JSON RPC request and unwrap its result
+    async fn rpc_call<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Vec<serde_json::Value>,
+    ) -> Result<T, Box<dyn std::error::Error>> {
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: 1,
+            method: method.to_string(),
+            params,
+        };
+
+        let json_response: JsonRpcResponse<T> =
+            self.rpc_logger.post(&self.rpc_url, &request).await?;
+
+        if let Some(error) = json_response.error {
+            return Err(format!("RPC Error: {} - {}", error.code, error.message).into());
         }
+
+        json_response
+            .result
+            .ok_or_else(|| "No result in response".into())
+    }
+
+    // Unix timestamp the cluster recorded for the block at `slot`
+    async fn get_block_time(&self, slot: u64) -> Result<Option<i64>, Box<dyn std::error::Error>> {
+        self.rpc_call("getBlockTime", vec![serde_json::json!(slot)])
+            .await
+    }
+
+    // Current block height at processed commitment, so new blocks are seen as early as possible
+    async fn get_block_height(&self) -> Result<u64, Box<dyn std::error::Error>> {
+        self.rpc_call(
+            "getBlockHeight",
+            vec![serde_json::json!({ "commitment": "processed" })],
+        )
+        .await
+    }
+
+    // Set the commitment transfers must reach and how long to wait for it
+    pub(crate) fn with_confirmation_target(
+        mut self,
+        level: ConfirmationLevel,
+        timeout: Option<Duration>,
+    ) -> Self {
+        self.confirmation_level = level;
+        self.confirmation_timeout = timeout.unwrap_or_else(|| level.default_timeout());
+        self
     }
 
     // Convert SOL to lamports
@@ -121,15 +801,8 @@ impl SolTransfer {
             })],
         };
 
-        let response = self
-            .client
-            .post(&self.rpc_url)
-            .header("Content-Type", "application/json")
-            .json(&request)
-            .send()
-            .await?;
-
-        let json_response: JsonRpcResponse<BlockhashResult> = response.json().await?;
+        let json_response: JsonRpcResponse<BlockhashResult> =
+            self.rpc_logger.post(&self.rpc_url, &request).await?;
 
         if let Some(error) = json_response.error {
             return Err(format!("RPC Error: {} - {}", error.code, error.message).into());
@@ -145,34 +818,144 @@ impl SolTransfer {
         }
     }
 
-    // Create a real transfer transaction
-    fn create_transfer_transaction(
+    // Create a transfer transaction whose message differs from every one in `seen`.
+    // Identical messages (same sender, recipient, amount and blockhash) would produce
+    // identical signatures and be rejected as `AlreadyProcessed`, so duplicates get a memo.
+    fn create_unique_transfer_transaction(
         &self,
         sender_keypair: &Keypair,
         recipient_pubkey: &Pubkey,
-        lamports: u64,
+        transfer: &PlannedTransfer,
         recent_blockhash: Hash,
+        seen: &mut HashSet<Hash>,
     ) -> Result<Transaction, Box<dyn std::error::Error>> {
-        let instruction =
-            system_instruction::transfer(&sender_keypair.pubkey(), recipient_pubkey, lamports);
-
-        let transaction = Transaction::new_signed_with_payer(
-            &[instruction],
-            Some(&sender_keypair.pubkey()),
-            &[sender_keypair],
-            recent_blockhash,
-        );
+        let instructions =
+            self.transfer_instructions(&sender_keypair.pubkey(), recipient_pubkey, transfer);
+
+        Ok(self.sign_unique_transaction(instructions, sender_keypair, recent_blockhash, seen)?)
+    }
+
+    // A SOL transfer, or an SPL transfer (plus account creation) when a token is configured
+    fn transfer_instructions(
+        &self,
+        sender: &Pubkey,
+        recipient: &Pubkey,
+        transfer: &PlannedTransfer,
+    ) -> Vec<Instruction> {
+        match &self.spl_token {
+            Some(mint) => mint.transfer_instructions(
+                sender,
+                recipient,
+                mint.base_units(transfer.lamports),
+                transfer.create_token_account,
+            ),
+            None => vec![system_instruction::transfer(
+                sender,
+                recipient,
+                transfer.lamports,
+            )],
+        }
+    }
+
+    // Every account the transaction's message needs a signature from, fee payer first
+    pub(crate) fn get_required_signers(transaction: &Transaction) -> Vec<Pubkey> {
+        let required = usize::from(transaction.message.header.num_required_signatures);
+        transaction
+            .message
+            .account_keys
+            .iter()
+            .take(required)
+            .copied()
+            .collect()
+    }
+
+    // Fail, naming them, when some required signers have no keypair among `available_keypairs`
+    pub(crate) fn verify_all_signers_available(
+        transaction: &Transaction,
+        available_keypairs: &[&Keypair],
+    ) -> Result<(), String> {
+        let missing: Vec<String> = Self::get_required_signers(transaction)
+            .into_iter()
+            .filter(|signer| {
+                !available_keypairs
+                    .iter()
+                    .any(|keypair| keypair.pubkey() == *signer)
+            })
+            .map(|signer| signer.to_string())
+            .collect();
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("Missing signer(s): {}", missing.join(", ")))
+        }
+    }
+
+    // Sign `instructions` for `payer`, appending a dedup memo until the message is unseen.
+    // Fails before signing when an instruction needs a signature `payer` can't give.
+    fn sign_unique_transaction(
+        &self,
+        mut instructions: Vec<Instruction>,
+        payer: &Keypair,
+        recent_blockhash: Hash,
+        seen: &mut HashSet<Hash>,
+    ) -> Result<Transaction, String> {
+        if let Some(memo) = &self.run_memo {
+            instructions.push(memo_instruction(memo));
+        }
+        // Memos take no accounts, so the dedup ones below add no signers
+        Self::verify_all_signers_available(
+            &Transaction::new_with_payer(&instructions, Some(&payer.pubkey())),
+            &[payer],
+        )?;
+        let base_len = instructions.len();
+        let mut nonce = 0u32;
+
+        loop {
+            let transaction = Transaction::new_signed_with_payer(
+                &instructions,
+                Some(&payer.pubkey()),
+                &[payer],
+                recent_blockhash,
+            );
+            if seen.insert(hash(&transaction.message_data())) {
+                return Ok(transaction);
+            }
 
-        Ok(transaction)
+            nonce += 1;
+            instructions.truncate(base_len);
+            instructions.push(memo_instruction(&format!("dedup:{}", nonce)));
+        }
     }
 
-    // Send a transaction
-    async fn send_transaction(
+    // Send a transaction, explaining preflight failures by instruction and program error
+    // instead of the raw simulation error
+    async fn send_transaction_with_preflight_error_parsing(
         &self,
         transaction: &Transaction,
     ) -> Result<String, Box<dyn std::error::Error>> {
+        let json_response = self.post_transaction(transaction).await?;
+
+        if let Some(error) = json_response.error {
+            return Err(match preflight::parse_preflight_error(&error) {
+                Some(parsed) => format!("Preflight failed: {} (RPC Error: {})", parsed, error.code),
+                None => format!("RPC Error: {} - {}", error.code, error.message),
+            }
+            .into());
+        }
+
+        match json_response.result {
+            Some(signature) => Ok(signature),
+            None => Err("No signature in response".into()),
+        }
+    }
+
+    // Submit a signed transaction and return the raw `sendTransaction` response
+    async fn post_transaction(
+        &self,
+        transaction: &Transaction,
+    ) -> Result<JsonRpcResponse<String>, Box<dyn std::error::Error>> {
         let serialized_transaction = bincode::serialize(transaction)?;
-        let encoded_transaction = base64::encode(serialized_transaction);
+        let encoded_transaction = STANDARD.encode(serialized_transaction);
 
         let request = JsonRpcRequest {
             jsonrpc: "2.0".to_string(),
@@ -183,68 +966,192 @@ impl SolTransfer {
                 serde_json::json!({
                     "encoding": "base64",
                     "preflightCommitment": "confirmed",
-                    "skipPreflight": false
+                    "skipPreflight": self.skip_preflight
                 }),
             ],
         };
 
-        let response = self
-            .client
-            .post(&self.rpc_url)
-            .header("Content-Type", "application/json")
-            .json(&request)
-            .send()
-            .await?;
+        self.rpc_logger.post(&self.rpc_url, &request).await
+    }
 
-        let json_response: JsonRpcResponse<String> = response.json().await?;
+    // Check transaction status. Without `search_history` only recently processed
+    // signatures are found, which is much cheaper for the node.
+    async fn get_signature_status(
+        &self,
+        signature: &str,
+        search_history: bool,
+    ) -> Result<Option<SignatureStatus>, Box<dyn std::error::Error>> {
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: 1,
+            method: "getSignatureStatuses".to_string(),
+            params: vec![
+                serde_json::json!([signature]),
+                serde_json::json!({
+                    "searchTransactionHistory": search_history
+                }),
+            ],
+        };
+
+        let json_response: JsonRpcResponse<SignatureStatusResult> =
+            self.rpc_logger.post(&self.rpc_url, &request).await?;
 
         if let Some(error) = json_response.error {
             return Err(format!("RPC Error: {} - {}", error.code, error.message).into());
         }
 
         match json_response.result {
-            Some(signature) => Ok(signature),
-            None => Err("No signature in response".into()),
+            Some(result) => Ok(result.value.into_iter().next().flatten()),
+            None => Err("No result in response".into()),
         }
     }
 
-    // Check transaction status
-    async fn get_signature_status(
+    // Send one transaction and wait for the configured commitment.
+    // Returns the signature and the level it reached.
+    pub(crate) async fn send_and_confirm(
         &self,
-        signature: &str,
-    ) -> Result<Option<SignatureStatus>, Box<dyn std::error::Error>> {
+        transaction: &Transaction,
+    ) -> Result<(String, ConfirmationLevel), Box<dyn std::error::Error>> {
+        let signature = self
+            .send_transaction_with_preflight_error_parsing(transaction)
+            .await
+            .map_err(|e| format!("Failed to send transaction: {}", e))?;
+
+        let outcome = self.wait_for_confirmation(&signature).await;
+        match (&outcome.status, outcome.reached_level) {
+            (Some(status), _) if status.err.is_some() => {
+                Err(format!("Transaction {} failed: {:?}", signature, status.err).into())
+            }
+            (Some(_), Some(level)) if level >= self.confirmation_level => Ok((signature, level)),
+            _ => Err(format!(
+                "Transaction {} did not reach {} in time",
+                signature, self.confirmation_level
+            )
+            .into()),
+        }
+    }
+
+    // Poll the signature status until it reaches the configured level, fails, or times out
+    async fn wait_for_confirmation(&self, signature: &str) -> ConfirmationOutcome {
+        let start_time = Instant::now();
+        let mut outcome = ConfirmationOutcome {
+            status: None,
+            reached_level: None,
+            confirmation_time: None,
+        };
+
+        loop {
+            match self.get_signature_status(signature, true).await {
+                Ok(Some(status)) => {
+                    let level = status.level();
+                    if level > outcome.reached_level {
+                        outcome.reached_level = level;
+                        outcome.confirmation_time = Some(start_time.elapsed());
+                    }
+
+                    let failed = status.err.is_some();
+                    outcome.status = Some(status);
+                    if failed || outcome.reached_level >= Some(self.confirmation_level) {
+                        return outcome;
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    self.notice(format!(
+                        "⚠️  Warning: Failed to get status for {}: {}",
+                        signature, e
+                    ));
+                }
+            }
+
+            if start_time.elapsed() >= self.confirmation_timeout {
+                return outcome;
+            }
+
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+    }
+
+    // Request a single airdrop from the cluster faucet
+    #[cfg(any(test, feature = "devnet-utils"))]
+    async fn request_airdrop(
+        &self,
+        wallet: &Pubkey,
+        lamports: u64,
+    ) -> Result<String, Box<dyn std::error::Error>> {
         let request = JsonRpcRequest {
             jsonrpc: "2.0".to_string(),
             id: 1,
-            method: "getSignatureStatus".to_string(),
+            method: "requestAirdrop".to_string(),
             params: vec![
-                serde_json::Value::String(signature.to_string()),
+                serde_json::Value::String(wallet.to_string()),
+                serde_json::json!(lamports),
                 serde_json::json!({
-                    "searchTransactionHistory": true
+                    "commitment": "confirmed"
                 }),
             ],
         };
 
-        let response = self
-            .client
-            .post(&self.rpc_url)
-            .header("Content-Type", "application/json")
-            .json(&request)
-            .send()
-            .await?;
-
-        let json_response: JsonRpcResponse<SignatureStatusResult> = response.json().await?;
+        let json_response: JsonRpcResponse<String> =
+            self.rpc_logger.post(&self.rpc_url, &request).await?;
 
         if let Some(error) = json_response.error {
             return Err(format!("RPC Error: {} - {}", error.code, error.message).into());
         }
 
         match json_response.result {
-            Some(result) => Ok(result.value),
-            None => Err("No result in response".into()),
+            Some(signature) => Ok(signature),
+            None => Err("No signature in response".into()),
         }
     }
 
+    // Fund test wallets concurrently. Amounts above the faucet limit are split into
+    // several airdrops, each of which must confirm before the next is requested.
+    // Only compiled for tests and the `devnet-utils` feature so mainnet builds can't call it.
+    #[cfg(any(test, feature = "devnet-utils"))]
+    pub async fn request_airdrops(
+        &self,
+        wallets: Vec<Pubkey>,
+        lamports: u64,
+    ) -> Vec<AirdropResult> {
+        let tasks = wallets.into_iter().map(|wallet| async move {
+            let mut result = AirdropResult {
+                wallet,
+                lamports,
+                signatures: Vec::new(),
+                error: None,
+            };
+
+            let mut remaining = lamports;
+            while remaining > 0 {
+                let chunk = remaining.min(MAX_AIRDROP_LAMPORTS);
+
+                let signature = match self.request_airdrop(&wallet, chunk).await {
+                    Ok(sig) => sig,
+                    Err(e) => {
+                        result.error = Some(format!("Airdrop request failed: {}", e));
+                        break;
+                    }
+                };
+
+                let outcome = self.wait_for_confirmation(&signature).await;
+                let failed = outcome.status.as_ref().is_some_and(|s| s.err.is_some());
+                if failed || outcome.reached_level < Some(self.confirmation_level) {
+                    result.error = Some(format!("Airdrop {} did not confirm", signature));
+                    result.signatures.push(signature);
+                    break;
+                }
+
+                result.signatures.push(signature);
+                remaining -= chunk;
+            }
+
+            result
+        });
+
+        futures::future::join_all(tasks).await
+    }
+
     // Parse private key from base58
     fn parse_keypair(private_key_base58: &str) -> Result<Keypair, Box<dyn std::error::Error>> {
         let private_key_bytes = bs58::decode(private_key_base58).into_vec()?;
@@ -258,146 +1165,395 @@ impl SolTransfer {
         Ok(Keypair::from_bytes(&private_key_bytes)?)
     }
 
-    // Execute all transfers concurrently
-    pub async fn execute_transfers(
+    // Parse keys and build a signed transaction for every planned transfer
+    fn prepare_transfers(
         &self,
-        sender_wallets: Vec<SenderWallet>,
-        recipients: Vec<String>,
-        amount_lamports: u64,
+        planned: Vec<PlannedTransfer>,
+        blockhash: Hash,
+    ) -> Vec<PreparedTransfer> {
+        if self.batch_recipients {
+            return self.prepare_batched_transfers(planned, blockhash);
+        }
+
+        let mut seen_messages = HashSet::new();
+        let mut prepared = Vec::with_capacity(planned.len());
+
+        for transfer in planned {
+            let start_time = Instant::now();
+
+            let signed = Self::parse_keypair(&transfer.sender.private_key)
+                .map_err(|e| format!("Failed to parse keypair: {}", e))
+                .and_then(|sender_keypair| {
+                    let recipient_pubkey = Pubkey::from_str(&transfer.recipient)
+                        .map_err(|e| format!("Invalid recipient address: {}", e))?;
+
+                    let transaction = self
+                        .create_unique_transfer_transaction(
+                            &sender_keypair,
+                            &recipient_pubkey,
+                            &transfer,
+                            blockhash,
+                            &mut seen_messages,
+                        )
+                        .map_err(|e| format!("Failed to create transaction: {}", e))?;
+                    Ok((transaction, Arc::new(sender_keypair)))
+                });
+            let (transaction, signer) = match signed {
+                Ok((transaction, signer)) => (Ok(transaction), Some(signer)),
+                Err(e) => (Err(e), None),
+            };
+
+            prepared.push(PreparedTransfer {
+                legs: vec![TransferLeg::from_planned(&transfer)],
+                from_address: transfer.sender.address,
+                transaction,
+                signer,
+                start_time,
+            });
+        }
+
+        prepared
+    }
+
+    // Check every built transaction carries all its signatures and that they're valid,
+    // failing the ones that don't before anything is sent. Returns how many failed.
+    fn sign_and_verify_all(prepared: &mut [PreparedTransfer]) -> usize {
+        let mut failed = 0;
+        for transfer in prepared.iter_mut() {
+            let Ok(transaction) = &transfer.transaction else {
+                continue;
+            };
+            if transaction.verify().is_err() {
+                transfer.transaction = Err("Signature verification failed".to_string());
+                failed += 1;
+            }
+        }
+        failed
+    }
+
+    // Execute all planned transfers concurrently
+    pub(crate) async fn execute_transfers(
+        &self,
+        planned: Vec<PlannedTransfer>,
+    ) -> Vec<TransferResult> {
+        let results = self.dispatch(planned, 0).await;
+        self.emit(progress::TransferEvent::Finished);
+        results
+    }
+
+    // Send planned transfers concurrently and wait for their confirmations.
+    // Progress events are numbered from `first_index` so chunks share one table.
+    pub(crate) async fn dispatch(
+        &self,
+        planned: Vec<PlannedTransfer>,
+        first_index: usize,
     ) -> Vec<TransferResult> {
+        let (planned, unverified) = if self.skip_nonexistent_recipients {
+            self.drop_nonexistent_recipients(planned).await
+        } else {
+            (planned, Vec::new())
+        };
+        let (planned, mut rejected) = self.run_pre_transfer_hooks(planned).await;
+        rejected.extend(unverified);
+
         // Get recent blockhash
         let blockhash = match self.get_recent_blockhash().await {
             Ok(hash) => hash,
             Err(e) => {
-                println!("❌ Failed to get blockhash: {}", e);
-                return vec![];
+                self.notice(format!("❌ Failed to get blockhash: {}", e));
+                let error = format!("Failed to get blockhash: {}", e);
+                let start_time = Instant::now();
+                let mut results: Vec<TransferResult> = planned
+                    .into_iter()
+                    .map(|transfer| {
+                        TransferResult::failed(
+                            transfer.sender.address,
+                            transfer.recipient,
+                            transfer.label,
+                            error.clone(),
+                            start_time,
+                        )
+                    })
+                    .collect();
+                for result in results.iter().chain(&rejected) {
+                    self.run_post_transfer_hooks(result).await;
+                }
+                results.extend(rejected);
+                return results;
             }
         };
 
-        println!("✅ Using blockhash: {}", blockhash);
-        println!(
-            "🚀 Starting {} transfers...\n",
-            sender_wallets.len() * recipients.len()
-        );
-
-        let mut tasks = Vec::new();
-
-        // Create transfer tasks for each sender-recipient pair
-        for sender in &sender_wallets {
-            for recipient in &recipients {
-                let sender_clone = sender.clone();
-                let recipient_clone = recipient.clone();
-                let blockhash_clone = blockhash;
-                let transfer_client = &self;
-
-                let task = async move {
-                    let start_time = Instant::now();
-
-                    // Parse sender keypair
-                    let sender_keypair = match Self::parse_keypair(&sender_clone.private_key) {
-                        Ok(keypair) => keypair,
-                        Err(e) => {
-                            let processing_time = start_time.elapsed();
-                            return TransferResult {
-                                from_address: sender_clone.address,
-                                to_address: recipient_clone,
-                                signature: String::new(),
-                                status: None,
-                                processing_time,
-                                error: Some(format!("Failed to parse keypair: {}", e)),
-                            };
-                        }
-                    };
-
-                    // Parse recipient pubkey
-                    let recipient_pubkey = match Pubkey::from_str(&recipient_clone) {
-                        Ok(pubkey) => pubkey,
-                        Err(e) => {
-                            let processing_time = start_time.elapsed();
-                            return TransferResult {
-                                from_address: sender_clone.address,
-                                to_address: recipient_clone,
-                                signature: String::new(),
-                                status: None,
-                                processing_time,
-                                error: Some(format!("Invalid recipient address: {}", e)),
-                            };
-                        }
-                    };
-
-                    // Create transaction
-                    let transaction = match transfer_client.create_transfer_transaction(
-                        &sender_keypair,
-                        &recipient_pubkey,
-                        amount_lamports,
-                        blockhash_clone,
-                    ) {
-                        Ok(tx) => tx,
-                        Err(e) => {
-                            let processing_time = start_time.elapsed();
-                            return TransferResult {
-                                from_address: sender_clone.address,
-                                to_address: recipient_clone,
-                                signature: String::new(),
-                                status: None,
-                                processing_time,
-                                error: Some(format!("Failed to create transaction: {}", e)),
-                            };
-                        }
-                    };
-
-                    // Send transaction
-                    let signature = match transfer_client.send_transaction(&transaction).await {
-                        Ok(sig) => sig,
-                        Err(e) => {
-                            let processing_time = start_time.elapsed();
-                            return TransferResult {
-                                from_address: sender_clone.address,
-                                to_address: recipient_clone,
-                                signature: String::new(),
-                                status: None,
-                                processing_time,
-                                error: Some(format!("Failed to send transaction: {}", e)),
-                            };
-                        }
-                    };
+        self.notice(format!("✅ Using blockhash: {}", blockhash));
+        self.notice(format!("🚀 Starting {} transfers...\n", planned.len()));
 
-                    // Wait for confirmation
-                    tokio::time::sleep(Duration::from_millis(2000)).await;
+        let planned_count = planned.len();
+        let mut prepared = self.prepare_transfers(planned, blockhash);
+        if self.batch_recipients {
+            self.notice(batching::batch_plan(&prepared, planned_count));
+        }
+        if self.estimate_compute_units {
+            self.apply_compute_unit_limits(&mut prepared).await;
+        }
+        let unverified = Self::sign_and_verify_all(&mut prepared);
+        if unverified > 0 {
+            self.notice(format!(
+                "❌ {} transaction(s) failed signature verification and won't be sent\n",
+                unverified
+            ));
+        }
 
-                    // Check status
-                    let status = match transfer_client.get_signature_status(&signature).await {
-                        Ok(status) => status,
-                        Err(e) => {
-                            println!("⚠️  Warning: Failed to get status for {}: {}", signature, e);
-                            None
-                        }
-                    };
+        let vetoes = if self.pre_send_simulation {
+            let vetoes = self.pre_send_vetoes(&prepared).await;
+            if !vetoes.is_empty() {
+                self.notice(format!(
+                    "🧪 Simulation dropped {} transaction(s) that would fail\n",
+                    vetoes.len()
+                ));
+            }
+            vetoes
+        } else {
+            HashMap::new()
+        };
+        let vetoes = &vetoes;
 
-                    let processing_time = start_time.elapsed();
+        for (index, transfer) in prepared.iter().enumerate() {
+            let index = first_index + index;
+            let to = match transfer.legs.as_slice() {
+                [leg] => leg.to_address.clone(),
+                legs => format!("{} recipients", legs.len()),
+            };
+            self.emit(progress::TransferEvent::Queued {
+                index,
+                from: transfer.from_address.clone(),
+                to,
+            });
+        }
 
-                    TransferResult {
-                        from_address: sender_clone.address,
-                        to_address: recipient_clone,
-                        signature,
-                        status,
-                        processing_time,
-                        error: None,
+        let tasks = prepared
+            .into_iter()
+            .enumerate()
+            .map(|(local_index, transfer)| async move {
+                let index = first_index + local_index;
+                let veto = vetoes.get(&local_index);
+                let sent = match (&transfer.transaction, veto) {
+                    (Err(e), _) => Err(e.clone()),
+                    (Ok(_), Some(veto)) => Err(veto.error.clone()),
+                    (Ok(transaction), None) => {
+                        self.submit(index, transaction, transfer.signer.as_deref())
+                            .await
                     }
                 };
 
-                tasks.push(task);
+                let confirmed = matches!(&sent, Ok((_, outcome))
+                    if outcome.status.as_ref().is_some_and(|status| status.err.is_none())
+                        && outcome.reached_level >= Some(self.confirmation_level));
+                self.emit(progress::TransferEvent::Stage {
+                    index,
+                    stage: if confirmed {
+                        progress::TransferStage::Confirmed
+                    } else {
+                        progress::TransferStage::Failed
+                    },
+                    signature: None,
+                });
+
+                // Best effort: a node without the block just leaves the time out
+                let block_time = match (&sent, confirmed) {
+                    (Ok((_, outcome)), true) => match &outcome.status {
+                        Some(status) => self.get_block_time(status.slot).await.ok().flatten(),
+                        None => None,
+                    },
+                    _ => None,
+                };
+
+                let mut results = transfer.into_results(sent);
+                for result in &mut results {
+                    result.block_time = block_time;
+                }
+                if let Some(veto) = veto {
+                    for result in &mut results {
+                        result.simulation_logs = veto.logs.clone();
+                    }
+                }
+                if let Some(store) = &self.transfer_store {
+                    for result in &results {
+                        if let Err(e) = store.insert(result) {
+                            self.notice(format!(
+                                "⚠️  Warning: Failed to record transfer history: {}",
+                                e
+                            ));
+                        }
+                    }
+                }
+                for result in &results {
+                    self.run_post_transfer_hooks(result).await;
+                }
+                results
+            });
+
+        // Execute all transfers concurrently
+        let mut results: Vec<TransferResult> = futures::future::join_all(tasks)
+            .await
+            .into_iter()
+            .flatten()
+            .collect();
+        for result in &rejected {
+            self.run_post_transfer_hooks(result).await;
+        }
+        results.extend(rejected);
+        results
+    }
+
+    // Send a prepared transaction (respecting block pacing) and wait for its confirmation.
+    // With the signer at hand, a transaction whose blockhash went stale is re-signed and
+    // sent again, but only once the previous attempt is known not to have landed.
+    async fn submit(
+        &self,
+        index: usize,
+        transaction: &Transaction,
+        signer: Option<&Keypair>,
+    ) -> Result<(String, ConfirmationOutcome), String> {
+        let mut transaction = transaction.clone();
+        let mut retries = 0;
+
+        loop {
+            // Wait for budget in the current block when pacing is enabled
+            if let Some(pacer) = &self.pacer {
+                pacer.acquire(self).await;
+            }
+
+            if self.is_cancelled() {
+                return Err("Cancelled before sending".to_string());
             }
+
+            self.emit(progress::TransferEvent::Stage {
+                index,
+                stage: progress::TransferStage::Sent,
+                signature: None,
+            });
+            let sent = self
+                .send_transaction_with_preflight_error_parsing(&transaction)
+                .await
+                .map_err(|e| format!("Failed to send transaction: {}", e));
+            let signature = match (sent, signer) {
+                (Ok(signature), _) => signature,
+                // Rejected outright, so this attempt can't land and earlier ones were
+                // already checked
+                (Err(e), Some(signer))
+                    if resubmit::is_stale_blockhash_error(&e)
+                        && retries < resubmit::MAX_STALE_BLOCKHASH_RETRIES =>
+                {
+                    self.notice("🔁 Blockhash went stale before sending; re-signing".to_string());
+                    transaction = self
+                        .resign_with_fresh_blockhash(&transaction, signer)
+                        .await
+                        .map_err(|e| format!("Failed to re-sign transaction: {}", e))?;
+                    retries += 1;
+                    continue;
+                }
+                (Err(e), _) => return Err(e),
+            };
+
+            self.emit(progress::TransferEvent::Stage {
+                index,
+                stage: progress::TransferStage::Confirming,
+                signature: Some(signature.clone()),
+            });
+            let outcome = self.wait_for_confirmation(&signature).await;
+
+            // Not seen at all before the timeout: it may still land until its
+            // blockhash expires
+            let Some(signer) = signer.filter(|_| {
+                outcome.status.is_none()
+                    && retries < resubmit::MAX_STALE_BLOCKHASH_RETRIES
+                    && !self.is_cancelled()
+            }) else {
+                return Ok((signature, outcome));
+            };
+
+            // Re-signing while the old blockhash is valid could land both copies
+            if let Err(e) = self
+                .wait_for_blockhash_expiry(&transaction.message.recent_blockhash)
+                .await
+            {
+                self.notice(format!(
+                    "⚠️  Warning: {} may still land, not re-sending: {}",
+                    signature, e
+                ));
+                return Ok((signature, outcome));
+            }
+
+            match self.check_if_already_confirmed(&signature).await {
+                Ok(Some(found)) => {
+                    self.notice(format!(
+                        "✅ {} landed after all in slot {}{}; not re-sending",
+                        signature,
+                        found.status.slot,
+                        if found.from_history {
+                            " (found in transaction history)"
+                        } else {
+                            ""
+                        }
+                    ));
+                    let outcome = self.wait_for_confirmation(&signature).await;
+                    return Ok((signature, outcome));
+                }
+                Ok(None) => {}
+                // Without a definite answer a re-send could pay twice
+                Err(e) => {
+                    self.notice(format!(
+                        "⚠️  Warning: Could not check whether {} landed, not re-sending: {}",
+                        signature, e
+                    ));
+                    return Ok((signature, outcome));
+                }
+            }
+
+            self.notice(format!(
+                "🔁 {} expired without landing; re-sending with a fresh blockhash",
+                signature
+            ));
+            transaction = match self.resign_with_fresh_blockhash(&transaction, signer).await {
+                Ok(resigned) => resigned,
+                Err(e) => {
+                    self.notice(format!(
+                        "⚠️  Warning: Failed to re-sign {}: {}",
+                        signature, e
+                    ));
+                    return Ok((signature, outcome));
+                }
+            };
+            retries += 1;
         }
+    }
 
-        // Execute all transfers concurrently
-        futures::future::join_all(tasks).await
+    // Classify a finished transfer against the configured confirmation target
+    fn outcome(&self, result: &TransferResult) -> TransferOutcome {
+        if result.already_paid {
+            return TransferOutcome::AlreadyPaid;
+        }
+        if result.error.is_some() {
+            return TransferOutcome::Failed;
+        }
+        match &result.status {
+            Some(status) if status.err.is_some() => TransferOutcome::TransactionFailed,
+            Some(_) if result.reached_level >= Some(self.confirmation_level) => {
+                TransferOutcome::Success
+            }
+            Some(_) => TransferOutcome::TimedOut,
+            None => TransferOutcome::Pending,
+        }
     }
 
     // Print transfer statistics
-    pub fn print_statistics(&self, results: &[TransferResult]) {
+    pub(crate) fn print_statistics(
+        &self,
+        results: &[TransferResult],
+        epoch_schedule: Option<&epochs::EpochSchedule>,
+    ) {
         let mut successful = 0;
         let mut failed = 0;
+        let mut already_paid = 0;
         let mut total_time = Duration::new(0, 0);
         let mut min_time = Duration::from_secs(u64::MAX);
         let mut max_time = Duration::new(0, 0);
@@ -405,12 +1561,27 @@ impl SolTransfer {
         println!("\n=== Transfer Results ===\n");
 
         for result in results {
+            if result.already_paid {
+                already_paid += 1;
+                println!("{}", TransferOutcome::AlreadyPaid.display());
+                println!("From: {}", result.from_address);
+                println!("To: {}", result.to_address);
+                println!("Earlier signature: {}", result.signature);
+                println!("---");
+                continue;
+            }
             if let Some(error) = &result.error {
                 failed += 1;
                 println!("❌ FAILED TRANSFER");
                 println!("From: {}", result.from_address);
                 println!("To: {}", result.to_address);
+                if let Some(label) = &result.label {
+                    println!("Label: {}", label);
+                }
                 println!("Error: {}", error);
+                for line in &result.simulation_logs {
+                    println!("  | {}", line);
+                }
                 println!("Processing Time: {:?}", result.processing_time);
                 println!("---");
                 continue;
@@ -421,24 +1592,27 @@ impl SolTransfer {
             min_time = min_time.min(result.processing_time);
             max_time = max_time.max(result.processing_time);
 
-            let status_str = if let Some(status) = &result.status {
-                if status.err.is_some() {
-                    "❌ TRANSACTION FAILED"
-                } else {
-                    "✅ SUCCESS"
-                }
-            } else {
-                "⏳ PENDING"
-            };
-
             println!("From: {}", result.from_address);
             println!("To: {}", result.to_address);
+            if let Some(label) = &result.label {
+                println!("Label: {}", label);
+            }
             println!("Signature: {}", result.signature);
-            println!("Status: {}", status_str);
+            println!("Status: {}", self.outcome(result).display());
             println!("Processing Time: {:?}", result.processing_time);
 
             if let Some(status) = &result.status {
                 println!("Slot: {}", status.slot);
+                if let Some(schedule) = epoch_schedule {
+                    println!(
+                        "Epoch: {} ({} slots left)",
+                        epochs::slot_to_epoch(status.slot, schedule),
+                        epochs::slots_left_in_epoch(status.slot, schedule)
+                    );
+                }
+                if let Some(block_time) = result.block_time {
+                    println!("Block Time: {}", format_utc(block_time));
+                }
                 if let Some(confirmations) = status.confirmations {
                     println!("Confirmations: {}", confirmations);
                 }
@@ -446,13 +1620,22 @@ impl SolTransfer {
                     println!("Confirmation Status: {}", confirmation_status);
                 }
             }
+            if let (Some(level), Some(time)) = (result.reached_level, result.confirmation_time) {
+                println!(
+                    "Reached: {} in {:?} (target: {})",
+                    level, time, self.confirmation_level
+                );
+            }
             println!("---");
         }
 
         println!("\n=== Statistics ===");
-        println!("Total transfers: {}", successful + failed);
+        println!("Total transfers: {}", successful + failed + already_paid);
         println!("Successful: {}", successful);
         println!("Failed: {}", failed);
+        if already_paid > 0 {
+            println!("Already paid by an earlier run: {}", already_paid);
+        }
 
         if successful > 0 {
             let avg_time = total_time / successful as u32;
@@ -462,54 +1645,896 @@ impl SolTransfer {
             }
             println!("Max processing time: {:?}", max_time);
         }
+
+        if let Some(pacer) = &self.pacer {
+            pacer.print_summary();
+        }
+
+        if results.iter().any(|r| r.label.is_some()) {
+            println!("\n=== By Label ===");
+            // Counted the same way as the statistics above
+            for (label, group) in report::group_by_label(results) {
+                let already_paid = group.iter().filter(|r| r.already_paid).count();
+                let failed = group.iter().filter(|r| r.error.is_some()).count();
+                let successful = group.len() - failed - already_paid;
+                print!(
+                    "{}: {} transfers, {} successful, {} failed",
+                    label,
+                    group.len(),
+                    successful,
+                    failed
+                );
+                if already_paid > 0 {
+                    print!(", {} already paid", already_paid);
+                }
+                println!();
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TransferOutcome {
+    Failed,
+    TransactionFailed,
+    Success,
+    TimedOut,
+    Pending,
+    AlreadyPaid,
+}
+
+impl TransferOutcome {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Failed => "failed",
+            Self::TransactionFailed => "transaction_failed",
+            Self::Success => "success",
+            Self::TimedOut => "timed_out",
+            Self::Pending => "pending",
+            Self::AlreadyPaid => "already_paid",
+        }
+    }
+
+    fn display(self) -> &'static str {
+        match self {
+            Self::Failed => "❌ FAILED",
+            Self::TransactionFailed => "❌ TRANSACTION FAILED",
+            Self::Success => "✅ SUCCESS",
+            Self::TimedOut => "⌛ TIMED OUT BEFORE TARGET",
+            Self::Pending => "⏳ PENDING",
+            Self::AlreadyPaid => "⏭️  ALREADY PAID",
+        }
+    }
+}
+
+// Memo instruction with no signer accounts
+fn memo_instruction(memo: &str) -> Instruction {
+    Instruction {
+        program_id: MEMO_PROGRAM_ID,
+        accounts: vec![],
+        data: memo.as_bytes().to_vec(),
+    }
+}
+
+// Plan a transfer for each sender-recipient pair. A recipient's own amount wins over
+// the sender's resolved amount from `sender_lamports`.
+fn plan_transfers(
+    config: &Config,
+    sender_wallets: &[SenderWallet],
+    recipients: &[Recipient],
+    sender_lamports: &HashMap<String, u64>,
+) -> Vec<PlannedTransfer> {
+    let mut planned = Vec::new();
+    for sender in sender_wallets {
+        let amount_lamports = sender_lamports
+            .get(&sender.address)
+            .copied()
+            .unwrap_or_default();
+        for recipient in recipients {
+            planned.push(PlannedTransfer {
+                sender: sender.clone(),
+                recipient: recipient.address.clone(),
+                lamports: recipient.amount_lamports.unwrap_or(amount_lamports),
+                label: config.label_for(&sender.address, recipient),
+                create_token_account: false,
+            });
+        }
     }
+    planned
 }
 
-// Load configuration from YAML
+// Record how the run ended; a marker that can't be written only warns
+fn finish_run_marker(path: &str, marker: run_marker::RunMarker, outcome: run_marker::RunOutcome) {
+    if let Err(e) = run_marker::finish(path, marker, outcome) {
+        println!("⚠️  Warning: Failed to update run marker {}: {}", path, e);
+    }
+}
+
+// Load configuration from YAML, migrating older layouts
 fn load_config(path: &str) -> Result<Config, Box<dyn std::error::Error>> {
     let contents = fs::read_to_string(path)?;
-    let config: Config = serde_yaml::from_str(&contents)?;
+    let config: Config = config_migration::parse_config(&contents)?;
+    config.check_batch_sizes()?;
+    if config.config_version < config_migration::CURRENT_CONFIG_VERSION {
+        println!(
+            "⚠️  {} uses config version {}; it was migrated to version {} on load. \
+             See --generate-config for the current layout.",
+            path,
+            config.config_version,
+            config_migration::CURRENT_CONFIG_VERSION
+        );
+    }
     Ok(config)
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+
+    if let Some(shell) = cli.completions {
+        clap_complete::generate(
+            shell,
+            &mut Cli::command(),
+            env!("CARGO_PKG_NAME"),
+            &mut std::io::stdout(),
+        );
+        return Ok(());
+    }
+    if let Some(path) = &cli.generate_config {
+        Config::generate_template(path)?;
+        println!("📝 Example config written to {}", path);
+        return Ok(());
+    }
+
+    // Refuse rather than quietly send what the user meant to preview
+    let sends_transactions = cli
+        .command
+        .as_ref()
+        .is_some_and(Command::sends_transactions);
+    if cli.dry_run && sends_transactions {
+        return Err("--dry-run can't preview a subcommand that sends transactions".into());
+    }
+
+    let log_level = match (cli.log_level, cli.log_rpc) {
+        (Some(level), _) => Some(level),
+        (None, true) if cli.verbose => Some(tracing::Level::DEBUG),
+        (None, true) => Some(tracing::Level::INFO),
+        (None, false) => None,
+    };
+    if let Some(level) = log_level {
+        tracing_subscriber::fmt()
+            .with_max_level(level)
+            .with_writer(std::io::stderr)
+            .init();
+    }
+
     println!("🚀 SOL Transfer Tool Starting...\n");
 
     // Load configuration
-    let config = load_config("config.yaml")?;
+    let mut config = load_config(&cli.config)?;
+
+    // Resolve every sender key up front so nothing is dispatched with a bad or missing key
+    if let Err(errors) = keys::resolve_sender_keys(&mut config.sender_wallets).await {
+        println!("❌ {} sender key problem(s):", errors.len());
+        for error in &errors {
+            println!("  {}", error);
+        }
+        return Err("failed to resolve sender keys".into());
+    }
+
+    let rpc_headers = rpc_headers::resolve_headers(&config.rpc_headers)?;
 
     // Create transfer client
-    let sol_transfer = SolTransfer::new(config.solana_rpc_url);
+    let sol_transfer = SolTransfer::new(config.rpc.url.clone()).with_rpc_headers(rpc_headers)?;
+    let sol_transfer = if cli.auto_rpc {
+        let nodes = sol_transfer.get_cluster_nodes().await?;
+        let rpc_url =
+            cluster_nodes::select_fastest_rpc(&nodes, cluster_nodes::AUTO_RPC_TIMEOUT).await?;
+        if let Some(node) = nodes.iter().find(|node| {
+            node.rpc
+                .as_ref()
+                .is_some_and(|rpc| rpc_url == format!("http://{}", rpc))
+        }) {
+            println!(
+                "⚡ Using {} ({} v{}, feature set {}, gossip {}, tpu {}) out of {} node(s)\n",
+                rpc_url,
+                node.pubkey,
+                node.version.as_deref().unwrap_or("?"),
+                node.feature_set
+                    .map_or("?".to_string(), |feature_set| feature_set.to_string()),
+                node.gossip.as_deref().unwrap_or("-"),
+                node.tpu.as_deref().unwrap_or("-"),
+                nodes.len()
+            );
+        }
+        // The configured headers are credentials for the configured provider
+        SolTransfer::new(rpc_url)
+    } else {
+        sol_transfer
+    };
+    let sol_transfer = sol_transfer.with_confirmation_target(
+        config.confirmation_level,
+        config.confirmation_timeout_secs.map(Duration::from_secs),
+    );
 
-    // Convert SOL to lamports
-    let amount_lamports = SolTransfer::sol_to_lamports(config.amount_sol);
+    let sol_transfer = sol_transfer
+        .with_recipient_batching(config.batch_recipients)
+        .with_skip_nonexistent_recipients(config.skip_nonexistent_recipients)
+        .with_skip_preflight(config.skip_preflight)
+        .with_pre_send_simulation(config.pre_send_simulation)
+        .with_compute_unit_estimation(config.estimate_compute_units);
+    let transfer_store = config
+        .transfer_history_db
+        .as_deref()
+        .map(transfer_store::TransferStore::new)
+        .transpose()?;
+    let mut sol_transfer = sol_transfer.with_transfer_store(transfer_store);
+    if config.log_transfers || cli.verbose {
+        sol_transfer = sol_transfer.with_hook(Box::new(hooks::LoggingHook));
+    }
+    let spl_mint = config
+        .spl_token
+        .as_ref()
+        .map(spl::SplMint::from_config)
+        .transpose()?;
+    let sol_transfer = sol_transfer.with_spl_token(spl_mint);
+    let run_memo = if config.verify_on_chain_dedup {
+        let label = config
+            .run_label
+            .as_deref()
+            .ok_or("verify_on_chain_dedup needs a run_label to tag transfers with")?;
+        Some(replay::run_memo(label))
+    } else {
+        None
+    };
+    let sol_transfer = sol_transfer.with_run_memo(run_memo.clone());
+    let sol_transfer = match config.pace_per_block {
+        Some(0) => return Err("pace_per_block must be at least 1".into()),
+        Some(per_block) => sol_transfer.with_pace_per_block(per_block),
+        None => sol_transfer,
+    };
+
+    if let Some(command) = cli.command {
+        return match command {
+            Command::AccountCreate(args) => accounts::run(&sol_transfer, &config, args).await,
+            Command::AtaCreate(args) => spl::run(&sol_transfer, &config, args).await,
+            Command::AtomicSwap(args) => swap::run(&sol_transfer, &config, args).await,
+            Command::Drain(args) => sweep::run_drain(&sol_transfer, &config, args).await,
+            Command::ExportHistory(args) => transfer_store::run_export(&sol_transfer, args),
+            Command::History(args) => history::run(&sol_transfer, args).await,
+            Command::LookupTable(args) => lookup_tables::run(&sol_transfer, &config, args).await,
+            Command::MintInfo(args) => mint_info::run(&sol_transfer, args).await,
+            Command::NextLeader(args) => leader_schedule::run(&sol_transfer, args).await,
+            Command::ShowHistory(args) => transfer_store::run_show(&sol_transfer, args),
+            Command::Sign(args) => message_signing::run_sign(&config, args),
+            Command::SupplyMonitor(args) => supply::run(&sol_transfer, args).await,
+            Command::SweepClose(args) => sweep::run(&sol_transfer, &config, args).await,
+            Command::TxDecode(args) => tx_decode::run(&sol_transfer, args).await,
+            Command::Verify(args) => message_signing::run_verify(args),
+        };
+    }
+
+    // A weighted distribution replaces the other recipient sources
+    let distribution_shares = match &config.distribution {
+        Some(distribution) => {
+            if config.sender_wallets.len() != 1 {
+                return Err("distribution needs exactly one sender wallet".into());
+            }
+            if !config.recipient_addresses.is_empty()
+                || config.recipients_file.is_some()
+                || cli.recipients.is_some()
+            {
+                return Err(
+                    "distribution can't be combined with recipient_addresses or a recipients file"
+                        .into(),
+                );
+            }
+
+            let weights = match distribution::load_weights(&distribution.file) {
+                Ok(weights) => weights,
+                Err(errors) => {
+                    println!("❌ {} invalid distribution row(s):", errors.len());
+                    for error in &errors {
+                        println!("  {}", error);
+                    }
+                    return Err(format!("invalid distribution in {}", distribution.file).into());
+                }
+            };
+            if weights.is_empty() {
+                return Err(format!("distribution {} has no recipients", distribution.file).into());
+            }
+            Some(distribution::split_lamports(
+                distribution.total_lamports(),
+                &weights,
+            ))
+        }
+        None => None,
+    };
+
+    // Load extra recipients, reporting every bad row before doing anything else
+    let file_recipients = match cli.recipients.as_ref().or(config.recipients_file.as_ref()) {
+        Some(source) => match recipients::load(source) {
+            Ok(recipients) => recipients,
+            Err(errors) => {
+                println!("❌ {} invalid recipient row(s):", errors.len());
+                for error in &errors {
+                    println!("  {}", error);
+                }
+                return Err(format!("invalid recipients in {}", source).into());
+            }
+        },
+        None => distribution_shares
+            .as_deref()
+            .map(distribution::into_recipients)
+            .unwrap_or_default(),
+    };
+    let mut recipient_list = recipients::merge(&config.recipient_addresses, file_recipients);
+
+    // Check every recipient against the allowlist before anything is planned
+    let allowlist_summary = match &config.recipient_allowlist {
+        Some(path) => {
+            let allowlist = match allowlist::Allowlist::load(path) {
+                Ok(allowlist) => allowlist,
+                Err(errors) => {
+                    println!("❌ {} invalid allowlist row(s):", errors.len());
+                    for error in &errors {
+                        println!("  {}", error);
+                    }
+                    return Err(format!("invalid allowlist {}", path).into());
+                }
+            };
+
+            let check = allowlist.check(std::mem::take(&mut recipient_list.recipients));
+            if !check.rejected.is_empty() {
+                println!(
+                    "❌ {} recipient(s) not in allowlist {}:",
+                    check.rejected.len(),
+                    path
+                );
+                for recipient in &check.rejected {
+                    println!("  {}", recipient.address);
+                }
+                if config.allowlist_policy == allowlist::AllowlistPolicy::Abort {
+                    return Err("recipients not in allowlist (set allowlist_policy: skip to leave them out)".into());
+                }
+                println!("⚠️  Skipping them (allowlist_policy: skip)\n");
+            }
+
+            let summary = format!(
+                "- Allowlist: {} recipients verified against {} ({} entries), {} skipped",
+                check.approved.len(),
+                path,
+                allowlist.len(),
+                check.rejected.len()
+            );
+            recipient_list.recipients = check.approved;
+            Some(summary)
+        }
+        None => None,
+    };
+
+    // Every sender must resolve to an amount (sender > group > global), in lamports.
+    // A distribution gives each recipient its own share instead.
+    let sender_lamports = match amounts::resolve_sender_amounts(&config) {
+        _ if config.distribution.is_some() => HashMap::new(),
+        Ok(amounts) => amounts,
+        Err(errors) => {
+            println!("❌ {} sender amount problem(s):", errors.len());
+            for error in &errors {
+                println!("  {}", error);
+            }
+            return Err("every sender needs an amount".into());
+        }
+    };
 
     println!("Configuration loaded:");
     println!("- Sender wallets: {}", config.sender_wallets.len());
-    println!("- Recipients: {}", config.recipient_addresses.len());
-    println!(
-        "- Amount per transfer: {} SOL ({} lamports)",
-        config.amount_sol, amount_lamports
-    );
+    recipients::print_summary(&recipient_list);
+    if let (Some(distribution), Some(shares)) = (&config.distribution, &distribution_shares) {
+        distribution::print_table(distribution.total_lamports(), shares);
+    }
+    if let Some(summary) = &allowlist_summary {
+        println!("{}", summary);
+    }
+    match (&config.spl_token, &spl_mint, config.amount_sol) {
+        (Some(token), Some(mint), amount_sol) => {
+            if let Some(amount_sol) = amount_sol {
+                println!(
+                    "- Amount per transfer: {} tokens of {} ({} base units)",
+                    amount_sol,
+                    token.mint,
+                    mint.base_units(SolTransfer::sol_to_lamports(amount_sol))
+                );
+            }
+            println!("- Missing token accounts: {}", token.ata_policy);
+        }
+        (_, _, Some(amount_sol)) => println!(
+            "- Amount per transfer: {} SOL ({} lamports)",
+            amount_sol,
+            SolTransfer::sol_to_lamports(amount_sol)
+        ),
+        _ => {}
+    }
+    let mut groups: Vec<(&String, &f64)> = config.groups.iter().collect();
+    groups.sort_by(|a, b| a.0.cmp(b.0));
+    for (group, amount_sol) in groups {
+        println!("- Group {}: {} per transfer", group, amount_sol);
+    }
+    println!("- Confirmation level: {}", config.confirmation_level);
+    if let Some(per_block) = config.pace_per_block {
+        println!("- Pacing: at most {} transactions per block", per_block);
+    }
+    if config.batch_recipients {
+        println!("- Recipient batching: enabled");
+    }
+    if config.skip_nonexistent_recipients {
+        println!("- Skipping recipients without an account: enabled");
+    }
     println!(
         "- Total transfers: {}\n",
-        config.sender_wallets.len() * config.recipient_addresses.len()
+        config.sender_wallets.len() * recipient_list.recipients.len()
     );
 
+    // Unwrapped SOL has to land before planning so the run can count and spend it
+    if cli.unwrap_wsol && cli.dry_run {
+        println!("ℹ️  Dry run: leaving wrapped SOL as it is\n");
+    } else if cli.unwrap_wsol {
+        for wallet in &config.sender_wallets {
+            let owner = SolTransfer::parse_keypair(&wallet.private_key)?;
+            match sol_transfer.unwrap_wsol(&owner).await {
+                Ok(Some(signature)) => {
+                    println!("🔓 Unwrapped wSOL of {}: {}", wallet.address, signature)
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    return Err(
+                        format!("Failed to unwrap wSOL of {}: {}", wallet.address, e).into(),
+                    );
+                }
+            }
+        }
+    }
+
     // Execute transfers
-    let results = sol_transfer
-        .execute_transfers(
-            config.sender_wallets,
-            config.recipient_addresses,
-            amount_lamports,
+    let planned = plan_transfers(
+        &config,
+        &config.sender_wallets,
+        &recipient_list.recipients,
+        &sender_lamports,
+    );
+    amounts::print_group_totals(
+        &planned,
+        if config.spl_token.is_some() {
+            "tokens"
+        } else {
+            "SOL"
+        },
+    );
+    // Find recipients without a token account and apply the configured policy
+    let planned = match (&config.spl_token, &spl_mint) {
+        (Some(token), Some(mint)) => {
+            let recipients: Vec<String> = planned
+                .iter()
+                .map(|transfer| transfer.recipient.clone())
+                .collect();
+            let check = sol_transfer.check_token_accounts(mint, &recipients).await?;
+            if !check.missing.is_empty() {
+                println!(
+                    "🪙 {} recipient(s) have no token account; creating them costs {} SOL in rent ({} lamports each)",
+                    check.missing.len(),
+                    check.total_rent(&planned) as f64 / 1_000_000_000.0,
+                    check.rent_per_account
+                );
+            }
+
+            let (kept, skipped) = spl::apply_ata_policy(token.ata_policy, planned, &check.missing)?;
+            for transfer in &skipped {
+                println!(
+                    "⚠️  Skipping {} -> {}: recipient has no token account",
+                    transfer.sender.address, transfer.recipient
+                );
+            }
+            println!();
+            kept
+        }
+        _ => planned,
+    };
+    // Skip recipients an earlier run with the same label already paid on chain
+    let (planned, already_paid) = match &run_memo {
+        Some(memo) => {
+            sol_transfer
+                .skip_already_paid(
+                    planned,
+                    memo,
+                    config
+                        .on_chain_dedup_lookback
+                        .unwrap_or(replay::DEFAULT_LOOKBACK),
+                )
+                .await
+        }
+        None => (planned, Vec::new()),
+    };
+    // Show the fee budget before anything is sent; an estimate failure doesn't stop the run
+    let recipient_addresses: Vec<String> = recipient_list
+        .recipients
+        .iter()
+        .map(|recipient| recipient.address.clone())
+        .collect();
+    match sol_transfer
+        .estimate_batch_fees(
+            &config.sender_wallets,
+            &recipient_addresses,
+            // Fees don't depend on the amount, so any sender's will do
+            sender_lamports.values().copied().max().unwrap_or_default(),
         )
-        .await;
+        .await
+    {
+        Ok(estimate) => {
+            println!(
+                "💸 Estimated fees: {} lamports across {} transactions",
+                estimate.total_fee_lamports, estimate.transactions
+            );
+            if estimate.unavailable > 0 {
+                println!(
+                    "   ({} transactions were priced locally; the cluster couldn't price them)",
+                    estimate.unavailable
+                );
+            }
+            println!();
+        }
+        Err(e) => println!("⚠️  Warning: Failed to estimate fees: {}\n", e),
+    }
+
+    if cli.dry_run {
+        println!("🧪 Dry run, nothing will be sent:");
+        for transfer in &planned {
+            println!(
+                "  {} -> {}: {} lamports{}",
+                transfer.sender.address,
+                transfer.recipient,
+                transfer.lamports,
+                transfer
+                    .label
+                    .as_deref()
+                    .map(|label| format!(" ({})", label))
+                    .unwrap_or_default()
+            );
+        }
+        println!(
+            "\n{} transfer(s) planned, {} already paid",
+            planned.len(),
+            already_paid.len()
+        );
+        return Ok(());
+    }
+
+    // Refuse to repeat an identical run by accident
+    let marker_path = config
+        .run_marker_path
+        .as_deref()
+        .unwrap_or(run_marker::DEFAULT_MARKER_PATH);
+    let duplicate_window = Duration::from_secs(
+        config
+            .duplicate_window_hours
+            .unwrap_or(run_marker::DEFAULT_DUPLICATE_WINDOW_HOURS)
+            * 3600,
+    );
+    let marker = run_marker::start(
+        marker_path,
+        run_marker::fingerprint(&config.rpc.url, &planned),
+        config.run_label.clone(),
+        duplicate_window,
+        cli.allow_duplicate_run,
+    )?;
+
+    let audit = match (cli.balance_audit, spl_mint) {
+        (false, _) => None,
+        (true, None) => {
+            println!("ℹ️  --balance-audit only covers SPL token transfers; skipping it\n");
+            None
+        }
+        (true, Some(mint)) => {
+            match balance_audit::BalanceAudit::start(&sol_transfer, mint, &planned).await {
+                Ok(audit) => Some(audit),
+                Err(e) => {
+                    // Nothing was sent, so the next run should see this one as aborted
+                    finish_run_marker(marker_path, marker, run_marker::RunOutcome::Aborted);
+                    return Err(e);
+                }
+            }
+        }
+    };
+
+    progress::cancel_on_ctrl_c(sol_transfer.cancellation_flag());
+
+    // Hand progress events to the status table when it can be shown
+    let (sol_transfer, table) = if cli.tui && tui::is_supported() {
+        let (events, receiver) = tokio::sync::mpsc::unbounded_channel();
+        let table = tokio::spawn(tui::run(receiver, sol_transfer.cancellation_flag()));
+        (sol_transfer.with_progress_events(events), Some(table))
+    } else {
+        if cli.tui {
+            println!("ℹ️  Not running in a terminal, using plain output\n");
+        }
+        (sol_transfer, None)
+    };
+
+    let (results, chunks) = match config.chunk_size {
+        Some(size) => {
+            let settings = chunking::ChunkSettings {
+                size,
+                failure_threshold_percent: config
+                    .chunk_failure_threshold_percent
+                    .unwrap_or(chunking::DEFAULT_FAILURE_THRESHOLD_PERCENT),
+                checkpoint_path: config.checkpoint_file.clone(),
+            };
+            sol_transfer.execute_in_chunks(planned, &settings).await
+        }
+        None => match config.max_concurrent_transfers.or(config.batch_size) {
+            Some(max_concurrent) => {
+                let settings = chunking::SplitSettings {
+                    max_concurrent,
+                    batch_size: config.batch_size.unwrap_or(max_concurrent),
+                    delay: Duration::from_millis(config.batch_delay_ms),
+                };
+                (
+                    sol_transfer.split_large_batch(planned, &settings).await,
+                    Vec::new(),
+                )
+            }
+            None => (sol_transfer.execute_transfers(planned).await, Vec::new()),
+        },
+    };
+    if let Some(table) = table {
+        match table.await {
+            Ok(Ok(notices)) => {
+                for notice in notices {
+                    println!("{}", notice);
+                }
+            }
+            Ok(Err(e)) => println!("⚠️  Warning: Status table failed: {}", e),
+            Err(_) => {}
+        }
+    }
+    let results: Vec<TransferResult> = already_paid.into_iter().chain(results).collect();
+    if let Some(audit) = &audit {
+        audit.finish(&sol_transfer).await;
+    }
 
     // Print results and statistics
-    sol_transfer.print_statistics(&results);
+    // Only used to annotate slots, so a node without it just leaves epochs out
+    let epoch_schedule = sol_transfer.get_epoch_schedule().await.ok();
+    sol_transfer.print_statistics(&results, epoch_schedule.as_ref());
+    chunking::print_chunk_summary(&chunks);
+
+    let run_outcome = if sol_transfer.is_cancelled() {
+        run_marker::RunOutcome::Aborted
+    } else if !results.is_empty()
+        && results.iter().all(|result| {
+            matches!(
+                sol_transfer.outcome(result),
+                TransferOutcome::Success | TransferOutcome::AlreadyPaid
+            )
+        })
+    {
+        run_marker::RunOutcome::Succeeded
+    } else {
+        run_marker::RunOutcome::Failed
+    };
+    finish_run_marker(marker_path, marker, run_outcome);
+
+    if let Some(path) = cli.output.as_ref().or(config.results_csv.as_ref()) {
+        report::export_csv(&sol_transfer, &results, path)?;
+        println!("📄 Results written to {}", path);
+    }
+    if let Some(path) = &config.summary_json {
+        report::write_json_summary(&sol_transfer, &results, &chunks, path)?;
+        println!("📄 Summary written to {}", path);
+    }
 
     println!("\n🎉 Transfer process completed!");
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::Signature;
+
+    fn test_sender(keypair: &Keypair) -> SenderWallet {
+        SenderWallet {
+            address: keypair.pubkey().to_string(),
+            private_key: bs58::encode(keypair.to_bytes()).into_string(),
+            private_key_source: None,
+            amount_sol: None,
+            group: None,
+        }
+    }
+
+    #[test]
+    fn test_identical_planned_transfers_get_distinct_signatures() {
+        let sol_transfer = SolTransfer::new("http://127.0.0.1:8899".to_string());
+        let sender = test_sender(&Keypair::new());
+        let recipient = Pubkey::new_unique().to_string();
+
+        let planned = vec![
+            PlannedTransfer {
+                sender: sender.clone(),
+                recipient: recipient.clone(),
+                lamports: 1_000_000,
+                label: None,
+                create_token_account: false,
+            },
+            PlannedTransfer {
+                sender,
+                recipient,
+                lamports: 1_000_000,
+                label: None,
+                create_token_account: false,
+            },
+        ];
+
+        let prepared = sol_transfer.prepare_transfers(planned, Hash::new_unique());
+        let signatures: Vec<Signature> = prepared
+            .iter()
+            .map(|p| p.transaction.as_ref().unwrap().signatures[0])
+            .collect();
+
+        assert_eq!(signatures.len(), 2);
+        assert_ne!(signatures[0], signatures[1]);
+    }
+
+    #[test]
+    fn test_missing_signer_is_reported_before_signing() {
+        let sol_transfer = SolTransfer::new("http://127.0.0.1:8899".to_string());
+        let payer = Keypair::new();
+        let co_signer = Keypair::new();
+        let mut instruction =
+            system_instruction::transfer(&co_signer.pubkey(), &Pubkey::new_unique(), 1);
+        instruction
+            .accounts
+            .push(solana_sdk::instruction::AccountMeta::new_readonly(
+                payer.pubkey(),
+                true,
+            ));
+
+        let unsigned = Transaction::new_with_payer(&[instruction.clone()], Some(&payer.pubkey()));
+        assert_eq!(
+            SolTransfer::get_required_signers(&unsigned),
+            [payer.pubkey(), co_signer.pubkey()]
+        );
+        assert!(
+            SolTransfer::verify_all_signers_available(&unsigned, &[&payer, &co_signer]).is_ok()
+        );
+
+        let error = sol_transfer
+            .sign_unique_transaction(
+                vec![instruction],
+                &payer,
+                Hash::new_unique(),
+                &mut HashSet::new(),
+            )
+            .unwrap_err();
+        assert_eq!(error, format!("Missing signer(s): {}", co_signer.pubkey()));
+    }
+
+    #[test]
+    fn test_corrupted_signature_fails_verification() {
+        let sol_transfer = SolTransfer::new("http://127.0.0.1:8899".to_string());
+        let planned = (0..2)
+            .map(|_| PlannedTransfer {
+                sender: test_sender(&Keypair::new()),
+                recipient: Pubkey::new_unique().to_string(),
+                lamports: 1_000_000,
+                label: None,
+                create_token_account: false,
+            })
+            .collect();
+
+        let mut prepared = sol_transfer.prepare_transfers(planned, Hash::new_unique());
+        if let Ok(transaction) = &mut prepared[1].transaction {
+            let mut bytes: [u8; 64] = transaction.signatures[0].as_ref().try_into().unwrap();
+            bytes[0] ^= 0xff;
+            transaction.signatures[0] = Signature::from(bytes);
+        }
+
+        assert_eq!(SolTransfer::sign_and_verify_all(&mut prepared), 1);
+        assert!(prepared[0].transaction.is_ok());
+        assert_eq!(
+            prepared[1].transaction.as_ref().err().map(String::as_str),
+            Some("Signature verification failed")
+        );
+    }
+
+    fn test_rpc_url() -> String {
+        std::env::var("SOLANA_TEST_RPC_URL").unwrap_or_else(|_| "http://127.0.0.1:8899".to_string())
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a local validator or devnet RPC (SOLANA_TEST_RPC_URL)"]
+    async fn test_transfer_between_airdropped_wallets() {
+        let sol_transfer = SolTransfer::new(test_rpc_url());
+        let sender_keypair = Keypair::new();
+        let recipient = Pubkey::new_unique();
+
+        let airdrops = sol_transfer
+            .request_airdrops(vec![sender_keypair.pubkey()], 1_000_000_000)
+            .await;
+        assert_eq!(airdrops.len(), 1);
+        assert_eq!(airdrops[0].wallet, sender_keypair.pubkey());
+        assert!(airdrops[0].error.is_none(), "{:?}", airdrops[0].error);
+        assert_eq!(airdrops[0].lamports, 1_000_000_000);
+
+        let results = sol_transfer
+            .execute_transfers(vec![PlannedTransfer {
+                sender: test_sender(&sender_keypair),
+                recipient: recipient.to_string(),
+                lamports: SolTransfer::sol_to_lamports(0.001),
+                label: Some("integration".to_string()),
+                create_token_account: false,
+            }])
+            .await;
+        assert_eq!(results.len(), 1);
+        assert!(results[0].error.is_none(), "{:?}", results[0].error);
+        assert!(results[0].reached_level >= Some(ConfirmationLevel::Confirmed));
+    }
+
+    #[test]
+    fn test_format_utc() {
+        assert_eq!(format_utc(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(format_utc(1_700_000_000), "2023-11-14 22:13:20 UTC");
+        assert_eq!(format_utc(951_782_400), "2000-02-29 00:00:00 UTC");
+    }
+
+    #[test]
+    fn test_config_template_parses() {
+        let config: Config = serde_yaml::from_str(CONFIG_TEMPLATE).unwrap();
+        assert_eq!(
+            config.config_version,
+            config_migration::CURRENT_CONFIG_VERSION
+        );
+        assert_eq!(config.sender_wallets.len(), 1);
+        assert_eq!(config.confirmation_level, ConfirmationLevel::Confirmed);
+        assert!(config.account_create.is_some());
+    }
+
+    #[test]
+    fn test_zero_batch_size_is_rejected() {
+        let mut config: Config = serde_yaml::from_str(CONFIG_TEMPLATE).unwrap();
+        assert!(config.check_batch_sizes().is_ok());
+        config.batch_size = Some(0);
+        assert_eq!(
+            config.check_batch_sizes().unwrap_err(),
+            "batch_size must be at least 1"
+        );
+    }
+
+    #[test]
+    fn test_distinct_transfers_are_not_tagged() {
+        let sol_transfer = SolTransfer::new("http://127.0.0.1:8899".to_string());
+        let sender_keypair = Keypair::new();
+        let mut seen = HashSet::new();
+
+        for _ in 0..2 {
+            let recipient = Pubkey::new_unique();
+            let transfer = PlannedTransfer {
+                sender: test_sender(&sender_keypair),
+                recipient: recipient.to_string(),
+                lamports: 1_000_000,
+                label: None,
+                create_token_account: false,
+            };
+            let transaction = sol_transfer
+                .create_unique_transfer_transaction(
+                    &sender_keypair,
+                    &recipient,
+                    &transfer,
+                    Hash::new_unique(),
+                    &mut seen,
+                )
+                .unwrap();
+            assert_eq!(transaction.message.instructions.len(), 1);
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use clap::Args;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+};
+use std::str::FromStr;
+
+use crate::{Config, SolTransfer};
+
+impl SolTransfer {
+    // Sign the UTF-8 bytes of `message` off-chain; the base58 signature is what
+    // wallets show for "sign message"
+    pub fn sign_message(keypair: &Keypair, message: &str) -> String {
+        keypair.sign_message(message.as_bytes()).to_string()
+    }
+
+    // Whether `signature` (base58) is `pubkey`'s signature over `message`.
+    // A malformed signature is an error; a well-formed one that doesn't match is `false`.
+    pub fn verify_message(
+        pubkey: &Pubkey,
+        message: &str,
+        signature: &str,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        let signature = Signature::from_str(signature)
+            .map_err(|e| format!("Invalid signature '{}': {}", signature, e))?;
+        Ok(signature.verify(pubkey.as_ref(), message.as_bytes()))
+    }
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct SignArgs {
+    /// Message to sign
+    message: String,
+    /// Index into `sender_wallets` of the signing wallet
+    #[arg(long, default_value_t = 0)]
+    signer: usize,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct VerifyArgs {
+    /// Address that supposedly signed
+    pubkey: String,
+    /// Message that was signed
+    message: String,
+    /// Base58 signature
+    signature: String,
+}
+
+// `sign` subcommand
+pub(crate) fn run_sign(config: &Config, args: SignArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let signer = config
+        .sender_wallets
+        .get(args.signer)
+        .ok_or_else(|| format!("No sender wallet at index {}", args.signer))?;
+    let keypair = SolTransfer::parse_keypair(&signer.private_key)?;
+
+    println!("Signer: {}", keypair.pubkey());
+    println!(
+        "Signature: {}",
+        SolTransfer::sign_message(&keypair, &args.message)
+    );
+    Ok(())
+}
+
+// `verify` subcommand; exits with an error when the signature doesn't match
+pub(crate) fn run_verify(args: VerifyArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let pubkey = Pubkey::from_str(&args.pubkey)
+        .map_err(|e| format!("Invalid address '{}': {}", args.pubkey, e))?;
+
+    if SolTransfer::verify_message(&pubkey, &args.message, &args.signature)? {
+        println!("✅ Valid signature by {}", pubkey);
+        Ok(())
+    } else {
+        Err(format!("signature does not match {} and this message", pubkey).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_then_verify() {
+        let keypair = Keypair::new();
+        let signature = SolTransfer::sign_message(&keypair, "login nonce 42");
+
+        assert!(
+            SolTransfer::verify_message(&keypair.pubkey(), "login nonce 42", &signature).unwrap()
+        );
+        assert!(
+            !SolTransfer::verify_message(&keypair.pubkey(), "login nonce 43", &signature).unwrap()
+        );
+        assert!(
+            !SolTransfer::verify_message(&Pubkey::new_unique(), "login nonce 42", &signature)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_malformed_signature_is_an_error() {
+        assert!(SolTransfer::verify_message(&Pubkey::new_unique(), "hi", "not-base58!").is_err());
+    }
+}
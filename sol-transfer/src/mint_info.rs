@@ -0,0 +1,302 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use clap::Args;
+use serde::Deserialize;
+use solana_sdk::{account::Account, pubkey, pubkey::Pubkey};
+use std::str::FromStr;
+
+use crate::{SolTransfer, spl};
+
+// Metaplex Token Metadata program, owner of the metadata account of most mints
+const METADATA_PROGRAM_ID: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+// Size of the SPL Token `Mint` state
+const MINT_LEN: usize = 82;
+
+#[derive(Debug, Deserialize)]
+struct AccountInfoResult {
+    value: Option<EncodedAccount>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EncodedAccount {
+    lamports: u64,
+    // [data, encoding]
+    data: (String, String),
+    owner: String,
+    executable: bool,
+    rent_epoch: u64,
+}
+
+impl EncodedAccount {
+    fn into_account(self) -> Result<Account, Box<dyn std::error::Error>> {
+        Ok(Account {
+            lamports: self.lamports,
+            data: STANDARD.decode(&self.data.0)?,
+            owner: Pubkey::from_str(&self.owner)?,
+            executable: self.executable,
+            rent_epoch: self.rent_epoch,
+        })
+    }
+}
+
+// The fields of an SPL Token mint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MintInfo {
+    pub(crate) mint_authority: Option<Pubkey>,
+    // In base units
+    pub(crate) supply: u64,
+    pub(crate) decimals: u8,
+    pub(crate) is_initialized: bool,
+    pub(crate) freeze_authority: Option<Pubkey>,
+}
+
+// Name, symbol and URI from a mint's Metaplex metadata account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TokenMetadata {
+    pub(crate) name: String,
+    pub(crate) symbol: String,
+    pub(crate) uri: String,
+}
+
+impl SolTransfer {
+    // `address`'s account, or `None` when it doesn't exist
+    async fn get_account(
+        &self,
+        address: &Pubkey,
+    ) -> Result<Option<Account>, Box<dyn std::error::Error>> {
+        let result: AccountInfoResult = self
+            .rpc_call(
+                "getAccountInfo",
+                vec![
+                    serde_json::json!(address.to_string()),
+                    serde_json::json!({ "encoding": "base64", "commitment": "confirmed" }),
+                ],
+            )
+            .await?;
+        result.value.map(EncodedAccount::into_account).transpose()
+    }
+
+    // Supply, decimals and authorities of the token mint at `mint`
+    pub(crate) async fn get_token_mint_info(
+        &self,
+        mint: &str,
+    ) -> Result<MintInfo, Box<dyn std::error::Error>> {
+        let mint = Pubkey::from_str(mint).map_err(|e| format!("Invalid mint '{}': {}", mint, e))?;
+        let account = self
+            .get_account(&mint)
+            .await?
+            .ok_or_else(|| format!("Mint {} does not exist", mint))?;
+        // Token-2022 mints start with the same layout as classic SPL Token mints
+        if account.owner != spl::TOKEN_PROGRAM_ID && account.owner != spl::TOKEN_2022_PROGRAM_ID {
+            return Err(format!("{} is not a token mint (owner {})", mint, account.owner).into());
+        }
+        Ok(parse_mint(&account.data)?)
+    }
+
+    // Name, symbol and URI of `mint` from its Metaplex metadata account
+    pub(crate) async fn get_token_metadata(
+        &self,
+        mint: &str,
+    ) -> Result<TokenMetadata, Box<dyn std::error::Error>> {
+        let mint = Pubkey::from_str(mint).map_err(|e| format!("Invalid mint '{}': {}", mint, e))?;
+        let metadata_address = metadata_address(&mint);
+        let account = self
+            .get_account(&metadata_address)
+            .await?
+            .ok_or_else(|| format!("Mint {} has no Metaplex metadata", mint))?;
+        Ok(parse_metadata(&account.data)?)
+    }
+}
+
+// The Metaplex metadata PDA of `mint`
+pub(crate) fn metadata_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"metadata", METADATA_PROGRAM_ID.as_ref(), mint.as_ref()],
+        &METADATA_PROGRAM_ID,
+    )
+    .0
+}
+
+// Little-endian reader over account data that reports short data as an error
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.data.len() < len {
+            return Err("account data is too short".to_string());
+        }
+        let (head, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(head)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn pubkey(&mut self) -> Result<Pubkey, String> {
+        Ok(Pubkey::try_from(self.take(32)?).unwrap())
+    }
+
+    // SPL's `COption<Pubkey>`: a 4-byte tag, then the key whether set or not
+    fn coption_pubkey(&mut self) -> Result<Option<Pubkey>, String> {
+        let tag = self.u32()?;
+        let key = self.pubkey()?;
+        match tag {
+            0 => Ok(None),
+            1 => Ok(Some(key)),
+            other => Err(format!("invalid option tag {}", other)),
+        }
+    }
+
+    // Borsh string; Metaplex pads names and symbols with NULs
+    fn string(&mut self) -> Result<String, String> {
+        let len = self.u32()? as usize;
+        let bytes = self.take(len)?;
+        let text = String::from_utf8_lossy(bytes);
+        Ok(text.trim_end_matches('\0').to_string())
+    }
+}
+
+// Decode the `spl_token::state::Mint` layout
+pub(crate) fn parse_mint(data: &[u8]) -> Result<MintInfo, String> {
+    if data.len() < MINT_LEN {
+        return Err(format!(
+            "mint data is {} bytes, expected at least {}",
+            data.len(),
+            MINT_LEN
+        ));
+    }
+    let mut reader = Reader { data };
+    Ok(MintInfo {
+        mint_authority: reader.coption_pubkey()?,
+        supply: reader.u64()?,
+        decimals: reader.take(1)?[0],
+        is_initialized: reader.take(1)?[0] != 0,
+        freeze_authority: reader.coption_pubkey()?,
+    })
+}
+
+// Decode name, symbol and URI from a Metaplex metadata account: a key byte, the
+// update authority and mint, then the three strings
+pub(crate) fn parse_metadata(data: &[u8]) -> Result<TokenMetadata, String> {
+    let mut reader = Reader { data };
+    reader.take(1 + 32 + 32)?;
+    Ok(TokenMetadata {
+        name: reader.string()?,
+        symbol: reader.string()?,
+        uri: reader.string()?,
+    })
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct MintInfoArgs {
+    /// Token mint address
+    mint: String,
+}
+
+fn format_authority(authority: Option<Pubkey>) -> String {
+    authority.map_or_else(|| "none".to_string(), |key| key.to_string())
+}
+
+// `mint-info` subcommand
+pub(crate) async fn run(
+    sol_transfer: &SolTransfer,
+    args: MintInfoArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let info = sol_transfer.get_token_mint_info(&args.mint).await?;
+
+    println!("🪙 Mint {}", args.mint);
+    println!(
+        "  Supply:           {} ({} base units)",
+        info.supply as f64 / 10f64.powi(i32::from(info.decimals)),
+        info.supply
+    );
+    println!("  Decimals:         {}", info.decimals);
+    println!("  Initialized:      {}", info.is_initialized);
+    println!(
+        "  Mint authority:   {}",
+        format_authority(info.mint_authority)
+    );
+    println!(
+        "  Freeze authority: {}",
+        format_authority(info.freeze_authority)
+    );
+
+    match sol_transfer.get_token_metadata(&args.mint).await {
+        Ok(metadata) => {
+            println!("  Name:             {}", metadata.name);
+            println!("  Symbol:           {}", metadata.symbol);
+            println!("  URI:              {}", metadata.uri);
+        }
+        Err(e) => println!("  Metadata:         unavailable ({})", e),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn borsh_string(text: &str, padded_to: usize) -> Vec<u8> {
+        let mut bytes = text.as_bytes().to_vec();
+        bytes.resize(padded_to, 0);
+        let mut encoded = (bytes.len() as u32).to_le_bytes().to_vec();
+        encoded.extend(bytes);
+        encoded
+    }
+
+    #[test]
+    fn test_parse_mint_layout() {
+        let authority = Pubkey::new_unique();
+        let mut data = Vec::new();
+        data.extend(1u32.to_le_bytes());
+        data.extend(authority.to_bytes());
+        data.extend(1_000_000_000u64.to_le_bytes());
+        data.push(6);
+        data.push(1);
+        data.extend(0u32.to_le_bytes());
+        data.extend([0u8; 32]);
+
+        assert_eq!(
+            parse_mint(&data).unwrap(),
+            MintInfo {
+                mint_authority: Some(authority),
+                supply: 1_000_000_000,
+                decimals: 6,
+                is_initialized: true,
+                freeze_authority: None,
+            }
+        );
+        assert!(parse_mint(&data[..40]).is_err());
+    }
+
+    #[test]
+    fn test_parse_metadata_trims_padding() {
+        let mut data = vec![4u8];
+        data.extend([0u8; 64]);
+        data.extend(borsh_string("USD Coin", 32));
+        data.extend(borsh_string("USDC", 10));
+        data.extend(borsh_string("https://example.com/usdc.json", 200));
+        data.extend(0u16.to_le_bytes());
+
+        assert_eq!(
+            parse_metadata(&data).unwrap(),
+            TokenMetadata {
+                name: "USD Coin".to_string(),
+                symbol: "USDC".to_string(),
+                uri: "https://example.com/usdc.json".to_string(),
+            }
+        );
+        assert!(parse_metadata(&data[..70]).is_err());
+    }
+}
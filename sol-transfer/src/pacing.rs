@@ -0,0 +1,120 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+use crate::SolTransfer;
+
+// How often to poll the block height while waiting for the next block
+const BLOCK_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+struct PacerState {
+    current_height: Option<u64>,
+    sent_in_block: usize,
+    per_block_counts: BTreeMap<u64, usize>,
+}
+
+// Limits submissions to `per_block` transactions per observed block height
+pub(crate) struct BlockPacer {
+    per_block: usize,
+    state: Mutex<PacerState>,
+}
+
+impl BlockPacer {
+    // A budget of 0 could never be met, so it still lets one transaction through
+    pub(crate) fn new(per_block: usize) -> Self {
+        Self {
+            per_block: per_block.max(1),
+            state: Mutex::new(PacerState {
+                current_height: None,
+                sent_in_block: 0,
+                per_block_counts: BTreeMap::new(),
+            }),
+        }
+    }
+
+    // Wait until the current block has submission budget left and claim one slot.
+    // Returns the block height the submission is attributed to.
+    pub(crate) async fn acquire(&self, sol_transfer: &SolTransfer) -> u64 {
+        // Holding the lock while polling queues the other senders behind us
+        let mut state = self.state.lock().await;
+
+        loop {
+            if let Some(height) = state.current_height
+                && state.sent_in_block < self.per_block
+            {
+                state.sent_in_block += 1;
+                *state.per_block_counts.entry(height).or_default() += 1;
+                return height;
+            }
+
+            match sol_transfer.get_block_height().await {
+                Ok(height) if Some(height) > state.current_height => {
+                    state.current_height = Some(height);
+                    state.sent_in_block = 0;
+                    continue;
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    sol_transfer.notice(format!("⚠️  Warning: Failed to get block height: {}", e))
+                }
+            }
+
+            tokio::time::sleep(BLOCK_POLL_INTERVAL).await;
+        }
+    }
+
+    pub(crate) fn print_summary(&self) {
+        let Ok(state) = self.state.try_lock() else {
+            return;
+        };
+        let (Some(first), Some(last)) = (
+            state.per_block_counts.keys().next(),
+            state.per_block_counts.keys().next_back(),
+        ) else {
+            return;
+        };
+
+        println!("\n=== Block Pacing ===");
+        println!("Limit: {} transactions per block", self.per_block);
+        println!(
+            "Blocks spanned: {} (heights {}..={}, {} with submissions)",
+            last - first + 1,
+            first,
+            last,
+            state.per_block_counts.len()
+        );
+        for (height, count) in &state.per_block_counts {
+            println!("  Block {}: {} submitted", height, count);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_zero_budget_still_sends_one_per_block() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(
+                serde_json::json!({ "method": "getBlockHeight" }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": 10
+            })))
+            .mount(&server)
+            .await;
+
+        let sol_transfer = SolTransfer::new(server.uri());
+        let pacer = BlockPacer::new(0);
+        let height = tokio::time::timeout(Duration::from_secs(5), pacer.acquire(&sol_transfer))
+            .await
+            .expect("a zero budget must not wait forever");
+        assert_eq!(height, 10);
+    }
+}
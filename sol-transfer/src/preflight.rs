@@ -0,0 +1,268 @@
+use solana_sdk::{pubkey, pubkey::Pubkey};
+use std::fmt;
+
+use crate::JsonRpcError;
+use crate::spl::{TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID};
+
+const SYSTEM_PROGRAM_ID: Pubkey = pubkey!("11111111111111111111111111111111");
+
+// Known instruction failures, keyed by the program that raised them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ProgramError {
+    // System program custom errors
+    AccountAlreadyInUse,
+    ResultWithNegativeLamports,
+    InvalidProgramId,
+    InvalidAccountDataLength,
+    MaxSeedLengthExceeded,
+    AddressWithSeedMismatch,
+    NonceNoRecentBlockhashes,
+    NonceBlockhashNotExpired,
+    NonceUnexpectedBlockhashValue,
+    // Token program custom errors (shared by Token-2022)
+    TokenNotRentExempt,
+    TokenInsufficientFunds,
+    TokenInvalidMint,
+    TokenMintMismatch,
+    TokenOwnerMismatch,
+    TokenFixedSupply,
+    TokenAlreadyInUse,
+    TokenUninitializedState,
+    TokenNonNativeHasBalance,
+    TokenAccountFrozen,
+    // A custom code we have no mapping for
+    Custom {
+        program_id: Option<String>,
+        code: u32,
+    },
+    // A built-in InstructionError variant such as `InsufficientFunds`
+    Builtin(String),
+}
+
+impl ProgramError {
+    fn from_custom(program_id: Option<&str>, code: u32) -> Self {
+        let program = program_id.and_then(|id| id.parse::<Pubkey>().ok());
+        let known = match program {
+            Some(id) if id == SYSTEM_PROGRAM_ID => match code {
+                0 => Some(Self::AccountAlreadyInUse),
+                1 => Some(Self::ResultWithNegativeLamports),
+                2 => Some(Self::InvalidProgramId),
+                3 => Some(Self::InvalidAccountDataLength),
+                4 => Some(Self::MaxSeedLengthExceeded),
+                5 => Some(Self::AddressWithSeedMismatch),
+                6 => Some(Self::NonceNoRecentBlockhashes),
+                7 => Some(Self::NonceBlockhashNotExpired),
+                8 => Some(Self::NonceUnexpectedBlockhashValue),
+                _ => None,
+            },
+            Some(id) if id == TOKEN_PROGRAM_ID || id == TOKEN_2022_PROGRAM_ID => match code {
+                0 => Some(Self::TokenNotRentExempt),
+                1 => Some(Self::TokenInsufficientFunds),
+                2 => Some(Self::TokenInvalidMint),
+                3 => Some(Self::TokenMintMismatch),
+                4 => Some(Self::TokenOwnerMismatch),
+                5 => Some(Self::TokenFixedSupply),
+                6 => Some(Self::TokenAlreadyInUse),
+                9 => Some(Self::TokenUninitializedState),
+                11 => Some(Self::TokenNonNativeHasBalance),
+                17 => Some(Self::TokenAccountFrozen),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        known.unwrap_or_else(|| Self::Custom {
+            program_id: program_id.map(str::to_string),
+            code,
+        })
+    }
+
+    pub(crate) fn description(&self) -> String {
+        match self {
+            Self::AccountAlreadyInUse => "account already in use".to_string(),
+            Self::ResultWithNegativeLamports => {
+                "insufficient lamports: sender balance too low for this transfer".to_string()
+            }
+            Self::InvalidProgramId => "cannot assign account to this program id".to_string(),
+            Self::InvalidAccountDataLength => {
+                "cannot allocate account data of this length".to_string()
+            }
+            Self::MaxSeedLengthExceeded => "seed is longer than 32 bytes".to_string(),
+            Self::AddressWithSeedMismatch => {
+                "address does not match the derived seed address".to_string()
+            }
+            Self::NonceNoRecentBlockhashes => {
+                "no recent blockhashes available for the nonce".to_string()
+            }
+            Self::NonceBlockhashNotExpired => {
+                "stored nonce is still in recent blockhashes".to_string()
+            }
+            Self::NonceUnexpectedBlockhashValue => {
+                "nonce does not match the stored value".to_string()
+            }
+            Self::TokenNotRentExempt => "token account is not rent exempt".to_string(),
+            Self::TokenInsufficientFunds => "insufficient token balance".to_string(),
+            Self::TokenInvalidMint => "invalid mint".to_string(),
+            Self::TokenMintMismatch => "account is not associated with this mint".to_string(),
+            Self::TokenOwnerMismatch => "owner does not match".to_string(),
+            Self::TokenFixedSupply => "mint has a fixed supply".to_string(),
+            Self::TokenAlreadyInUse => "token account already in use".to_string(),
+            Self::TokenUninitializedState => "token account is not initialized".to_string(),
+            Self::TokenNonNativeHasBalance => {
+                "non-native account can only be closed with a zero balance".to_string()
+            }
+            Self::TokenAccountFrozen => "token account is frozen".to_string(),
+            Self::Custom {
+                program_id: Some(program_id),
+                code,
+            } => format!("custom program error 0x{:x} from {}", code, program_id),
+            Self::Custom {
+                program_id: None,
+                code,
+            } => format!("custom program error 0x{:x}", code),
+            Self::Builtin(name) => name.clone(),
+        }
+    }
+}
+
+// Where a simulated transaction failed and why
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ParsedPreflightError {
+    pub(crate) instruction_index: u8,
+    pub(crate) error: ProgramError,
+}
+
+impl fmt::Display for ParsedPreflightError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "instruction {} failed: {}",
+            self.instruction_index,
+            self.error.description()
+        )
+    }
+}
+
+// Program that failed, taken from the first "Program <id> failed" simulation log line
+fn failing_program<'a>(logs: &[&'a str]) -> Option<&'a str> {
+    logs.iter().find_map(|&line| {
+        let rest = line.strip_prefix("Program ")?;
+        let (program_id, status) = rest.split_once(' ')?;
+        status.starts_with("failed").then_some(program_id)
+    })
+}
+
+// Pull the `InstructionError` out of a failed preflight's `data.err`, e.g.
+// `{"InstructionError": [0, {"Custom": 1}]}` or `{"InstructionError": [1, "InsufficientFunds"]}`
+pub(crate) fn parse_preflight_error(rpc_error: &JsonRpcError) -> Option<ParsedPreflightError> {
+    let data = rpc_error.data.as_ref()?;
+    let logs: Vec<&str> = data
+        .get("logs")
+        .and_then(|logs| logs.as_array())
+        .map(|logs| logs.iter().filter_map(|line| line.as_str()).collect())
+        .unwrap_or_default();
+
+    parse_transaction_error(data.get("err")?, &logs)
+}
+
+// Same as `parse_preflight_error`, for a transaction error and logs taken from a simulation
+pub(crate) fn parse_transaction_error(
+    err: &serde_json::Value,
+    logs: &[&str],
+) -> Option<ParsedPreflightError> {
+    let instruction_error = err.get("InstructionError")?.as_array()?;
+    let [index, error] = instruction_error.as_slice() else {
+        return None;
+    };
+    let instruction_index = u8::try_from(index.as_u64()?).ok()?;
+
+    let error = match error {
+        serde_json::Value::String(name) => ProgramError::Builtin(name.clone()),
+        serde_json::Value::Object(fields) => {
+            if let Some(code) = fields.get("Custom").and_then(|code| code.as_u64()) {
+                ProgramError::from_custom(failing_program(logs), code as u32)
+            } else {
+                ProgramError::Builtin(serde_json::Value::Object(fields.clone()).to_string())
+            }
+        }
+        _ => return None,
+    };
+
+    Some(ParsedPreflightError {
+        instruction_index,
+        error,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rpc_error(data: serde_json::Value) -> JsonRpcError {
+        JsonRpcError {
+            code: -32002,
+            message: "Transaction simulation failed".to_string(),
+            data: Some(data),
+        }
+    }
+
+    #[test]
+    fn test_system_transfer_with_low_balance() {
+        let error = rpc_error(serde_json::json!({
+            "err": {"InstructionError": [0, {"Custom": 1}]},
+            "logs": [
+                "Program 11111111111111111111111111111111 invoke [1]",
+                "Transfer: insufficient lamports 10, need 1000000",
+                "Program 11111111111111111111111111111111 failed: custom program error: 0x1"
+            ]
+        }));
+
+        let parsed = parse_preflight_error(&error).unwrap();
+        assert_eq!(parsed.instruction_index, 0);
+        assert_eq!(parsed.error, ProgramError::ResultWithNegativeLamports);
+    }
+
+    #[test]
+    fn test_token_error_uses_failing_program() {
+        let error = rpc_error(serde_json::json!({
+            "err": {"InstructionError": [2, {"Custom": 1}]},
+            "logs": [
+                "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA invoke [1]",
+                "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA failed: custom program error: 0x1"
+            ]
+        }));
+
+        let parsed = parse_preflight_error(&error).unwrap();
+        assert_eq!(parsed.instruction_index, 2);
+        assert_eq!(parsed.error, ProgramError::TokenInsufficientFunds);
+        assert_eq!(
+            parsed.to_string(),
+            "instruction 2 failed: insufficient token balance"
+        );
+    }
+
+    #[test]
+    fn test_builtin_and_unknown_errors() {
+        let builtin = rpc_error(serde_json::json!({
+            "err": {"InstructionError": [1, "InsufficientFunds"]}
+        }));
+        assert_eq!(
+            parse_preflight_error(&builtin).unwrap().error,
+            ProgramError::Builtin("InsufficientFunds".to_string())
+        );
+
+        let unknown = rpc_error(serde_json::json!({
+            "err": {"InstructionError": [0, {"Custom": 6000}]}
+        }));
+        assert_eq!(
+            parse_preflight_error(&unknown).unwrap().error,
+            ProgramError::Custom {
+                program_id: None,
+                code: 6000
+            }
+        );
+
+        let not_instruction = rpc_error(serde_json::json!({"err": "BlockhashNotFound"}));
+        assert!(parse_preflight_error(&not_instruction).is_none());
+    }
+}
@@ -0,0 +1,159 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde_json::Value;
+
+// One recognised line of a transaction's `logMessages`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ProgramLogEvent {
+    // `Program log: <message>`
+    Message(String),
+    // `Program data: <base64>...`, e.g. Anchor events; the parts are concatenated
+    Data(Vec<u8>),
+    // `Program <id> consumed <n> of <limit> compute units`
+    ComputeUnitsConsumed(u64),
+    // `Program <id> invoke [<depth>]`
+    ProgramInvoke { program_id: String },
+    // `Program return: <id> <base64>`
+    ProgramReturn { program_id: String, data: Vec<u8> },
+}
+
+impl ProgramLogEvent {
+    // A log message that is itself JSON, as some programs emit
+    pub(crate) fn json(&self) -> Option<Value> {
+        match self {
+            Self::Message(message) => serde_json::from_str(message)
+                .ok()
+                .filter(|value: &Value| value.is_object() || value.is_array()),
+            _ => None,
+        }
+    }
+}
+
+fn decode_base64(encoded: &str) -> Option<Vec<u8>> {
+    STANDARD.decode(encoded).ok()
+}
+
+// Parse a single log line; success/failure lines and anything unrecognised are skipped
+fn parse_log_line(line: &str) -> Option<ProgramLogEvent> {
+    if let Some(message) = line.strip_prefix("Program log: ") {
+        return Some(ProgramLogEvent::Message(message.to_string()));
+    }
+    if let Some(encoded) = line.strip_prefix("Program data: ") {
+        let mut data = Vec::new();
+        for part in encoded.split_whitespace() {
+            data.extend(decode_base64(part)?);
+        }
+        return Some(ProgramLogEvent::Data(data));
+    }
+    if let Some(returned) = line.strip_prefix("Program return: ") {
+        let (program_id, encoded) = returned.split_once(' ')?;
+        return Some(ProgramLogEvent::ProgramReturn {
+            program_id: program_id.to_string(),
+            data: decode_base64(encoded.trim())?,
+        });
+    }
+
+    let rest = line.strip_prefix("Program ")?;
+    let (program_id, rest) = rest.split_once(' ')?;
+    if rest.starts_with("invoke [") {
+        return Some(ProgramLogEvent::ProgramInvoke {
+            program_id: program_id.to_string(),
+        });
+    }
+    rest.strip_prefix("consumed ")?
+        .split_once(' ')?
+        .0
+        .parse()
+        .ok()
+        .map(ProgramLogEvent::ComputeUnitsConsumed)
+}
+
+pub(crate) fn parse_program_logs(logs: &[String]) -> Vec<ProgramLogEvent> {
+    logs.iter()
+        .filter_map(|line| parse_log_line(line))
+        .collect()
+}
+
+// Events grouped by the top-level instruction that produced them, in instruction order.
+// Each `invoke [1]` line starts the next instruction.
+pub(crate) fn group_by_instruction(logs: &[String]) -> Vec<Vec<ProgramLogEvent>> {
+    let starts: Vec<usize> = logs
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| line.starts_with("Program ") && line.ends_with(" invoke [1]"))
+        .map(|(index, _)| index)
+        .collect();
+    starts
+        .iter()
+        .enumerate()
+        .map(|(n, &start)| {
+            let end = starts.get(n + 1).copied().unwrap_or(logs.len());
+            parse_program_logs(&logs[start..end])
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn logs(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_program_logs() {
+        let events = parse_program_logs(&logs(&[
+            "Program Vau1t11111111111111111111111111111111111111 invoke [1]",
+            "Program log: Instruction: Deposit",
+            "Program data: AQID BAU=",
+            "Program return: Vau1t11111111111111111111111111111111111111 KgAAAAAAAAA=",
+            "Program Vau1t11111111111111111111111111111111111111 consumed 4521 of 200000 compute units",
+            "Program Vau1t11111111111111111111111111111111111111 success",
+        ]));
+
+        assert_eq!(
+            events,
+            vec![
+                ProgramLogEvent::ProgramInvoke {
+                    program_id: "Vau1t11111111111111111111111111111111111111".to_string()
+                },
+                ProgramLogEvent::Message("Instruction: Deposit".to_string()),
+                ProgramLogEvent::Data(vec![1, 2, 3, 4, 5]),
+                ProgramLogEvent::ProgramReturn {
+                    program_id: "Vau1t11111111111111111111111111111111111111".to_string(),
+                    data: 42u64.to_le_bytes().to_vec(),
+                },
+                ProgramLogEvent::ComputeUnitsConsumed(4521),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_json_messages() {
+        assert_eq!(
+            ProgramLogEvent::Message(r#"{"amount": 5}"#.to_string()).json(),
+            Some(serde_json::json!({"amount": 5}))
+        );
+        assert_eq!(ProgramLogEvent::Message("5".to_string()).json(), None);
+        assert_eq!(ProgramLogEvent::Data(vec![]).json(), None);
+    }
+
+    #[test]
+    fn test_nested_invokes_stay_with_their_instruction() {
+        let groups = group_by_instruction(&logs(&[
+            "Program ComputeBudget111111111111111111111111111111 invoke [1]",
+            "Program ComputeBudget111111111111111111111111111111 success",
+            "Program Vau1t11111111111111111111111111111111111111 invoke [1]",
+            "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA invoke [2]",
+            "Program log: Instruction: Transfer",
+            "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA success",
+            "Program Vau1t11111111111111111111111111111111111111 consumed 9000 of 200000 compute units",
+        ]));
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].len(), 1);
+        assert_eq!(groups[1].len(), 4);
+        assert_eq!(groups[1][3], ProgramLogEvent::ComputeUnitsConsumed(9000));
+    }
+}
@@ -0,0 +1,107 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::mpsc;
+
+use crate::SolTransfer;
+
+// Where a single transaction is in its lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TransferStage {
+    Building,
+    Sent,
+    Confirming,
+    Confirmed,
+    Failed,
+}
+
+impl TransferStage {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Building => "building",
+            Self::Sent => "sent",
+            Self::Confirming => "confirming",
+            Self::Confirmed => "confirmed",
+            Self::Failed => "failed",
+        }
+    }
+
+    pub(crate) fn is_final(self) -> bool {
+        matches!(self, Self::Confirmed | Self::Failed)
+    }
+}
+
+// Progress updates published while transfers run, indexed by prepared transaction
+#[derive(Debug, Clone)]
+pub(crate) enum TransferEvent {
+    Queued {
+        index: usize,
+        from: String,
+        to: String,
+    },
+    Stage {
+        index: usize,
+        stage: TransferStage,
+        signature: Option<String>,
+    },
+    // A status line, e.g. a warning, that would otherwise have been printed
+    Notice(String),
+    // Every transfer has a final result
+    Finished,
+}
+
+pub(crate) type EventSender = mpsc::UnboundedSender<TransferEvent>;
+pub(crate) type EventReceiver = mpsc::UnboundedReceiver<TransferEvent>;
+
+impl SolTransfer {
+    // Publish progress events for every transfer on `events`
+    pub(crate) fn with_progress_events(mut self, events: EventSender) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    pub(crate) fn emit(&self, event: TransferEvent) {
+        if let Some(events) = &self.events {
+            // Nobody listening any more is not an error
+            let _ = events.send(event);
+        }
+    }
+
+    // Print a status line while transfers run. With a progress listener it becomes
+    // an event instead, since printing over the status table would garble it.
+    pub(crate) fn notice(&self, message: String) {
+        match &self.events {
+            Some(_) => self.emit(TransferEvent::Notice(message)),
+            None => println!("{}", message),
+        }
+    }
+
+    // Shared flag that stops new transactions from being sent once set.
+    // Transactions already sent are still followed to confirmation.
+    pub(crate) fn cancellation_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.cancelled)
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+// Cancel on the first Ctrl+C, exit immediately on the second
+pub(crate) fn cancel_on_ctrl_c(cancelled: Arc<AtomicBool>) {
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_err() {
+            return;
+        }
+        request_cancel(&cancelled);
+
+        if tokio::signal::ctrl_c().await.is_ok() {
+            std::process::exit(130);
+        }
+    });
+}
+
+pub(crate) fn request_cancel(cancelled: &AtomicBool) {
+    if !cancelled.swap(true, Ordering::Relaxed) {
+        println!("\n🛑 Cancelling: no new transfers will be sent (Ctrl+C again to abort)");
+    }
+}
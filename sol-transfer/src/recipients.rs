@@ -0,0 +1,547 @@
+use serde::Deserialize;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashSet;
+use std::fs;
+use std::io::BufRead;
+use std::str::FromStr;
+use std::time::Instant;
+
+use crate::{PlannedTransfer, SolTransfer, TransferResult};
+
+// Most addresses `getMultipleAccounts` accepts in one call
+pub(crate) const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+#[derive(Debug, Deserialize)]
+struct MultipleAccountsResult {
+    value: Vec<Option<AccountLamports>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AccountLamports {
+    lamports: u64,
+}
+
+// A recipient with optional per-recipient overrides
+#[derive(Debug, Clone)]
+pub(crate) struct Recipient {
+    pub(crate) address: String,
+    pub(crate) amount_lamports: Option<u64>,
+    pub(crate) label: Option<String>,
+}
+
+impl Recipient {
+    pub(crate) fn from_address(address: String) -> Self {
+        Self {
+            address,
+            amount_lamports: None,
+            label: None,
+        }
+    }
+}
+
+// A row that couldn't be turned into a recipient
+#[derive(Debug)]
+pub(crate) struct RowError {
+    pub(crate) source: String,
+    pub(crate) line: usize,
+    pub(crate) message: String,
+}
+
+impl std::fmt::Display for RowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.source, self.line, self.message)
+    }
+}
+
+// Merged recipient list plus where its entries came from
+#[derive(Debug)]
+pub(crate) struct RecipientList {
+    pub(crate) recipients: Vec<Recipient>,
+    pub(crate) from_config: usize,
+    pub(crate) from_file: usize,
+    pub(crate) duplicates_skipped: usize,
+}
+
+// Split a CSV line into fields, honouring double-quoted fields with "" escapes
+pub(crate) fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+
+    fields.into_iter().map(|f| f.trim().to_string()).collect()
+}
+
+fn parse_row(fields: &[String]) -> Result<Recipient, String> {
+    let address = fields.first().map(String::as_str).unwrap_or_default();
+    if address.is_empty() {
+        return Err("missing address".to_string());
+    }
+    Pubkey::from_str(address).map_err(|e| format!("invalid address '{}': {}", address, e))?;
+
+    let amount_lamports = match fields.get(1).map(String::as_str) {
+        None | Some("") => None,
+        Some(amount) => {
+            let sol = amount
+                .parse::<f64>()
+                .map_err(|_| format!("invalid amount_sol '{}'", amount))?;
+            if !sol.is_finite() || sol <= 0.0 {
+                return Err(format!("amount_sol must be positive, got '{}'", amount));
+            }
+            Some(SolTransfer::sol_to_lamports(sol))
+        }
+    };
+
+    let label = fields.get(2).filter(|label| !label.is_empty()).cloned();
+
+    if fields.len() > 3 {
+        return Err(format!("expected at most 3 columns, got {}", fields.len()));
+    }
+
+    Ok(Recipient {
+        address: address.to_string(),
+        amount_lamports,
+        label,
+    })
+}
+
+// Parse CSV rows (address, optional amount_sol, optional label).
+// Every invalid row is reported rather than stopping at the first one.
+pub(crate) fn parse_csv(source: &str, contents: &str) -> Result<Vec<Recipient>, Vec<RowError>> {
+    let mut recipients = Vec::new();
+    let mut errors = Vec::new();
+
+    for (index, line) in contents.lines().enumerate() {
+        let line_number = index + 1;
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        let fields = split_csv_line(trimmed);
+        if line_number == 1 && fields[0].eq_ignore_ascii_case("address") {
+            continue;
+        }
+
+        match parse_row(&fields) {
+            Ok(recipient) => recipients.push(recipient),
+            Err(message) => errors.push(RowError {
+                source: source.to_string(),
+                line: line_number,
+                message,
+            }),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(recipients)
+    } else {
+        Err(errors)
+    }
+}
+
+// Read newline-separated addresses, one per line
+pub(crate) fn parse_address_lines(
+    source: &str,
+    reader: impl BufRead,
+) -> Result<Vec<Recipient>, Vec<RowError>> {
+    let mut recipients = Vec::new();
+    let mut errors = Vec::new();
+
+    for (index, line) in reader.lines().enumerate() {
+        let line_number = index + 1;
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => {
+                errors.push(RowError {
+                    source: source.to_string(),
+                    line: line_number,
+                    message: format!("read error: {}", e),
+                });
+                break;
+            }
+        };
+
+        let address = line.trim();
+        if address.is_empty() || address.starts_with('#') {
+            continue;
+        }
+
+        match Pubkey::from_str(address) {
+            Ok(_) => recipients.push(Recipient::from_address(address.to_string())),
+            Err(e) => errors.push(RowError {
+                source: source.to_string(),
+                line: line_number,
+                message: format!("invalid address '{}': {}", address, e),
+            }),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(recipients)
+    } else {
+        Err(errors)
+    }
+}
+
+// Load recipients from a CSV file, or from stdin when `source` is "-"
+pub(crate) fn load(source: &str) -> Result<Vec<Recipient>, Vec<RowError>> {
+    if source == "-" {
+        return parse_address_lines("stdin", std::io::stdin().lock());
+    }
+
+    match fs::read_to_string(source) {
+        Ok(contents) => parse_csv(source, &contents),
+        Err(e) => Err(vec![RowError {
+            source: source.to_string(),
+            line: 0,
+            message: format!("failed to read file: {}", e),
+        }]),
+    }
+}
+
+// Config recipients first, then file recipients whose address isn't already present
+pub(crate) fn merge(config_addresses: &[String], file_recipients: Vec<Recipient>) -> RecipientList {
+    let mut seen = HashSet::new();
+    let mut recipients = Vec::new();
+    let mut duplicates_skipped = 0;
+
+    for address in config_addresses {
+        if seen.insert(address.clone()) {
+            recipients.push(Recipient::from_address(address.clone()));
+        } else {
+            duplicates_skipped += 1;
+        }
+    }
+    let from_config = recipients.len();
+
+    for recipient in file_recipients {
+        if seen.insert(recipient.address.clone()) {
+            recipients.push(recipient);
+        } else {
+            duplicates_skipped += 1;
+        }
+    }
+    let from_file = recipients.len() - from_config;
+
+    RecipientList {
+        recipients,
+        from_config,
+        from_file,
+        duplicates_skipped,
+    }
+}
+
+// Print the merged list size with its first and last few addresses
+pub(crate) fn print_summary(list: &RecipientList) {
+    const PREVIEW: usize = 3;
+
+    println!(
+        "- Recipients: {} ({} from config, {} from file, {} duplicates skipped)",
+        list.recipients.len(),
+        list.from_config,
+        list.from_file,
+        list.duplicates_skipped
+    );
+
+    let total = list.recipients.len();
+    let print_entry = |index: usize| {
+        let recipient = &list.recipients[index];
+        let mut line = format!("    {:>5}. {}", index + 1, recipient.address);
+        if let Some(lamports) = recipient.amount_lamports {
+            line.push_str(&format!(" ({} lamports)", lamports));
+        }
+        if let Some(label) = &recipient.label {
+            line.push_str(&format!(" [{}]", label));
+        }
+        println!("{}", line);
+    };
+
+    if total <= PREVIEW * 2 {
+        (0..total).for_each(&print_entry);
+    } else {
+        (0..PREVIEW).for_each(&print_entry);
+        println!("    ... {} more ...", total - PREVIEW * 2);
+        (total - PREVIEW..total).for_each(&print_entry);
+    }
+}
+
+impl SolTransfer {
+    // Whether each address currently holds lamports, checked 100 at a time with
+    // `getMultipleAccounts`. Fails if any batch can't be checked, rather than
+    // guessing either way for the addresses in it.
+    pub async fn verify_recipient_exists(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<(Pubkey, bool)>, String> {
+        let mut existence = Vec::with_capacity(pubkeys.len());
+
+        for chunk in pubkeys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let addresses: Vec<String> = chunk.iter().map(Pubkey::to_string).collect();
+            let result: Result<MultipleAccountsResult, _> = self
+                .rpc_call(
+                    "getMultipleAccounts",
+                    vec![
+                        serde_json::json!(addresses),
+                        serde_json::json!({ "commitment": "confirmed", "dataSlice": { "offset": 0, "length": 0 } }),
+                    ],
+                )
+                .await;
+
+            match result {
+                Ok(result) if result.value.len() == chunk.len() => {
+                    existence.extend(chunk.iter().zip(result.value).map(|(pubkey, account)| {
+                        (*pubkey, account.is_some_and(|account| account.lamports > 0))
+                    }));
+                }
+                Ok(_) => {
+                    return Err("getMultipleAccounts returned the wrong number of accounts".into());
+                }
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+
+        Ok(existence)
+    }
+
+    // Remove transfers to recipients without an account, warning about each one.
+    // If the accounts can't be checked, every transfer comes back as a failed result
+    // instead of being sent unverified.
+    pub(crate) async fn drop_nonexistent_recipients(
+        &self,
+        planned: Vec<PlannedTransfer>,
+    ) -> (Vec<PlannedTransfer>, Vec<TransferResult>) {
+        // Invalid addresses are left in place to fail with a proper error later
+        let pubkeys: Vec<Pubkey> = planned
+            .iter()
+            .filter_map(|transfer| Pubkey::from_str(&transfer.recipient).ok())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        let existence = match self.verify_recipient_exists(&pubkeys).await {
+            Ok(existence) => existence,
+            Err(e) => {
+                self.notice(format!(
+                    "❌ Failed to check recipient accounts, sending nothing: {}\n",
+                    e
+                ));
+                let error = format!("Could not check that the recipient exists: {}", e);
+                let unverified = planned
+                    .into_iter()
+                    .map(|transfer| {
+                        TransferResult::failed(
+                            transfer.sender.address,
+                            transfer.recipient,
+                            transfer.label,
+                            error.clone(),
+                            Instant::now(),
+                        )
+                    })
+                    .collect();
+                return (Vec::new(), unverified);
+            }
+        };
+        let missing: HashSet<String> = existence
+            .into_iter()
+            .filter(|(_, exists)| !exists)
+            .map(|(pubkey, _)| pubkey.to_string())
+            .collect();
+
+        let (kept, skipped) = retain_existing(planned, &missing);
+        for transfer in &skipped {
+            self.notice(format!(
+                "⚠️  Skipping {} -> {}: recipient account does not exist",
+                transfer.sender.address, transfer.recipient
+            ));
+        }
+        if !skipped.is_empty() {
+            self.notice(format!(
+                "⚠️  Skipped {} transfer(s) to nonexistent recipients\n",
+                skipped.len()
+            ));
+        }
+
+        (kept, Vec::new())
+    }
+}
+
+// Split planned transfers into those whose recipient exists and those in `missing`
+fn retain_existing(
+    planned: Vec<PlannedTransfer>,
+    missing: &HashSet<String>,
+) -> (Vec<PlannedTransfer>, Vec<PlannedTransfer>) {
+    planned
+        .into_iter()
+        .partition(|transfer| !missing.contains(&transfer.recipient))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_parse_csv_reports_every_invalid_row() {
+        let valid = Pubkey::new_unique().to_string();
+        let contents = format!(
+            "address,amount_sol,label\n{},0.5,vip\nnot-an-address\n{},abc\n\"{}\",,\"a, b\"\n",
+            valid, valid, valid
+        );
+
+        let errors = parse_csv("recipients.csv", &contents).unwrap_err();
+        let lines: Vec<usize> = errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![3, 4]);
+
+        let contents = format!("{},0.5,vip\n\"{}\",,\"a, b\"\n", valid, valid);
+        let recipients = parse_csv("recipients.csv", &contents).unwrap();
+        assert_eq!(recipients[0].amount_lamports, Some(500_000_000));
+        assert_eq!(recipients[0].label.as_deref(), Some("vip"));
+        assert_eq!(recipients[1].amount_lamports, None);
+        assert_eq!(recipients[1].label.as_deref(), Some("a, b"));
+    }
+
+    #[test]
+    fn test_merge_dedupes_against_config() {
+        let a = Pubkey::new_unique().to_string();
+        let b = Pubkey::new_unique().to_string();
+
+        let list = merge(
+            std::slice::from_ref(&a),
+            vec![
+                Recipient::from_address(a.clone()),
+                Recipient::from_address(b.clone()),
+                Recipient::from_address(b.clone()),
+            ],
+        );
+
+        let addresses: Vec<&str> = list.recipients.iter().map(|r| r.address.as_str()).collect();
+        assert_eq!(addresses, vec![a.as_str(), b.as_str()]);
+        assert_eq!(list.from_config, 1);
+        assert_eq!(list.from_file, 1);
+        assert_eq!(list.duplicates_skipped, 2);
+    }
+
+    #[test]
+    fn test_parse_address_lines() {
+        let valid = Pubkey::new_unique().to_string();
+        let input = format!("{}\n\n  {}  \nbogus\n", valid, valid);
+
+        let errors = parse_address_lines("stdin", input.as_bytes()).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line, 4);
+    }
+
+    #[test]
+    fn test_missing_recipients_are_skipped() {
+        let sender = crate::SenderWallet {
+            address: Pubkey::new_unique().to_string(),
+            private_key: String::new(),
+            private_key_source: None,
+            amount_sol: None,
+            group: None,
+        };
+        let existing = Pubkey::new_unique().to_string();
+        let missing = Pubkey::new_unique().to_string();
+        let planned = [&existing, &missing, &existing]
+            .into_iter()
+            .map(|recipient| PlannedTransfer {
+                sender: sender.clone(),
+                recipient: recipient.clone(),
+                lamports: 1,
+                label: None,
+                create_token_account: false,
+            })
+            .collect();
+
+        let (kept, skipped) = retain_existing(planned, &HashSet::from([missing.clone()]));
+        assert_eq!(kept.len(), 2);
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].recipient, missing);
+    }
+
+    #[tokio::test]
+    async fn test_unchecked_recipients_are_failed_not_sent() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+
+        let planned = vec![PlannedTransfer {
+            sender: crate::SenderWallet {
+                address: Pubkey::new_unique().to_string(),
+                private_key: String::new(),
+                private_key_source: None,
+                amount_sol: None,
+                group: None,
+            },
+            recipient: Pubkey::new_unique().to_string(),
+            lamports: 1,
+            label: None,
+            create_token_account: false,
+        }];
+        let (kept, unverified) = SolTransfer::new(server.uri())
+            .drop_nonexistent_recipients(planned)
+            .await;
+        assert!(kept.is_empty());
+        assert_eq!(unverified.len(), 1);
+        assert!(
+            unverified[0]
+                .error
+                .as_ref()
+                .unwrap()
+                .contains("Could not check")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_multiple_accounts_null_means_missing() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(
+                serde_json::json!({ "method": "getMultipleAccounts" }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {
+                    "context": { "slot": 1 },
+                    "value": [
+                        null,
+                        { "lamports": 0 },
+                        { "lamports": 890880, "owner": "11111111111111111111111111111111" }
+                    ]
+                }
+            })))
+            .mount(&server)
+            .await;
+
+        let pubkeys = [
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+        let existence = SolTransfer::new(server.uri())
+            .verify_recipient_exists(&pubkeys)
+            .await
+            .unwrap();
+        assert_eq!(
+            existence,
+            [(pubkeys[0], false), (pubkeys[1], false), (pubkeys[2], true)]
+        );
+    }
+}
@@ -0,0 +1,318 @@
+use futures::StreamExt;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::time::Instant;
+
+use crate::history::{HistoryEntry, SignaturePageFetcher};
+use crate::tx_decode::{ParsedInstruction, ParsedTransaction};
+use crate::{PlannedTransfer, SolTransfer, TransferResult};
+
+// Signatures per recipient checked when `on_chain_dedup_lookback` isn't set
+pub(crate) const DEFAULT_LOOKBACK: usize = 100;
+
+// Recipients whose history is fetched at once; each check is one page plus a
+// `getTransaction` per memo match, so keep this low on rate-limited RPCs
+const CHECK_CONCURRENCY: usize = 4;
+
+// Memo that tags every transaction of a run, so a later run can find it
+pub(crate) fn run_memo(run_label: &str) -> String {
+    format!("sol-transfer:{}", run_label)
+}
+
+// Whether a signature listing entry could be one of this run's transfers.
+// `getSignaturesForAddress` reports memos as "[len] text", joined with "; ".
+fn mentions_memo(entry: &HistoryEntry, memo: &str) -> bool {
+    entry.err.is_none()
+        && entry
+            .memo
+            .as_deref()
+            .is_some_and(|memos| memos.contains(memo))
+}
+
+// Whether `transaction` carries `memo` and pays `recipient` from `sender`.
+// For token sends `destination` is the recipient's token account.
+fn pays(transaction: &ParsedTransaction, memo: &str, sender: &str, destination: &str) -> bool {
+    let tagged = transaction
+        .instructions
+        .iter()
+        .any(|instruction| matches!(instruction, ParsedInstruction::Memo(text) if text == memo));
+    let paid = transaction
+        .instructions
+        .iter()
+        .any(|instruction| match instruction {
+            ParsedInstruction::SystemTransfer {
+                source,
+                destination: to,
+                ..
+            } => source == sender && to == destination,
+            ParsedInstruction::SplTokenTransfer {
+                authority,
+                destination: to,
+                ..
+            } => authority.as_deref() == Some(sender) && to == destination,
+            _ => false,
+        });
+
+    transaction.err.is_none() && tagged && paid
+}
+
+impl SolTransfer {
+    // Where a transfer to `recipient` lands: the wallet itself, or its token account
+    fn payment_destination(&self, recipient: &str) -> String {
+        match (&self.spl_token, Pubkey::from_str(recipient)) {
+            (Some(mint), Ok(owner)) => mint.associated_token_address(&owner).to_string(),
+            _ => recipient.to_string(),
+        }
+    }
+
+    // Earlier transactions tagged with `memo` that already paid `recipient`, keyed by sender.
+    // A token transfer only touches the recipient's token account, not the wallet, so
+    // that's the address whose history is searched.
+    async fn prior_payments(
+        &self,
+        recipient: &str,
+        memo: &str,
+        lookback: usize,
+    ) -> Result<HashMap<String, String>, String> {
+        let destination = self.payment_destination(recipient);
+        let entries = self.fetch_page(&destination, None, None, lookback).await?;
+
+        let mut paid_by = HashMap::new();
+        for entry in entries.iter().filter(|entry| mentions_memo(entry, memo)) {
+            let transaction = self
+                .get_parsed_transaction(&entry.signature)
+                .await
+                .map_err(|e| e.to_string())?;
+            let senders =
+                transaction
+                    .instructions
+                    .iter()
+                    .filter_map(|instruction| match instruction {
+                        ParsedInstruction::SystemTransfer { source, .. } => Some(source),
+                        ParsedInstruction::SplTokenTransfer { authority, .. } => authority.as_ref(),
+                        _ => None,
+                    });
+            for sender in senders.filter(|sender| pays(&transaction, memo, sender, &destination)) {
+                paid_by
+                    .entry(sender.clone())
+                    .or_insert_with(|| entry.signature.clone());
+            }
+        }
+
+        Ok(paid_by)
+    }
+
+    // Look through each recipient's recent history for this run's memo and split off the
+    // transfers that were already paid. Those come back as results referencing the earlier
+    // signature; a recipient whose history can't be checked is kept and warned about.
+    pub(crate) async fn skip_already_paid(
+        &self,
+        planned: Vec<PlannedTransfer>,
+        memo: &str,
+        lookback: usize,
+    ) -> (Vec<PlannedTransfer>, Vec<TransferResult>) {
+        let recipients: HashSet<String> = planned
+            .iter()
+            .map(|transfer| transfer.recipient.clone())
+            .collect();
+        println!(
+            "🔎 Checking the last {} signatures of {} recipient(s) for earlier \"{}\" payments...",
+            lookback,
+            recipients.len(),
+            memo
+        );
+
+        let checks = futures::stream::iter(recipients)
+            .map(|recipient| async move {
+                let paid_by = self.prior_payments(&recipient, memo, lookback).await;
+                (recipient, paid_by)
+            })
+            .buffer_unordered(CHECK_CONCURRENCY)
+            .collect::<Vec<_>>()
+            .await;
+
+        let mut prior: HashMap<(String, String), String> = HashMap::new();
+        for (recipient, paid_by) in checks {
+            match paid_by {
+                Ok(paid_by) => {
+                    for (sender, signature) in paid_by {
+                        prior.insert((sender, recipient.clone()), signature);
+                    }
+                }
+                Err(e) => println!(
+                    "⚠️  Warning: Could not check {} for earlier payments: {}",
+                    recipient, e
+                ),
+            }
+        }
+
+        let (kept, already_paid) = split_already_paid(planned, &prior);
+        for result in &already_paid {
+            println!(
+                "⏭️  Skipping {} -> {}: already paid by {}",
+                result.from_address, result.to_address, result.signature
+            );
+        }
+        if !already_paid.is_empty() {
+            println!(
+                "⏭️  Skipped {} transfer(s) paid by an earlier run\n",
+                already_paid.len()
+            );
+        } else {
+            println!("✅ No earlier payments found\n");
+        }
+
+        (kept, already_paid)
+    }
+}
+
+// Separate transfers found in `prior` (keyed by sender and recipient) into results
+fn split_already_paid(
+    planned: Vec<PlannedTransfer>,
+    prior: &HashMap<(String, String), String>,
+) -> (Vec<PlannedTransfer>, Vec<TransferResult>) {
+    let mut kept = Vec::new();
+    let mut already_paid = Vec::new();
+
+    for transfer in planned {
+        let key = (transfer.sender.address.clone(), transfer.recipient.clone());
+        match prior.get(&key) {
+            Some(signature) => already_paid.push(TransferResult {
+                signature: signature.clone(),
+                already_paid: true,
+                error: None,
+                ..TransferResult::failed(
+                    transfer.sender.address,
+                    transfer.recipient,
+                    transfer.label,
+                    String::new(),
+                    Instant::now(),
+                )
+            }),
+            None => kept.push(transfer),
+        }
+    }
+
+    (kept, already_paid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SenderWallet;
+    use crate::spl::SplMint;
+    use wiremock::matchers::{body_partial_json, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn transfer(source: &str, destination: &str) -> ParsedInstruction {
+        ParsedInstruction::SystemTransfer {
+            source: source.to_string(),
+            destination: destination.to_string(),
+            lamports: 1_000_000,
+        }
+    }
+
+    fn transaction(instructions: Vec<ParsedInstruction>) -> ParsedTransaction {
+        ParsedTransaction {
+            signature: "sig".to_string(),
+            slot: 1,
+            fee: Some(5000),
+            err: None,
+            instructions,
+            log_events: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_payment_needs_memo_sender_and_recipient() {
+        let memo = run_memo("payroll");
+        let tagged = transaction(vec![
+            transfer("S", "R"),
+            ParsedInstruction::Memo(memo.clone()),
+        ]);
+
+        assert!(pays(&tagged, &memo, "S", "R"));
+        assert!(!pays(&tagged, &memo, "other", "R"));
+        assert!(!pays(&tagged, &memo, "S", "other"));
+        assert!(!pays(&tagged, &run_memo("bonus"), "S", "R"));
+        assert!(!pays(
+            &transaction(vec![transfer("S", "R")]),
+            &memo,
+            "S",
+            "R"
+        ));
+    }
+
+    #[test]
+    fn test_memo_listing_match() {
+        let entry: HistoryEntry = serde_json::from_str(
+            r#"{"signature": "sig", "slot": 1, "err": null, "memo": "[20] sol-transfer:payroll", "blockTime": null, "confirmationStatus": "finalized"}"#,
+        )
+        .unwrap();
+
+        assert!(mentions_memo(&entry, &run_memo("payroll")));
+        assert!(!mentions_memo(&entry, &run_memo("bonus")));
+    }
+
+    #[tokio::test]
+    async fn test_token_runs_search_the_recipient_token_account() {
+        let mint = SplMint {
+            mint: Pubkey::new_unique(),
+            decimals: 6,
+        };
+        let recipient = Pubkey::new_unique();
+        let token_account = mint.associated_token_address(&recipient);
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(serde_json::json!({
+                "method": "getSignaturesForAddress",
+                "params": [token_account.to_string()],
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": []
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let paid_by = SolTransfer::new(server.uri())
+            .with_spl_token(Some(mint))
+            .prior_payments(&recipient.to_string(), &run_memo("payroll"), 10)
+            .await
+            .unwrap();
+        assert!(paid_by.is_empty());
+    }
+
+    #[test]
+    fn test_already_paid_transfers_reference_prior_signature() {
+        let sender = SenderWallet {
+            address: "S".to_string(),
+            private_key: String::new(),
+            private_key_source: None,
+            amount_sol: None,
+            group: None,
+        };
+        let planned = ["R1", "R2"]
+            .into_iter()
+            .map(|recipient| PlannedTransfer {
+                sender: sender.clone(),
+                recipient: recipient.to_string(),
+                lamports: 1,
+                label: None,
+                create_token_account: false,
+            })
+            .collect();
+        let prior = HashMap::from([(("S".to_string(), "R2".to_string()), "old".to_string())]);
+
+        let (kept, already_paid) = split_already_paid(planned, &prior);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].recipient, "R1");
+        assert_eq!(already_paid[0].signature, "old");
+        assert!(already_paid[0].already_paid);
+        assert!(already_paid[0].error.is_none());
+    }
+}
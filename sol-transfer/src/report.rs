@@ -0,0 +1,145 @@
+use std::collections::BTreeMap;
+use std::fs;
+
+use crate::chunking::{self, ChunkStats};
+use crate::{SolTransfer, TransferOutcome, TransferResult};
+
+const UNLABELLED: &str = "(unlabelled)";
+
+// Group results by label, unlabelled transfers last
+pub(crate) fn group_by_label(results: &[TransferResult]) -> Vec<(String, Vec<&TransferResult>)> {
+    let mut groups: BTreeMap<&str, Vec<&TransferResult>> = BTreeMap::new();
+    let mut unlabelled = Vec::new();
+
+    for result in results {
+        match &result.label {
+            Some(label) => groups.entry(label.as_str()).or_default().push(result),
+            None => unlabelled.push(result),
+        }
+    }
+
+    let mut grouped: Vec<_> = groups
+        .into_iter()
+        .map(|(label, group)| (label.to_string(), group))
+        .collect();
+    if !unlabelled.is_empty() {
+        grouped.push((UNLABELLED.to_string(), unlabelled));
+    }
+    grouped
+}
+
+// Quote a CSV field when it contains a delimiter, quote or newline
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// Write one row per transfer
+pub(crate) fn export_csv(
+    sol_transfer: &SolTransfer,
+    results: &[TransferResult],
+    path: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut csv = String::from(
+        "from,to,label,signature,outcome,slot,block_time,confirmation_level,confirmation_time_ms,processing_time_ms,created_token_account,error\n",
+    );
+
+    for result in results {
+        let row = [
+            result.from_address.clone(),
+            result.to_address.clone(),
+            result.label.clone().unwrap_or_default(),
+            result.signature.clone(),
+            sol_transfer.outcome(result).as_str().to_string(),
+            result
+                .status
+                .as_ref()
+                .map(|s| s.slot.to_string())
+                .unwrap_or_default(),
+            result.block_time.map(|t| t.to_string()).unwrap_or_default(),
+            result
+                .reached_level
+                .map(|l| l.to_string())
+                .unwrap_or_default(),
+            result
+                .confirmation_time
+                .map(|t| t.as_millis().to_string())
+                .unwrap_or_default(),
+            result.processing_time.as_millis().to_string(),
+            result.created_token_account.to_string(),
+            result.error.clone().unwrap_or_default(),
+        ];
+        let row: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+
+    fs::write(path, csv)?;
+    Ok(())
+}
+
+fn outcome_counts(sol_transfer: &SolTransfer, results: &[&TransferResult]) -> serde_json::Value {
+    let count = |outcome: TransferOutcome| {
+        results
+            .iter()
+            .filter(|r| sol_transfer.outcome(r) == outcome)
+            .count()
+    };
+
+    serde_json::json!({
+        "total": results.len(),
+        "success": count(TransferOutcome::Success),
+        "failed": count(TransferOutcome::Failed),
+        "transaction_failed": count(TransferOutcome::TransactionFailed),
+        "timed_out": count(TransferOutcome::TimedOut),
+        "pending": count(TransferOutcome::Pending),
+        "already_paid": count(TransferOutcome::AlreadyPaid),
+    })
+}
+
+// Write run totals, per-label totals, chunk totals and every transfer as JSON
+pub(crate) fn write_json_summary(
+    sol_transfer: &SolTransfer,
+    results: &[TransferResult],
+    chunks: &[ChunkStats],
+    path: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let all: Vec<&TransferResult> = results.iter().collect();
+
+    let by_label: serde_json::Map<String, serde_json::Value> = group_by_label(results)
+        .into_iter()
+        .map(|(label, group)| (label, outcome_counts(sol_transfer, &group)))
+        .collect();
+
+    let transfers: Vec<serde_json::Value> = results
+        .iter()
+        .map(|result| {
+            serde_json::json!({
+                "from": result.from_address,
+                "to": result.to_address,
+                "label": result.label,
+                "signature": result.signature,
+                "outcome": sol_transfer.outcome(result).as_str(),
+                "slot": result.status.as_ref().map(|s| s.slot),
+                "confirmation_level": result.reached_level.map(|l| l.to_string()),
+                "confirmation_time_ms": result.confirmation_time.map(|t| t.as_millis() as u64),
+                "processing_time_ms": result.processing_time.as_millis() as u64,
+                "created_token_account": result.created_token_account,
+                "error": result.error,
+            })
+        })
+        .collect();
+
+    let summary = serde_json::json!({
+        "totals": outcome_counts(sol_transfer, &all),
+        "by_label": by_label,
+        "chunks": chunking::chunks_json(chunks),
+        "transfers": transfers,
+    });
+
+    fs::write(path, serde_json::to_string_pretty(&summary)?)?;
+    Ok(())
+}
@@ -0,0 +1,227 @@
+use serde::Deserialize;
+use solana_sdk::{hash::Hash, signature::Keypair, transaction::Transaction};
+use std::time::{Duration, Instant};
+
+use crate::{SignatureStatus, SolTransfer};
+
+// Times a transaction is re-signed after its blockhash expired before it landed
+pub(crate) const MAX_STALE_BLOCKHASH_RETRIES: usize = 2;
+
+// How often the old blockhash is checked while waiting for it to expire
+const BLOCKHASH_POLL_INTERVAL: Duration = Duration::from_secs(2);
+// A blockhash lasts 150 blocks, roughly a minute; stop waiting well after that
+const MAX_BLOCKHASH_WAIT: Duration = Duration::from_secs(180);
+
+#[derive(Debug, Deserialize)]
+struct BlockhashValidResult {
+    value: bool,
+}
+
+// A signature the cluster already has a status for
+#[derive(Debug, Clone)]
+pub(crate) struct ConfirmedStatus {
+    pub(crate) status: SignatureStatus,
+    // Only found by searching the full transaction history
+    pub(crate) from_history: bool,
+}
+
+// Send errors meaning the transaction's blockhash is too old to land
+pub(crate) fn is_stale_blockhash_error(error: &str) -> bool {
+    error.contains("Blockhash not found") || error.contains("BlockhashNotFound")
+}
+
+// The same message under `blockhash`, signed again. The signature changes with it.
+fn resign(
+    transaction: &Transaction,
+    signer: &Keypair,
+    blockhash: Hash,
+) -> Result<Transaction, Box<dyn std::error::Error>> {
+    let mut resigned = Transaction::new_unsigned(transaction.message.clone());
+    resigned.try_sign(&[signer], blockhash)?;
+    Ok(resigned)
+}
+
+impl SolTransfer {
+    // Whether a previous attempt already landed, so re-sending it would pay twice.
+    // Recent statuses are checked first; the full history search is much slower and
+    // only runs when the signature isn't among them.
+    pub(crate) async fn check_if_already_confirmed(
+        &self,
+        signature: &str,
+    ) -> Result<Option<ConfirmedStatus>, Box<dyn std::error::Error>> {
+        if let Some(status) = self.get_signature_status(signature, false).await? {
+            return Ok(Some(ConfirmedStatus {
+                status,
+                from_history: false,
+            }));
+        }
+        Ok(self
+            .get_signature_status(signature, true)
+            .await?
+            .map(|status| ConfirmedStatus {
+                status,
+                from_history: true,
+            }))
+    }
+
+    // Whether a transaction using `blockhash` can still land
+    pub(crate) async fn is_blockhash_valid(
+        &self,
+        blockhash: &Hash,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        let result: BlockhashValidResult = self
+            .rpc_call(
+                "isBlockhashValid",
+                vec![
+                    serde_json::json!(blockhash.to_string()),
+                    serde_json::json!({ "commitment": "processed" }),
+                ],
+            )
+            .await?;
+        Ok(result.value)
+    }
+
+    // Wait until `blockhash` has expired, so an attempt signed with it can no longer
+    // land next to its re-signed copy. Fails if it outlives `MAX_BLOCKHASH_WAIT` or
+    // the run is cancelled first.
+    pub(crate) async fn wait_for_blockhash_expiry(
+        &self,
+        blockhash: &Hash,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let start = Instant::now();
+        while self.is_blockhash_valid(blockhash).await? {
+            if self.is_cancelled() {
+                return Err("cancelled while waiting for the blockhash to expire".into());
+            }
+            if start.elapsed() >= MAX_BLOCKHASH_WAIT {
+                return Err(format!(
+                    "blockhash {} still valid after {}s",
+                    blockhash,
+                    MAX_BLOCKHASH_WAIT.as_secs()
+                )
+                .into());
+            }
+            tokio::time::sleep(BLOCKHASH_POLL_INTERVAL).await;
+        }
+        Ok(())
+    }
+
+    // Re-sign `transaction` with the latest blockhash
+    pub(crate) async fn resign_with_fresh_blockhash(
+        &self,
+        transaction: &Transaction,
+        signer: &Keypair,
+    ) -> Result<Transaction, Box<dyn std::error::Error>> {
+        let blockhash = self.get_recent_blockhash().await?;
+        resign(transaction, signer, blockhash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{signature::Signer, system_instruction};
+    use wiremock::matchers::{body_partial_json, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn statuses(value: serde_json::Value) -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": { "context": { "slot": 100 }, "value": [value] }
+        }))
+    }
+
+    #[test]
+    fn test_resign_keeps_message_under_new_blockhash() {
+        let payer = Keypair::new();
+        let transaction = Transaction::new_signed_with_payer(
+            &[system_instruction::transfer(
+                &payer.pubkey(),
+                &Keypair::new().pubkey(),
+                1,
+            )],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::new_unique(),
+        );
+
+        let blockhash = Hash::new_unique();
+        let resigned = resign(&transaction, &payer, blockhash).unwrap();
+        assert_eq!(resigned.message.recent_blockhash, blockhash);
+        assert_eq!(
+            resigned.message.instructions,
+            transaction.message.instructions
+        );
+        assert_ne!(resigned.signatures, transaction.signatures);
+        assert!(resigned.verify().is_ok());
+    }
+
+    #[test]
+    fn test_stale_blockhash_errors() {
+        assert!(is_stale_blockhash_error(
+            "Failed to send transaction: RPC Error: -32002 - Transaction simulation failed: Blockhash not found"
+        ));
+        assert!(!is_stale_blockhash_error(
+            "Failed to send transaction: RPC Error: -32002 - insufficient funds"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_history_is_searched_only_when_recent_status_is_missing() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(serde_json::json!({
+                "method": "getSignatureStatuses",
+                "params": [["sig"], { "searchTransactionHistory": false }]
+            })))
+            .respond_with(statuses(serde_json::Value::Null))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(serde_json::json!({
+                "method": "getSignatureStatuses",
+                "params": [["sig"], { "searchTransactionHistory": true }]
+            })))
+            .respond_with(statuses(serde_json::json!({
+                "slot": 90,
+                "confirmations": null,
+                "err": null,
+                "confirmationStatus": "finalized"
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let found = SolTransfer::new(server.uri())
+            .check_if_already_confirmed("sig")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(found.from_history);
+        assert_eq!(found.status.slot, 90);
+    }
+
+    #[tokio::test]
+    async fn test_expired_blockhash_needs_no_wait() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(
+                serde_json::json!({ "method": "isBlockhashValid" }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": { "context": { "slot": 100 }, "value": false }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        SolTransfer::new(server.uri())
+            .wait_for_blockhash_expiry(&Hash::new_unique())
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,151 @@
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::collections::HashMap;
+
+use crate::{SolTransfer, rpc_logger::RpcLogger};
+
+// Replace every `${VAR}` in `value` with that environment variable
+fn interpolate_env(value: &str) -> Result<String, String> {
+    let mut output = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(start) = rest.find("${") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after
+            .find('}')
+            .ok_or_else(|| "unterminated ${...} reference".to_string())?;
+        let var = &after[..end];
+        let resolved =
+            std::env::var(var).map_err(|_| format!("environment variable {} is not set", var))?;
+        output.push_str(&resolved);
+        rest = &after[end + 1..];
+    }
+    output.push_str(rest);
+
+    Ok(output)
+}
+
+// Build the headers sent with every RPC request. Values may reference environment
+// variables as `${VAR}`; errors name the header and variable but never the value.
+pub(crate) fn resolve_headers(headers: &HashMap<String, String>) -> Result<HeaderMap, String> {
+    let mut resolved = HeaderMap::new();
+
+    for (name, value) in headers {
+        let header_name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| format!("invalid RPC header name '{}'", name))?;
+        let value = interpolate_env(value).map_err(|e| format!("RPC header '{}': {}", name, e))?;
+        let mut header_value = HeaderValue::from_str(&value)
+            .map_err(|_| format!("RPC header '{}' has an invalid value", name))?;
+        // Keep credentials out of debug output
+        header_value.set_sensitive(true);
+
+        resolved.insert(header_name, header_value);
+    }
+
+    Ok(resolved)
+}
+
+impl SolTransfer {
+    // Send `headers` (e.g. `Authorization: Bearer ...`) with every RPC request
+    pub fn with_rpc_headers(
+        mut self,
+        headers: HeaderMap,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        self.rpc_logger = RpcLogger::new(
+            reqwest::Client::builder()
+                .default_headers(headers)
+                .build()?,
+        );
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{
+        hash::Hash,
+        signature::{Keypair, Signer},
+        system_instruction,
+        transaction::Transaction,
+    };
+    use wiremock::matchers::{body_partial_json, header, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_interpolate_env() {
+        // SAFETY: the variable name is unique to this test
+        unsafe { std::env::set_var("SOL_TRANSFER_TEST_RPC_TOKEN", "s3cret") };
+
+        assert_eq!(
+            interpolate_env("Bearer ${SOL_TRANSFER_TEST_RPC_TOKEN}").unwrap(),
+            "Bearer s3cret"
+        );
+        assert_eq!(interpolate_env("plain").unwrap(), "plain");
+        assert!(interpolate_env("Bearer ${SOL_TRANSFER_TEST_UNSET_VAR}").is_err());
+        assert!(interpolate_env("Bearer ${OPEN").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_headers_sent_on_every_rpc_call() {
+        let server = MockServer::start().await;
+        let blockhash = Hash::new_unique();
+
+        Mock::given(method("POST"))
+            .and(header("authorization", "Bearer test-token"))
+            .and(body_partial_json(
+                serde_json::json!({ "method": "getLatestBlockhash" }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {
+                    "context": { "slot": 1 },
+                    "value": { "blockhash": blockhash.to_string(), "lastValidBlockHeight": 100 }
+                }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(header("authorization", "Bearer test-token"))
+            .and(body_partial_json(serde_json::json!({ "method": "sendTransaction" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": "5VERv8NMvzbJMEkV8xnrLkEaWRtSz9CosKDYjCJjBRnbJLgp8uirBgmQpjKhoR4tjF3ZpRzrFmBV6UjKdiSZkQUW"
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let headers = resolve_headers(&HashMap::from([(
+            "Authorization".to_string(),
+            "Bearer test-token".to_string(),
+        )]))
+        .unwrap();
+        let sol_transfer = SolTransfer::new(server.uri())
+            .with_rpc_headers(headers)
+            .unwrap();
+
+        let recent_blockhash = sol_transfer.get_recent_blockhash().await.unwrap();
+        assert_eq!(recent_blockhash, blockhash);
+
+        let payer = Keypair::new();
+        let transaction = Transaction::new_signed_with_payer(
+            &[system_instruction::transfer(
+                &payer.pubkey(),
+                &Keypair::new().pubkey(),
+                1,
+            )],
+            Some(&payer.pubkey()),
+            &[&payer],
+            recent_blockhash,
+        );
+        sol_transfer
+            .send_transaction_with_preflight_error_parsing(&transaction)
+            .await
+            .unwrap();
+    }
+}
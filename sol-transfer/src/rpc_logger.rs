@@ -0,0 +1,135 @@
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+use std::time::Instant;
+
+use crate::{JsonRpcRequest, JsonRpcResponse};
+
+// The HTTP client every JSON RPC call goes through, logging each call and how long
+// it took so latency can be broken down per method
+pub(crate) struct RpcLogger {
+    client: Client,
+}
+
+impl RpcLogger {
+    pub(crate) fn new(client: Client) -> Self {
+        Self { client }
+    }
+
+    // POST `request` to `url` and decode the response. The call is logged at debug
+    // before sending, and its outcome and latency at info once it returns.
+    pub(crate) async fn post<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        request: &JsonRpcRequest,
+    ) -> Result<JsonRpcResponse<T>, Box<dyn std::error::Error>> {
+        tracing::debug!(
+            method = %request.method,
+            id = request.id,
+            params = %serde_json::Value::from(request.params.clone()),
+            "RPC request"
+        );
+        let start = Instant::now();
+        let response = self.send(url, request).await;
+        let elapsed_ms = start.elapsed().as_millis() as u64;
+
+        match &response {
+            Ok(response) => match &response.error {
+                None => tracing::info!(
+                    method = %request.method,
+                    elapsed_ms,
+                    result = "success",
+                    "RPC call"
+                ),
+                Some(error) => tracing::info!(
+                    method = %request.method,
+                    elapsed_ms,
+                    result = "error",
+                    code = error.code,
+                    "RPC call"
+                ),
+            },
+            Err(e) => tracing::info!(
+                method = %request.method,
+                elapsed_ms,
+                result = "error",
+                error = %e,
+                "RPC call"
+            ),
+        }
+        response
+    }
+
+    async fn send<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        request: &JsonRpcRequest,
+    ) -> Result<JsonRpcResponse<T>, Box<dyn std::error::Error>> {
+        let response = self
+            .client
+            .post(url)
+            .header("Content-Type", "application/json")
+            .json(request)
+            .send()
+            .await?;
+        Ok(response.json().await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn request(method: &str) -> JsonRpcRequest {
+        JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: 7,
+            method: method.to_string(),
+            params: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_post_returns_results_and_rpc_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(
+                serde_json::json!({ "method": "getSlot" }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "jsonrpc": "2.0", "id": 7, "result": 42
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(
+                serde_json::json!({ "method": "getBalance" }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "jsonrpc": "2.0", "id": 7,
+                "error": { "code": -32602, "message": "Invalid params" }
+            })))
+            .mount(&server)
+            .await;
+
+        let logger = RpcLogger::new(Client::new());
+        let slot: JsonRpcResponse<u64> = logger
+            .post(&server.uri(), &request("getSlot"))
+            .await
+            .unwrap();
+        assert_eq!(slot.result, Some(42));
+
+        let balance: JsonRpcResponse<u64> = logger
+            .post(&server.uri(), &request("getBalance"))
+            .await
+            .unwrap();
+        assert_eq!(balance.error.unwrap().code, -32602);
+        assert!(
+            logger
+                .post::<u64>("http://127.0.0.1:1", &request("getSlot"))
+                .await
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,190 @@
+use serde::{Deserialize, Serialize};
+use solana_sdk::hash::hashv;
+use std::fs;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::PlannedTransfer;
+
+pub(crate) const DEFAULT_MARKER_PATH: &str = ".sol-transfer-run.json";
+pub(crate) const DEFAULT_DUPLICATE_WINDOW_HOURS: u64 = 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum RunOutcome {
+    Running,
+    Succeeded,
+    Failed,
+    Aborted,
+}
+
+// What the marker file records about the latest run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct RunMarker {
+    pub(crate) fingerprint: String,
+    pub(crate) label: Option<String>,
+    pub(crate) started_at: u64,
+    pub(crate) outcome: RunOutcome,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+fn format_age(secs: u64) -> String {
+    let (hours, minutes) = (secs / 3600, secs % 3600 / 60);
+    if hours > 0 {
+        format!("{}h {}m ago", hours, minutes)
+    } else {
+        format!("{}m ago", minutes)
+    }
+}
+
+// Identify a run by the cluster and exactly who gets paid how much, independent of
+// the order transfers were planned in
+pub(crate) fn fingerprint(rpc_url: &str, planned: &[PlannedTransfer]) -> String {
+    let mut legs: Vec<String> = planned
+        .iter()
+        .map(|t| format!("{}>{}:{}", t.sender.address, t.recipient, t.lamports))
+        .collect();
+    legs.sort();
+
+    let mut parts: Vec<&[u8]> = vec![rpc_url.as_bytes()];
+    parts.extend(legs.iter().map(|leg| leg.as_bytes()));
+    hashv(&parts).to_string()
+}
+
+// A previous run with the same fingerprint inside the window, described for the user
+fn duplicate_of(
+    previous: &RunMarker,
+    fingerprint: &str,
+    window: Duration,
+    now: u64,
+) -> Option<String> {
+    let age = now.saturating_sub(previous.started_at);
+    if previous.fingerprint != fingerprint || age >= window.as_secs() {
+        return None;
+    }
+
+    let outcome = match previous.outcome {
+        RunOutcome::Running => "did not finish",
+        RunOutcome::Succeeded => "succeeded",
+        RunOutcome::Failed => "failed",
+        RunOutcome::Aborted => "was aborted",
+    };
+    let label = previous
+        .label
+        .as_deref()
+        .map(|label| format!(" (label '{}')", label))
+        .unwrap_or_default();
+
+    Some(format!(
+        "an identical run{} started {} and {}",
+        label,
+        format_age(age),
+        outcome
+    ))
+}
+
+fn write(path: &str, marker: &RunMarker) -> Result<(), Box<dyn std::error::Error>> {
+    fs::write(path, serde_json::to_string_pretty(marker)?)?;
+    Ok(())
+}
+
+// Refuse to start when the marker shows the same run inside the window,
+// otherwise record this run as started
+pub(crate) fn start(
+    path: &str,
+    fingerprint: String,
+    label: Option<String>,
+    window: Duration,
+    allow_duplicate: bool,
+) -> Result<RunMarker, Box<dyn std::error::Error>> {
+    let now = now_secs();
+
+    // An unreadable or corrupt marker is not a reason to block a run
+    let previous = fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str::<RunMarker>(&contents).ok());
+    if let Some(duplicate) = previous.and_then(|p| duplicate_of(&p, &fingerprint, window, now)) {
+        if !allow_duplicate {
+            return Err(format!(
+                "refusing to run: {} (pass --allow-duplicate-run to run anyway)",
+                duplicate
+            )
+            .into());
+        }
+        println!("⚠️  Warning: {}; running anyway\n", duplicate);
+    }
+
+    let marker = RunMarker {
+        fingerprint,
+        label,
+        started_at: now,
+        outcome: RunOutcome::Running,
+    };
+    write(path, &marker)?;
+    Ok(marker)
+}
+
+// Record how the run ended
+pub(crate) fn finish(
+    path: &str,
+    mut marker: RunMarker,
+    outcome: RunOutcome,
+) -> Result<(), Box<dyn std::error::Error>> {
+    marker.outcome = outcome;
+    write(path, &marker)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn marker(fingerprint: &str, started_at: u64, outcome: RunOutcome) -> RunMarker {
+        RunMarker {
+            fingerprint: fingerprint.to_string(),
+            label: Some("airdrop-1".to_string()),
+            started_at,
+            outcome,
+        }
+    }
+
+    #[test]
+    fn test_duplicate_only_within_window() {
+        let window = Duration::from_secs(24 * 3600);
+        let now = 1_700_100_000;
+        let recent = marker("abc", now - 2 * 3600, RunOutcome::Succeeded);
+
+        let message = duplicate_of(&recent, "abc", window, now).unwrap();
+        assert!(message.contains("airdrop-1"));
+        assert!(message.contains("2h 0m ago"));
+        assert!(message.contains("succeeded"));
+
+        assert!(duplicate_of(&recent, "other", window, now).is_none());
+
+        let old = marker("abc", now - 25 * 3600, RunOutcome::Failed);
+        assert!(duplicate_of(&old, "abc", window, now).is_none());
+    }
+
+    #[test]
+    fn test_refuses_duplicate_unless_allowed() {
+        let path = std::env::temp_dir().join(format!(
+            "sol-transfer-marker-{}.json",
+            solana_sdk::pubkey::Pubkey::new_unique()
+        ));
+        let path = path.to_str().unwrap();
+        let window = Duration::from_secs(3600);
+
+        let first = start(path, "abc".to_string(), None, window, false).unwrap();
+        finish(path, first, RunOutcome::Failed).unwrap();
+
+        let error = start(path, "abc".to_string(), None, window, false).unwrap_err();
+        assert!(error.to_string().contains("failed"));
+        assert!(start(path, "abc".to_string(), None, window, true).is_ok());
+        assert!(start(path, "def".to_string(), None, window, false).is_ok());
+
+        fs::remove_file(path).unwrap();
+    }
+}
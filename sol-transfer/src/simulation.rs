@@ -0,0 +1,411 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::Deserialize;
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction,
+    instruction::{AccountMeta, Instruction},
+    message::Message,
+    packet::PACKET_DATA_SIZE,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use std::collections::HashMap;
+
+use crate::{PreparedTransfer, SolTransfer, batching, preflight};
+
+// Headroom added to the simulated compute unit consumption, in percent
+const COMPUTE_UNIT_MARGIN_PERCENT: u64 = 10;
+// The most compute units a transaction may request
+pub(crate) const MAX_COMPUTE_UNIT_LIMIT: u64 = 1_400_000;
+
+// Transaction errors that will fail the same way when sent for real.
+// Anything else (node behind, blockhash churn) is treated as transient.
+const PERMANENT_ERRORS: [&str; 8] = [
+    "InsufficientFundsForFee",
+    "InsufficientFundsForRent",
+    "AccountNotFound",
+    "ProgramAccountNotFound",
+    "InvalidAccountForFee",
+    "InvalidAccountIndex",
+    "AccountLoadedTwice",
+    "InvalidProgramForExecution",
+];
+
+#[derive(Debug, Deserialize)]
+struct SimulationResult {
+    value: SimulationValue,
+}
+
+#[derive(Debug, Deserialize)]
+struct SimulationValue {
+    err: Option<serde_json::Value>,
+    logs: Option<Vec<String>>,
+    #[serde(rename = "unitsConsumed")]
+    units_consumed: Option<u64>,
+}
+
+// Why a transaction was dropped before sending
+#[derive(Debug, Clone)]
+pub(crate) struct SimulationVeto {
+    pub(crate) error: String,
+    pub(crate) logs: Vec<String>,
+}
+
+fn is_permanent(err: &serde_json::Value) -> bool {
+    match err {
+        serde_json::Value::String(name) => PERMANENT_ERRORS.contains(&name.as_str()),
+        // Instruction failures (e.g. the system program's insufficient lamports) replay identically
+        serde_json::Value::Object(fields) => {
+            fields.contains_key("InstructionError")
+                || fields.contains_key("InsufficientFundsForRent")
+        }
+        _ => false,
+    }
+}
+
+// A veto for a simulation that failed in a way sending can't fix
+fn veto_for(value: SimulationValue) -> Option<SimulationVeto> {
+    let err = value.err?;
+    if !is_permanent(&err) {
+        return None;
+    }
+
+    let logs = value.logs.unwrap_or_default();
+    let log_lines: Vec<&str> = logs.iter().map(String::as_str).collect();
+    let error = match preflight::parse_transaction_error(&err, &log_lines) {
+        Some(parsed) => format!("Simulation failed: {}", parsed),
+        None => format!("Simulation failed: {}", err),
+    };
+
+    Some(SimulationVeto { error, logs })
+}
+
+// Simulated consumption plus the safety margin, within the per-transaction maximum
+fn compute_unit_limit(units_consumed: u64) -> u32 {
+    (units_consumed * (100 + COMPUTE_UNIT_MARGIN_PERCENT))
+        .div_ceil(100)
+        .min(MAX_COMPUTE_UNIT_LIMIT) as u32
+}
+
+// The instructions a compiled message was built from
+fn instructions_of(message: &Message) -> Vec<Instruction> {
+    message
+        .instructions
+        .iter()
+        .map(|instruction| Instruction {
+            program_id: message.account_keys[instruction.program_id_index as usize],
+            accounts: instruction
+                .accounts
+                .iter()
+                .map(|&index| {
+                    let index = index as usize;
+                    AccountMeta {
+                        pubkey: message.account_keys[index],
+                        is_signer: message.is_signer(index),
+                        is_writable: message.is_maybe_writable(index, None),
+                    }
+                })
+                .collect(),
+            data: instruction.data.clone(),
+        })
+        .collect()
+}
+
+// `transaction` with a `SetComputeUnitLimit` in front, signed again by `signer`
+fn with_compute_unit_limit(
+    transaction: &Transaction,
+    signer: &Keypair,
+    units: u32,
+) -> Result<Transaction, String> {
+    let mut instructions = vec![ComputeBudgetInstruction::set_compute_unit_limit(units)];
+    instructions.extend(instructions_of(&transaction.message));
+    if batching::transaction_wire_size(&signer.pubkey(), &instructions) > PACKET_DATA_SIZE {
+        return Err("no room left for a compute unit limit".to_string());
+    }
+
+    Ok(Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&signer.pubkey()),
+        &[signer],
+        transaction.message.recent_blockhash,
+    ))
+}
+
+impl SolTransfer {
+    async fn simulate_transaction(
+        &self,
+        transaction: &Transaction,
+    ) -> Result<SimulationValue, Box<dyn std::error::Error>> {
+        let encoded = STANDARD.encode(bincode::serialize(transaction)?);
+        let result: SimulationResult = self
+            .rpc_call(
+                "simulateTransaction",
+                vec![
+                    serde_json::json!(encoded),
+                    serde_json::json!({
+                        "encoding": "base64",
+                        "sigVerify": false,
+                        "replaceRecentBlockhash": true,
+                        "commitment": "confirmed",
+                    }),
+                ],
+            )
+            .await?;
+        Ok(result.value)
+    }
+
+    // Compute units `transaction` needs, from a simulation plus a safety margin.
+    // Signatures aren't checked and the blockhash is replaced, so any built
+    // transaction can be measured.
+    pub(crate) async fn estimate_compute_units(
+        &self,
+        transaction: &Transaction,
+    ) -> Result<u32, Box<dyn std::error::Error>> {
+        let value = self.simulate_transaction(transaction).await?;
+        if let Some(err) = value.err {
+            return Err(format!("simulation failed: {}", err).into());
+        }
+        let units_consumed = value
+            .units_consumed
+            .ok_or("simulation did not report unitsConsumed")?;
+        Ok(compute_unit_limit(units_consumed))
+    }
+
+    // Give every built transaction a compute unit limit sized from its simulation.
+    // What's simulated is the final instruction set, `SetComputeUnitLimit` included,
+    // so the limit covers that instruction's own cost. Transactions that can't be
+    // measured or re-signed go out unchanged.
+    pub(crate) async fn apply_compute_unit_limits(&self, prepared: &mut [PreparedTransfer]) {
+        let estimates = futures::future::join_all(prepared.iter().map(|transfer| async move {
+            let (Ok(transaction), Some(signer)) = (&transfer.transaction, &transfer.signer) else {
+                return None;
+            };
+            let measured =
+                match with_compute_unit_limit(transaction, signer, MAX_COMPUTE_UNIT_LIMIT as u32) {
+                    Ok(measured) => measured,
+                    Err(e) => return Some(Err(e)),
+                };
+            Some(
+                self.estimate_compute_units(&measured)
+                    .await
+                    .map_err(|e| e.to_string()),
+            )
+        }))
+        .await;
+
+        for (transfer, estimate) in prepared.iter_mut().zip(estimates) {
+            let (Ok(transaction), Some(signer)) = (&transfer.transaction, &transfer.signer) else {
+                continue;
+            };
+            let limited = match estimate {
+                Some(Ok(units)) => with_compute_unit_limit(transaction, signer, units),
+                Some(Err(e)) => Err(e),
+                None => continue,
+            };
+            match limited {
+                Ok(limited) => transfer.transaction = Ok(limited),
+                Err(e) => self.notice(format!(
+                    "⚠️  Warning: Sending {} without a compute unit limit: {}",
+                    transfer.from_address, e
+                )),
+            }
+        }
+    }
+
+    // Simulate every built transaction concurrently and return, by index into `prepared`,
+    // those that should not be sent. A simulation that can't run never blocks a send.
+    pub(crate) async fn pre_send_vetoes(
+        &self,
+        prepared: &[PreparedTransfer],
+    ) -> HashMap<usize, SimulationVeto> {
+        let simulations = prepared.iter().enumerate().filter_map(|(index, transfer)| {
+            let transaction = transfer.transaction.as_ref().ok()?;
+            Some(async move { (index, self.simulate_transaction(transaction).await) })
+        });
+
+        futures::future::join_all(simulations)
+            .await
+            .into_iter()
+            .filter_map(|(index, simulation)| match simulation {
+                Ok(value) => veto_for(value).map(|veto| (index, veto)),
+                Err(e) => {
+                    self.notice(format!(
+                        "⚠️  Warning: Simulation unavailable, sending anyway: {}",
+                        e
+                    ));
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{hash::Hash, system_instruction};
+    use std::{sync::Arc, time::Instant};
+    use wiremock::matchers::{body_partial_json, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn transfer_transaction(payer: &Keypair) -> Transaction {
+        Transaction::new_signed_with_payer(
+            &[system_instruction::transfer(
+                &payer.pubkey(),
+                &Keypair::new().pubkey(),
+                1_000_000,
+            )],
+            Some(&payer.pubkey()),
+            &[payer],
+            Hash::new_unique(),
+        )
+    }
+
+    fn simulation(json: &str) -> SimulationValue {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_insufficient_lamports_is_vetoed_with_logs() {
+        let veto = veto_for(simulation(
+            r#"{
+                "err": {"InstructionError": [0, {"Custom": 1}]},
+                "logs": [
+                    "Program 11111111111111111111111111111111 invoke [1]",
+                    "Transfer: insufficient lamports 0, need 1000000",
+                    "Program 11111111111111111111111111111111 failed: custom program error: 0x1"
+                ]
+            }"#,
+        ))
+        .unwrap();
+
+        assert!(veto.error.contains("insufficient lamports"));
+        assert_eq!(veto.logs.len(), 3);
+    }
+
+    #[test]
+    fn test_transient_and_successful_simulations_pass() {
+        assert!(veto_for(simulation(r#"{"err": null, "logs": []}"#)).is_none());
+        assert!(veto_for(simulation(r#"{"err": "BlockhashNotFound", "logs": null}"#)).is_none());
+        assert!(veto_for(simulation(r#"{"err": "AccountNotFound", "logs": []}"#)).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_estimate_adds_margin_to_simulated_units() {
+        // A plain system transfer consumes 150 compute units
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(
+                serde_json::json!({ "method": "simulateTransaction" }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {
+                    "context": { "slot": 1 },
+                    "value": {
+                        "err": null,
+                        "logs": [
+                            "Program 11111111111111111111111111111111 invoke [1]",
+                            "Program 11111111111111111111111111111111 success"
+                        ],
+                        "accounts": null,
+                        "unitsConsumed": 150,
+                        "returnData": null
+                    }
+                }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let payer = Keypair::new();
+        let units = SolTransfer::new(server.uri())
+            .estimate_compute_units(&transfer_transaction(&payer))
+            .await
+            .unwrap();
+        assert_eq!(units, 165);
+    }
+
+    #[tokio::test]
+    async fn test_limit_covers_the_simulated_limit_instruction() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(
+                serde_json::json!({ "method": "simulateTransaction" }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {
+                    "context": { "slot": 1 },
+                    "value": { "err": null, "logs": [], "unitsConsumed": 300 }
+                }
+            })))
+            .mount(&server)
+            .await;
+
+        let payer = Arc::new(Keypair::new());
+        let transaction = transfer_transaction(&payer);
+        let mut prepared = [PreparedTransfer {
+            from_address: payer.pubkey().to_string(),
+            legs: Vec::new(),
+            transaction: Ok(transaction.clone()),
+            signer: Some(payer),
+            start_time: Instant::now(),
+        }];
+        SolTransfer::new(server.uri())
+            .apply_compute_unit_limits(&mut prepared)
+            .await;
+
+        // The simulated transaction already carried a compute unit limit
+        let request: serde_json::Value =
+            serde_json::from_slice(&server.received_requests().await.unwrap()[0].body).unwrap();
+        let simulated: Transaction = bincode::deserialize(
+            &STANDARD
+                .decode(request["params"][0].as_str().unwrap())
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            instructions_of(&simulated.message)[0],
+            ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_UNIT_LIMIT as u32)
+        );
+
+        let limited = prepared[0].transaction.as_ref().unwrap();
+        assert_eq!(
+            instructions_of(&limited.message)[0],
+            ComputeBudgetInstruction::set_compute_unit_limit(330)
+        );
+        assert_eq!(
+            instructions_of(&limited.message)[1..],
+            instructions_of(&transaction.message)[..]
+        );
+    }
+
+    #[test]
+    fn test_compute_unit_limit_is_capped() {
+        assert_eq!(compute_unit_limit(0), 0);
+        assert_eq!(compute_unit_limit(1_000), 1_100);
+        assert_eq!(compute_unit_limit(1_399_999), 1_400_000);
+    }
+
+    #[test]
+    fn test_limit_is_prepended_and_signed() {
+        let payer = Keypair::new();
+        let transaction = transfer_transaction(&payer);
+        let limited = with_compute_unit_limit(&transaction, &payer, 165).unwrap();
+
+        let instructions = instructions_of(&limited.message);
+        assert_eq!(
+            instructions[0],
+            ComputeBudgetInstruction::set_compute_unit_limit(165)
+        );
+        assert_eq!(instructions[1..], instructions_of(&transaction.message)[..]);
+        assert_eq!(
+            limited.message.recent_blockhash,
+            transaction.message.recent_blockhash
+        );
+        assert!(limited.verify().is_ok());
+    }
+}
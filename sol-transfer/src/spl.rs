@@ -0,0 +1,749 @@
+use clap::Args;
+use serde::Deserialize;
+use solana_sdk::{
+    hash::Hash,
+    instruction::{AccountMeta, Instruction},
+    packet::PACKET_DATA_SIZE,
+    pubkey,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::Transaction,
+};
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use crate::{Config, PlannedTransfer, SolTransfer, batching, recipients};
+
+pub(crate) const TOKEN_PROGRAM_ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+pub(crate) const TOKEN_2022_PROGRAM_ID: Pubkey =
+    pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey = pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+// Wrapped SOL: a token account of this mint holds lamports as tokens
+const NATIVE_MINT: Pubkey = pubkey!("So11111111111111111111111111111111111111112");
+
+// Size of an SPL token account, which sets the rent a new ATA has to hold
+const TOKEN_ACCOUNT_LEN: u64 = 165;
+
+// Token program instruction tags
+const CLOSE_ACCOUNT: u8 = 9;
+const TRANSFER_CHECKED: u8 = 12;
+const CREATE_IDEMPOTENT: u8 = 1;
+
+// Send an SPL token instead of SOL. Amounts (`amount_sol` and per-recipient
+// amounts) are then read in whole tokens of `mint`.
+#[derive(Debug, Deserialize, Clone)]
+pub(crate) struct SplTokenConfig {
+    pub(crate) mint: String,
+    pub(crate) decimals: u8,
+    #[serde(default)]
+    pub(crate) ata_policy: AtaPolicy,
+}
+
+// What to do about recipients without an associated token account
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum AtaPolicy {
+    // Create the account in the transfer transaction; the sender pays its rent
+    #[default]
+    Create,
+    // Only pay recipients who already have an account
+    SkipMissing,
+    // Refuse to run if any recipient lacks an account
+    Fail,
+}
+
+impl std::fmt::Display for AtaPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Create => "create",
+            Self::SkipMissing => "skip_missing",
+            Self::Fail => "fail",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// `owner`'s associated token account for `mint`
+pub(crate) fn associated_token_address(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[owner.as_ref(), TOKEN_PROGRAM_ID.as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+    .0
+}
+
+// Create `owner`'s associated token account for `mint`, paid by `payer`.
+// The idempotent variant succeeds if the account already exists.
+pub(crate) fn create_associated_token_account(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: ASSOCIATED_TOKEN_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(associated_token_address(owner, mint), false),
+            AccountMeta::new_readonly(*owner, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+        ],
+        data: vec![CREATE_IDEMPOTENT],
+    }
+}
+
+// Close `account`, sending its lamports (for wSOL, the wrapped balance too) to
+// `destination`. `owner` must sign.
+pub(crate) fn close_account(account: &Pubkey, destination: &Pubkey, owner: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: TOKEN_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*account, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+        data: vec![CLOSE_ACCOUNT],
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenAccountsResult {
+    value: Vec<TokenAccountEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenAccountEntry {
+    pubkey: String,
+    account: TokenAccountData,
+}
+
+// Just the path to the balance in a `jsonParsed` token account
+#[derive(Debug, Deserialize)]
+pub(crate) struct TokenAccountData {
+    data: ParsedData,
+}
+
+impl TokenAccountData {
+    // Balance in base units
+    pub(crate) fn amount(&self) -> Result<u64, std::num::ParseIntError> {
+        self.data.parsed.info.token_amount.amount.parse()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ParsedData {
+    parsed: ParsedTokenAccount,
+}
+
+#[derive(Debug, Deserialize)]
+struct ParsedTokenAccount {
+    info: TokenAccountInfo,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenAccountInfo {
+    #[serde(rename = "tokenAmount")]
+    token_amount: TokenAmount,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenAmount {
+    // A decimal string, to avoid JSON number precision loss
+    amount: String,
+}
+
+// The mint every transfer moves, resolved from config
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SplMint {
+    pub(crate) mint: Pubkey,
+    pub(crate) decimals: u8,
+}
+
+impl SplMint {
+    pub(crate) fn from_config(config: &SplTokenConfig) -> Result<Self, String> {
+        let mint =
+            Pubkey::from_str(&config.mint).map_err(|e| format!("Invalid token mint: {}", e))?;
+        Ok(Self {
+            mint,
+            decimals: config.decimals,
+        })
+    }
+
+    // `owner`'s associated token account for this mint
+    pub(crate) fn associated_token_address(&self, owner: &Pubkey) -> Pubkey {
+        associated_token_address(owner, &self.mint)
+    }
+
+    // Amounts are planned in 9-decimal units like lamports; rescale them to the mint's decimals
+    pub(crate) fn base_units(&self, nine_decimal_amount: u64) -> u64 {
+        let decimals = u32::from(self.decimals);
+        if decimals <= 9 {
+            nine_decimal_amount / 10u64.pow(9 - decimals)
+        } else {
+            nine_decimal_amount.saturating_mul(10u64.pow(decimals - 9))
+        }
+    }
+
+    // Transfer `amount` base units from `sender`'s token account to `recipient`'s,
+    // creating the recipient's account first when asked to
+    pub(crate) fn transfer_instructions(
+        &self,
+        sender: &Pubkey,
+        recipient: &Pubkey,
+        amount: u64,
+        create_token_account: bool,
+    ) -> Vec<Instruction> {
+        let source = self.associated_token_address(sender);
+        let destination = self.associated_token_address(recipient);
+        let mut instructions = Vec::with_capacity(2);
+
+        if create_token_account {
+            instructions.push(create_associated_token_account(
+                sender, recipient, &self.mint,
+            ));
+        }
+
+        let mut data = Vec::with_capacity(10);
+        data.push(TRANSFER_CHECKED);
+        data.extend_from_slice(&amount.to_le_bytes());
+        data.push(self.decimals);
+        instructions.push(Instruction {
+            program_id: TOKEN_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(source, false),
+                AccountMeta::new_readonly(self.mint, false),
+                AccountMeta::new(destination, false),
+                AccountMeta::new_readonly(*sender, true),
+            ],
+            data,
+        });
+
+        instructions
+    }
+}
+
+// Recipients lacking a token account and the rent creating them all would take
+#[derive(Debug)]
+pub(crate) struct AtaCheck {
+    pub(crate) missing: HashSet<String>,
+    pub(crate) rent_per_account: u64,
+}
+
+impl AtaCheck {
+    pub(crate) fn total_rent(&self, planned: &[PlannedTransfer]) -> u64 {
+        self.rent_per_account * count_missing(planned, &self.missing) as u64
+    }
+}
+
+// Transfers whose recipient is in `missing`
+fn count_missing(planned: &[PlannedTransfer], missing: &HashSet<String>) -> usize {
+    planned
+        .iter()
+        .filter(|transfer| missing.contains(&transfer.recipient))
+        .count()
+}
+
+// Apply `policy` to planned transfers: mark those needing an account created, or
+// drop them. Returns the skipped transfers, or an error when the policy is `fail`.
+pub(crate) fn apply_ata_policy(
+    policy: AtaPolicy,
+    planned: Vec<PlannedTransfer>,
+    missing: &HashSet<String>,
+) -> Result<(Vec<PlannedTransfer>, Vec<PlannedTransfer>), String> {
+    match policy {
+        AtaPolicy::Create => Ok((
+            planned
+                .into_iter()
+                .map(|mut transfer| {
+                    transfer.create_token_account = missing.contains(&transfer.recipient);
+                    transfer
+                })
+                .collect(),
+            Vec::new(),
+        )),
+        AtaPolicy::SkipMissing => Ok(planned
+            .into_iter()
+            .partition(|transfer| !missing.contains(&transfer.recipient))),
+        AtaPolicy::Fail => match count_missing(&planned, missing) {
+            0 => Ok((planned, Vec::new())),
+            count => Err(format!(
+                "{} transfer(s) go to recipients without a token account (ata_policy: fail)",
+                count
+            )),
+        },
+    }
+}
+
+// What happened to one owner's account in `batch_create_atas`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum AtaCreation {
+    // Created by the transaction with this signature
+    Created { signature: String },
+    // The account was already there, so nothing was sent
+    AlreadyExists,
+}
+
+// One result per requested owner, in order, plus how many were created or skipped
+#[derive(Debug)]
+pub(crate) struct AtaBatchResult {
+    pub(crate) results: Vec<(Pubkey, Result<AtaCreation, String>)>,
+    pub(crate) created: usize,
+    pub(crate) skipped: usize,
+}
+
+impl AtaBatchResult {
+    fn from_results(results: Vec<(Pubkey, Result<AtaCreation, String>)>) -> Self {
+        let count = |wanted: fn(&AtaCreation) -> bool| {
+            results
+                .iter()
+                .filter(|(_, result)| result.as_ref().is_ok_and(wanted))
+                .count()
+        };
+        let created = count(|creation| matches!(creation, AtaCreation::Created { .. }));
+        let skipped = count(|creation| *creation == AtaCreation::AlreadyExists);
+        Self {
+            results,
+            created,
+            skipped,
+        }
+    }
+
+    pub(crate) fn failed(&self) -> usize {
+        self.results.len() - self.created - self.skipped
+    }
+}
+
+impl SolTransfer {
+    // Create the `mint` token accounts of every owner that lacks one, packing as many
+    // creations into each transaction as fit and sending the transactions concurrently
+    pub(crate) async fn batch_create_atas(
+        &self,
+        payer: &Keypair,
+        owners: &[Pubkey],
+        mint: &Pubkey,
+        recent_blockhash: Hash,
+    ) -> AtaBatchResult {
+        let token_accounts: Vec<Pubkey> = owners
+            .iter()
+            .map(|owner| associated_token_address(owner, mint))
+            .collect();
+        let existence = match self.verify_recipient_exists(&token_accounts).await {
+            Ok(existence) => existence,
+            Err(e) => {
+                let error = format!("Could not check for an existing token account: {}", e);
+                let results = owners.iter().map(|owner| (*owner, Err(error.clone())));
+                return AtaBatchResult::from_results(results.collect());
+            }
+        };
+
+        // Each missing owner once; repeats share the first one's outcome
+        let mut queued = HashSet::new();
+        let items: Vec<(Pubkey, Vec<Instruction>)> = owners
+            .iter()
+            .zip(&existence)
+            .filter(|(owner, (_, exists))| !exists && queued.insert(**owner))
+            .map(|(owner, _)| {
+                let instruction = create_associated_token_account(&payer.pubkey(), owner, mint);
+                (*owner, vec![instruction])
+            })
+            .collect();
+
+        let batches = batching::batch_transfers_per_tx(1, items, PACKET_DATA_SIZE);
+        let tasks = batches.into_iter().map(|batch| async move {
+            let (batch_owners, instructions): (Vec<Pubkey>, Vec<Vec<Instruction>>) =
+                batch.into_iter().unzip();
+            let transaction = Transaction::new_signed_with_payer(
+                &instructions.concat(),
+                Some(&payer.pubkey()),
+                &[payer],
+                recent_blockhash,
+            );
+            let outcome = self
+                .send_and_confirm(&transaction)
+                .await
+                .map(|(signature, _)| AtaCreation::Created { signature })
+                .map_err(|e| e.to_string());
+            batch_owners
+                .into_iter()
+                .map(|owner| (owner, outcome.clone()))
+                .collect::<Vec<_>>()
+        });
+        let sent: Vec<(Pubkey, Result<AtaCreation, String>)> = futures::future::join_all(tasks)
+            .await
+            .into_iter()
+            .flatten()
+            .collect();
+
+        let results = owners
+            .iter()
+            .zip(existence)
+            .map(|(owner, (_, exists))| {
+                let result = if exists {
+                    Ok(AtaCreation::AlreadyExists)
+                } else {
+                    sent.iter()
+                        .find(|(sent_owner, _)| sent_owner == owner)
+                        .map(|(_, result)| result.clone())
+                        .unwrap_or_else(|| Err("not sent".to_string()))
+                };
+                (*owner, result)
+            })
+            .collect();
+
+        AtaBatchResult::from_results(results)
+    }
+
+    // Close `owner`'s wrapped SOL account back into the wallet when it holds any.
+    // Returns the signature, or `None` when there is no wSOL to unwrap.
+    pub(crate) async fn unwrap_wsol(
+        &self,
+        owner: &Keypair,
+    ) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let wsol_account = associated_token_address(&owner.pubkey(), &NATIVE_MINT);
+        let accounts: TokenAccountsResult = self
+            .rpc_call(
+                "getTokenAccountsByOwner",
+                vec![
+                    serde_json::json!(owner.pubkey().to_string()),
+                    serde_json::json!({ "mint": NATIVE_MINT.to_string() }),
+                    serde_json::json!({ "encoding": "jsonParsed", "commitment": "confirmed" }),
+                ],
+            )
+            .await?;
+        let balance = accounts
+            .value
+            .iter()
+            .find(|entry| entry.pubkey == wsol_account.to_string())
+            .map(|entry| entry.account.amount())
+            .transpose()
+            .map_err(|e| format!("Invalid wSOL balance: {}", e))?
+            .unwrap_or_default();
+        if balance == 0 {
+            return Ok(None);
+        }
+
+        let transaction = Transaction::new_signed_with_payer(
+            &[close_account(
+                &wsol_account,
+                &owner.pubkey(),
+                &owner.pubkey(),
+            )],
+            Some(&owner.pubkey()),
+            &[owner],
+            self.get_recent_blockhash().await?,
+        );
+        let (signature, _) = self.send_and_confirm(&transaction).await?;
+        Ok(Some(signature))
+    }
+
+    // Which recipients have no associated token account for `mint`, found with a
+    // batched `getMultipleAccounts` over the derived addresses
+    pub(crate) async fn check_token_accounts(
+        &self,
+        mint: &SplMint,
+        recipients: &[String],
+    ) -> Result<AtaCheck, Box<dyn std::error::Error>> {
+        let owners: Vec<(String, Pubkey)> = recipients
+            .iter()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .filter_map(|address| {
+                let owner = Pubkey::from_str(address).ok()?;
+                Some((address.clone(), mint.associated_token_address(&owner)))
+            })
+            .collect();
+        let token_accounts: Vec<Pubkey> = owners.iter().map(|(_, ata)| *ata).collect();
+
+        let existence = self.verify_recipient_exists(&token_accounts).await?;
+        let missing = owners
+            .into_iter()
+            .zip(existence)
+            .filter(|(_, (_, exists))| !exists)
+            .map(|((address, _), _)| address)
+            .collect();
+
+        let rent_per_account = self
+            .rpc_call(
+                "getMinimumBalanceForRentExemption",
+                vec![serde_json::json!(TOKEN_ACCOUNT_LEN)],
+            )
+            .await?;
+
+        Ok(AtaCheck {
+            missing,
+            rent_per_account,
+        })
+    }
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct AtaCreateArgs {
+    /// Token mint the accounts are for
+    #[arg(long)]
+    mint: String,
+    /// Wallets that should get a token account
+    owners: Vec<String>,
+    /// Also read owners from a CSV file (address column), or `-` for addresses on stdin
+    #[arg(long, value_name = "PATH")]
+    owners_file: Option<String>,
+    /// Index into `sender_wallets` of the wallet that pays fees and rent
+    #[arg(long, default_value_t = 0)]
+    payer: usize,
+}
+
+// `ata-create` subcommand
+pub(crate) async fn run(
+    sol_transfer: &SolTransfer,
+    config: &Config,
+    args: AtaCreateArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mint = Pubkey::from_str(&args.mint).map_err(|e| format!("Invalid mint: {}", e))?;
+    let payer = config
+        .sender_wallets
+        .get(args.payer)
+        .ok_or_else(|| format!("No sender wallet at index {}", args.payer))?;
+    let payer = SolTransfer::parse_keypair(&payer.private_key)?;
+
+    let mut addresses = args.owners;
+    if let Some(path) = &args.owners_file {
+        match recipients::load(path) {
+            Ok(loaded) => addresses.extend(loaded.into_iter().map(|recipient| recipient.address)),
+            Err(errors) => {
+                println!("❌ {} invalid owner row(s):", errors.len());
+                for error in &errors {
+                    println!("  {}", error);
+                }
+                return Err(format!("invalid owners in {}", path).into());
+            }
+        }
+    }
+    let owners = addresses
+        .iter()
+        .map(|address| {
+            Pubkey::from_str(address).map_err(|e| format!("Invalid owner '{}': {}", address, e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if owners.is_empty() {
+        return Err("no owners given".into());
+    }
+
+    println!(
+        "Creating {} token accounts for {} owner(s), paid by {}",
+        mint,
+        owners.len(),
+        payer.pubkey()
+    );
+    let blockhash = sol_transfer.get_recent_blockhash().await?;
+    let batch = sol_transfer
+        .batch_create_atas(&payer, &owners, &mint, blockhash)
+        .await;
+
+    for (owner, result) in &batch.results {
+        match result {
+            Ok(AtaCreation::Created { signature }) => println!("✅ {}: {}", owner, signature),
+            Ok(AtaCreation::AlreadyExists) => {}
+            Err(e) => println!("❌ {}: {}", owner, e),
+        }
+    }
+    println!(
+        "✅ Created: {}  ⏭️  Already existed: {}  ❌ Failed: {}",
+        batch.created,
+        batch.skipped,
+        batch.failed()
+    );
+
+    if batch.failed() > 0 {
+        return Err(format!("{} token account(s) could not be created", batch.failed()).into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SenderWallet;
+
+    fn usdc() -> SplMint {
+        SplMint {
+            mint: pubkey!("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"),
+            decimals: 6,
+        }
+    }
+
+    fn planned_to(recipients: &[&str]) -> Vec<PlannedTransfer> {
+        let sender = SenderWallet {
+            address: Pubkey::new_unique().to_string(),
+            private_key: String::new(),
+            private_key_source: None,
+            amount_sol: None,
+            group: None,
+        };
+        recipients
+            .iter()
+            .map(|recipient| PlannedTransfer {
+                sender: sender.clone(),
+                recipient: recipient.to_string(),
+                lamports: 1,
+                label: None,
+                create_token_account: false,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_associated_token_address_is_a_pda_per_owner() {
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let first_ata = usdc().associated_token_address(&first);
+
+        assert!(!first_ata.is_on_curve());
+        assert_eq!(first_ata, usdc().associated_token_address(&first));
+        assert_ne!(first_ata, usdc().associated_token_address(&second));
+    }
+
+    #[test]
+    fn test_base_units_rescale_to_mint_decimals() {
+        let one_token = 1_000_000_000;
+        assert_eq!(usdc().base_units(one_token), 1_000_000);
+        let nine = SplMint {
+            decimals: 9,
+            ..usdc()
+        };
+        assert_eq!(nine.base_units(one_token), one_token);
+        let twelve = SplMint {
+            decimals: 12,
+            ..usdc()
+        };
+        assert_eq!(twelve.base_units(one_token), 1_000_000_000_000);
+    }
+
+    #[test]
+    fn test_transfer_creates_account_only_when_asked() {
+        let sender = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+
+        let plain = usdc().transfer_instructions(&sender, &recipient, 5, false);
+        assert_eq!(plain.len(), 1);
+        assert_eq!(plain[0].data, [12, 5, 0, 0, 0, 0, 0, 0, 0, 6]);
+
+        let creating = usdc().transfer_instructions(&sender, &recipient, 5, true);
+        assert_eq!(creating.len(), 2);
+        assert_eq!(creating[0].program_id, ASSOCIATED_TOKEN_PROGRAM_ID);
+        assert_eq!(
+            creating[0].accounts[1].pubkey,
+            creating[1].accounts[2].pubkey
+        );
+    }
+
+    #[test]
+    fn test_ata_policies() {
+        let missing = HashSet::from(["b".to_string()]);
+
+        let (kept, skipped) =
+            apply_ata_policy(AtaPolicy::Create, planned_to(&["a", "b"]), &missing).unwrap();
+        assert!(skipped.is_empty());
+        let flags: Vec<bool> = kept.iter().map(|t| t.create_token_account).collect();
+        assert_eq!(flags, [false, true]);
+
+        let (kept, skipped) =
+            apply_ata_policy(AtaPolicy::SkipMissing, planned_to(&["a", "b"]), &missing).unwrap();
+        assert_eq!(kept.len(), 1);
+        assert_eq!(skipped[0].recipient, "b");
+
+        assert!(apply_ata_policy(AtaPolicy::Fail, planned_to(&["a", "b"]), &missing).is_err());
+        assert!(apply_ata_policy(AtaPolicy::Fail, planned_to(&["a"]), &missing).is_ok());
+    }
+
+    #[test]
+    fn test_batch_result_counts() {
+        let owner = Pubkey::new_unique();
+        let created = AtaCreation::Created {
+            signature: "sig".to_string(),
+        };
+        let batch = AtaBatchResult::from_results(vec![
+            (owner, Ok(created.clone())),
+            (owner, Ok(created)),
+            (owner, Ok(AtaCreation::AlreadyExists)),
+            (owner, Err("boom".to_string())),
+        ]);
+
+        assert_eq!(batch.created, 2);
+        assert_eq!(batch.skipped, 1);
+        assert_eq!(batch.failed(), 1);
+    }
+
+    #[test]
+    fn test_close_account_returns_lamports_to_the_owner() {
+        let owner = Pubkey::new_unique();
+        let wsol_account = associated_token_address(&owner, &NATIVE_MINT);
+        let instruction = close_account(&wsol_account, &owner, &owner);
+
+        assert_eq!(instruction.data, [CLOSE_ACCOUNT]);
+        assert_eq!(instruction.accounts[0].pubkey, wsol_account);
+        assert_eq!(instruction.accounts[1].pubkey, owner);
+        assert!(instruction.accounts[2].is_signer);
+    }
+
+    #[tokio::test]
+    async fn test_unwrap_wsol_skips_empty_accounts() {
+        use wiremock::matchers::{body_partial_json, method};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let owner = Keypair::new();
+        let wsol_account = associated_token_address(&owner.pubkey(), &NATIVE_MINT);
+        // Only the token accounts are queried; sending would fail against the mock
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(
+                serde_json::json!({ "method": "getTokenAccountsByOwner" }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {
+                    "context": { "slot": 1 },
+                    "value": [{
+                        "pubkey": wsol_account.to_string(),
+                        "account": {
+                            "data": {
+                                "parsed": { "info": { "tokenAmount": { "amount": "0" } } }
+                            }
+                        }
+                    }]
+                }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let sol_transfer = SolTransfer::new(server.uri());
+        assert!(sol_transfer.unwrap_wsol(&owner).await.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_many_creations_are_packed_within_size_limit() {
+        let payer = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let items: Vec<(usize, Vec<Instruction>)> = (0..40)
+            .map(|index| {
+                let owner = Pubkey::new_unique();
+                (
+                    index,
+                    vec![create_associated_token_account(&payer, &owner, &mint)],
+                )
+            })
+            .collect();
+
+        let batches = batching::batch_transfers_per_tx(1, items, PACKET_DATA_SIZE);
+        assert!(batches.len() > 1);
+        assert_eq!(batches.iter().map(Vec::len).sum::<usize>(), 40);
+        for batch in batches {
+            let instructions: Vec<Instruction> = batch
+                .into_iter()
+                .flat_map(|(_, instructions)| instructions)
+                .collect();
+            assert!(batching::transaction_wire_size(&payer, &instructions) <= PACKET_DATA_SIZE);
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use clap::Args;
+use serde::Deserialize;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::SolTransfer;
+
+#[derive(Debug, Deserialize)]
+struct SupplyResult {
+    value: RpcSupply,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcSupply {
+    total: u64,
+    circulating: u64,
+    non_circulating: u64,
+    non_circulating_accounts: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SupplyInfo {
+    pub(crate) total_lamports: u64,
+    pub(crate) circulating_lamports: u64,
+    pub(crate) non_circulating_lamports: u64,
+    // Empty when the list was excluded from the request
+    pub(crate) non_circulating_accounts: Vec<Pubkey>,
+}
+
+impl SolTransfer {
+    // Cluster supply; `exclude_non_circulating` skips the (long) list of
+    // non-circulating accounts but still returns the totals
+    pub(crate) async fn get_supply(
+        &self,
+        exclude_non_circulating: bool,
+    ) -> Result<SupplyInfo, Box<dyn std::error::Error>> {
+        let supply = self
+            .rpc_call::<SupplyResult>(
+                "getSupply",
+                vec![serde_json::json!({
+                    "commitment": "confirmed",
+                    "excludeNonCirculatingAccountsList": exclude_non_circulating,
+                })],
+            )
+            .await?
+            .value;
+
+        let non_circulating_accounts = supply
+            .non_circulating_accounts
+            .iter()
+            .map(|address| {
+                Pubkey::from_str(address)
+                    .map_err(|e| format!("invalid non-circulating account '{}': {}", address, e))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(SupplyInfo {
+            total_lamports: supply.total,
+            circulating_lamports: supply.circulating,
+            non_circulating_lamports: supply.non_circulating,
+            non_circulating_accounts,
+        })
+    }
+}
+
+// Tracks circulating supply against the last reported value
+struct SupplyWatch {
+    threshold_lamports: u64,
+    baseline: Option<u64>,
+}
+
+impl SupplyWatch {
+    fn new(threshold_lamports: u64) -> Self {
+        Self {
+            threshold_lamports,
+            baseline: None,
+        }
+    }
+
+    // The change since the last reported value, once it exceeds the threshold. Small
+    // changes add up until they cross it.
+    fn observe(&mut self, circulating_lamports: u64) -> Option<i128> {
+        let Some(baseline) = self.baseline else {
+            self.baseline = Some(circulating_lamports);
+            return None;
+        };
+        let change = circulating_lamports as i128 - baseline as i128;
+        if change.unsigned_abs() <= self.threshold_lamports as u128 {
+            return None;
+        }
+        self.baseline = Some(circulating_lamports);
+        Some(change)
+    }
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct SupplyMonitorArgs {
+    /// Seconds between `getSupply` polls
+    #[arg(long, default_value_t = 60)]
+    interval: u64,
+    /// Report once circulating supply has moved by more than this many lamports
+    #[arg(long, default_value_t = 1_000 * LAMPORTS_PER_SOL)]
+    threshold_lamports: u64,
+}
+
+// `supply-monitor` subcommand: poll until Ctrl+C
+pub(crate) async fn run(
+    sol_transfer: &SolTransfer,
+    args: SupplyMonitorArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // `--log-rpc` may have set one up already
+    let _ = tracing_subscriber::fmt().try_init();
+
+    let mut watch = SupplyWatch::new(args.threshold_lamports);
+    let mut ticker = tokio::time::interval(Duration::from_secs(args.interval.max(1)));
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+
+        let supply = match sol_transfer.get_supply(true).await {
+            Ok(supply) => supply,
+            Err(e) => {
+                tracing::warn!(error = %e, "getSupply failed");
+                continue;
+            }
+        };
+        let first = watch.baseline.is_none();
+        match watch.observe(supply.circulating_lamports) {
+            Some(change_lamports) => tracing::info!(
+                circulating_lamports = supply.circulating_lamports,
+                change_lamports,
+                total_lamports = supply.total_lamports,
+                "circulating supply changed"
+            ),
+            None if first => tracing::info!(
+                circulating_lamports = supply.circulating_lamports,
+                non_circulating_lamports = supply.non_circulating_lamports,
+                total_lamports = supply.total_lamports,
+                "monitoring circulating supply"
+            ),
+            None => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_get_supply_parses_response() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(serde_json::json!({
+                "method": "getSupply",
+                "params": [{ "excludeNonCirculatingAccountsList": false }]
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {
+                    "context": { "slot": 1114 },
+                    "value": {
+                        "total": 1016000,
+                        "circulating": 16000,
+                        "nonCirculating": 1000000,
+                        "nonCirculatingAccounts": [
+                            "FEy8pTbP5fEoqMV1GdTz83byuJ8hUhAuZ4a3N5nQjxBf",
+                            "9huDUZfxoJ7wGMTffUE7vh1xePqef7gyrLJu9NApncqA"
+                        ]
+                    }
+                }
+            })))
+            .mount(&server)
+            .await;
+
+        let supply = SolTransfer::new(server.uri())
+            .get_supply(false)
+            .await
+            .unwrap();
+        assert_eq!(supply.total_lamports, 1_016_000);
+        assert_eq!(supply.circulating_lamports, 16_000);
+        assert_eq!(supply.non_circulating_lamports, 1_000_000);
+        assert_eq!(supply.non_circulating_accounts.len(), 2);
+    }
+
+    #[test]
+    fn test_changes_are_reported_past_the_threshold() {
+        let mut watch = SupplyWatch::new(100);
+        assert_eq!(watch.observe(1_000), None);
+        assert_eq!(watch.observe(1_060), None);
+        // Drift adds up against the last reported value
+        assert_eq!(watch.observe(1_120), Some(120));
+        assert_eq!(watch.observe(1_020), None);
+        assert_eq!(watch.observe(1_000), Some(-120));
+    }
+}
@@ -0,0 +1,139 @@
+// Peer-to-peer SOL swaps without an escrow or a trusted middleman: both transfers go
+// in one transaction, so the runtime executes them all-or-nothing. Neither side can
+// receive without paying, because a transaction missing either signature, or whose
+// either transfer fails, changes no balances at all.
+//
+// Each party only needs to sign the exact message it agreed to. Here both keypairs
+// are at hand (e.g. two wallets from `sender_wallets`); between strangers, one side
+// builds the message and signs it, then hands it to the other to add the second
+// signature and submit.
+use clap::Args;
+use solana_sdk::{
+    hash::Hash,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::Transaction,
+};
+
+use crate::{Config, SolTransfer};
+
+// Alice sends `alice_to_bob_lamports` and receives `bob_to_alice_lamports` in the same
+// transaction. Both must sign; Alice is the fee payer.
+pub(crate) fn build_atomic_swap_transaction(
+    alice: &Keypair,
+    bob: &Keypair,
+    alice_to_bob_lamports: u64,
+    bob_to_alice_lamports: u64,
+    recent_blockhash: Hash,
+) -> Result<Transaction, Box<dyn std::error::Error>> {
+    if alice.pubkey() == bob.pubkey() {
+        return Err("A swap needs two different wallets".into());
+    }
+    if alice_to_bob_lamports == 0 || bob_to_alice_lamports == 0 {
+        return Err("Both sides of a swap must send a non-zero amount".into());
+    }
+
+    let instructions = [
+        system_instruction::transfer(&alice.pubkey(), &bob.pubkey(), alice_to_bob_lamports),
+        system_instruction::transfer(&bob.pubkey(), &alice.pubkey(), bob_to_alice_lamports),
+    ];
+    let mut transaction = Transaction::new_with_payer(&instructions, Some(&alice.pubkey()));
+    transaction.try_sign(&[alice, bob], recent_blockhash)?;
+    Ok(transaction)
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct AtomicSwapArgs {
+    /// Index into `sender_wallets` of the first party, who also pays the fee
+    #[arg(long)]
+    alice: usize,
+    /// Index into `sender_wallets` of the second party
+    #[arg(long)]
+    bob: usize,
+    /// SOL the first party sends to the second
+    #[arg(long)]
+    alice_sends: f64,
+    /// SOL the second party sends to the first
+    #[arg(long)]
+    bob_sends: f64,
+}
+
+// `atomic-swap` subcommand
+pub(crate) async fn run(
+    sol_transfer: &SolTransfer,
+    config: &Config,
+    args: AtomicSwapArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let keypair = |index: usize| -> Result<Keypair, Box<dyn std::error::Error>> {
+        let wallet = config
+            .sender_wallets
+            .get(index)
+            .ok_or_else(|| format!("No sender wallet at index {}", index))?;
+        SolTransfer::parse_keypair(&wallet.private_key)
+    };
+    let alice = keypair(args.alice)?;
+    let bob = keypair(args.bob)?;
+    let alice_sends = SolTransfer::sol_to_lamports(args.alice_sends);
+    let bob_sends = SolTransfer::sol_to_lamports(args.bob_sends);
+
+    let blockhash = sol_transfer.get_recent_blockhash().await?;
+    let transaction =
+        build_atomic_swap_transaction(&alice, &bob, alice_sends, bob_sends, blockhash)?;
+
+    println!(
+        "{} -> {}: {} SOL",
+        alice.pubkey(),
+        bob.pubkey(),
+        args.alice_sends
+    );
+    println!(
+        "{} -> {}: {} SOL",
+        bob.pubkey(),
+        alice.pubkey(),
+        args.bob_sends
+    );
+
+    let (signature, level) = sol_transfer.send_and_confirm(&transaction).await?;
+    println!("Signature: {}", signature);
+    println!("✅ Swapped ({})", level);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::system_program;
+
+    #[test]
+    fn test_swap_needs_both_signatures() {
+        let alice = Keypair::new();
+        let bob = Keypair::new();
+        let transaction =
+            build_atomic_swap_transaction(&alice, &bob, 1_000, 2_000, Hash::new_unique()).unwrap();
+
+        assert!(transaction.verify().is_ok());
+        assert_eq!(transaction.message.header.num_required_signatures, 2);
+        assert_eq!(transaction.message.account_keys[0], alice.pubkey());
+        assert_eq!(transaction.message.account_keys[1], bob.pubkey());
+        assert_eq!(transaction.message.instructions.len(), 2);
+        assert!(
+            transaction
+                .message
+                .instructions
+                .iter()
+                .all(
+                    |ix| transaction.message.account_keys[ix.program_id_index as usize]
+                        == system_program::id()
+                )
+        );
+    }
+
+    #[test]
+    fn test_swap_rejects_one_sided_or_self_swaps() {
+        let alice = Keypair::new();
+        let bob = Keypair::new();
+        let blockhash = Hash::new_unique();
+        assert!(build_atomic_swap_transaction(&alice, &bob, 1_000, 0, blockhash).is_err());
+        assert!(build_atomic_swap_transaction(&alice, &alice, 1_000, 1_000, blockhash).is_err());
+    }
+}
@@ -0,0 +1,358 @@
+use clap::Args;
+use serde::Deserialize;
+use solana_sdk::{
+    hash::Hash,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::Transaction,
+};
+use std::str::FromStr;
+
+use crate::accounts::rent_exempt_minimum;
+use crate::{Config, ConfirmationLevel, SolTransfer};
+
+#[derive(Debug, Deserialize)]
+struct BalanceResult {
+    value: u64,
+}
+
+// How much of the sender's balance a sweep moves
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SweepMode {
+    // Leave the rent-exempt minimum so the account stays open
+    KeepOpen,
+    // Move everything; at zero lamports the account is garbage collected
+    Close,
+}
+
+// Lamports to transfer out of `balance` once the sender has paid `fee`
+fn sweep_amount(balance: u64, fee: u64, mode: SweepMode) -> Result<u64, String> {
+    let reserve = match mode {
+        SweepMode::KeepOpen => rent_exempt_minimum(0),
+        SweepMode::Close => 0,
+    };
+
+    balance
+        .checked_sub(fee + reserve)
+        .filter(|amount| *amount > 0)
+        .ok_or_else(|| {
+            format!(
+                "balance of {} lamports doesn't cover the {} lamport fee{}",
+                balance,
+                fee,
+                if reserve > 0 {
+                    format!(" plus the {} lamport rent-exempt reserve", reserve)
+                } else {
+                    String::new()
+                }
+            )
+        })
+}
+
+// `leave_lamports` for a drain: zero empties the wallet, anything else has to keep
+// it rent exempt or the transfer fails on chain
+fn check_leave_lamports(leave_lamports: u64) -> Result<(), String> {
+    let minimum = rent_exempt_minimum(0);
+    if leave_lamports > 0 && leave_lamports < minimum {
+        return Err(format!(
+            "leaving {} lamports would make the wallet rent-paying; leave 0 or at least {}",
+            leave_lamports, minimum
+        ));
+    }
+    Ok(())
+}
+
+// A transfer of `source`'s balance to `destination`, priced before the amount is fixed
+struct FullBalanceTransfer<'a> {
+    source: &'a Keypair,
+    destination: &'a Pubkey,
+    recent_blockhash: Hash,
+    balance: u64,
+    fee: u64,
+}
+
+impl FullBalanceTransfer<'_> {
+    // The transfer of `lamports`, signed and paid for by the source
+    fn build(&self, lamports: u64) -> Transaction {
+        Transaction::new_signed_with_payer(
+            &[system_instruction::transfer(
+                &self.source.pubkey(),
+                self.destination,
+                lamports,
+            )],
+            Some(&self.source.pubkey()),
+            &[self.source],
+            self.recent_blockhash,
+        )
+    }
+}
+
+impl SolTransfer {
+    async fn get_balance(&self, address: &Pubkey) -> Result<u64, Box<dyn std::error::Error>> {
+        let result: BalanceResult = self
+            .rpc_call(
+                "getBalance",
+                vec![
+                    serde_json::json!(address.to_string()),
+                    serde_json::json!({ "commitment": "confirmed" }),
+                ],
+            )
+            .await?;
+        Ok(result.value)
+    }
+
+    // Read `source`'s balance and price moving all of it to `destination`; the amount
+    // doesn't change the fee
+    async fn price_full_balance_transfer<'a>(
+        &self,
+        source: &'a Keypair,
+        destination: &'a Pubkey,
+        recent_blockhash: Hash,
+    ) -> Result<FullBalanceTransfer<'a>, Box<dyn std::error::Error>> {
+        let mut transfer = FullBalanceTransfer {
+            source,
+            destination,
+            recent_blockhash,
+            balance: self.get_balance(&source.pubkey()).await?,
+            fee: 0,
+        };
+        transfer.fee = self
+            .get_multiple_transaction_fees(&[transfer.build(transfer.balance)])
+            .await?
+            .first()
+            .copied()
+            .flatten()
+            .ok_or("the cluster returned no fee for the transfer")?;
+        Ok(transfer)
+    }
+
+    // Move the sender's whole balance to `recipient` in one transaction the sender pays for.
+    // `KeepOpen` leaves the rent-exempt minimum behind; `Close` empties the account.
+    // The fee is priced before the amount is fixed, so no dust is left over for a second
+    // transfer to a separate fee payer. Returns the transaction and the amount it moves.
+    pub(crate) async fn build_sweep_and_close_transaction(
+        &self,
+        sender: &Keypair,
+        recipient: &Pubkey,
+        recent_blockhash: Hash,
+        mode: SweepMode,
+    ) -> Result<(Transaction, u64), Box<dyn std::error::Error>> {
+        let transfer = self
+            .price_full_balance_transfer(sender, recipient, recent_blockhash)
+            .await?;
+        let amount = sweep_amount(transfer.balance, transfer.fee, mode)?;
+        Ok((transfer.build(amount), amount))
+    }
+
+    // Send everything above `leave_lamports` and the fee from `source` to `destination`.
+    // Returns the signature and the level it was confirmed at, or `None` when there is
+    // nothing to drain. `leave_lamports`
+    // must be zero or keep the wallet rent exempt.
+    pub(crate) async fn drain_wallet(
+        &self,
+        source: &Keypair,
+        destination: &Pubkey,
+        leave_lamports: u64,
+    ) -> Result<Option<(String, ConfirmationLevel)>, Box<dyn std::error::Error>> {
+        check_leave_lamports(leave_lamports)?;
+
+        let recent_blockhash = self.get_recent_blockhash().await?;
+        let transfer = self
+            .price_full_balance_transfer(source, destination, recent_blockhash)
+            .await?;
+        let Some(available) = transfer
+            .balance
+            .checked_sub(leave_lamports + transfer.fee)
+            .filter(|available| *available > 0)
+        else {
+            return Ok(None);
+        };
+        Ok(Some(
+            self.send_and_confirm(&transfer.build(available)).await?,
+        ))
+    }
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct SweepCloseArgs {
+    /// Address that receives the swept lamports
+    recipient: String,
+    /// Index into `sender_wallets` of the wallet to sweep
+    #[arg(long, default_value_t = 0)]
+    sender: usize,
+    /// Move the full balance so the account is closed, instead of keeping it rent exempt
+    #[arg(long)]
+    close: bool,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct DrainArgs {
+    /// Address that receives the drained lamports
+    #[arg(long)]
+    destination: String,
+    /// SOL to leave in each sender wallet
+    #[arg(long, default_value_t = 0.0)]
+    leave: f64,
+}
+
+// `drain` subcommand: empty every sender wallet into one destination
+pub(crate) async fn run_drain(
+    sol_transfer: &SolTransfer,
+    config: &Config,
+    args: DrainArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let destination = Pubkey::from_str(&args.destination)
+        .map_err(|e| format!("Invalid destination '{}': {}", args.destination, e))?;
+    let leave_lamports = SolTransfer::sol_to_lamports(args.leave);
+    check_leave_lamports(leave_lamports)?;
+
+    let mut failed = 0;
+    for wallet in &config.sender_wallets {
+        let source = SolTransfer::parse_keypair(&wallet.private_key)?;
+        match sol_transfer
+            .drain_wallet(&source, &destination, leave_lamports)
+            .await
+        {
+            Ok(Some((signature, level))) => {
+                println!("✅ {}: drained ({}) {}", source.pubkey(), level, signature)
+            }
+            Ok(None) => println!("⏭️  {}: nothing to drain", source.pubkey()),
+            Err(e) => {
+                println!("❌ {}: {}", source.pubkey(), e);
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(format!("{} wallet(s) could not be drained", failed).into());
+    }
+    Ok(())
+}
+
+// `sweep-close` subcommand
+pub(crate) async fn run(
+    sol_transfer: &SolTransfer,
+    config: &Config,
+    args: SweepCloseArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let recipient = Pubkey::from_str(&args.recipient)
+        .map_err(|e| format!("Invalid recipient '{}': {}", args.recipient, e))?;
+    let sender = config
+        .sender_wallets
+        .get(args.sender)
+        .ok_or_else(|| format!("No sender wallet at index {}", args.sender))?;
+    let sender = SolTransfer::parse_keypair(&sender.private_key)?;
+    let mode = if args.close {
+        SweepMode::Close
+    } else {
+        SweepMode::KeepOpen
+    };
+
+    let blockhash = sol_transfer.get_recent_blockhash().await?;
+    let (transaction, amount) = sol_transfer
+        .build_sweep_and_close_transaction(&sender, &recipient, blockhash, mode)
+        .await?;
+
+    println!("From: {}", sender.pubkey());
+    println!("To: {}", recipient);
+    println!(
+        "Amount: {} SOL ({} lamports){}",
+        amount as f64 / 1_000_000_000.0,
+        amount,
+        if mode == SweepMode::Close {
+            ", closing the account"
+        } else {
+            ""
+        }
+    );
+
+    let (signature, level) = sol_transfer.send_and_confirm(&transaction).await?;
+    println!("Signature: {}", signature);
+    println!("✅ Swept ({})", level);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_keep_open_leaves_rent_reserve() {
+        let balance = 10_000_000;
+        let amount = sweep_amount(balance, 5_000, SweepMode::KeepOpen).unwrap();
+        assert_eq!(balance - amount - 5_000, rent_exempt_minimum(0));
+    }
+
+    #[test]
+    fn test_close_moves_everything_but_the_fee() {
+        assert_eq!(
+            sweep_amount(10_000_000, 5_000, SweepMode::Close).unwrap(),
+            9_995_000
+        );
+    }
+
+    #[test]
+    fn test_balance_too_small_to_sweep() {
+        assert!(sweep_amount(5_000, 5_000, SweepMode::Close).is_err());
+        assert!(sweep_amount(500_000, 5_000, SweepMode::KeepOpen).is_err());
+    }
+
+    #[test]
+    fn test_drain_leaves_nothing_or_a_rent_exempt_balance() {
+        assert!(check_leave_lamports(0).is_ok());
+        assert!(check_leave_lamports(rent_exempt_minimum(0)).is_ok());
+        assert!(check_leave_lamports(1_000).is_err());
+    }
+
+    async fn mock_result(server: &MockServer, rpc_method: &str, result: serde_json::Value) {
+        Mock::given(method("POST"))
+            .and(body_partial_json(
+                serde_json::json!({ "method": rpc_method }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": result
+            })))
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_drain_skips_empty_wallets() {
+        // Nothing is sent: a sendTransaction would fail against the mock
+        let server = MockServer::start().await;
+        mock_result(
+            &server,
+            "getBalance",
+            serde_json::json!({ "context": { "slot": 1 }, "value": 0 }),
+        )
+        .await;
+        mock_result(
+            &server,
+            "getLatestBlockhash",
+            serde_json::json!({
+                "context": { "slot": 1 },
+                "value": { "blockhash": Hash::new_unique().to_string(), "lastValidBlockHeight": 100 }
+            }),
+        )
+        .await;
+        mock_result(
+            &server,
+            "getFeeForMessage",
+            serde_json::json!({ "context": { "slot": 1 }, "value": 5_000 }),
+        )
+        .await;
+
+        let sol_transfer = SolTransfer::new(server.uri());
+        let drained = sol_transfer
+            .drain_wallet(&Keypair::new(), &Pubkey::new_unique(), 0)
+            .await
+            .unwrap();
+        assert!(drained.is_none());
+    }
+}
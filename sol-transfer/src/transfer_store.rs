@@ -0,0 +1,254 @@
+use clap::Args;
+
+use crate::{SolTransfer, TransferResult, report};
+
+// Audit log of every transfer this tool submitted, kept in an embedded sled database.
+// Records are stored as JSON in insertion order, with an index from signature to record.
+#[derive(Clone)]
+pub(crate) struct TransferStore {
+    // Hands out the record ids; trees can't
+    db: sled::Db,
+    transfers: sled::Tree,
+    by_signature: sled::Tree,
+}
+
+impl TransferStore {
+    pub(crate) fn new(db_path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        // Every insert flushes; without the background flusher the file lock is
+        // released as soon as the store is dropped
+        let db = sled::Config::new()
+            .path(db_path)
+            .flush_every_ms(None)
+            .open()
+            .map_err(|e| format!("Failed to open transfer history {}: {}", db_path, e))?;
+        Self::from_db(db)
+    }
+
+    fn from_db(db: sled::Db) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            transfers: db.open_tree("transfers")?,
+            by_signature: db.open_tree("by_signature")?,
+            db,
+        })
+    }
+
+    pub(crate) fn insert(&self, result: &TransferResult) -> Result<(), Box<dyn std::error::Error>> {
+        // Ids only grow, so iterating the tree lists transfers oldest first
+        let id = self.db.generate_id()?.to_be_bytes();
+        self.transfers.insert(id, serde_json::to_vec(result)?)?;
+        // Transfers that never got a signature are only reachable through the listings
+        if !result.signature.is_empty() {
+            self.by_signature.insert(result.signature.as_bytes(), &id)?;
+        }
+        self.transfers.flush()?;
+        Ok(())
+    }
+
+    // The transfer sent with `signature`; for a batched transaction, its last recipient
+    pub(crate) fn get(
+        &self,
+        signature: &str,
+    ) -> Result<Option<TransferResult>, Box<dyn std::error::Error>> {
+        let Some(id) = self.by_signature.get(signature.as_bytes())? else {
+            return Ok(None);
+        };
+        match self.transfers.get(id)? {
+            Some(record) => Ok(Some(serde_json::from_slice(&record)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub(crate) fn list_all(&self) -> Result<Vec<TransferResult>, Box<dyn std::error::Error>> {
+        self.transfers
+            .iter()
+            .values()
+            .map(|record| Ok(serde_json::from_slice(&record?)?))
+            .collect()
+    }
+
+    // Transfers that errored before landing or whose transaction failed on chain
+    pub(crate) fn list_failed(&self) -> Result<Vec<TransferResult>, Box<dyn std::error::Error>> {
+        Ok(self
+            .list_all()?
+            .into_iter()
+            .filter(|result| {
+                result.error.is_some()
+                    || result
+                        .status
+                        .as_ref()
+                        .is_some_and(|status| status.err.is_some())
+            })
+            .collect())
+    }
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct ShowHistoryArgs {
+    /// Only list failed transfers
+    #[arg(long)]
+    failed: bool,
+    /// Only show the transfer sent with this signature
+    #[arg(long, conflicts_with = "failed")]
+    signature: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct ExportHistoryArgs {
+    /// CSV file to write, in the same layout as `results_csv`
+    output: String,
+    /// Only export failed transfers
+    #[arg(long)]
+    failed: bool,
+}
+
+fn store(sol_transfer: &SolTransfer) -> Result<&TransferStore, Box<dyn std::error::Error>> {
+    Ok(sol_transfer
+        .transfer_store
+        .as_ref()
+        .ok_or("Set transfer_history_db in config.yaml to keep a transfer history")?)
+}
+
+fn load(
+    sol_transfer: &SolTransfer,
+    failed_only: bool,
+) -> Result<Vec<TransferResult>, Box<dyn std::error::Error>> {
+    let store = store(sol_transfer)?;
+    if failed_only {
+        store.list_failed()
+    } else {
+        store.list_all()
+    }
+}
+
+// `show-history` subcommand
+pub(crate) fn run_show(
+    sol_transfer: &SolTransfer,
+    args: ShowHistoryArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let results = match &args.signature {
+        Some(signature) => store(sol_transfer)?.get(signature)?.into_iter().collect(),
+        None => load(sol_transfer, args.failed)?,
+    };
+    if results.is_empty() {
+        println!("No transfers recorded");
+        return Ok(());
+    }
+
+    for result in &results {
+        println!(
+            "{} -> {}  {}  {}{}",
+            result.from_address,
+            result.to_address,
+            sol_transfer.outcome(result).as_str(),
+            if result.signature.is_empty() {
+                "-"
+            } else {
+                &result.signature
+            },
+            result
+                .error
+                .as_deref()
+                .map(|error| format!("  ({})", error))
+                .unwrap_or_default()
+        );
+    }
+    println!("\n{} transfer(s)", results.len());
+    Ok(())
+}
+
+// `export-history` subcommand
+pub(crate) fn run_export(
+    sol_transfer: &SolTransfer,
+    args: ExportHistoryArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let results = load(sol_transfer, args.failed)?;
+    report::export_csv(sol_transfer, &results, &args.output)?;
+    println!(
+        "📄 {} transfer(s) written to {}",
+        results.len(),
+        args.output
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn store() -> TransferStore {
+        TransferStore::from_db(sled::Config::new().temporary(true).open().unwrap()).unwrap()
+    }
+
+    fn sent(signature: &str, to: &str) -> TransferResult {
+        TransferResult {
+            signature: signature.to_string(),
+            error: None,
+            ..TransferResult::failed(
+                "sender".to_string(),
+                to.to_string(),
+                None,
+                String::new(),
+                Instant::now(),
+            )
+        }
+    }
+
+    #[test]
+    fn test_insert_get_and_list() {
+        let store = store();
+        store.insert(&sent("sig1", "alice")).unwrap();
+        store
+            .insert(&TransferResult::failed(
+                "sender".to_string(),
+                "bob".to_string(),
+                Some("payroll".to_string()),
+                "insufficient funds".to_string(),
+                Instant::now(),
+            ))
+            .unwrap();
+        store.insert(&sent("sig2", "carol")).unwrap();
+
+        assert_eq!(store.get("sig2").unwrap().unwrap().to_address, "carol");
+        assert!(store.get("missing").unwrap().is_none());
+
+        let all: Vec<String> = store
+            .list_all()
+            .unwrap()
+            .into_iter()
+            .map(|result| result.to_address)
+            .collect();
+        assert_eq!(all, ["alice", "bob", "carol"]);
+
+        let failed = store.list_failed().unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].label.as_deref(), Some("payroll"));
+        assert_eq!(failed[0].error.as_deref(), Some("insufficient funds"));
+    }
+
+    #[test]
+    fn test_history_survives_reopening() {
+        let path =
+            std::env::temp_dir().join(format!("sol-transfer-history-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let path_str = path.to_str().unwrap();
+
+        {
+            let store = TransferStore::new(path_str).unwrap();
+            store.insert(&sent("sig1", "alice")).unwrap();
+        }
+        let store = TransferStore::new(path_str).unwrap();
+        store.insert(&sent("sig2", "bob")).unwrap();
+
+        let all: Vec<String> = store
+            .list_all()
+            .unwrap()
+            .into_iter()
+            .map(|result| result.to_address)
+            .collect();
+        assert_eq!(all, ["alice", "bob"]);
+        assert_eq!(store.get("sig1").unwrap().unwrap().to_address, "alice");
+        drop(store);
+        std::fs::remove_dir_all(path).unwrap();
+    }
+}
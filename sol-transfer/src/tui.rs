@@ -0,0 +1,284 @@
+use crossterm::{
+    cursor,
+    event::{Event, EventStream, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute, queue,
+    style::Print,
+    terminal::{self, ClearType},
+};
+use futures::StreamExt;
+use std::io::{self, IsTerminal, Write};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::progress::{EventReceiver, TransferEvent, TransferStage};
+
+// Redraw at least this often so elapsed times keep moving
+const REFRESH_INTERVAL: Duration = Duration::from_millis(250);
+// Most recent notices shown under the table; all of them are printed once it closes
+const NOTICE_LINES: usize = 4;
+
+// The table only makes sense when a person is watching an interactive terminal
+pub(crate) fn is_supported() -> bool {
+    io::stdout().is_terminal() && io::stdin().is_terminal()
+}
+
+struct Row {
+    from: String,
+    to: String,
+    stage: TransferStage,
+    signature: Option<String>,
+    started: Instant,
+    finished: Option<Duration>,
+}
+
+impl Row {
+    fn elapsed(&self) -> Duration {
+        self.finished.unwrap_or_else(|| self.started.elapsed())
+    }
+}
+
+// Restores the terminal however the table loop exits
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn short(value: &str, keep: usize) -> String {
+    if value.chars().count() <= keep {
+        return value.to_string();
+    }
+    let prefix: String = value.chars().take(keep).collect();
+    format!("{}…", prefix)
+}
+
+fn suffix(value: &str, keep: usize) -> String {
+    let count = value.chars().count();
+    if count <= keep {
+        return value.to_string();
+    }
+    let tail: String = value.chars().skip(count - keep).collect();
+    format!("…{}", tail)
+}
+
+fn draw(rows: &[Row], notices: &[String], cancelling: bool) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    let (_, height) = terminal::size()?;
+
+    let done = rows.iter().filter(|row| row.stage.is_final()).count();
+    let confirmed = rows
+        .iter()
+        .filter(|row| row.stage == TransferStage::Confirmed)
+        .count();
+
+    queue!(
+        stdout,
+        cursor::MoveTo(0, 0),
+        terminal::Clear(ClearType::All),
+        Print(format!(
+            "SOL transfers: {}/{} done, {} confirmed   [q] cancel\r\n\r\n",
+            done,
+            rows.len(),
+            confirmed
+        )),
+        Print(format!(
+            "{:<4} {:<14} {:<14} {:<11} {:>8}  {}\r\n",
+            "#", "From", "To", "Stage", "Elapsed", "Signature"
+        )),
+    )?;
+
+    // Header, column titles and footer take five lines, the notices one more than shown
+    let recent = &notices[notices.len().saturating_sub(NOTICE_LINES)..];
+    let notice_lines = if recent.is_empty() {
+        0
+    } else {
+        recent.len() + 1
+    };
+    let visible = (height as usize).saturating_sub(5 + notice_lines);
+    for (index, row) in rows.iter().enumerate().take(visible) {
+        queue!(
+            stdout,
+            Print(format!(
+                "{:<4} {:<14} {:<14} {:<11} {:>7.1}s  {}\r\n",
+                index + 1,
+                short(&row.from, 12),
+                short(&row.to, 12),
+                row.stage.as_str(),
+                row.elapsed().as_secs_f64(),
+                row.signature
+                    .as_deref()
+                    .map(|signature| suffix(signature, 8))
+                    .unwrap_or_default()
+            ))
+        )?;
+    }
+    if rows.len() > visible {
+        queue!(
+            stdout,
+            Print(format!("… {} more\r\n", rows.len() - visible))
+        )?;
+    }
+    if !recent.is_empty() {
+        queue!(stdout, Print("\r\n"))?;
+        for notice in recent {
+            queue!(stdout, Print(format!("{}\r\n", notice)))?;
+        }
+    }
+    if cancelling {
+        queue!(
+            stdout,
+            Print("\r\n🛑 Cancelling: waiting for sent transfers to finish\r\n")
+        )?;
+    }
+
+    stdout.flush()
+}
+
+fn apply(rows: &mut Vec<Row>, event: TransferEvent) {
+    match event {
+        TransferEvent::Queued { index, from, to } => {
+            if rows.len() <= index {
+                rows.resize_with(index + 1, || Row {
+                    from: String::new(),
+                    to: String::new(),
+                    stage: TransferStage::Building,
+                    signature: None,
+                    started: Instant::now(),
+                    finished: None,
+                });
+            }
+            rows[index].from = from;
+            rows[index].to = to;
+        }
+        TransferEvent::Stage {
+            index,
+            stage,
+            signature,
+        } => {
+            if let Some(row) = rows.get_mut(index) {
+                row.stage = stage;
+                if signature.is_some() {
+                    row.signature = signature;
+                }
+                if stage.is_final() && row.finished.is_none() {
+                    row.finished = Some(row.started.elapsed());
+                }
+            }
+        }
+        TransferEvent::Notice(_) | TransferEvent::Finished => {}
+    }
+}
+
+// A notice's non-blank lines, as the table shows them
+fn notice_lines(message: &str) -> impl Iterator<Item = String> + '_ {
+    message
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(str::to_string)
+}
+
+fn is_cancel_key(key: &KeyEvent) -> bool {
+    key.kind == KeyEventKind::Press
+        && (matches!(key.code, KeyCode::Char('q') | KeyCode::Char('Q'))
+            || (key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL)))
+}
+
+// Render the live status table until every transfer has finished, returning the
+// notices received so they can be printed once the terminal is restored.
+// 'q' (or Ctrl+C, which raw mode delivers as a key) requests cancellation.
+pub(crate) async fn run(
+    mut events: EventReceiver,
+    cancelled: Arc<AtomicBool>,
+) -> io::Result<Vec<String>> {
+    let _guard = TerminalGuard::enter()?;
+    let mut keys = EventStream::new();
+    let mut refresh = tokio::time::interval(REFRESH_INTERVAL);
+    let mut rows = Vec::new();
+    let mut notices = Vec::new();
+
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Some(TransferEvent::Finished) | None => break,
+                Some(TransferEvent::Notice(message)) => notices.extend(notice_lines(&message)),
+                Some(event) => apply(&mut rows, event),
+            },
+            key = keys.next() => {
+                if let Some(Ok(Event::Key(key))) = key
+                    && is_cancel_key(&key) {
+                        cancelled.store(true, Ordering::Relaxed);
+                    }
+            }
+            _ = refresh.tick() => {}
+        }
+
+        draw(&rows, &notices, cancelled.load(Ordering::Relaxed))?;
+    }
+
+    Ok(notices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_final_stage_freezes_elapsed_time() {
+        let mut rows = Vec::new();
+        apply(
+            &mut rows,
+            TransferEvent::Queued {
+                index: 1,
+                from: "sender".to_string(),
+                to: "recipient".to_string(),
+            },
+        );
+        assert_eq!(rows.len(), 2);
+
+        apply(
+            &mut rows,
+            TransferEvent::Stage {
+                index: 1,
+                stage: TransferStage::Confirming,
+                signature: Some("5xSignature".to_string()),
+            },
+        );
+        apply(
+            &mut rows,
+            TransferEvent::Stage {
+                index: 1,
+                stage: TransferStage::Confirmed,
+                signature: None,
+            },
+        );
+
+        assert_eq!(rows[1].stage, TransferStage::Confirmed);
+        assert_eq!(rows[1].signature.as_deref(), Some("5xSignature"));
+        assert!(rows[1].finished.is_some());
+        assert!(rows[0].finished.is_none());
+    }
+
+    #[test]
+    fn test_notices_drop_blank_lines() {
+        let lines: Vec<String> = notice_lines("📦 Packed 3 transfers:\n  Tx 1\n\n").collect();
+        assert_eq!(lines, ["📦 Packed 3 transfers:", "  Tx 1"]);
+    }
+
+    #[test]
+    fn test_signature_suffix() {
+        assert_eq!(suffix("abcdefghij", 4), "…ghij");
+        assert_eq!(suffix("abc", 4), "abc");
+    }
+}
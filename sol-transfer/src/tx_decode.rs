@@ -0,0 +1,584 @@
+use clap::Args;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::SolTransfer;
+use crate::anchor_idl::{self, AnchorIdl};
+use crate::program_logs::{self, ProgramLogEvent};
+
+const COMPUTE_BUDGET_PROGRAM_ID: &str = "ComputeBudget111111111111111111111111111111";
+// ComputeBudgetInstruction::SetComputeUnitPrice discriminator
+const SET_COMPUTE_UNIT_PRICE: u8 = 3;
+
+// A top-level instruction, decoded from the RPC's `jsonParsed` encoding
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ParsedInstruction {
+    SystemTransfer {
+        source: String,
+        destination: String,
+        lamports: u64,
+    },
+    SplTokenTransfer {
+        source: String,
+        destination: String,
+        authority: Option<String>,
+        // Only `transferChecked` carries the mint and decimals
+        mint: Option<String>,
+        amount: u64,
+        decimals: Option<u8>,
+    },
+    ComputeBudgetSetPrice {
+        micro_lamports: u64,
+    },
+    Memo(String),
+    // A call into a program we have an Anchor IDL for
+    Anchor {
+        program: String,
+        instruction: String,
+        args: Vec<(String, String)>,
+        // (IDL account name, address) in instruction order
+        accounts: Vec<(String, String)>,
+    },
+    // Anything else, described by program and instruction type where known
+    Unknown(String),
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ParsedTransaction {
+    pub(crate) signature: String,
+    pub(crate) slot: u64,
+    pub(crate) fee: Option<u64>,
+    pub(crate) err: Option<Value>,
+    pub(crate) instructions: Vec<ParsedInstruction>,
+    // Log events of each top-level instruction, by index; empty without logs
+    pub(crate) log_events: Vec<Vec<ProgramLogEvent>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawTransaction {
+    slot: u64,
+    meta: Option<RawMeta>,
+    transaction: RawTransactionBody,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawMeta {
+    fee: u64,
+    err: Option<Value>,
+    #[serde(rename = "logMessages", default)]
+    log_messages: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawTransactionBody {
+    signatures: Vec<String>,
+    message: RawMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawMessage {
+    instructions: Vec<Value>,
+}
+
+fn string_field(info: &Value, key: &str) -> Option<String> {
+    info.get(key)?.as_str().map(str::to_string)
+}
+
+// Token amounts arrive as decimal strings to avoid JSON number precision loss
+fn amount_field(value: &Value) -> Option<u64> {
+    match value {
+        Value::String(amount) => amount.parse().ok(),
+        Value::Number(amount) => amount.as_u64(),
+        _ => None,
+    }
+}
+
+fn parse_token_transfer(kind: &str, info: &Value) -> Option<ParsedInstruction> {
+    let (amount, decimals) = match kind {
+        "transfer" => (amount_field(info.get("amount")?)?, None),
+        "transferChecked" => {
+            let token_amount = info.get("tokenAmount")?;
+            (
+                amount_field(token_amount.get("amount")?)?,
+                token_amount
+                    .get("decimals")
+                    .and_then(Value::as_u64)
+                    .map(|decimals| decimals as u8),
+            )
+        }
+        _ => return None,
+    };
+
+    Some(ParsedInstruction::SplTokenTransfer {
+        source: string_field(info, "source")?,
+        destination: string_field(info, "destination")?,
+        authority: string_field(info, "authority")
+            .or_else(|| string_field(info, "multisigAuthority")),
+        mint: string_field(info, "mint"),
+        amount,
+        decimals,
+    })
+}
+
+// The RPC doesn't parse compute budget instructions, so decode the raw data
+fn parse_compute_budget(data: &str) -> Option<ParsedInstruction> {
+    let bytes = bs58::decode(data).into_vec().ok()?;
+    match bytes.split_first()? {
+        (&SET_COMPUTE_UNIT_PRICE, rest) => Some(ParsedInstruction::ComputeBudgetSetPrice {
+            micro_lamports: u64::from_le_bytes(rest.get(..8)?.try_into().ok()?),
+        }),
+        _ => None,
+    }
+}
+
+// Decode an instruction the RPC left unparsed against its program's IDL
+fn parse_anchor(instruction: &Value, idl: &AnchorIdl) -> Option<ParsedInstruction> {
+    let data = bs58::decode(instruction.get("data")?.as_str()?)
+        .into_vec()
+        .ok()?;
+    let decoded = anchor_idl::decode_instruction(idl, &data).ok()?;
+    let addresses = instruction
+        .get("accounts")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    Some(ParsedInstruction::Anchor {
+        program: idl.program_name().to_string(),
+        instruction: decoded.name,
+        args: decoded
+            .args
+            .into_iter()
+            .map(|(name, value)| (name, value.to_string()))
+            .collect(),
+        accounts: decoded
+            .account_names
+            .into_iter()
+            .zip(addresses.iter().filter_map(Value::as_str))
+            .map(|(name, address)| (name, address.to_string()))
+            .collect(),
+    })
+}
+
+// Classify a jsonParsed instruction, decoding calls into programs in `idls` (keyed by
+// program id)
+pub(crate) fn parse_instruction_with_idls(
+    instruction: &Value,
+    idls: &HashMap<String, AnchorIdl>,
+) -> ParsedInstruction {
+    let program = instruction
+        .get("program")
+        .and_then(Value::as_str)
+        .or_else(|| instruction.get("programId").and_then(Value::as_str))
+        .unwrap_or("unknown program");
+    let parsed = instruction.get("parsed");
+    let kind = parsed
+        .and_then(|parsed| parsed.get("type"))
+        .and_then(Value::as_str);
+    let info = parsed.and_then(|parsed| parsed.get("info"));
+
+    let decoded = match (program, kind, info) {
+        ("system", Some("transfer"), Some(info)) => Some(ParsedInstruction::SystemTransfer {
+            source: string_field(info, "source").unwrap_or_default(),
+            destination: string_field(info, "destination").unwrap_or_default(),
+            lamports: info
+                .get("lamports")
+                .and_then(amount_field)
+                .unwrap_or_default(),
+        }),
+        ("spl-token" | "spl-token-2022", Some(kind), Some(info)) => {
+            parse_token_transfer(kind, info)
+        }
+        ("spl-memo", None, _) => parsed
+            .and_then(Value::as_str)
+            .map(|memo| ParsedInstruction::Memo(memo.to_string())),
+        (COMPUTE_BUDGET_PROGRAM_ID, None, _) => instruction
+            .get("data")
+            .and_then(Value::as_str)
+            .and_then(parse_compute_budget),
+        (program, None, _) => idls
+            .get(program)
+            .and_then(|idl| parse_anchor(instruction, idl)),
+        _ => None,
+    };
+
+    decoded.unwrap_or_else(|| match kind {
+        Some(kind) => ParsedInstruction::Unknown(format!("{}: {}", program, kind)),
+        None => ParsedInstruction::Unknown(program.to_string()),
+    })
+}
+
+impl SolTransfer {
+    // Fetch a transaction with `jsonParsed` encoding and decode its top-level instructions
+    pub(crate) async fn get_parsed_transaction(
+        &self,
+        signature: &str,
+    ) -> Result<ParsedTransaction, Box<dyn std::error::Error>> {
+        self.get_parsed_transaction_with_idls(signature, &HashMap::new())
+            .await
+    }
+
+    // Same, also decoding Anchor calls into the programs in `idls`
+    pub(crate) async fn get_parsed_transaction_with_idls(
+        &self,
+        signature: &str,
+        idls: &HashMap<String, AnchorIdl>,
+    ) -> Result<ParsedTransaction, Box<dyn std::error::Error>> {
+        // A transaction the node doesn't have comes back as a null result
+        let raw: RawTransaction = self
+            .rpc_call(
+                "getTransaction",
+                vec![
+                    serde_json::json!(signature),
+                    serde_json::json!({
+                        "encoding": "jsonParsed",
+                        "commitment": "confirmed",
+                        "maxSupportedTransactionVersion": 0,
+                    }),
+                ],
+            )
+            .await
+            .map_err(|e| format!("Failed to fetch transaction {}: {}", signature, e))?;
+
+        Ok(ParsedTransaction {
+            signature: raw
+                .transaction
+                .signatures
+                .first()
+                .cloned()
+                .unwrap_or_else(|| signature.to_string()),
+            slot: raw.slot,
+            fee: raw.meta.as_ref().map(|meta| meta.fee),
+            log_events: raw
+                .meta
+                .as_ref()
+                .and_then(|meta| meta.log_messages.as_deref())
+                .map(program_logs::group_by_instruction)
+                .unwrap_or_default(),
+            err: raw.meta.and_then(|meta| meta.err),
+            instructions: raw
+                .transaction
+                .message
+                .instructions
+                .iter()
+                .map(|instruction| parse_instruction_with_idls(instruction, idls))
+                .collect(),
+        })
+    }
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct TxDecodeArgs {
+    /// Transaction signature
+    signature: String,
+    /// Anchor IDL used to decode calls into its program, as PATH or PROGRAM_ID=PATH
+    /// (the program id defaults to the IDL's `address`); repeatable
+    #[arg(long = "idl", value_name = "IDL")]
+    idls: Vec<String>,
+}
+
+// IDLs keyed by the program id they decode
+fn load_idls(specs: &[String]) -> Result<HashMap<String, AnchorIdl>, Box<dyn std::error::Error>> {
+    let mut idls = HashMap::new();
+    for spec in specs {
+        let (program_id, path) = match spec.split_once('=') {
+            Some((program_id, path)) => (Some(program_id.to_string()), path),
+            None => (None, spec.as_str()),
+        };
+        let idl = anchor_idl::load_idl(path)?;
+        let program_id = program_id
+            .or_else(|| idl.program_id().map(str::to_string))
+            .ok_or_else(|| {
+                format!(
+                    "IDL {} has no program address; pass it as PROGRAM_ID={}",
+                    path, path
+                )
+            })?;
+        idls.insert(program_id, idl);
+    }
+    Ok(idls)
+}
+
+fn sol(lamports: u64) -> f64 {
+    lamports as f64 / 1_000_000_000.0
+}
+
+fn token_amount(amount: u64, decimals: Option<u8>) -> String {
+    match decimals {
+        Some(decimals) => format!(
+            "{} ({} raw)",
+            amount as f64 / 10f64.powi(decimals as i32),
+            amount
+        ),
+        None => format!("{} (raw)", amount),
+    }
+}
+
+// Lines for one instruction; the first is its headline, the rest are details
+fn describe(instruction: &ParsedInstruction) -> Vec<String> {
+    match instruction {
+        ParsedInstruction::SystemTransfer {
+            source,
+            destination,
+            lamports,
+        } => vec![
+            format!(
+                "System transfer: {} SOL ({} lamports)",
+                sol(*lamports),
+                lamports
+            ),
+            format!("from: {}", source),
+            format!("to: {}", destination),
+        ],
+        ParsedInstruction::SplTokenTransfer {
+            source,
+            destination,
+            authority,
+            mint,
+            amount,
+            decimals,
+        } => {
+            let mut lines = vec![
+                format!("Token transfer: {}", token_amount(*amount, *decimals)),
+                format!("from: {}", source),
+                format!("to: {}", destination),
+            ];
+            if let Some(mint) = mint {
+                lines.push(format!("mint: {}", mint));
+            }
+            if let Some(authority) = authority {
+                lines.push(format!("authority: {}", authority));
+            }
+            lines
+        }
+        ParsedInstruction::ComputeBudgetSetPrice { micro_lamports } => vec![format!(
+            "Compute budget: priority fee {} micro-lamports/CU",
+            micro_lamports
+        )],
+        ParsedInstruction::Memo(memo) => vec![format!("Memo: {}", memo)],
+        ParsedInstruction::Anchor {
+            program,
+            instruction,
+            args,
+            accounts,
+        } => {
+            let mut lines = vec![format!("{}: {}", program, instruction)];
+            lines.extend(
+                args.iter()
+                    .map(|(name, value)| format!("{}: {}", name, value)),
+            );
+            lines.extend(
+                accounts
+                    .iter()
+                    .map(|(name, address)| format!("{} (account): {}", name, address)),
+            );
+            lines
+        }
+        ParsedInstruction::Unknown(description) => vec![format!("Other: {}", description)],
+    }
+}
+
+// Detail lines for what an instruction logged. Compute units are reported once per
+// program, so only the instruction's own (the last one) is shown.
+fn describe_logs(events: &[ProgramLogEvent]) -> Vec<String> {
+    let mut lines: Vec<String> = events
+        .iter()
+        .filter_map(|event| match event {
+            ProgramLogEvent::Message(message) => Some(match event.json() {
+                Some(json) => format!("log (json): {}", json),
+                None => format!("log: {}", message),
+            }),
+            ProgramLogEvent::Data(data) => Some(format!("event data: {} bytes", data.len())),
+            ProgramLogEvent::ProgramReturn { program_id, data } => {
+                Some(format!("returned {} bytes from {}", data.len(), program_id))
+            }
+            ProgramLogEvent::ProgramInvoke { .. } | ProgramLogEvent::ComputeUnitsConsumed(_) => {
+                None
+            }
+        })
+        .collect();
+    let inner_calls = events
+        .iter()
+        .filter(|event| matches!(event, ProgramLogEvent::ProgramInvoke { .. }))
+        .count()
+        .saturating_sub(1);
+    if inner_calls > 0 {
+        lines.push(format!("inner calls: {}", inner_calls));
+    }
+    let compute_units = events.iter().rev().find_map(|event| match event {
+        ProgramLogEvent::ComputeUnitsConsumed(units) => Some(*units),
+        _ => None,
+    });
+    if let Some(units) = compute_units {
+        lines.push(format!("compute units: {}", units));
+    }
+    lines
+}
+
+pub(crate) fn print_tree(transaction: &ParsedTransaction) {
+    println!("Transaction {}", transaction.signature);
+    println!("├─ Slot: {}", transaction.slot);
+    if let Some(fee) = transaction.fee {
+        println!("├─ Fee: {} lamports", fee);
+    }
+    match &transaction.err {
+        Some(err) => println!("├─ Status: ❌ {}", err),
+        None => println!("├─ Status: ✅ success"),
+    }
+    println!("└─ Instructions ({})", transaction.instructions.len());
+
+    let count = transaction.instructions.len();
+    for (index, instruction) in transaction.instructions.iter().enumerate() {
+        let last = index + 1 == count;
+        let (branch, indent) = if last {
+            ("└─", "   ")
+        } else {
+            ("├─", "│  ")
+        };
+
+        let mut lines = describe(instruction);
+        if let Some(events) = transaction.log_events.get(index) {
+            lines.extend(describe_logs(events));
+        }
+        println!("   {} #{} {}", branch, index + 1, lines[0]);
+        for (detail_index, detail) in lines.iter().enumerate().skip(1) {
+            let detail_branch = if detail_index + 1 == lines.len() {
+                "└─"
+            } else {
+                "├─"
+            };
+            println!("   {}{} {}", indent, detail_branch, detail);
+        }
+    }
+}
+
+// `tx-decode` subcommand
+pub(crate) async fn run(
+    sol_transfer: &SolTransfer,
+    args: TxDecodeArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let idls = load_idls(&args.idls)?;
+    let transaction = sol_transfer
+        .get_parsed_transaction_with_idls(&args.signature, &idls)
+        .await?;
+    print_tree(&transaction);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_instruction(instruction: &Value) -> ParsedInstruction {
+        parse_instruction_with_idls(instruction, &HashMap::new())
+    }
+
+    #[test]
+    fn test_parse_known_instructions() {
+        let instructions: Vec<Value> = serde_json::from_str(
+            r#"[
+                {"programId": "ComputeBudget111111111111111111111111111111", "accounts": [], "data": "3GAG5eogvTjV", "stackHeight": null},
+                {"program": "system", "programId": "11111111111111111111111111111111", "parsed": {"type": "transfer", "info": {"source": "A", "destination": "B", "lamports": 1000000}}},
+                {"program": "spl-token", "programId": "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA", "parsed": {"type": "transferChecked", "info": {"source": "C", "destination": "D", "authority": "E", "mint": "M", "tokenAmount": {"amount": "2500000", "decimals": 6, "uiAmount": 2.5, "uiAmountString": "2.5"}}}},
+                {"program": "spl-memo", "programId": "MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr", "parsed": "hello"}
+            ]"#,
+        )
+        .unwrap();
+
+        let parsed: Vec<ParsedInstruction> = instructions.iter().map(parse_instruction).collect();
+
+        assert_eq!(
+            parsed[0],
+            ParsedInstruction::ComputeBudgetSetPrice {
+                micro_lamports: 10_000
+            }
+        );
+        assert_eq!(
+            parsed[1],
+            ParsedInstruction::SystemTransfer {
+                source: "A".to_string(),
+                destination: "B".to_string(),
+                lamports: 1_000_000,
+            }
+        );
+        assert_eq!(
+            parsed[2],
+            ParsedInstruction::SplTokenTransfer {
+                source: "C".to_string(),
+                destination: "D".to_string(),
+                authority: Some("E".to_string()),
+                mint: Some("M".to_string()),
+                amount: 2_500_000,
+                decimals: Some(6),
+            }
+        );
+        assert_eq!(parsed[3], ParsedInstruction::Memo("hello".to_string()));
+    }
+
+    #[test]
+    fn test_describe_logs_uses_the_instructions_own_compute_units() {
+        let logs: Vec<String> = [
+            "Program Vau1t11111111111111111111111111111111111111 invoke [1]",
+            "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA invoke [2]",
+            "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA consumed 3000 of 190000 compute units",
+            "Program log: {\"deposited\": 42}",
+            "Program Vau1t11111111111111111111111111111111111111 consumed 9000 of 200000 compute units",
+        ]
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+        let groups = program_logs::group_by_instruction(&logs);
+
+        assert_eq!(
+            describe_logs(&groups[0]),
+            vec![
+                "log (json): {\"deposited\":42}".to_string(),
+                "inner calls: 1".to_string(),
+                "compute units: 9000".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_anchor_instruction_with_idl() {
+        let idl: AnchorIdl = serde_json::from_str(
+            r#"{
+                "address": "Vau1t11111111111111111111111111111111111111",
+                "metadata": {"name": "vault", "version": "0.1.0", "spec": "0.1.0"},
+                "instructions": [
+                    {"name": "deposit", "discriminator": [1, 2, 3, 4, 5, 6, 7, 8],
+                     "accounts": [{"name": "owner"}, {"name": "vault"}],
+                     "args": [{"name": "amount", "type": "u64"}]}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let mut data = vec![1, 2, 3, 4, 5, 6, 7, 8];
+        data.extend_from_slice(&42u64.to_le_bytes());
+        let instruction = serde_json::json!({
+            "programId": "Vau1t11111111111111111111111111111111111111",
+            "accounts": ["O", "V"],
+            "data": bs58::encode(&data).into_string(),
+        });
+
+        let idls = HashMap::from([(idl.program_id().unwrap().to_string(), idl)]);
+        assert_eq!(
+            parse_instruction_with_idls(&instruction, &idls),
+            ParsedInstruction::Anchor {
+                program: "vault".to_string(),
+                instruction: "deposit".to_string(),
+                args: vec![("amount".to_string(), "42".to_string())],
+                accounts: vec![
+                    ("owner".to_string(), "O".to_string()),
+                    ("vault".to_string(), "V".to_string()),
+                ],
+            }
+        );
+        // Without the IDL it stays opaque
+        assert_eq!(
+            parse_instruction(&instruction),
+            ParsedInstruction::Unknown("Vau1t11111111111111111111111111111111111111".to_string())
+        );
+    }
+}